@@ -0,0 +1,263 @@
+use std::fmt::Display;
+
+use proto::common::RowKind;
+
+use crate::types::TypedValue;
+
+const DEBEZIUM_SCHEMA_FIELD: &str = "schema";
+const DEBEZIUM_PAYLOAD_FIELD: &str = "payload";
+const DEBEZIUM_OP_FIELD: &str = "op";
+const DEBEZIUM_BEFORE_FIELD: &str = "before";
+const DEBEZIUM_AFTER_FIELD: &str = "after";
+
+/// A row decoded from a change data capture record
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChangeRow {
+    pub row_kind: RowKind,
+    pub value: TypedValue,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CdcDecodeError {
+    InvalidJson(String),
+    MissingOperation,
+    UnsupportedOperation(String),
+    MissingRowImage(&'static str),
+}
+
+impl Display for CdcDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidJson(msg) => f.write_fmt(format_args!("invalid json: {}", msg)),
+            Self::MissingOperation => f.write_str("missing field op"),
+            Self::UnsupportedOperation(op) => {
+                f.write_fmt(format_args!("unsupported operation {}", op))
+            }
+            Self::MissingRowImage(field) => f.write_fmt(format_args!("missing field {}", field)),
+        }
+    }
+}
+
+impl From<serde_json::Error> for CdcDecodeError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::InvalidJson(format!("{}", err))
+    }
+}
+
+/// Decode a Debezium change event envelope in JSON.
+///
+/// Both the envelope with schema (`{"schema": {...}, "payload": {...}}`) and without schema are supported.
+/// The operation of an envelope is mapped to rows as below:
+/// - `c` (create) and `r` (snapshot read): an INSERT row of `after`;
+/// - `u` (update): an UPDATE_BEFORE row of `before` if it exists, and an UPDATE_AFTER row of `after`;
+/// - `d` (delete): a DELETE row of `before`;
+/// - `t` (truncate): no rows, because a truncate does not carry any row image;
+///
+/// A tombstone (empty payload) which follows a delete envelope is also decoded as no rows.
+pub fn decode_debezium_json(payload: &[u8]) -> Result<Vec<ChangeRow>, CdcDecodeError> {
+    if payload.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let envelope = unwrap_debezium_payload(serde_json::from_slice(payload)?);
+    if envelope.is_null() {
+        return Ok(vec![]);
+    }
+
+    let row_image = |field: &'static str| match envelope.get(field) {
+        Some(value) if !value.is_null() => Ok(TypedValue::from_json_value(value.clone())),
+        _ => Err(CdcDecodeError::MissingRowImage(field)),
+    };
+
+    let op = envelope
+        .get(DEBEZIUM_OP_FIELD)
+        .and_then(|op| op.as_str())
+        .ok_or(CdcDecodeError::MissingOperation)?;
+
+    match op {
+        "c" | "r" => Ok(vec![ChangeRow {
+            row_kind: RowKind::Insert,
+            value: row_image(DEBEZIUM_AFTER_FIELD)?,
+        }]),
+        "u" => {
+            let mut rows = vec![];
+            // before image is absent if the table is not configured with full row image
+            if let Ok(before) = row_image(DEBEZIUM_BEFORE_FIELD) {
+                rows.push(ChangeRow {
+                    row_kind: RowKind::UpdateBefore,
+                    value: before,
+                });
+            }
+            rows.push(ChangeRow {
+                row_kind: RowKind::UpdateAfter,
+                value: row_image(DEBEZIUM_AFTER_FIELD)?,
+            });
+            Ok(rows)
+        }
+        "d" => Ok(vec![ChangeRow {
+            row_kind: RowKind::Delete,
+            value: row_image(DEBEZIUM_BEFORE_FIELD)?,
+        }]),
+        "t" => Ok(vec![]),
+        _ => Err(CdcDecodeError::UnsupportedOperation(op.to_string())),
+    }
+}
+
+/// Decode the key of a Debezium change event, which is the primary key of the changed row.
+/// A table without primary key has no message key and it will be decoded as [TypedValue::Null].
+pub fn decode_debezium_json_key(key: &[u8]) -> Result<TypedValue, CdcDecodeError> {
+    if key.is_empty() {
+        return Ok(TypedValue::Null);
+    }
+
+    serde_json::from_slice(key)
+        .map(|value| TypedValue::from_json_value(unwrap_debezium_payload(value)))
+        .map_err(|err| err.into())
+}
+
+fn unwrap_debezium_payload(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(mut obj)
+            if obj.contains_key(DEBEZIUM_SCHEMA_FIELD)
+                && obj.contains_key(DEBEZIUM_PAYLOAD_FIELD) =>
+        {
+            obj.remove(DEBEZIUM_PAYLOAD_FIELD).unwrap_or_default()
+        }
+        _ => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use proto::common::RowKind;
+
+    use crate::types::TypedValue;
+
+    use super::{decode_debezium_json, decode_debezium_json_key, CdcDecodeError, ChangeRow};
+
+    fn person(id: i64, name: &str) -> TypedValue {
+        TypedValue::Object(BTreeMap::from([
+            ("id".to_string(), TypedValue::BigInt(id)),
+            ("name".to_string(), TypedValue::String(name.to_string())),
+        ]))
+    }
+
+    #[test]
+    fn test_decode_debezium_create() {
+        let payload = r#"{
+            "before": null,
+            "after": {"id": 1, "name": "jason"},
+            "source": {"version": "1.9.7.Final", "connector": "mysql", "db": "ci", "table": "person"},
+            "op": "c",
+            "ts_ms": 1672502400000
+        }"#;
+        let result = decode_debezium_json(payload.as_bytes());
+        assert_eq!(
+            result,
+            Ok(vec![ChangeRow {
+                row_kind: RowKind::Insert,
+                value: person(1, "jason")
+            }])
+        );
+
+        let payload = r#"{"before": null, "after": {"id": 1, "name": "jason"}, "op": "r"}"#;
+        let result = decode_debezium_json(payload.as_bytes());
+        assert_eq!(
+            result,
+            Ok(vec![ChangeRow {
+                row_kind: RowKind::Insert,
+                value: person(1, "jason")
+            }])
+        );
+    }
+
+    #[test]
+    fn test_decode_debezium_update() {
+        let payload = r#"{
+            "schema": {"type": "struct", "name": "ci.person.Envelope"},
+            "payload": {
+                "before": {"id": 1, "name": "jason"},
+                "after": {"id": 1, "name": "thon"},
+                "op": "u",
+                "ts_ms": 1672502400000
+            }
+        }"#;
+        let result = decode_debezium_json(payload.as_bytes());
+        assert_eq!(
+            result,
+            Ok(vec![
+                ChangeRow {
+                    row_kind: RowKind::UpdateBefore,
+                    value: person(1, "jason")
+                },
+                ChangeRow {
+                    row_kind: RowKind::UpdateAfter,
+                    value: person(1, "thon")
+                }
+            ])
+        );
+
+        let payload = r#"{"before": null, "after": {"id": 1, "name": "thon"}, "op": "u"}"#;
+        let result = decode_debezium_json(payload.as_bytes());
+        assert_eq!(
+            result,
+            Ok(vec![ChangeRow {
+                row_kind: RowKind::UpdateAfter,
+                value: person(1, "thon")
+            }])
+        );
+    }
+
+    #[test]
+    fn test_decode_debezium_delete() {
+        let payload = r#"{"before": {"id": 1, "name": "thon"}, "after": null, "op": "d"}"#;
+        let result = decode_debezium_json(payload.as_bytes());
+        assert_eq!(
+            result,
+            Ok(vec![ChangeRow {
+                row_kind: RowKind::Delete,
+                value: person(1, "thon")
+            }])
+        );
+
+        // tombstone
+        assert_eq!(decode_debezium_json(&[]), Ok(vec![]));
+        assert_eq!(decode_debezium_json(b"null"), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_decode_debezium_invalid_envelope() {
+        let result = decode_debezium_json(br#"{"before": null, "after": null}"#);
+        assert_eq!(result, Err(CdcDecodeError::MissingOperation));
+
+        let result = decode_debezium_json(br#"{"before": null, "after": null, "op": "c"}"#);
+        assert_eq!(result, Err(CdcDecodeError::MissingRowImage("after")));
+
+        let result = decode_debezium_json(br#"{"before": null, "after": null, "op": "x"}"#);
+        assert_eq!(
+            result,
+            Err(CdcDecodeError::UnsupportedOperation("x".to_string()))
+        );
+
+        let result = decode_debezium_json(b"{");
+        assert!(matches!(result, Err(CdcDecodeError::InvalidJson(_))));
+
+        let result = decode_debezium_json(br#"{"before": null, "after": null, "op": "t"}"#);
+        assert_eq!(result, Ok(vec![]));
+    }
+
+    #[test]
+    fn test_decode_debezium_key() {
+        let key = TypedValue::Object(BTreeMap::from([("id".to_string(), TypedValue::BigInt(1))]));
+        assert_eq!(decode_debezium_json_key(br#"{"id": 1}"#), Ok(key.clone()));
+        assert_eq!(
+            decode_debezium_json_key(
+                br#"{"schema": {"type": "struct", "name": "ci.person.Key"}, "payload": {"id": 1}}"#
+            ),
+            Ok(key)
+        );
+        assert_eq!(decode_debezium_json_key(&[]), Ok(TypedValue::Null));
+    }
+}
@@ -3,10 +3,23 @@ pub mod env_keys {
     pub const SEND_OPERATOR_EVENT_CONNECT_TIMEOUT: &str =
         "lightflus.send_operator_event.connect_timeout";
     pub const SEND_OPERATOR_EVENT_RPC_TIMEOUT: &str = "lightflus.send_operator_event.rpc_timeout";
+    pub const MYSQL_POOL_MAX_CONNECTIONS: &str = "lightflus.mysql.pool.max_connections";
+    pub const POSTGRES_POOL_MAX_CONNECTIONS: &str = "lightflus.postgres.pool.max_connections";
+    pub const EDGE_SPILL_DIR: &str = "lightflus.edge.spill.dir";
+    pub const EDGE_SPILL_MEMORY_EVENTS: &str = "lightflus.edge.spill.memory_events";
+    pub const EDGE_SPILL_DISK_BYTES: &str = "lightflus.edge.spill.disk_bytes";
+    pub const TASK_TERMINATE_TIMEOUT: &str = "lightflus.task.terminate_timeout";
 }
 
 pub mod default_configs {
     pub const DEFAULT_CHANNEL_SIZE: usize = 1000;
     pub const DEFAULT_SEND_OPERATOR_EVENT_RPC_TIMEOUT_MILLIS: u64 = 3000;
     pub const DEFAULT_SEND_OPERATOR_EVENT_CONNECT_TIMEOUT_MILLIS: u64 = 3000;
+    pub const DEFAULT_MYSQL_POOL_MAX_CONNECTIONS: u32 = 10;
+    pub const DEFAULT_POSTGRES_POOL_MAX_CONNECTIONS: u32 = 10;
+    pub const DEFAULT_EDGE_SPILL_DIR: &str = "lightflus/spill";
+    pub const DEFAULT_EDGE_SPILL_MEMORY_EVENTS: usize = 10000;
+    pub const DEFAULT_EDGE_SPILL_DISK_BYTES: u64 = 1 << 30;
+    // shorter than the timeout of the RPCs from the coordinator, which wait for the executors of a stopped job
+    pub const DEFAULT_TASK_TERMINATE_TIMEOUT_MILLIS: u64 = 2000;
 }
@@ -1,13 +1,29 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, OnceLock},
+};
+
 use futures_util::{TryFuture, TryStreamExt};
 use prost::Message;
-use proto::common::mysql_desc;
-use sqlx::{Arguments, ConnectOptions};
+use proto::common::{mysql_desc, postgres_desc};
+use sqlx::{Arguments, Column, Row, TypeInfo, ValueRef};
 
-use crate::types::TypedValue;
+use crate::{
+    consts::{
+        default_configs::{
+            DEFAULT_MYSQL_POOL_MAX_CONNECTIONS, DEFAULT_POSTGRES_POOL_MAX_CONNECTIONS,
+        },
+        env_keys::{MYSQL_POOL_MAX_CONNECTIONS, POSTGRES_POOL_MAX_CONNECTIONS},
+    },
+    types::TypedValue,
+    utils::get_env,
+};
 
 /// Connection of MySQL
 ///
-/// [MysqlConn] can create multiple mysql clients [sqlx::mysql::MySqlConnection].
+/// [MysqlConn] acquires mysql clients [sqlx::mysql::MySqlConnection] from a pool [sqlx::MySqlPool].
+/// Pools are shared by all [MysqlConn] instances in the same process (a TaskManager) with the same connection options,
+/// so the number of connections to a database is limited by env `lightflus.mysql.pool.max_connections` regardless of how many connectors there are.
 ///
 /// A [MysqlConn] instance support two different ways to process the query and its results:
 /// - fetch all results in a single set. Such way may blocks current thread in a long duration and consumes a lot of memory.
@@ -18,7 +34,7 @@ use crate::types::TypedValue;
 /// - with placeholder symbol '?': e.g. 'select a, b from t where b=?'
 pub struct MysqlConn {
     conn_opts: mysql_desc::ConnectionOpts,
-    inner: Option<sqlx::MySqlConnection>,
+    pool: Option<sqlx::MySqlPool>,
 }
 
 impl MysqlConn {
@@ -58,19 +74,9 @@ impl MysqlConn {
         statement: &str,
         arguments: Vec<TypedValue>,
     ) -> Result<sqlx::mysql::MySqlQueryResult, sqlx::Error> {
-        let mut mysql_arg = sqlx::mysql::MySqlArguments::default();
-        arguments.iter().for_each(|val| match val {
-            TypedValue::String(v) => mysql_arg.add(v),
-            TypedValue::BigInt(v) => mysql_arg.add(v),
-            TypedValue::Boolean(v) => mysql_arg.add(v),
-            TypedValue::Number(v) => mysql_arg.add(v),
-            _ => {}
-        });
-
-        self.connect().await?;
-
-        sqlx::query_with(statement, mysql_arg)
-            .execute(self.inner.as_mut().unwrap())
+        let pool = self.connect().await?;
+        sqlx::query_with(statement, to_mysql_arguments(&arguments))
+            .execute(pool)
             .await
     }
 
@@ -125,40 +131,41 @@ impl MysqlConn {
         arguments: Vec<TypedValue>,
         mut f: F,
     ) -> Result<(), sqlx::Error> {
-        let mut mysql_arg = sqlx::mysql::MySqlArguments::default();
-        arguments.iter().for_each(|val| match val {
-            TypedValue::String(v) => mysql_arg.add(v),
-            TypedValue::BigInt(v) => mysql_arg.add(v),
-            TypedValue::Boolean(v) => mysql_arg.add(v),
-            TypedValue::Number(v) => mysql_arg.add(v),
-            _ => {}
-        });
-
-        self.connect().await?;
-        sqlx::query_with(statement, mysql_arg)
-            .fetch(self.inner.as_mut().unwrap())
+        let pool = self.connect().await?;
+        sqlx::query_with(statement, to_mysql_arguments(&arguments))
+            .fetch(pool)
             .try_for_each(|row| f(row))
             .await
     }
 
-    async fn connect(&mut self) -> Result<(), sqlx::Error> {
-        if self.inner.is_none() {
-            let opts = sqlx::mysql::MySqlConnectOptions::new()
-                .host(&self.conn_opts.host)
-                .port(3306)
-                .username(&self.conn_opts.username)
-                .password(&self.conn_opts.password)
-                .database(&self.conn_opts.database);
+    /// # Execute statements in one transaction
+    ///
+    /// Statements are executed in order with their arguments. The transaction will be rolled back if any of them fails.
+    pub async fn execute_in_transaction(
+        &mut self,
+        statements: Vec<(String, Vec<TypedValue>)>,
+    ) -> Result<(), sqlx::Error> {
+        let pool = self.connect().await?;
+        let mut tx = pool.begin().await?;
+        for (statement, arguments) in statements {
+            sqlx::query_with(&statement, to_mysql_arguments(&arguments))
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await
+    }
 
-            opts.connect().await.map(|conn| self.inner = Some(conn))
-        } else {
-            Ok(())
+    async fn connect(&mut self) -> Result<&sqlx::MySqlPool, sqlx::Error> {
+        if self.pool.is_none() {
+            self.pool = Some(get_mysql_pool(&self.conn_opts));
         }
+        Ok(self.pool.as_ref().unwrap())
     }
 
+    /// the pool is shared with other connections, so closing a [MysqlConn] only releases its reference
     pub fn close(&mut self) {
         self.conn_opts.clear();
-        self.inner = None;
+        self.pool = None;
     }
 }
 
@@ -166,7 +173,715 @@ impl From<mysql_desc::ConnectionOpts> for MysqlConn {
     fn from(conn_opts: mysql_desc::ConnectionOpts) -> Self {
         Self {
             conn_opts,
-            inner: None,
+            pool: None,
+        }
+    }
+}
+
+/// pools are keyed by host, username, password and database
+type PoolKey = (String, String, String, String);
+
+static MYSQL_POOLS: OnceLock<Mutex<BTreeMap<PoolKey, sqlx::MySqlPool>>> = OnceLock::new();
+
+/// get the pool of the database or create a new one. It must be called in the context of tokio runtime
+fn get_mysql_pool(conn_opts: &mysql_desc::ConnectionOpts) -> sqlx::MySqlPool {
+    let key = (
+        conn_opts.host.clone(),
+        conn_opts.username.clone(),
+        conn_opts.password.clone(),
+        conn_opts.database.clone(),
+    );
+    let mut pools = MYSQL_POOLS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+
+    pools
+        .entry(key)
+        .or_insert_with(|| {
+            let opts = sqlx::mysql::MySqlConnectOptions::new()
+                .host(&conn_opts.host)
+                .port(3306)
+                .username(&conn_opts.username)
+                .password(&conn_opts.password)
+                .database(&conn_opts.database);
+
+            sqlx::mysql::MySqlPoolOptions::new()
+                .max_connections(
+                    get_env(MYSQL_POOL_MAX_CONNECTIONS)
+                        .and_then(|size| size.parse().ok())
+                        .unwrap_or(DEFAULT_MYSQL_POOL_MAX_CONNECTIONS),
+                )
+                .connect_lazy_with(opts)
+        })
+        .clone()
+}
+
+/// Every value is bound so that the arguments are still aligned with placeholders:
+/// objects and arrays are bound as JSON strings, and NULL and undefined values are bound as NULL
+fn to_mysql_arguments(arguments: &[TypedValue]) -> sqlx::mysql::MySqlArguments {
+    let mut mysql_arg = sqlx::mysql::MySqlArguments::default();
+    arguments.iter().for_each(|val| match val {
+        TypedValue::String(v) => mysql_arg.add(v),
+        TypedValue::BigInt(v) => mysql_arg.add(v),
+        TypedValue::Boolean(v) => mysql_arg.add(v),
+        TypedValue::Number(v) => mysql_arg.add(v),
+        TypedValue::Object(_) | TypedValue::Array(_) => mysql_arg.add(val.to_string()),
+        TypedValue::Null | TypedValue::Invalid => mysql_arg.add(Option::<String>::None),
+    });
+    mysql_arg
+}
+
+/// Rewrite an INSERT or REPLACE statement with a single row of placeholders to a statement inserting `rows` rows.
+///
+/// For example, `insert into t(a, b) values (?, ?) on duplicate key update b = values(b)` with 2 rows is rewritten to
+/// `insert into t(a, b) values (?, ?), (?, ?) on duplicate key update b = values(b)`.
+///
+/// [None] will be returned if the statement can not be rewritten, e.g. UPDATE and DELETE statements, INSERT ... SELECT or an INSERT with multiple rows already.
+pub fn to_multi_row_statement(statement: &str, rows: usize) -> Option<String> {
+    let lowercase = statement.to_ascii_lowercase();
+    let trimmed = lowercase.trim_start();
+    if rows == 0 || !(trimmed.starts_with("insert") || trimmed.starts_with("replace")) {
+        return None;
+    }
+
+    let (start, end) = find_values_row(&lowercase)?;
+    let row = &statement[start..end];
+    let mut multi_row_statement = String::with_capacity(statement.len() + (row.len() + 2) * rows);
+    multi_row_statement.push_str(&statement[..start]);
+    multi_row_statement.push_str(&vec![row; rows].join(", "));
+    multi_row_statement.push_str(&statement[end..]);
+    Some(multi_row_statement)
+}
+
+/// find the range of the only row following keyword `values`, quoted strings and identifiers are skipped
+fn find_values_row(statement: &str) -> Option<(usize, usize)> {
+    let bytes = statement.as_bytes();
+    let is_word = |i: usize| bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_';
+    let mut quote = None;
+    let mut start = None;
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        match quote {
+            Some(q) => {
+                if c == b'\\' {
+                    i += 1;
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None => match start {
+                None if c == b'\'' || c == b'"' || c == b'`' => quote = Some(c),
+                None => {
+                    if statement[i..].starts_with("values")
+                        && (i == 0 || !is_word(i - 1))
+                        && (i + 6 >= bytes.len() || !is_word(i + 6))
+                    {
+                        i += 6;
+                        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                            i += 1;
+                        }
+                        if i >= bytes.len() || bytes[i] != b'(' {
+                            return None;
+                        }
+                        start = Some(i);
+                        continue;
+                    }
+                }
+                Some(s) => match c {
+                    b'\'' | b'"' | b'`' => quote = Some(c),
+                    b'(' => depth += 1,
+                    b')' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            let end = i + 1;
+                            // the statement has multiple rows already
+                            if statement[end..].trim_start().starts_with(',') {
+                                return None;
+                            }
+                            return Some((s, end));
+                        }
+                    }
+                    _ => {}
+                },
+            },
+        }
+        i += 1;
+    }
+
+    None
+}
+
+/// Connection of PostgreSQL
+///
+/// Same as [MysqlConn], [PostgresConn] acquires clients from a pool shared by all [PostgresConn] instances in the process with the same connection options.
+/// The size of a pool is limited by env `lightflus.postgres.pool.max_connections`.
+///
+/// Statements use placeholders `$1`, `$2`, ... and arguments are bound in order:
+/// - [TypedValue::String] as TEXT;
+/// - [TypedValue::BigInt] as INT8;
+/// - [TypedValue::Number] as FLOAT8;
+/// - [TypedValue::Boolean] as BOOL;
+/// - [TypedValue::Null] as a TEXT NULL;
+///
+/// Postgres will not cast a TEXT argument to other types implicitly, so a placeholder of other types should be casted explicitly, e.g. `$1::timestamp`.
+pub struct PostgresConn {
+    conn_opts: postgres_desc::ConnectionOpts,
+    pool: Option<sqlx::PgPool>,
+}
+
+impl PostgresConn {
+    /// Execute the statement and return the result
+    pub async fn execute(
+        &mut self,
+        statement: &str,
+        arguments: Vec<TypedValue>,
+    ) -> Result<sqlx::postgres::PgQueryResult, sqlx::Error> {
+        let pool = self.connect().await?;
+        sqlx::query_with(statement, to_postgres_arguments(&arguments))
+            .execute(pool)
+            .await
+    }
+
+    /// Process rows of the result set iteratively
+    pub async fn try_for_each<
+        Fut: TryFuture<Ok = (), Error = sqlx::Error>,
+        F: FnMut(sqlx::postgres::PgRow) -> Fut,
+    >(
+        &mut self,
+        statement: &str,
+        arguments: Vec<TypedValue>,
+        mut f: F,
+    ) -> Result<(), sqlx::Error> {
+        let pool = self.connect().await?;
+        sqlx::query_with(statement, to_postgres_arguments(&arguments))
+            .fetch(pool)
+            .try_for_each(|row| f(row))
+            .await
+    }
+
+    /// Execute statements in order in one transaction. The transaction will be rolled back if any of them fails.
+    pub async fn execute_in_transaction(
+        &mut self,
+        statements: Vec<(String, Vec<TypedValue>)>,
+    ) -> Result<(), sqlx::Error> {
+        let pool = self.connect().await?;
+        let mut tx = pool.begin().await?;
+        for (statement, arguments) in statements {
+            sqlx::query_with(&statement, to_postgres_arguments(&arguments))
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+
+    async fn connect(&mut self) -> Result<&sqlx::PgPool, sqlx::Error> {
+        if self.pool.is_none() {
+            self.pool = Some(get_postgres_pool(&self.conn_opts));
+        }
+        Ok(self.pool.as_ref().unwrap())
+    }
+
+    /// the pool is shared with other connections, so closing a [PostgresConn] only releases its reference
+    pub fn close(&mut self) {
+        self.conn_opts.clear();
+        self.pool = None;
+    }
+}
+
+impl From<postgres_desc::ConnectionOpts> for PostgresConn {
+    fn from(conn_opts: postgres_desc::ConnectionOpts) -> Self {
+        Self {
+            conn_opts,
+            pool: None,
         }
     }
 }
+
+static POSTGRES_POOLS: OnceLock<Mutex<BTreeMap<PoolKey, sqlx::PgPool>>> = OnceLock::new();
+
+/// get the pool of the database or create a new one. It must be called in the context of tokio runtime
+fn get_postgres_pool(conn_opts: &postgres_desc::ConnectionOpts) -> sqlx::PgPool {
+    let key = (
+        conn_opts.host.clone(),
+        conn_opts.username.clone(),
+        conn_opts.password.clone(),
+        conn_opts.database.clone(),
+    );
+    let mut pools = POSTGRES_POOLS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+
+    pools
+        .entry(key)
+        .or_insert_with(|| {
+            let opts = sqlx::postgres::PgConnectOptions::new()
+                .host(&conn_opts.host)
+                .port(5432)
+                .username(&conn_opts.username)
+                .password(&conn_opts.password)
+                .database(&conn_opts.database);
+
+            sqlx::postgres::PgPoolOptions::new()
+                .max_connections(
+                    get_env(POSTGRES_POOL_MAX_CONNECTIONS)
+                        .and_then(|size| size.parse().ok())
+                        .unwrap_or(DEFAULT_POSTGRES_POOL_MAX_CONNECTIONS),
+                )
+                .connect_lazy_with(opts)
+        })
+        .clone()
+}
+
+/// Every value is bound so that the arguments are still aligned with placeholders:
+/// objects and arrays are bound as JSON text, which should be cast explicitly like `$1::jsonb` if the column is JSON,
+/// and NULL and undefined values are bound as NULL
+fn to_postgres_arguments(arguments: &[TypedValue]) -> sqlx::postgres::PgArguments {
+    let mut pg_arg = sqlx::postgres::PgArguments::default();
+    arguments.iter().for_each(|val| match val {
+        TypedValue::String(v) => pg_arg.add(v),
+        TypedValue::BigInt(v) => pg_arg.add(v),
+        TypedValue::Boolean(v) => pg_arg.add(v),
+        TypedValue::Number(v) => pg_arg.add(v),
+        TypedValue::Object(_) | TypedValue::Array(_) => pg_arg.add(val.to_string()),
+        TypedValue::Null | TypedValue::Invalid => pg_arg.add(Option::<String>::None),
+    });
+    pg_arg
+}
+
+/// Rewrite an INSERT statement of Postgres with a single row of placeholders to a statement inserting `rows` rows.
+/// Placeholders of the following rows are renumbered.
+///
+/// For example, `insert into t(a, b) values ($1, $2) on conflict (a) do update set b = excluded.b` with 2 rows is rewritten to
+/// `insert into t(a, b) values ($1, $2), ($3, $4) on conflict (a) do update set b = excluded.b`.
+///
+/// [None] will be returned if the statement can not be rewritten, e.g. UPDATE and DELETE statements,
+/// an INSERT with multiple rows already or an INSERT with placeholders out of the row.
+pub fn to_postgres_multi_row_statement(statement: &str, rows: usize) -> Option<String> {
+    let lowercase = statement.to_ascii_lowercase();
+    if rows == 0 || !lowercase.trim_start().starts_with("insert") {
+        return None;
+    }
+
+    let (start, end) = find_values_row(&lowercase)?;
+    let (_, params) = shift_postgres_placeholders(&statement[start..end], 0);
+    let (_, prefix_params) = shift_postgres_placeholders(&statement[..start], 0);
+    let (_, suffix_params) = shift_postgres_placeholders(&statement[end..], 0);
+    if prefix_params > 0 || suffix_params > 0 {
+        return None;
+    }
+
+    let rows = (0..rows)
+        .map(|row| shift_postgres_placeholders(&statement[start..end], row * params).0)
+        .collect::<Vec<_>>();
+    Some(format!(
+        "{}{}{}",
+        &statement[..start],
+        rows.join(", "),
+        &statement[end..]
+    ))
+}
+
+/// Find the arguments bound to the conflict target of an upsert statement of Postgres, i.e. `INSERT ... ON CONFLICT (...) DO UPDATE ...`.
+///
+/// For example, the conflict arguments of `insert into t(a, b) values ($1, $2) on conflict (a) do update set b = excluded.b` are `[0]`.
+/// A multi-row upsert fails if two of its rows have the same conflict arguments, so they should be merged before the statement is rewritten by [to_postgres_multi_row_statement].
+///
+/// [None] will be returned if the statement is not an upsert, or a column of the conflict target is not bound to a placeholder directly.
+pub fn to_postgres_conflict_arguments(statement: &str) -> Option<Vec<usize>> {
+    let lowercase = statement.to_ascii_lowercase();
+    if !lowercase.trim_start().starts_with("insert") {
+        return None;
+    }
+
+    let (start, end) = find_values_row(&lowercase)?;
+    let columns_end = lowercase[..start].rfind(')')?;
+    let columns_start = lowercase[..columns_end].rfind('(')?;
+    let columns = split_sql_list(&lowercase[columns_start + 1..columns_end]);
+    let values = split_sql_list(&lowercase[start + 1..end - 1]);
+    if columns.len() != values.len() {
+        return None;
+    }
+
+    let suffix = lowercase[end..]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let target = suffix
+        .strip_prefix("on conflict")?
+        .trim_start()
+        .strip_prefix('(')?;
+    let target_end = target.find(')')?;
+    if !target[target_end..].contains("do update") {
+        return None;
+    }
+
+    split_sql_list(&target[..target_end])
+        .iter()
+        .map(|column| {
+            let index = columns.iter().position(|c| c == column)?;
+            let placeholder = values[index].split("::").next()?.trim();
+            placeholder
+                .strip_prefix('$')?
+                .parse::<usize>()
+                .ok()
+                .filter(|number| *number > 0)
+                .map(|number| number - 1)
+        })
+        .collect()
+}
+
+/// split a list of columns or values by the commas out of parentheses and quotes. Quotes of identifiers are removed
+fn split_sql_list(list: &str) -> Vec<String> {
+    let mut items = vec![];
+    let mut item = String::new();
+    let mut quote = None;
+    let mut depth = 0;
+    for c in list.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == '(' => depth += 1,
+            None if c == ')' => depth -= 1,
+            None if c == ',' && depth == 0 => {
+                items.push(std::mem::take(&mut item));
+                continue;
+            }
+            None => {}
+        }
+        item.push(c);
+    }
+    items.push(item);
+
+    items
+        .into_iter()
+        .map(|item| item.trim().trim_matches('"').to_string())
+        .collect()
+}
+
+/// add `offset` to the numbers of placeholders out of quotes, and return the rewritten sql with the largest number of placeholders
+fn shift_postgres_placeholders(sql: &str, offset: usize) -> (String, usize) {
+    let mut result = String::with_capacity(sql.len());
+    let mut max = 0;
+    let mut quote = None;
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => {
+                if c == q {
+                    quote = None;
+                }
+                result.push(c);
+            }
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                result.push(c);
+            }
+            None if c == '$' && chars.peek().filter(|c| c.is_ascii_digit()).is_some() => {
+                let mut number = 0;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                    number = number * 10 + digit as usize;
+                    chars.next();
+                }
+                max = max.max(number);
+                result.push('$');
+                result.push_str(&(number + offset).to_string());
+            }
+            None => result.push(c),
+        }
+    }
+
+    (result, max)
+}
+
+/// Advance the high-water mark of an incrementing column by a value of the column.
+///
+/// The mark may not have the type of the column, e.g. `start_offset` of a polling source is always a string and the default mark is `0`,
+/// so it's converted to the type of `value` before comparing. If it can not be converted, e.g. the default mark of a DATETIME column, `value` becomes the new mark.
+///
+/// Returns the new mark if `value` is greater than `mark`.
+pub fn advance_high_water_mark(mark: &TypedValue, value: &TypedValue) -> Option<TypedValue> {
+    let mark = match (mark, value) {
+        (TypedValue::String(mark), TypedValue::BigInt(_)) => {
+            mark.trim().parse().ok().map(TypedValue::BigInt)
+        }
+        (TypedValue::String(mark), TypedValue::Number(_)) => {
+            mark.trim().parse().ok().map(TypedValue::Number)
+        }
+        (TypedValue::BigInt(_), TypedValue::BigInt(_) | TypedValue::Number(_))
+        | (TypedValue::Number(_), TypedValue::BigInt(_) | TypedValue::Number(_))
+        | (TypedValue::String(_), TypedValue::String(_)) => Some(mark.clone()),
+        _ => None,
+    };
+
+    match mark {
+        Some(mark) if *value <= mark => None,
+        _ => Some(value.clone()),
+    }
+}
+
+/// Convert a row of MySQL to an object [TypedValue] whose fields are named by the columns.
+///
+/// Column types are mapped as below:
+/// - BOOLEAN: [TypedValue::Boolean];
+/// - integer types: [TypedValue::BigInt];
+/// - FLOAT, DOUBLE and DECIMAL: [TypedValue::Number];
+/// - string types: [TypedValue::String];
+/// - DATETIME, TIMESTAMP and DATE: [TypedValue::String] formatted like `2023-01-01 00:00:00`, which can be bound as an argument of a statement again;
+/// - JSON: the parsed [TypedValue];
+/// - NULL: [TypedValue::Null];
+///
+/// Other types are not supported and will be converted to [TypedValue::Invalid].
+pub fn to_typed_value(row: &sqlx::mysql::MySqlRow) -> TypedValue {
+    TypedValue::Object(BTreeMap::from_iter(row.columns().iter().map(|column| {
+        (
+            column.name().to_string(),
+            get_column_value(row, column.ordinal()),
+        )
+    })))
+}
+
+fn get_column_value(row: &sqlx::mysql::MySqlRow, index: usize) -> TypedValue {
+    let type_name = match row.try_get_raw(index) {
+        Ok(value) => {
+            if value.is_null() {
+                return TypedValue::Null;
+            }
+            value.type_info().name().to_string()
+        }
+        Err(_) => return TypedValue::Invalid,
+    };
+
+    let result = match type_name.as_str() {
+        "BOOLEAN" => row.try_get::<bool, _>(index).map(TypedValue::Boolean),
+        "TINYINT" | "SMALLINT" | "INT" | "MEDIUMINT" | "BIGINT" => {
+            row.try_get::<i64, _>(index).map(TypedValue::BigInt)
+        }
+        "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "INT UNSIGNED" | "MEDIUMINT UNSIGNED"
+        | "BIGINT UNSIGNED" => row
+            .try_get::<u64, _>(index)
+            .map(|v| TypedValue::BigInt(v as i64)),
+        "FLOAT" => row
+            .try_get::<f32, _>(index)
+            .map(|v| TypedValue::Number(v as f64)),
+        "DOUBLE" => row.try_get::<f64, _>(index).map(TypedValue::Number),
+        // DECIMAL is transferred as string
+        "DECIMAL" => row.try_get_unchecked::<&str, _>(index).map(|v| {
+            v.parse::<f64>()
+                .map(TypedValue::Number)
+                .unwrap_or(TypedValue::Invalid)
+        }),
+        "CHAR" | "VARCHAR" | "TINYTEXT" | "TEXT" | "MEDIUMTEXT" | "LONGTEXT" | "ENUM" => {
+            row.try_get::<String, _>(index).map(TypedValue::String)
+        }
+        "DATETIME" | "TIMESTAMP" => row
+            .try_get::<chrono::NaiveDateTime, _>(index)
+            .map(|v| TypedValue::String(v.format("%Y-%m-%d %H:%M:%S%.f").to_string())),
+        "DATE" => row
+            .try_get::<chrono::NaiveDate, _>(index)
+            .map(|v| TypedValue::String(v.format("%Y-%m-%d").to_string())),
+        "JSON" => row.try_get_unchecked::<&str, _>(index).map(|v| {
+            serde_json::from_str(v)
+                .map(TypedValue::from_json_value)
+                .unwrap_or(TypedValue::Invalid)
+        }),
+        _ => Ok(TypedValue::Invalid),
+    };
+
+    result.unwrap_or_else(|err| {
+        tracing::error!("get value of column {} failed: {}", index, err);
+        TypedValue::Invalid
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::TypedValue;
+
+    use super::{
+        advance_high_water_mark, to_multi_row_statement, to_postgres_conflict_arguments,
+        to_postgres_multi_row_statement,
+    };
+
+    #[test]
+    fn test_to_multi_row_statement() {
+        assert_eq!(
+            to_multi_row_statement("insert into t(a, b) values (?, ?)", 3),
+            Some("insert into t(a, b) values (?, ?), (?, ?), (?, ?)".to_string())
+        );
+        assert_eq!(
+            to_multi_row_statement(
+                "INSERT INTO t(a, b) VALUES(?, concat(?, ')')) ON DUPLICATE KEY UPDATE b = VALUES(b)",
+                2
+            ),
+            Some(
+                "INSERT INTO t(a, b) VALUES(?, concat(?, ')')), (?, concat(?, ')')) ON DUPLICATE KEY UPDATE b = VALUES(b)"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            to_multi_row_statement("replace into `values`(a) values (?)", 2),
+            Some("replace into `values`(a) values (?), (?)".to_string())
+        );
+        assert_eq!(
+            to_multi_row_statement("insert into t(a) values (?)", 1),
+            Some("insert into t(a) values (?)".to_string())
+        );
+
+        assert_eq!(
+            to_multi_row_statement("insert into t(a) values (?), (?)", 2),
+            None
+        );
+        assert_eq!(
+            to_multi_row_statement("insert into t(a) select a from s", 2),
+            None
+        );
+        assert_eq!(
+            to_multi_row_statement("update t set a = ? where b = ?", 2),
+            None
+        );
+        assert_eq!(to_multi_row_statement("delete from t where a = ?", 2), None);
+        assert_eq!(
+            to_multi_row_statement("insert into t(a) values (?)", 0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_to_postgres_multi_row_statement() {
+        assert_eq!(
+            to_postgres_multi_row_statement(
+                "insert into t(a, b) values ($1, $2) on conflict (a) do update set b = excluded.b",
+                3
+            ),
+            Some(
+                "insert into t(a, b) values ($1, $2), ($3, $4), ($5, $6) on conflict (a) do update set b = excluded.b"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            to_postgres_multi_row_statement("INSERT INTO t(a, b) VALUES ($2, '$1' || $1)", 2),
+            Some("INSERT INTO t(a, b) VALUES ($2, '$1' || $1), ($4, '$1' || $3)".to_string())
+        );
+
+        assert_eq!(
+            to_postgres_multi_row_statement(
+                "insert into t(a, b) values ($1, $2) on conflict (a) do update set b = $3",
+                2
+            ),
+            None
+        );
+        assert_eq!(
+            to_postgres_multi_row_statement("insert into t(a) values ($1), ($2)", 2),
+            None
+        );
+        assert_eq!(
+            to_postgres_multi_row_statement("update t set a = $1 where b = $2", 2),
+            None
+        );
+        assert_eq!(
+            to_postgres_multi_row_statement("insert into t(a) values ($1)", 0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_advance_high_water_mark() {
+        // the default mark of an `updated_at` DATETIME column
+        let updated_at = TypedValue::String("2023-01-01 00:00:00".to_string());
+        assert_eq!(
+            advance_high_water_mark(&TypedValue::BigInt(0), &updated_at),
+            Some(updated_at.clone())
+        );
+        assert_eq!(
+            advance_high_water_mark(
+                &updated_at,
+                &TypedValue::String("2023-01-01 00:00:01.5".to_string())
+            ),
+            Some(TypedValue::String("2023-01-01 00:00:01.5".to_string()))
+        );
+        assert_eq!(
+            advance_high_water_mark(
+                &updated_at,
+                &TypedValue::String("2022-12-31 23:59:59".to_string())
+            ),
+            None
+        );
+        assert_eq!(advance_high_water_mark(&updated_at, &updated_at), None);
+
+        // `start_offset` of an integer column
+        let start_offset = TypedValue::String("100".to_string());
+        assert_eq!(
+            advance_high_water_mark(&start_offset, &TypedValue::BigInt(99)),
+            None
+        );
+        assert_eq!(
+            advance_high_water_mark(&start_offset, &TypedValue::BigInt(101)),
+            Some(TypedValue::BigInt(101))
+        );
+        assert_eq!(
+            advance_high_water_mark(
+                &TypedValue::String("1.5".to_string()),
+                &TypedValue::Number(2.5)
+            ),
+            Some(TypedValue::Number(2.5))
+        );
+
+        // `start_offset` of a DATETIME column
+        assert_eq!(
+            advance_high_water_mark(&TypedValue::String("2023-01-01".to_string()), &updated_at),
+            Some(updated_at.clone())
+        );
+
+        // the default mark of an integer column
+        assert_eq!(
+            advance_high_water_mark(&TypedValue::BigInt(0), &TypedValue::BigInt(1)),
+            Some(TypedValue::BigInt(1))
+        );
+    }
+
+    #[test]
+    fn test_to_postgres_conflict_arguments() {
+        assert_eq!(
+            to_postgres_conflict_arguments(
+                "insert into word_count (word,count) values ($1,$2) on conflict (word) do update set count = excluded.count"
+            ),
+            Some(vec![0])
+        );
+        assert_eq!(
+            to_postgres_conflict_arguments(
+                "INSERT INTO t (\"a\", b, c) VALUES (coalesce($3, 0), $1::jsonb, $2)\nON CONFLICT(c, \"b\") DO UPDATE SET a = excluded.a"
+            ),
+            Some(vec![1, 0])
+        );
+
+        // conflicting rows are ignored by DO NOTHING
+        assert_eq!(
+            to_postgres_conflict_arguments(
+                "insert into t (a, b) values ($1, $2) on conflict (a) do nothing"
+            ),
+            None
+        );
+        // the conflict target is not bound to a placeholder
+        assert_eq!(
+            to_postgres_conflict_arguments(
+                "insert into t (a, b) values (coalesce($1, 0), $2) on conflict (a) do update set b = excluded.b"
+            ),
+            None
+        );
+        assert_eq!(
+            to_postgres_conflict_arguments("insert into t (a, b) values ($1, $2)"),
+            None
+        );
+        assert_eq!(
+            to_postgres_conflict_arguments("delete from t where a = $1"),
+            None
+        );
+    }
+}
@@ -112,4 +112,36 @@ pub enum RedisException {
     SetMultipleValueFailed(String),
     GetValueFailed(String),
     DelValueFailed(String),
+    ExecuteCommandsFailed(String),
+    CreateGroupFailed(String),
+    ReadStreamFailed(String),
+    AckStreamFailed(String),
+}
+
+#[derive(Debug)]
+pub enum FileException {
+    ListFilesFailed(String),
+    DecodeFailed(String),
+    EncodeFailed(String),
+    WriteFailed(String),
+}
+
+#[derive(Debug)]
+pub enum MqttException {
+    PublishFailed(String),
+    AckFailed(String),
+}
+
+#[derive(Debug)]
+pub enum HttpException {
+    InvalidHeader(String),
+    RequestFailed(String),
+    UnexpectedStatus(u16),
+}
+
+#[derive(Debug)]
+pub enum ParquetException {
+    InvalidSchema(String),
+    WriteFailed(String),
+    CommitFailed(String),
 }
@@ -3,6 +3,7 @@ use std::time::SystemTime;
 
 use proto::common::KeyedDataEvent;
 use proto::common::ResourceId;
+use proto::common::RowKind;
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -152,6 +153,20 @@ impl StreamEvent for LocalEvent {
                     .and_then(|k| {
                         let mut messages = vec![];
                         let timestamp = chrono::DateTime::<chrono::Utc>::from(SystemTime::now());
+                        match e.row_kind() {
+                            // a delete event is sent as a tombstone of its key
+                            RowKind::Delete => {
+                                messages.push(KafkaMessage {
+                                    key: bytes::Bytes::copy_from_slice(&k),
+                                    payload: bytes::Bytes::new(),
+                                    timestamp: Some(timestamp.timestamp_millis()),
+                                });
+                                return Ok(messages);
+                            }
+                            // the row will be overwritten by the following update-after event
+                            RowKind::UpdateBefore => return Ok(messages),
+                            _ => {}
+                        }
                         for val in values {
                             let payload_result = serde_json::to_vec(&val);
                             if payload_result.is_err() {
@@ -209,3 +224,49 @@ impl StreamEvent for LocalEvent {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proto::common::{Entry, KeyedDataEvent, RowKind};
+
+    use crate::types::TypedValue;
+
+    use super::{LocalEvent, StreamEvent};
+
+    #[test]
+    fn test_to_kafka_message_with_row_kind() {
+        let val = TypedValue::String("value".to_string());
+        let key = TypedValue::String("key".to_string());
+        let mut event = KeyedDataEvent::default();
+        event.key = Some(Entry {
+            data_type: key.get_type() as i32,
+            value: key.get_data_bytes(),
+        });
+        event.data = vec![Entry {
+            data_type: val.get_type() as i32,
+            value: val.get_data_bytes(),
+        }];
+
+        event.set_row_kind(RowKind::UpdateAfter);
+        let result = LocalEvent::KeyedDataStreamEvent(event.clone()).to_kafka_message();
+        assert!(result.is_ok());
+        let messages = result.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(!messages[0].is_tombstone());
+        assert_eq!(messages[0].key.as_ref(), b"\"key\"");
+        assert_eq!(messages[0].payload.as_ref(), b"\"value\"");
+
+        event.set_row_kind(RowKind::UpdateBefore);
+        let result = LocalEvent::KeyedDataStreamEvent(event.clone()).to_kafka_message();
+        assert!(result.is_ok());
+        assert!(result.unwrap().is_empty());
+
+        event.set_row_kind(RowKind::Delete);
+        let result = LocalEvent::KeyedDataStreamEvent(event).to_kafka_message();
+        assert!(result.is_ok());
+        let messages = result.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].is_tombstone());
+        assert_eq!(messages[0].key.as_ref(), b"\"key\"");
+    }
+}
@@ -0,0 +1,288 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use proto::common::file_desc;
+use tokio::io::AsyncWriteExt;
+
+use crate::{err::FileException, types::TypedValue, utils::times::now_timestamp};
+
+/// Files matching the glob pattern in the order of their paths. Directories are skipped
+pub fn list_files(pattern: &str) -> Result<Vec<PathBuf>, FileException> {
+    let paths =
+        glob::glob(pattern).map_err(|err| FileException::ListFilesFailed(err.to_string()))?;
+    let mut files = paths
+        .filter_map(|path| match path {
+            Ok(path) => Some(path).filter(|path| path.is_file()),
+            Err(err) => {
+                tracing::error!("read path failed: {}", err);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
+}
+
+pub fn decode_json_line(line: &str) -> Result<TypedValue, FileException> {
+    serde_json::from_str::<serde_json::Value>(line)
+        .map(|value| TypedValue::from_json_value(value))
+        .map_err(|err| FileException::DecodeFailed(err.to_string()))
+}
+
+pub fn parse_csv_line(line: &str, delimiter: u8) -> Result<Vec<String>, FileException> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(delimiter)
+        .from_reader(line.as_bytes())
+        .records()
+        .next()
+        .unwrap_or_else(|| Ok(Default::default()))
+        .map(|record| record.iter().map(|field| field.to_string()).collect())
+        .map_err(|err| FileException::DecodeFailed(err.to_string()))
+}
+
+/// A CSV record is decoded as an object whose fields are string values named by `columns`.
+/// Fields without column are named by their indexes, and columns without field are null.
+pub fn to_csv_object(columns: &[String], record: Vec<String>) -> TypedValue {
+    let mut object = BTreeMap::from_iter(
+        columns
+            .iter()
+            .map(|column| (column.clone(), TypedValue::Null)),
+    );
+    record.into_iter().enumerate().for_each(|(index, field)| {
+        let column = columns
+            .get(index)
+            .map(|column| column.clone())
+            .unwrap_or_else(|| index.to_string());
+        object.insert(column, TypedValue::String(field));
+    });
+    TypedValue::Object(object)
+}
+
+pub fn encode_json_line(value: &TypedValue) -> Result<Vec<u8>, FileException> {
+    serde_json::to_vec(&value.to_json_value())
+        .map(|mut line| {
+            line.push(b'\n');
+            line
+        })
+        .map_err(|err| FileException::EncodeFailed(err.to_string()))
+}
+
+/// Fields of an object are written in the order of `columns`, or in the order of field names if `columns` is empty.
+/// Other values are written as a single field.
+pub fn encode_csv_line(
+    value: &TypedValue,
+    columns: &[String],
+    delimiter: u8,
+) -> Result<Vec<u8>, FileException> {
+    let fields = match value {
+        TypedValue::Object(object) if columns.is_empty() => {
+            object.values().map(|value| to_csv_field(value)).collect()
+        }
+        TypedValue::Object(object) => columns
+            .iter()
+            .map(|column| {
+                object
+                    .get(column)
+                    .map(|value| to_csv_field(value))
+                    .unwrap_or_default()
+            })
+            .collect(),
+        _ => vec![to_csv_field(value)],
+    };
+    write_csv_record(&fields, delimiter)
+}
+
+pub fn encode_csv_header(columns: &[String], delimiter: u8) -> Result<Vec<u8>, FileException> {
+    write_csv_record(columns, delimiter)
+}
+
+fn write_csv_record(fields: &[String], delimiter: u8) -> Result<Vec<u8>, FileException> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(vec![]);
+    writer
+        .write_record(fields)
+        .map_err(|err| FileException::EncodeFailed(err.to_string()))?;
+    writer
+        .into_inner()
+        .map_err(|err| FileException::EncodeFailed(err.to_string()))
+}
+
+fn to_csv_field(value: &TypedValue) -> String {
+    match value.to_json_value() {
+        serde_json::Value::Null => Default::default(),
+        serde_json::Value::String(value) => value,
+        value => value.to_string(),
+    }
+}
+
+pub fn get_file_extension(format: file_desc::Format) -> &'static str {
+    match format {
+        file_desc::Format::JsonLines => "jsonl",
+        file_desc::Format::Csv => "csv",
+    }
+}
+
+/// A writer of lines which rolls to a new file once the current file is larger than `max_bytes`
+/// or has been opened longer than `max_millis`. 0 means no limit.
+///
+/// Files are named as `<prefix>-<millis when it's opened>-<sequence>.<extension>` in the directory,
+/// and each file starts with `header` if it's set.
+pub struct RollingFileWriter {
+    dir: PathBuf,
+    prefix: String,
+    extension: &'static str,
+    header: Option<Vec<u8>>,
+    max_bytes: u64,
+    max_millis: u64,
+    sequence: u64,
+    current: Option<RollingFile>,
+}
+
+struct RollingFile {
+    writer: tokio::io::BufWriter<tokio::fs::File>,
+    written_bytes: u64,
+    opened_at: i64,
+}
+
+impl RollingFileWriter {
+    pub fn new<P: AsRef<Path>>(
+        dir: P,
+        prefix: &str,
+        extension: &'static str,
+        header: Option<Vec<u8>>,
+        rotation: &file_desc::RotationOpts,
+    ) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            prefix: prefix.to_string(),
+            extension,
+            header,
+            max_bytes: rotation.max_bytes,
+            max_millis: rotation.max_millis,
+            sequence: 0,
+            current: None,
+        }
+    }
+
+    pub async fn write(&mut self, line: &[u8]) -> Result<(), FileException> {
+        if self.should_roll() {
+            self.roll().await?;
+        }
+        match self.current.as_mut() {
+            Some(file) => {
+                file.writer
+                    .write_all(line)
+                    .await
+                    .map_err(|err| FileException::WriteFailed(err.to_string()))?;
+                file.written_bytes += line.len() as u64;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub async fn flush(&mut self) -> Result<(), FileException> {
+        match self.current.as_mut() {
+            Some(file) => file
+                .writer
+                .flush()
+                .await
+                .map_err(|err| FileException::WriteFailed(err.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn close(&mut self) -> Result<(), FileException> {
+        let result = self.flush().await;
+        self.current = None;
+        result
+    }
+
+    fn should_roll(&self) -> bool {
+        match self.current.as_ref() {
+            Some(file) => {
+                (self.max_bytes > 0 && file.written_bytes >= self.max_bytes)
+                    || (self.max_millis > 0
+                        && now_timestamp() - file.opened_at >= self.max_millis as i64)
+            }
+            None => true,
+        }
+    }
+
+    async fn roll(&mut self) -> Result<(), FileException> {
+        self.close().await?;
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|err| FileException::WriteFailed(err.to_string()))?;
+
+        let opened_at = now_timestamp();
+        let path = self.dir.join(format!(
+            "{}-{}-{}.{}",
+            &self.prefix, opened_at, self.sequence, self.extension
+        ));
+        self.sequence += 1;
+        let file = tokio::fs::File::create(&path)
+            .await
+            .map_err(|err| FileException::WriteFailed(err.to_string()))?;
+        let mut file = RollingFile {
+            writer: tokio::io::BufWriter::new(file),
+            written_bytes: 0,
+            opened_at,
+        };
+        if let Some(header) = self.header.as_ref() {
+            file.writer
+                .write_all(header)
+                .await
+                .map_err(|err| FileException::WriteFailed(err.to_string()))?;
+            file.written_bytes += header.len() as u64;
+        }
+        self.current = Some(file);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::types::TypedValue;
+
+    use super::{encode_csv_line, parse_csv_line, to_csv_object};
+
+    #[test]
+    fn test_csv_line() {
+        let record = parse_csv_line("1,\"hello, world\",", b',').expect("parse csv failed");
+        assert_eq!(
+            record,
+            vec!["1".to_string(), "hello, world".to_string(), "".to_string()]
+        );
+
+        let columns = vec!["id".to_string(), "text".to_string()];
+        let object = to_csv_object(&columns, record);
+        assert_eq!(
+            object,
+            TypedValue::Object(BTreeMap::from([
+                ("id".to_string(), TypedValue::String("1".to_string())),
+                (
+                    "text".to_string(),
+                    TypedValue::String("hello, world".to_string())
+                ),
+                ("2".to_string(), TypedValue::String("".to_string())),
+            ]))
+        );
+
+        let line = encode_csv_line(&object, &columns, b',').expect("encode csv failed");
+        assert_eq!(line, b"1,\"hello, world\"\n".to_vec());
+
+        let object = TypedValue::Object(BTreeMap::from([
+            ("id".to_string(), TypedValue::BigInt(1)),
+            ("text".to_string(), TypedValue::Null),
+        ]));
+        let line = encode_csv_line(&object, &[], b'|').expect("encode csv failed");
+        assert_eq!(line, b"1|\n".to_vec());
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+
+use proto::common::GeneratorDesc;
+
+use crate::{http::render_placeholders, types::TypedValue};
+
+const KEY_PREFIX: &str = "key-";
+const INDEX_FIELD: &str = "index";
+const KEY_FIELD: &str = "key";
+const RANDOM_FIELD: &str = "random";
+
+/// A pseudo-random sequence of SplitMix64. The same seed always produces the same sequence
+pub struct SeededRandom {
+    state: u64,
+}
+
+impl SeededRandom {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// a number in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Inputs of a generator source, which are objects of `index`, `key` and `random` described by [GeneratorDesc].
+/// It stops after `count` inputs if `count` is not 0
+pub struct GeneratorInputs {
+    random: SeededRandom,
+    rate_per_sec: u64,
+    count: u64,
+    key_cardinality: u64,
+    index: u64,
+}
+
+impl GeneratorInputs {
+    pub fn new(conf: &GeneratorDesc) -> Self {
+        Self {
+            random: SeededRandom::new(conf.seed),
+            rate_per_sec: conf.rate_per_sec,
+            count: conf.count,
+            key_cardinality: conf.key_cardinality,
+            index: 0,
+        }
+    }
+
+    /// the number of inputs which have been generated
+    pub fn generated(&self) -> u64 {
+        self.index
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.count > 0 && self.index >= self.count
+    }
+
+    /// the number of inputs which should have been generated `elapsed_millis` after the source starts.
+    /// It's only limited by `count` if `rate_per_sec` is 0
+    pub fn get_due_count(&self, elapsed_millis: u64) -> u64 {
+        let due = if self.rate_per_sec == 0 {
+            u64::MAX
+        } else {
+            u64::try_from(self.rate_per_sec as u128 * elapsed_millis as u128 / 1000)
+                .unwrap_or(u64::MAX)
+        };
+        if self.count > 0 {
+            due.min(self.count)
+        } else {
+            due
+        }
+    }
+
+    /// milliseconds after the source starts when the next input is due
+    pub fn get_next_due_millis(&self) -> u64 {
+        if self.rate_per_sec == 0 {
+            return 0;
+        }
+        let rate = self.rate_per_sec as u128;
+        u64::try_from(((self.index as u128 + 1) * 1000 + rate - 1) / rate).unwrap_or(u64::MAX)
+    }
+}
+
+impl Iterator for GeneratorInputs {
+    type Item = TypedValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_finished() {
+            return None;
+        }
+        let key = if self.key_cardinality == 0 {
+            TypedValue::Null
+        } else {
+            TypedValue::String(format!(
+                "{}{}",
+                KEY_PREFIX,
+                self.random.next_u64() % self.key_cardinality
+            ))
+        };
+        let input = TypedValue::Object(BTreeMap::from([
+            (
+                INDEX_FIELD.to_string(),
+                TypedValue::BigInt(self.index as i64),
+            ),
+            (KEY_FIELD.to_string(), key),
+            (
+                RANDOM_FIELD.to_string(),
+                TypedValue::Number(self.random.next_f64()),
+            ),
+        ]));
+        self.index += 1;
+        Some(input)
+    }
+}
+
+/// the key of an input, or null if keys are disabled
+pub fn get_input_key(input: &TypedValue) -> TypedValue {
+    match input {
+        TypedValue::Object(object) => object.get(KEY_FIELD).cloned().unwrap_or_default(),
+        _ => TypedValue::Null,
+    }
+}
+
+/// Render the template by fields of the input. The value is decoded from the rendered JSON, or it's the rendered string if it's not JSON
+pub fn render_template(template: &str, input: &TypedValue) -> TypedValue {
+    let rendered = render_placeholders(template, |placeholder| match input {
+        TypedValue::Object(object) => object.get(placeholder).cloned(),
+        _ => None,
+    });
+    match serde_json::from_str::<serde_json::Value>(&rendered) {
+        Ok(value) => TypedValue::from_json_value(value),
+        Err(_) => TypedValue::String(rendered),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use proto::common::GeneratorDesc;
+
+    use crate::types::TypedValue;
+
+    use super::{get_input_key, render_template, GeneratorInputs, SeededRandom};
+
+    #[test]
+    fn test_seeded_random() {
+        let sequence = |seed| {
+            let mut random = SeededRandom::new(seed);
+            (0..10).map(|_| random.next_u64()).collect::<Vec<_>>()
+        };
+        assert_eq!(sequence(1), sequence(1));
+        assert_ne!(sequence(1), sequence(2));
+
+        let mut random = SeededRandom::new(0);
+        (0..1000).for_each(|_| {
+            let value = random.next_f64();
+            assert!(value >= 0.0 && value < 1.0);
+        });
+    }
+
+    #[test]
+    fn test_generator_inputs() {
+        let conf = GeneratorDesc {
+            rate_per_sec: 0,
+            count: 100,
+            key_cardinality: 3,
+            seed: 42,
+            function: None,
+            template: Default::default(),
+        };
+        let inputs = GeneratorInputs::new(&conf).collect::<Vec<_>>();
+        assert_eq!(inputs.len(), 100);
+        assert_eq!(inputs, GeneratorInputs::new(&conf).collect::<Vec<_>>());
+
+        let keys = inputs
+            .iter()
+            .map(|input| get_input_key(input))
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(
+            keys,
+            ["key-0", "key-1", "key-2"]
+                .iter()
+                .map(|key| TypedValue::String(key.to_string()))
+                .collect()
+        );
+        match &inputs[99] {
+            TypedValue::Object(object) => {
+                assert_eq!(object.get("index"), Some(&TypedValue::BigInt(99)))
+            }
+            _ => panic!("input is not an object"),
+        }
+
+        let inputs = GeneratorInputs::new(&GeneratorDesc {
+            key_cardinality: 0,
+            count: 0,
+            ..conf
+        });
+        assert!(!inputs.is_finished());
+        assert_eq!(inputs.take(1000).count(), 1000);
+    }
+
+    #[test]
+    fn test_get_due_count() {
+        let mut inputs = GeneratorInputs::new(&GeneratorDesc {
+            rate_per_sec: 3,
+            count: 5,
+            key_cardinality: 0,
+            seed: 0,
+            function: None,
+            template: Default::default(),
+        });
+        assert_eq!(inputs.get_due_count(0), 0);
+        assert_eq!(inputs.get_due_count(1000), 3);
+        assert_eq!(inputs.get_due_count(10000), 5);
+        assert_eq!(inputs.get_next_due_millis(), 334);
+        inputs.next();
+        assert_eq!(inputs.get_next_due_millis(), 667);
+    }
+
+    #[test]
+    fn test_render_template() {
+        let input = TypedValue::Object(BTreeMap::from([
+            ("index".to_string(), TypedValue::BigInt(1)),
+            ("key".to_string(), TypedValue::String("key-0".to_string())),
+            ("random".to_string(), TypedValue::Number(0.5)),
+        ]));
+        assert_eq!(
+            render_template(
+                r#"{"id": {{index}}, "user": {{key}}, "score": {{ random }}}"#,
+                &input
+            ),
+            TypedValue::Object(BTreeMap::from([
+                ("id".to_string(), TypedValue::BigInt(1)),
+                ("user".to_string(), TypedValue::String("key-0".to_string())),
+                ("score".to_string(), TypedValue::Number(0.5)),
+            ]))
+        );
+        assert_eq!(
+            render_template("order-{{index}}", &input),
+            TypedValue::String("order-1".to_string())
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::time::Duration;
+
+use proto::common::{http_desc, HttpDesc};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+    Method,
+};
+
+use crate::{err::HttpException, types::TypedValue};
+
+const DEFAULT_TIMEOUT_MILLIS: u64 = 3000;
+const DEFAULT_INITIAL_BACKOFF_MILLIS: u64 = 100;
+const DEFAULT_MAX_BACKOFF_MILLIS: u64 = 10000;
+const JSON_CONTENT_TYPE: &str = "application/json";
+const VALUE_PLACEHOLDER: &str = "value";
+
+/// Replace `{{value}}` in the template by the JSON of the value, and `{{value.<field>}}` by the JSON of a field of an object value.
+/// A missing field is replaced by `null`, and other placeholders are kept as they are.
+pub fn render_body_template(template: &str, value: &TypedValue) -> String {
+    render_placeholders(template, |placeholder| {
+        resolve_placeholder(placeholder, value)
+    })
+}
+
+/// Replace each `{{<placeholder>}}` in the template by the JSON of the value resolved by `resolve`.
+/// Placeholders which can not be resolved are kept as they are.
+pub(crate) fn render_placeholders<F: Fn(&str) -> Option<TypedValue>>(
+    template: &str,
+    resolve: F,
+) -> String {
+    let mut body = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(end) => start + end,
+            None => break,
+        };
+        body.push_str(&rest[..start]);
+        match resolve(rest[start + 2..end].trim()) {
+            Some(value) => body.push_str(&value.to_json_value().to_string()),
+            None => body.push_str(&rest[start..end + 2]),
+        }
+        rest = &rest[end + 2..];
+    }
+    body.push_str(rest);
+    body
+}
+
+fn resolve_placeholder(placeholder: &str, value: &TypedValue) -> Option<TypedValue> {
+    if placeholder == VALUE_PLACEHOLDER {
+        return Some(value.clone());
+    }
+    placeholder
+        .strip_prefix(VALUE_PLACEHOLDER)
+        .and_then(|field| field.strip_prefix('.'))
+        .map(|field| match value {
+            TypedValue::Object(object) => object.get(field).cloned().unwrap_or_default(),
+            _ => TypedValue::Null,
+        })
+}
+
+fn to_method(method: http_desc::Method) -> Method {
+    match method {
+        http_desc::Method::Post => Method::POST,
+        http_desc::Method::Put => Method::PUT,
+        http_desc::Method::Patch => Method::PATCH,
+    }
+}
+
+/// A client sending request bodies to the url of [HttpDesc]
+#[derive(Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    url: String,
+    method: Method,
+    headers: HeaderMap,
+    retry: http_desc::RetryOpts,
+}
+
+impl HttpClient {
+    pub fn new(conf: &HttpDesc) -> Result<Self, HttpException> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &conf.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|err| HttpException::InvalidHeader(err.to_string()))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|err| HttpException::InvalidHeader(err.to_string()))?;
+            headers.insert(name, value);
+        }
+        if !headers.contains_key(CONTENT_TYPE) {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(JSON_CONTENT_TYPE));
+        }
+
+        let timeout_millis = if conf.timeout_millis == 0 {
+            DEFAULT_TIMEOUT_MILLIS
+        } else {
+            conf.timeout_millis
+        };
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(timeout_millis))
+            .build()
+            .map_err(|err| HttpException::RequestFailed(err.to_string()))?;
+
+        Ok(Self {
+            client,
+            url: conf.url.clone(),
+            method: to_method(conf.method()),
+            headers,
+            retry: conf.get_retry_opts(),
+        })
+    }
+
+    /// Send a request with the body. It's retried with exponential backoff if the response is 5xx or the request fails before a response is received.
+    /// Other responses which are not 2xx are not retried.
+    pub async fn send(&self, body: Vec<u8>) -> Result<(), HttpException> {
+        let mut retries = 0;
+        loop {
+            let result = self
+                .client
+                .request(self.method.clone(), self.url.as_str())
+                .headers(self.headers.clone())
+                .body(body.clone())
+                .send()
+                .await;
+            let err = match result {
+                Ok(resp) if resp.status().is_success() => return Ok(()),
+                Ok(resp) if resp.status().is_server_error() => {
+                    HttpException::UnexpectedStatus(resp.status().as_u16())
+                }
+                Ok(resp) => return Err(HttpException::UnexpectedStatus(resp.status().as_u16())),
+                Err(err) => HttpException::RequestFailed(err.to_string()),
+            };
+
+            if retries >= self.retry.max_retries {
+                return Err(err);
+            }
+            tracing::warn!(
+                "request to {} failed: {:?}, retry {} will start",
+                &self.url,
+                err,
+                retries + 1
+            );
+            tokio::time::sleep(get_backoff(&self.retry, retries)).await;
+            retries += 1;
+        }
+    }
+}
+
+/// backoff before the retry after `retries` retries
+fn get_backoff(opts: &http_desc::RetryOpts, retries: u32) -> Duration {
+    let initial_backoff_millis = if opts.initial_backoff_millis == 0 {
+        DEFAULT_INITIAL_BACKOFF_MILLIS
+    } else {
+        opts.initial_backoff_millis
+    };
+    let max_backoff_millis = if opts.max_backoff_millis == 0 {
+        DEFAULT_MAX_BACKOFF_MILLIS
+    } else {
+        opts.max_backoff_millis
+    };
+    Duration::from_millis(
+        initial_backoff_millis
+            .saturating_mul(1u64.checked_shl(retries).unwrap_or(u64::MAX))
+            .min(max_backoff_millis),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, time::Duration};
+
+    use proto::common::http_desc;
+
+    use crate::types::TypedValue;
+
+    use super::{get_backoff, render_body_template};
+
+    #[test]
+    fn test_render_body_template() {
+        let value = TypedValue::Object(BTreeMap::from([
+            ("id".to_string(), TypedValue::BigInt(1)),
+            (
+                "message".to_string(),
+                TypedValue::String("hello".to_string()),
+            ),
+        ]));
+
+        assert_eq!(
+            render_body_template(
+                r#"{"text": {{ value.message }}, "id": {{value.id}}}"#,
+                &value
+            ),
+            r#"{"text": "hello", "id": 1}"#
+        );
+        assert_eq!(
+            render_body_template("{{value}}", &value),
+            r#"{"id":1,"message":"hello"}"#
+        );
+        assert_eq!(
+            render_body_template("{{value.missing}} {{other}} {{", &value),
+            "null {{other}} {{"
+        );
+    }
+
+    #[test]
+    fn test_get_backoff() {
+        let opts = http_desc::RetryOpts {
+            max_retries: 10,
+            initial_backoff_millis: 0,
+            max_backoff_millis: 1000,
+        };
+        assert_eq!(get_backoff(&opts, 0), Duration::from_millis(100));
+        assert_eq!(get_backoff(&opts, 2), Duration::from_millis(400));
+        assert_eq!(get_backoff(&opts, 4), Duration::from_millis(1000));
+        assert_eq!(get_backoff(&opts, 64), Duration::from_millis(1000));
+    }
+}
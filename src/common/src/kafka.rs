@@ -85,6 +85,24 @@ impl KafkaProducer {
         }
     }
 
+    /// send a tombstone (a message with key and null payload) so that compacted topics can delete the key
+    pub async fn send_tombstone(&self, key: &[u8]) -> Result<(), KafkaException> {
+        let record = FutureRecord::<[u8], [u8]>::to(self.topic.as_str())
+            .partition(self.partition)
+            .key(key);
+        self.producer
+            .send(record, Duration::from_secs(3))
+            .await
+            .map(|(partition, offset)| {
+                tracing::debug!(
+                    "send tombstone to partition {} with offset {}",
+                    partition,
+                    offset
+                )
+            })
+            .map_err(|err| KafkaException { err: err.0 })
+    }
+
     pub fn close(&mut self) {
         self.topic.clear();
         drop(self.partition);
@@ -104,6 +122,13 @@ pub struct KafkaMessage {
     pub timestamp: Option<i64>,
 }
 
+impl KafkaMessage {
+    /// A message without payload is a tombstone of its key
+    pub fn is_tombstone(&self) -> bool {
+        self.payload.is_empty()
+    }
+}
+
 impl KafkaConsumer {
     pub fn new(consumer: StreamConsumer) -> Self {
         Self { consumer }
@@ -117,17 +142,20 @@ impl KafkaConsumer {
             .and_then(|msg| match msg {
                 Ok(msg) => {
                     let msg = msg.detach();
-                    msg.payload().map(|payload| {
-                        let key = msg
-                            .key()
-                            .map(|key| bytes::Bytes::copy_from_slice(key))
-                            .unwrap_or_default();
-                        processor(KafkaMessage {
-                            key,
-                            payload: bytes::Bytes::copy_from_slice(payload),
-                            timestamp: msg.timestamp().to_millis(),
-                        })
-                    })
+                    let key = msg
+                        .key()
+                        .map(|key| bytes::Bytes::copy_from_slice(key))
+                        .unwrap_or_default();
+                    // a message without payload is a tombstone
+                    let payload = msg
+                        .payload()
+                        .map(|payload| bytes::Bytes::copy_from_slice(payload))
+                        .unwrap_or_default();
+                    Some(processor(KafkaMessage {
+                        key,
+                        payload,
+                        timestamp: msg.timestamp().to_millis(),
+                    }))
                 }
                 Err(err) => {
                     tracing::error!("fail to fetch data from kafka: {}", err);
@@ -142,17 +170,20 @@ impl KafkaConsumer {
             .and_then(|result| match result {
                 Ok(msg) => {
                     let msg = msg.detach();
-                    msg.payload().map(|payload| {
-                        let key = msg
-                            .key()
-                            .map(|key| bytes::Bytes::copy_from_slice(key))
-                            .unwrap_or_default();
-                        processor(KafkaMessage {
-                            key,
-                            payload: bytes::Bytes::copy_from_slice(payload),
-                            timestamp: msg.timestamp().to_millis(),
-                        })
-                    })
+                    let key = msg
+                        .key()
+                        .map(|key| bytes::Bytes::copy_from_slice(key))
+                        .unwrap_or_default();
+                    // a message without payload is a tombstone
+                    let payload = msg
+                        .payload()
+                        .map(|payload| bytes::Bytes::copy_from_slice(payload))
+                        .unwrap_or_default();
+                    Some(processor(KafkaMessage {
+                        key,
+                        payload,
+                        timestamp: msg.timestamp().to_millis(),
+                    }))
                 }
                 Err(err) => {
                     tracing::error!("fail to fetch data from kafka: {}", err);
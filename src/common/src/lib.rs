@@ -1,11 +1,17 @@
+pub mod cdc;
 pub mod collections;
 #[cfg(not(tarpaulin_include))]
 pub mod consts;
 pub mod db;
 pub mod err;
 pub mod event;
+pub mod file;
+pub mod generator;
+pub mod http;
 pub mod kafka;
+pub mod mqtt;
 pub mod net;
+pub mod parquet;
 pub mod redis;
 pub mod types;
 pub mod utils;
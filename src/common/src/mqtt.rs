@@ -0,0 +1,340 @@
+use std::{collections::VecDeque, time::Duration};
+
+use proto::common::{mqtt_desc, MqttDesc};
+use rumqttc::{
+    AsyncClient, Event, EventLoop, MqttOptions, Outgoing, Packet, Publish, QoS, SubscribeFilter,
+};
+use tokio::{
+    sync::{mpsc, watch},
+    task::JoinHandle,
+};
+
+use crate::err::MqttException;
+
+const DEFAULT_KEEP_ALIVE_SECS: u64 = 60;
+const REQUEST_CHANNEL_CAPACITY: usize = 100;
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Options of the connection. The client id in [mqtt_desc::ConnectionOpts] overwrites `client_id`.
+/// Acks of received messages are sent manually by [MqttSubscriber::ack] after they have been processed.
+fn to_mqtt_options(conf: &MqttDesc, client_id: &str) -> MqttOptions {
+    let opts = conf.get_connection_opts();
+    let (host, port) = opts.get_host_and_port();
+    let client_id = if opts.client_id.is_empty() {
+        client_id
+    } else {
+        opts.client_id.as_str()
+    };
+    let keep_alive_secs = if opts.keep_alive_secs == 0 {
+        DEFAULT_KEEP_ALIVE_SECS
+    } else {
+        opts.keep_alive_secs
+    };
+
+    let mut options = MqttOptions::new(client_id, host, port);
+    options
+        .set_keep_alive(Duration::from_secs(keep_alive_secs))
+        .set_clean_session(!conf.persistent_session)
+        .set_request_channel_capacity(REQUEST_CHANNEL_CAPACITY)
+        .set_manual_acks(true);
+    if !opts.username.is_empty() {
+        options.set_credentials(opts.username, opts.password);
+    }
+    options
+}
+
+fn to_qos(qos: mqtt_desc::Qos) -> QoS {
+    match qos {
+        mqtt_desc::Qos::AtMostOnce => QoS::AtMostOnce,
+        mqtt_desc::Qos::AtLeastOnce => QoS::AtLeastOnce,
+        mqtt_desc::Qos::ExactlyOnce => QoS::ExactlyOnce,
+    }
+}
+
+/// Subscribe topic filters of [MqttDesc]. It must be called in the context of tokio runtime
+pub fn run_subscriber(conf: &MqttDesc, client_id: &str, channel_size: usize) -> MqttSubscriber {
+    let (client, eventloop) =
+        AsyncClient::new(to_mqtt_options(conf, client_id), REQUEST_CHANNEL_CAPACITY);
+    let qos = to_qos(conf.qos());
+    let filters = conf
+        .topic_filters
+        .iter()
+        .map(|filter| SubscribeFilter::new(filter.clone(), qos))
+        .collect();
+    let (tx, rx) = mpsc::channel(channel_size);
+    let (closed_tx, closed_rx) = watch::channel(false);
+    let handle = tokio::spawn(poll_eventloop(
+        client.clone(),
+        eventloop,
+        filters,
+        Some(tx),
+        channel_size,
+        closed_rx,
+    ));
+
+    MqttSubscriber {
+        client,
+        rx,
+        closed_tx,
+        handle,
+    }
+}
+
+/// Create a publisher of the topic in [MqttDesc]. It must be called in the context of tokio runtime
+pub fn run_publisher(conf: &MqttDesc, client_id: &str) -> MqttPublisher {
+    let (client, eventloop) =
+        AsyncClient::new(to_mqtt_options(conf, client_id), REQUEST_CHANNEL_CAPACITY);
+    let (closed_tx, closed_rx) = watch::channel(false);
+    let handle = tokio::spawn(poll_eventloop(
+        client.clone(),
+        eventloop,
+        vec![],
+        None,
+        0,
+        closed_rx,
+    ));
+
+    MqttPublisher {
+        client,
+        topic: conf.topic.clone(),
+        qos: to_qos(conf.qos()),
+        closed_tx,
+        handle,
+    }
+}
+
+/// Drive the connection until it's disconnected by the client.
+/// The eventloop reconnects the broker on the next poll after an error, and topic filters are subscribed again once it's connected.
+/// After the client is closed, it stops reconnecting.
+///
+/// Received messages are forwarded to `tx` while the eventloop is polled, so that pings and acks are still sent if the consumer is slow.
+/// Messages which can not be forwarded are kept in a window of at most `window` messages:
+/// - messages of QoS 1 and 2 are always kept. They are acked manually, so the broker won't send more than its in-flight limit before they are consumed;
+/// - messages of QoS 0 are dropped if the window is full;
+async fn poll_eventloop(
+    client: AsyncClient,
+    mut eventloop: EventLoop,
+    filters: Vec<SubscribeFilter>,
+    tx: Option<mpsc::Sender<MqttMessage>>,
+    window: usize,
+    closed: watch::Receiver<bool>,
+) {
+    let mut pending = VecDeque::<MqttMessage>::new();
+    loop {
+        let event = {
+            let poll = eventloop.poll();
+            tokio::pin!(poll);
+            loop {
+                tokio::select! {
+                    event = &mut poll => break event,
+                    permit = reserve(tx.as_ref()), if !pending.is_empty() => match permit {
+                        Some(permit) => {
+                            if let Some(message) = pending.pop_front() {
+                                permit.send(message)
+                            }
+                        }
+                        None => return,
+                    }
+                }
+            }
+        };
+
+        match event {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                if !filters.is_empty() {
+                    if let Err(err) = client.try_subscribe_many(filters.clone()) {
+                        tracing::error!("subscribe mqtt topics failed: {}", err)
+                    }
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                if let Some(tx) = tx.as_ref() {
+                    let message = MqttMessage::from(publish);
+                    if !pending.is_empty() {
+                        push_pending(&mut pending, message, window);
+                    } else if let Err(err) = tx.try_send(message) {
+                        match err {
+                            mpsc::error::TrySendError::Full(message) => {
+                                push_pending(&mut pending, message, window)
+                            }
+                            mpsc::error::TrySendError::Closed(_) => return,
+                        }
+                    }
+                }
+            }
+            Ok(Event::Outgoing(Outgoing::Disconnect)) => return,
+            Ok(_) => {}
+            Err(err) => {
+                if *closed.borrow() {
+                    return;
+                }
+                tracing::error!("mqtt connection failed: {}", err);
+                tokio::time::sleep(RECONNECT_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// wait for a slot of the channel. [None] will be returned if the channel is closed
+async fn reserve(tx: Option<&mpsc::Sender<MqttMessage>>) -> Option<mpsc::Permit<'_, MqttMessage>> {
+    match tx {
+        Some(tx) => tx.reserve().await.ok(),
+        None => None,
+    }
+}
+
+fn push_pending(pending: &mut VecDeque<MqttMessage>, message: MqttMessage, window: usize) {
+    if message.publish.qos == QoS::AtMostOnce && pending.len() >= window {
+        tracing::warn!(
+            "mqtt message of topic {} is dropped because the consumer is slow",
+            message.topic
+        );
+    } else {
+        pending.push_back(message);
+    }
+}
+
+/// A message received from the broker
+#[derive(Clone, Debug)]
+pub struct MqttMessage {
+    pub topic: String,
+    pub payload: bytes::Bytes,
+    publish: Publish,
+}
+
+impl From<Publish> for MqttMessage {
+    fn from(publish: Publish) -> Self {
+        Self {
+            topic: publish.topic.clone(),
+            payload: publish.payload.clone(),
+            publish,
+        }
+    }
+}
+
+pub struct MqttSubscriber {
+    client: AsyncClient,
+    rx: mpsc::Receiver<MqttMessage>,
+    closed_tx: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+impl MqttSubscriber {
+    pub async fn recv(&mut self) -> Option<MqttMessage> {
+        self.rx.recv().await
+    }
+
+    pub fn poll_recv(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<MqttMessage>> {
+        self.rx.poll_recv(cx)
+    }
+
+    /// Ack a message of QoS 1 or 2. The broker will redeliver messages not acked after reconnecting if the session is persistent.
+    pub fn ack(&self, message: &MqttMessage) -> Result<(), MqttException> {
+        self.client
+            .try_ack(&message.publish)
+            .map_err(|err| MqttException::AckFailed(err.to_string()))
+    }
+
+    /// Disconnect the broker and wait for the eventloop to finish
+    pub async fn close(&mut self) {
+        self.rx.close();
+        let _ = self.closed_tx.send(true);
+        if self.client.try_disconnect().is_ok() {
+            let _ = tokio::time::timeout(DISCONNECT_TIMEOUT, &mut self.handle).await;
+        }
+        self.handle.abort();
+    }
+}
+
+pub struct MqttPublisher {
+    client: AsyncClient,
+    topic: String,
+    qos: QoS,
+    closed_tx: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+impl MqttPublisher {
+    pub async fn publish(&self, payload: &[u8]) -> Result<(), MqttException> {
+        self.client
+            .publish(self.topic.as_str(), self.qos, false, payload)
+            .await
+            .map_err(|err| MqttException::PublishFailed(err.to_string()))
+    }
+
+    /// Disconnect the broker. The eventloop finishes after messages published before are sent
+    pub fn close(&mut self) {
+        let _ = self.closed_tx.send(true);
+        if self.client.try_disconnect().is_err() {
+            self.handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use proto::common::{mqtt_desc, MqttDesc};
+
+    use super::{push_pending, to_mqtt_options, MqttMessage};
+
+    #[test]
+    fn test_to_mqtt_options() {
+        let mut conf = MqttDesc {
+            connection_opts: Some(mqtt_desc::ConnectionOpts {
+                broker: "localhost".to_string(),
+                client_id: Default::default(),
+                username: Default::default(),
+                password: Default::default(),
+                keep_alive_secs: 0,
+            }),
+            topic_filters: vec!["devices/+/telemetry".to_string()],
+            topic: Default::default(),
+            qos: mqtt_desc::Qos::AtLeastOnce as i32,
+            data_type: 0,
+            persistent_session: true,
+        };
+
+        let options = to_mqtt_options(&conf, "job-1");
+        assert_eq!(options.broker_address(), ("localhost".to_string(), 1883));
+        assert_eq!(options.client_id(), "job-1".to_string());
+        assert_eq!(options.keep_alive(), Duration::from_secs(60));
+        assert!(!options.clean_session());
+
+        conf.connection_opts = Some(mqtt_desc::ConnectionOpts {
+            broker: "broker:8883".to_string(),
+            client_id: "device-gateway".to_string(),
+            username: Default::default(),
+            password: Default::default(),
+            keep_alive_secs: 10,
+        });
+        conf.persistent_session = false;
+
+        let options = to_mqtt_options(&conf, "job-1");
+        assert_eq!(options.broker_address(), ("broker".to_string(), 8883));
+        assert_eq!(options.client_id(), "device-gateway".to_string());
+        assert_eq!(options.keep_alive(), Duration::from_secs(10));
+        assert!(options.clean_session());
+    }
+
+    #[test]
+    fn test_push_pending() {
+        let message =
+            |qos| MqttMessage::from(rumqttc::Publish::new("devices/1/telemetry", qos, "1"));
+        let mut pending = Default::default();
+
+        push_pending(&mut pending, message(rumqttc::QoS::AtMostOnce), 1);
+        push_pending(&mut pending, message(rumqttc::QoS::AtMostOnce), 1);
+        assert_eq!(pending.len(), 1);
+
+        // messages of QoS 1 and 2 are never dropped
+        push_pending(&mut pending, message(rumqttc::QoS::AtLeastOnce), 1);
+        push_pending(&mut pending, message(rumqttc::QoS::ExactlyOnce), 1);
+        assert_eq!(pending.len(), 3);
+    }
+}
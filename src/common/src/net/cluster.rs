@@ -1,6 +1,6 @@
 use crate::collections::lang;
 use crate::types;
-use crate::types::SingleKV;
+use crate::utils::key_groups;
 
 use proto::common::DataflowMeta;
 use proto::common::{Dataflow, HostAddr};
@@ -12,7 +12,7 @@ use std::time::Duration;
 use std::vec;
 
 use super::gateway::taskmanager::SafeTaskManagerRpcGateway;
-use super::DEFAULT_TASKMANAGER_PORT;
+use super::{RpcCompression, TlsConfig, DEFAULT_TASKMANAGER_PORT};
 
 #[derive(Clone, Eq, PartialEq, Debug, Copy)]
 pub enum NodeStatus {
@@ -118,11 +118,40 @@ impl Cluster {
 
     /// A dataflow will be splitted into several partitions and deploy these sub-dataflow into different workers
     /// Graph-Partition is an NP-hard problem. Fortunately, a dataflow execution graph is too small to apply specific graph-partition algorithm
+    ///
+    /// Operators are expanded into their parallel instances first. The instances of an operator are deployed to the workers in turn,
+    /// starting from the one its id is partitioned to
     pub fn partition_dataflow(&self, dataflow: &mut Dataflow) {
-        dataflow.nodes.iter_mut().for_each(|entry| {
-            let addr = self.partition_key(&SingleKV::new(*entry.0));
+        dataflow.nodes.values_mut().for_each(|operator| {
+            if operator.parallelism > key_groups::KEY_GROUPS {
+                tracing::warn!(
+                    "parallelism {} of operator {} is larger than the number of key groups",
+                    operator.parallelism,
+                    operator.operator_id
+                );
+                operator.parallelism = key_groups::KEY_GROUPS;
+            }
+        });
+        dataflow.expand_parallelism();
+
+        let workers: Vec<HostAddr> = self
+            .workers
+            .iter()
+            .filter(|worker| worker.is_available())
+            .map(|node| node.host_addr.clone())
+            .collect();
+        if workers.is_empty() {
+            return;
+        }
+
+        dataflow.nodes.values_mut().for_each(|operator| {
+            let hash = key_groups::hash(&operator.get_logical_operator_id().to_be_bytes());
+            let index = (hash as usize % workers.len()
+                + operator.get_instance_index() as usize)
+                % workers.len();
+            let addr = workers[index].clone();
             if addr.is_valid() {
-                entry.1.host_addr = Some(addr);
+                operator.host_addr = Some(addr);
             }
         });
     }
@@ -222,20 +251,35 @@ pub struct ClusterBuilder {
     pub rpc_timeout: u64,
     /// rpc connection timeout
     pub connect_timeout: u64,
+    /// compression of the messages sent to task managers, `none` or `gzip`. It should be the same as the one of the task managers
+    #[serde(default)]
+    pub compression: RpcCompression,
+    /// TLS of the connections to task managers
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
 }
 
 impl ClusterBuilder {
     pub fn build(&self) -> Cluster {
+        let tls = self
+            .tls
+            .as_ref()
+            .map(|tls| tls.client_config().expect("load tls config failed: "));
         Cluster {
             workers: lang::index_map(&self.get_nodes(), |index, builder| {
-                let mut node = builder.build(SafeTaskManagerRpcGateway::with_timeout(
+                let gateway = SafeTaskManagerRpcGateway::with_timeout(
                     &HostAddr {
                         host: builder.host.clone(),
                         port: builder.port as u32,
                     },
                     Duration::from_secs(self.connect_timeout),
                     Duration::from_secs(self.rpc_timeout),
-                ));
+                )
+                .with_compression(self.compression);
+                let mut node = builder.build(match &tls {
+                    Some(tls) => gateway.with_tls(tls.clone()),
+                    None => gateway,
+                });
 
                 node.node_id = index as u32;
                 node
@@ -269,6 +313,8 @@ mod cluster_tests {
             nodes: "localhost:8080".to_string(),
             rpc_timeout: 3,
             connect_timeout: 3,
+            compression: Default::default(),
+            tls: None,
         };
         let mut cluster = builder.build();
 
@@ -292,6 +338,8 @@ mod cluster_tests {
             nodes: "198.0.0.1:8080,198.0.0.2:8080,198.0.0.3:8080".to_string(),
             rpc_timeout: 3,
             connect_timeout: 3,
+            compression: Default::default(),
+            tls: None,
         };
         let mut cluster = builder.build();
         let mut dataflow = Dataflow::default();
@@ -342,6 +390,125 @@ mod cluster_tests {
         });
     }
 
+    #[tokio::test]
+    pub async fn test_cluster_partition_dataflow_parallelism() {
+        use proto::common::{
+            operator_info::Details, Dataflow, DataflowMeta, KeyBy, Mapper, OperatorInfo, Reducer,
+            ResourceId,
+        };
+        use std::collections::HashSet;
+
+        use crate::net::cluster::NodeStatus;
+        let builder = ClusterBuilder {
+            nodes: "198.0.0.1:8080,198.0.0.2:8080,198.0.0.3:8080".to_string(),
+            rpc_timeout: 3,
+            connect_timeout: 3,
+            compression: Default::default(),
+            tls: None,
+        };
+        let mut cluster = builder.build();
+        cluster
+            .workers
+            .iter_mut()
+            .for_each(|node| node.status = NodeStatus::Running);
+
+        let operator =
+            |operator_id: u32, upstreams: Vec<u32>, parallelism: u32, details| OperatorInfo {
+                operator_id,
+                upstreams,
+                parallelism,
+                details: Some(details),
+                ..Default::default()
+            };
+        let mut dataflow = Dataflow {
+            job_id: Some(ResourceId::default()),
+            meta: vec![
+                DataflowMeta {
+                    center: 0,
+                    neighbors: vec![1],
+                },
+                DataflowMeta {
+                    center: 1,
+                    neighbors: vec![2],
+                },
+                DataflowMeta {
+                    center: 2,
+                    neighbors: vec![],
+                },
+            ],
+            nodes: vec![
+                (
+                    0,
+                    operator(0, vec![], 0, Details::Mapper(Mapper::default())),
+                ),
+                (1, operator(1, vec![0], 2, Details::KeyBy(KeyBy::default()))),
+                (
+                    2,
+                    operator(2, vec![1], 3, Details::Reducer(Reducer::default())),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            execution_id: None,
+        };
+
+        cluster.partition_dataflow(&mut dataflow);
+
+        // instance 0 keeps the id of the operator
+        assert_eq!(dataflow.nodes.len(), 6);
+        assert_eq!(dataflow.nodes[&1].get_instance_index(), 0);
+        assert_eq!(dataflow.nodes[&3].get_logical_operator_id(), 1);
+        assert_eq!(dataflow.nodes[&3].get_instance_index(), 1);
+        assert_eq!(dataflow.nodes[&4].get_logical_operator_id(), 2);
+        assert_eq!(dataflow.nodes[&5].get_instance_index(), 2);
+        assert_eq!(dataflow.nodes[&5].upstreams, vec![1, 3]);
+
+        let mut meta = dataflow.meta.clone();
+        meta.sort_by_key(|meta| meta.center);
+        assert_eq!(
+            meta,
+            vec![
+                DataflowMeta {
+                    center: 0,
+                    neighbors: vec![1, 3],
+                },
+                DataflowMeta {
+                    center: 1,
+                    neighbors: vec![2, 4, 5],
+                },
+                DataflowMeta {
+                    center: 2,
+                    neighbors: vec![],
+                },
+                DataflowMeta {
+                    center: 3,
+                    neighbors: vec![2, 4, 5],
+                },
+                DataflowMeta {
+                    center: 4,
+                    neighbors: vec![],
+                },
+                DataflowMeta {
+                    center: 5,
+                    neighbors: vec![],
+                },
+            ]
+        );
+        assert!(dataflow.validate().is_ok());
+
+        // the instances of an operator are deployed to different workers
+        let hosts = [2, 4, 5]
+            .iter()
+            .map(|operator_id| dataflow.nodes[operator_id].get_host_addr())
+            .collect::<HashSet<_>>();
+        assert_eq!(hosts.len(), 3);
+
+        // an expanded dataflow is left unchanged
+        let expanded = dataflow.clone();
+        cluster.partition_dataflow(&mut dataflow);
+        assert_eq!(dataflow, expanded);
+    }
+
     #[tokio::test]
     pub async fn test_split_into_subdataflow() {
         use proto::common::Dataflow;
@@ -354,6 +521,8 @@ mod cluster_tests {
             nodes: "198.0.0.1:8080, 198.0.0.2:8080, 198.0.0.3:8080".to_string(),
             rpc_timeout: 3,
             connect_timeout: 3,
+            compression: Default::default(),
+            tls: None,
         };
         let mut cluster = builder.build();
         let mut dataflow = Dataflow::default();
@@ -464,6 +633,8 @@ mod cluster_tests {
             nodes: "localhost_1:9999,localhost_2:9999".to_string(),
             rpc_timeout: 3,
             connect_timeout: 3,
+            compression: Default::default(),
+            tls: None,
         };
 
         let cluster = builder.build();
@@ -489,4 +660,26 @@ mod cluster_tests {
         assert_eq!(node.get_id(), 1);
         assert_eq!(node.get_status(), &super::NodeStatus::Pending);
     }
+
+    #[test]
+    fn test_cluster_builder_compression() {
+        use crate::net::RpcCompression;
+
+        let builder = serde_json::from_str::<super::ClusterBuilder>(
+            r#"{"nodes": "localhost:8080", "rpc_timeout": 3, "connect_timeout": 3}"#,
+        )
+        .expect("parse cluster config failed");
+        assert_eq!(builder.compression, RpcCompression::None);
+        assert_eq!(builder.compression.encoding(), None);
+
+        let builder = serde_json::from_str::<super::ClusterBuilder>(
+            r#"{"nodes": "localhost:8080", "rpc_timeout": 3, "connect_timeout": 3, "compression": "gzip"}"#,
+        )
+        .expect("parse cluster config failed");
+        assert_eq!(builder.compression, RpcCompression::Gzip);
+        assert_eq!(
+            builder.compression.encoding(),
+            Some(tonic::codec::CompressionEncoding::Gzip)
+        );
+    }
 }
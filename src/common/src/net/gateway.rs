@@ -85,14 +85,20 @@ pub mod taskmanager {
         },
         taskmanager::{
             task_manager_api_client::TaskManagerApiClient, BatchSendEventsToOperatorResponse,
-            CreateSubDataflowRequest, CreateSubDataflowResponse, SendEventToOperatorResponse,
-            StopDataflowResponse,
+            CreateSubDataflowRequest, CreateSubDataflowResponse, EdgeAck, EdgeFrame,
+            ExportKeyGroupsRequest, GetCollectedEventsRequest, GetCollectedEventsResponse,
+            KeyGroupStates, SendEventToOperatorResponse, StopDataflowResponse,
         },
     };
     use tokio::sync::Mutex;
-    use tonic::{async_trait, transport::Channel};
+    use tonic::{
+        async_trait,
+        codec::CompressionEncoding,
+        transport::{Channel, ClientTlsConfig, Endpoint},
+        Streaming,
+    };
 
-    use crate::net::DEFAULT_RPC_TIMEOUT;
+    use crate::net::{RpcCompression, DEFAULT_RPC_TIMEOUT};
 
     use super::{
         super::DEFAULT_CONNECT_TIMEOUT, ReceiveAckRpcGateway, ReceiveHeartbeatRpcGateway,
@@ -108,6 +114,8 @@ pub mod taskmanager {
         host_addr: HostAddr,
         connect_timeout: Duration,
         rpc_timeout: Duration,
+        compression: RpcCompression,
+        tls: Option<ClientTlsConfig>,
     }
 
     unsafe impl Send for SafeTaskManagerRpcGateway {}
@@ -121,14 +129,40 @@ pub mod taskmanager {
 
     impl Unpin for SafeTaskManagerRpcGateway {}
 
+    /// The client always accepts compressed responses, and it compresses requests only if compression is enabled.
+    /// The connection is encrypted if there's a TLS config
+    fn connect(
+        host_addr: &HostAddr,
+        connect_timeout: Duration,
+        compression: RpcCompression,
+        tls: Option<&ClientTlsConfig>,
+    ) -> TaskManagerApiClient<Channel> {
+        let mut endpoint = Endpoint::new(host_addr.as_uri())
+            .expect("parse endpoint failed")
+            .connect_timeout(connect_timeout);
+        if let Some(tls) = tls {
+            endpoint = endpoint
+                .tls_config(tls.clone())
+                .expect("invalid tls config");
+        }
+        let client = TaskManagerApiClient::new(endpoint.connect_lazy())
+            .accept_compressed(CompressionEncoding::Gzip);
+        match compression.encoding() {
+            Some(encoding) => client.send_compressed(encoding),
+            None => client,
+        }
+    }
+
     #[async_trait]
     impl ReceiveAckRpcGateway for SafeTaskManagerRpcGateway {
         async fn receive_ack(&self, request: Ack) -> Result<Response, tonic::Status> {
             let mut guard = self.inner.lock().await;
             let inner = guard.get_or_insert_with(|| {
-                TaskManagerApiClient::with_connection_timeout(
-                    self.host_addr.as_uri(),
+                connect(
+                    &self.host_addr,
                     Duration::from_secs(DEFAULT_CONNECT_TIMEOUT),
+                    self.compression,
+                    self.tls.as_ref(),
                 )
             });
 
@@ -144,9 +178,11 @@ pub mod taskmanager {
         async fn receive_heartbeat(&self, request: Heartbeat) -> Result<Response, tonic::Status> {
             let mut guard = self.inner.lock().await;
             let inner = guard.get_or_insert_with(|| {
-                TaskManagerApiClient::with_connection_timeout(
-                    self.host_addr.as_uri(),
+                connect(
+                    &self.host_addr,
                     Duration::from_secs(DEFAULT_CONNECT_TIMEOUT),
+                    self.compression,
+                    self.tls.as_ref(),
                 )
             });
 
@@ -159,15 +195,19 @@ pub mod taskmanager {
 
     impl SafeTaskManagerRpcGateway {
         pub fn new(host_addr: &HostAddr) -> Self {
-            let client = TaskManagerApiClient::with_connection_timeout(
-                host_addr.as_uri(),
+            let client = connect(
+                host_addr,
                 Duration::from_secs(DEFAULT_CONNECT_TIMEOUT),
+                Default::default(),
+                None,
             );
             Self {
                 inner: Arc::new(tokio::sync::Mutex::new(Some(client))),
                 host_addr: host_addr.clone(),
                 connect_timeout: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT),
                 rpc_timeout: Duration::from_secs(DEFAULT_RPC_TIMEOUT),
+                compression: Default::default(),
+                tls: None,
             }
         }
 
@@ -176,25 +216,65 @@ pub mod taskmanager {
             connect_timeout: Duration,
             rpc_timeout: Duration,
         ) -> Self {
-            let client =
-                TaskManagerApiClient::with_connection_timeout(host_addr.as_uri(), connect_timeout);
+            let client = connect(host_addr, connect_timeout, Default::default(), None);
             Self {
                 inner: Arc::new(tokio::sync::Mutex::new(Some(client))),
                 host_addr: host_addr.clone(),
                 connect_timeout,
                 rpc_timeout,
+                compression: Default::default(),
+                tls: None,
             }
         }
 
+        /// messages sent by the gateway are compressed if compression is enabled
+        pub fn with_compression(mut self, compression: RpcCompression) -> Self {
+            self.compression = compression;
+            self.reconnect();
+            self
+        }
+
+        /// the gateway connects to the server over TLS
+        pub fn with_tls(mut self, tls: ClientTlsConfig) -> Self {
+            self.tls = Some(tls);
+            self.reconnect();
+            self
+        }
+
+        /// A new gateway to the host, which has the same compression and TLS configs as this gateway
+        pub fn connect_to(
+            &self,
+            host_addr: &HostAddr,
+            connect_timeout: Duration,
+            rpc_timeout: Duration,
+        ) -> Self {
+            let mut gateway = Self::with_timeout(host_addr, connect_timeout, rpc_timeout);
+            gateway.compression = self.compression;
+            gateway.tls = self.tls.clone();
+            gateway.reconnect();
+            gateway
+        }
+
+        fn reconnect(&mut self) {
+            self.inner = Arc::new(tokio::sync::Mutex::new(Some(connect(
+                &self.host_addr,
+                self.connect_timeout,
+                self.compression,
+                self.tls.as_ref(),
+            ))));
+        }
+
         pub async fn send_event_to_operator(
             &self,
             event: KeyedDataEvent,
         ) -> Result<SendEventToOperatorResponse, tonic::Status> {
             let mut guard = self.inner.lock().await;
             let inner = guard.get_or_insert_with(|| {
-                TaskManagerApiClient::with_connection_timeout(
-                    self.host_addr.as_uri(),
+                connect(
+                    &self.host_addr,
                     self.connect_timeout,
+                    self.compression,
+                    self.tls.as_ref(),
                 )
             });
 
@@ -213,9 +293,11 @@ pub mod taskmanager {
         ) -> Result<StopDataflowResponse, tonic::Status> {
             let mut guard = self.inner.lock().await;
             let inner = guard.get_or_insert_with(|| {
-                TaskManagerApiClient::with_connection_timeout(
-                    self.host_addr.as_uri(),
+                connect(
+                    &self.host_addr,
                     self.connect_timeout,
+                    self.compression,
+                    self.tls.as_ref(),
                 )
             });
 
@@ -234,9 +316,11 @@ pub mod taskmanager {
         ) -> Result<CreateSubDataflowResponse, tonic::Status> {
             let mut guard = self.inner.lock().await;
             let inner = guard.get_or_insert_with(|| {
-                TaskManagerApiClient::with_connection_timeout(
-                    self.host_addr.as_uri(),
+                connect(
+                    &self.host_addr,
                     self.connect_timeout,
+                    self.compression,
+                    self.tls.as_ref(),
                 )
             });
 
@@ -263,9 +347,11 @@ pub mod taskmanager {
         ) -> Result<BatchSendEventsToOperatorResponse, tonic::Status> {
             let mut guard = self.inner.lock().await;
             let inner = guard.get_or_insert_with(|| {
-                TaskManagerApiClient::with_connection_timeout(
-                    self.host_addr.as_uri(),
+                connect(
+                    &self.host_addr,
                     self.connect_timeout,
+                    self.compression,
+                    self.tls.as_ref(),
                 )
             });
 
@@ -284,9 +370,11 @@ pub mod taskmanager {
         ) -> Result<SubDataflowStates, tonic::Status> {
             let mut guard = self.inner.lock().await;
             let inner = guard.get_or_insert_with(|| {
-                TaskManagerApiClient::with_connection_timeout(
-                    self.host_addr.as_uri(),
+                connect(
+                    &self.host_addr,
                     self.connect_timeout,
+                    self.compression,
+                    self.tls.as_ref(),
                 )
             });
 
@@ -298,6 +386,93 @@ pub mod taskmanager {
                 .await
                 .map(|resp| resp.into_inner())
         }
+
+        pub async fn get_collected_events(
+            &self,
+            req: GetCollectedEventsRequest,
+        ) -> Result<GetCollectedEventsResponse, tonic::Status> {
+            let mut guard = self.inner.lock().await;
+            let inner = guard.get_or_insert_with(|| {
+                connect(
+                    &self.host_addr,
+                    self.connect_timeout,
+                    self.compression,
+                    self.tls.as_ref(),
+                )
+            });
+
+            let mut request = tonic::Request::new(req);
+            request.set_timeout(self.rpc_timeout);
+
+            inner
+                .get_collected_events(request)
+                .await
+                .map(|resp| resp.into_inner())
+        }
+
+        pub async fn export_key_groups(
+            &self,
+            req: ExportKeyGroupsRequest,
+        ) -> Result<KeyGroupStates, tonic::Status> {
+            let mut guard = self.inner.lock().await;
+            let inner = guard.get_or_insert_with(|| {
+                connect(
+                    &self.host_addr,
+                    self.connect_timeout,
+                    self.compression,
+                    self.tls.as_ref(),
+                )
+            });
+
+            let mut request = tonic::Request::new(req);
+            request.set_timeout(self.rpc_timeout);
+
+            inner
+                .export_key_groups(request)
+                .await
+                .map(|resp| resp.into_inner())
+        }
+
+        pub async fn import_key_groups(
+            &self,
+            req: KeyGroupStates,
+        ) -> Result<Response, tonic::Status> {
+            let mut guard = self.inner.lock().await;
+            let inner = guard.get_or_insert_with(|| {
+                connect(
+                    &self.host_addr,
+                    self.connect_timeout,
+                    self.compression,
+                    self.tls.as_ref(),
+                )
+            });
+
+            let mut request = tonic::Request::new(req);
+            request.set_timeout(self.rpc_timeout);
+
+            inner
+                .import_key_groups(request)
+                .await
+                .map(|resp| resp.into_inner())
+        }
+
+        /// Open the stream of an edge. Unlike other calls, it has no rpc timeout because the stream is long-lived
+        pub async fn open_edge<S>(&self, frames: S) -> Result<Streaming<EdgeAck>, tonic::Status>
+        where
+            S: futures_util::Stream<Item = EdgeFrame> + Send + 'static,
+        {
+            let mut guard = self.inner.lock().await;
+            let inner = guard.get_or_insert_with(|| {
+                connect(
+                    &self.host_addr,
+                    self.connect_timeout,
+                    self.compression,
+                    self.tls.as_ref(),
+                )
+            });
+
+            inner.open_edge(frames).await.map(|resp| resp.into_inner())
+        }
     }
 
     #[derive(Clone)]
@@ -376,11 +551,17 @@ pub mod coordinator {
     use std::{sync::Arc, time::Duration};
 
     use tokio::sync::Mutex;
-    use tonic::async_trait;
+    use tonic::{
+        async_trait,
+        transport::{Channel, ClientTlsConfig, Endpoint},
+    };
 
     use proto::{
         common::{Ack, Dataflow, DataflowStates, Heartbeat, HostAddr, ResourceId, Response},
-        coordinator::{coordinator_api_client::CoordinatorApiClient, GetDataflowRequest},
+        coordinator::{
+            coordinator_api_client::CoordinatorApiClient, GetDataflowRequest,
+            RescaleDataflowRequest,
+        },
     };
 
     use crate::net::{DEFAULT_CONNECT_TIMEOUT, DEFAULT_RPC_TIMEOUT};
@@ -396,6 +577,24 @@ pub mod coordinator {
         host_addr: HostAddr,
         rpc_timeout: u64,
         connect_timeout: u64,
+        tls: Option<ClientTlsConfig>,
+    }
+
+    /// The connection is encrypted if there's a TLS config
+    fn connect(
+        host_addr: &HostAddr,
+        connect_timeout: Duration,
+        tls: Option<&ClientTlsConfig>,
+    ) -> CoordinatorApiClient<Channel> {
+        let mut endpoint = Endpoint::new(host_addr.as_uri())
+            .expect("parse endpoint failed")
+            .connect_timeout(connect_timeout);
+        if let Some(tls) = tls {
+            endpoint = endpoint
+                .tls_config(tls.clone())
+                .expect("invalid tls config");
+        }
+        CoordinatorApiClient::new(endpoint.connect_lazy())
     }
 
     impl RpcGateway for SafeCoordinatorRpcGateway {
@@ -410,9 +609,10 @@ pub mod coordinator {
         async fn receive_heartbeat(&self, request: Heartbeat) -> Result<Response, tonic::Status> {
             let mut guard = self.inner.lock().await;
             let inner = guard.get_or_insert_with(|| {
-                CoordinatorApiClient::with_connection_timeout(
-                    self.host_addr.as_uri(),
+                connect(
+                    &self.host_addr,
                     Duration::from_secs(DEFAULT_CONNECT_TIMEOUT),
+                    self.tls.as_ref(),
                 )
             });
 
@@ -431,9 +631,10 @@ pub mod coordinator {
         async fn receive_ack(&self, req: Ack) -> Result<Response, tonic::Status> {
             let mut guard = self.inner.lock().await;
             let inner = guard.get_or_insert_with(|| {
-                CoordinatorApiClient::with_connection_timeout(
-                    self.host_addr.as_uri(),
+                connect(
+                    &self.host_addr,
                     Duration::from_secs(DEFAULT_CONNECT_TIMEOUT),
+                    self.tls.as_ref(),
                 )
             });
             let mut request = tonic::Request::new(req);
@@ -458,15 +659,39 @@ pub mod coordinator {
                 host_addr: host_addr.clone(),
                 rpc_timeout: DEFAULT_RPC_TIMEOUT,
                 connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+                tls: None,
             }
         }
 
+        /// The gateway doesn't connect until the first request, which is sent over TLS if there's a TLS config
+        pub fn lazy(host_addr: &HostAddr, tls: Option<ClientTlsConfig>) -> Self {
+            Self {
+                inner: Default::default(),
+                host_addr: host_addr.clone(),
+                rpc_timeout: DEFAULT_RPC_TIMEOUT,
+                connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+                tls,
+            }
+        }
+
+        /// the gateway connects to the coordinator over TLS
+        pub fn with_tls(mut self, tls: ClientTlsConfig) -> Self {
+            self.inner = Arc::new(tokio::sync::Mutex::new(Some(connect(
+                &self.host_addr,
+                Duration::from_secs(self.connect_timeout),
+                Some(&tls),
+            ))));
+            self.tls = Some(tls);
+            self
+        }
+
         pub async fn create_dataflow(&self, dataflow: Dataflow) -> Result<Response, tonic::Status> {
             let mut guard = self.inner.lock().await;
             let inner = guard.get_or_insert_with(|| {
-                CoordinatorApiClient::with_connection_timeout(
-                    self.host_addr.as_uri(),
+                connect(
+                    &self.host_addr,
                     Duration::from_secs(self.connect_timeout),
+                    self.tls.as_ref(),
                 )
             });
 
@@ -482,9 +707,10 @@ pub mod coordinator {
         pub async fn terminate_dataflow(&self, req: ResourceId) -> Result<Response, tonic::Status> {
             let mut guard = self.inner.lock().await;
             let inner = guard.get_or_insert_with(|| {
-                CoordinatorApiClient::with_connection_timeout(
-                    self.host_addr.as_uri(),
+                connect(
+                    &self.host_addr,
                     Duration::from_secs(self.connect_timeout),
+                    self.tls.as_ref(),
                 )
             });
             let mut request = tonic::Request::new(req);
@@ -502,9 +728,10 @@ pub mod coordinator {
         ) -> Result<DataflowStates, tonic::Status> {
             let mut guard = self.inner.lock().await;
             let inner = guard.get_or_insert_with(|| {
-                CoordinatorApiClient::with_connection_timeout(
-                    self.host_addr.as_uri(),
+                connect(
+                    &self.host_addr,
                     Duration::from_secs(self.connect_timeout),
+                    self.tls.as_ref(),
                 )
             });
 
@@ -516,5 +743,27 @@ pub mod coordinator {
                 .await
                 .map(|resp| resp.into_inner())
         }
+
+        pub async fn rescale_dataflow(
+            &self,
+            req: RescaleDataflowRequest,
+        ) -> Result<Response, tonic::Status> {
+            let mut guard = self.inner.lock().await;
+            let inner = guard.get_or_insert_with(|| {
+                connect(
+                    &self.host_addr,
+                    Duration::from_secs(self.connect_timeout),
+                    self.tls.as_ref(),
+                )
+            });
+
+            let mut request = tonic::Request::new(req);
+            request.set_timeout(Duration::from_secs(self.rpc_timeout));
+
+            inner
+                .rescale_dataflow(request)
+                .await
+                .map(|resp| resp.into_inner())
+        }
     }
 }
@@ -9,6 +9,10 @@ use std::{
 use futures_util::{ready, Future, FutureExt};
 use proto::common::{Ack, Heartbeat, HostAddr, NodeType, SubDataflowId};
 use tokio::sync::mpsc;
+use tonic::{
+    codec::CompressionEncoding,
+    transport::{Certificate, ClientTlsConfig, Endpoint, Identity, ServerTlsConfig},
+};
 
 use crate::{futures::join_all, types::ExecutorId, utils};
 
@@ -17,6 +21,89 @@ use self::gateway::{ReceiveAckRpcGateway, ReceiveHeartbeatRpcGateway};
 pub(crate) const DEFAULT_RPC_TIMEOUT: u64 = 3;
 pub(crate) const DEFAULT_CONNECT_TIMEOUT: u64 = 3;
 pub(crate) const DEFAULT_TASKMANAGER_PORT: u16 = 8792;
+
+/// Compression of gRPC messages, which is configured for the whole cluster.
+/// Servers and clients always accept compressed messages, but they only send compressed messages if it's enabled.
+/// So that nodes with different configurations can still talk to each other during a rolling change,
+/// but the coordinator and all TaskManagers should be configured with the same value, otherwise only some of the links are compressed.
+///
+/// Only `gzip` is supported, since the gRPC library (tonic 0.8) doesn't implement zstd
+#[derive(Clone, Copy, Debug, Default, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RpcCompression {
+    #[default]
+    None,
+    Gzip,
+}
+
+impl RpcCompression {
+    /// the encoding of the sent messages. It's [None] if compression is disabled
+    pub fn encoding(&self) -> Option<CompressionEncoding> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some(CompressionEncoding::Gzip),
+        }
+    }
+}
+
+/// TLS configuration of a node. Certificates and keys are PEM files.
+///
+/// - servers present the certificate of the node. With `mutual`, they also require clients to present certificates signed by the CA
+/// - clients verify servers by the CA. With `mutual`, they also present the certificate of the node
+#[derive(Clone, Debug, serde::Deserialize, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// path of the certificate of the node
+    pub cert_path: String,
+    /// path of the private key of the certificate
+    pub key_path: String,
+    /// path of the CA certificate which verifies the peers
+    pub ca_path: String,
+    /// whether the peers verify each other (mTLS)
+    #[serde(default)]
+    pub mutual: bool,
+    /// the name verified in the certificates of servers. It's the host of the server by default
+    #[serde(default)]
+    pub domain_name: Option<String>,
+}
+
+impl TlsConfig {
+    pub fn server_config(&self) -> std::io::Result<ServerTlsConfig> {
+        let config = ServerTlsConfig::new().identity(self.identity()?);
+        if self.mutual {
+            Ok(config.client_ca_root(self.ca_certificate()?))
+        } else {
+            Ok(config)
+        }
+    }
+
+    /// The certificates are checked when the config is loaded, so that an invalid one fails on startup instead of on the first connection
+    pub fn client_config(&self) -> std::io::Result<ClientTlsConfig> {
+        let mut config = ClientTlsConfig::new().ca_certificate(self.ca_certificate()?);
+        if self.mutual {
+            config = config.identity(self.identity()?);
+        }
+        if let Some(domain_name) = &self.domain_name {
+            config = config.domain_name(domain_name);
+        }
+
+        Endpoint::from_static("http://localhost")
+            .tls_config(config.clone())
+            .map(|_| config)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    fn identity(&self) -> std::io::Result<Identity> {
+        Ok(Identity::from_pem(
+            std::fs::read(&self.cert_path)?,
+            std::fs::read(&self.key_path)?,
+        ))
+    }
+
+    fn ca_certificate(&self) -> std::io::Result<Certificate> {
+        std::fs::read(&self.ca_path).map(Certificate::from_pem)
+    }
+}
+
 pub mod cluster;
 #[cfg(not(tarpaulin_include))]
 pub mod gateway;
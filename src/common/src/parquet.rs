@@ -0,0 +1,463 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use chrono::{TimeZone, Utc};
+use parquet::{
+    basic::{Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType},
+    data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type},
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    format::MilliSeconds,
+    schema::types::{Type, TypePtr},
+};
+use proto::common::{parquet_desc, ParquetDesc};
+
+use crate::{err::ParquetException, types::TypedValue, utils::times::now_timestamp};
+
+const DEFAULT_ROW_GROUP_SIZE: usize = 10000;
+const PARQUET_EXTENSION: &str = "parquet";
+const IN_PROGRESS_SUFFIX: &str = "inprogress";
+/// name of the partition whose value is null, same as Hive
+const NULL_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// A value of a column, converted by the type of the column
+#[derive(Clone, Debug, PartialEq)]
+enum Cell {
+    String(String),
+    Int64(i64),
+    Double(f64),
+    Boolean(bool),
+}
+
+fn to_cell(column_type: parquet_desc::column::Type, value: &TypedValue) -> Option<Cell> {
+    use parquet_desc::column::Type as ColumnType;
+    match (column_type, value) {
+        (_, TypedValue::Null | TypedValue::Invalid) => None,
+        (ColumnType::String, TypedValue::String(v)) => Some(Cell::String(v.clone())),
+        (ColumnType::String, v) => Some(Cell::String(v.to_json_value().to_string())),
+        (ColumnType::Int64 | ColumnType::TimestampMillis, TypedValue::BigInt(v)) => {
+            Some(Cell::Int64(*v))
+        }
+        (ColumnType::Int64 | ColumnType::TimestampMillis, TypedValue::Number(v)) => {
+            Some(Cell::Int64(*v as i64))
+        }
+        (ColumnType::Int64 | ColumnType::TimestampMillis, TypedValue::String(v)) => {
+            v.parse().ok().map(Cell::Int64)
+        }
+        (ColumnType::Double, TypedValue::Number(v)) => Some(Cell::Double(*v)),
+        (ColumnType::Double, TypedValue::BigInt(v)) => Some(Cell::Double(*v as f64)),
+        (ColumnType::Double, TypedValue::String(v)) => v.parse().ok().map(Cell::Double),
+        (ColumnType::Boolean, TypedValue::Boolean(v)) => Some(Cell::Boolean(*v)),
+        (ColumnType::Boolean, TypedValue::String(v)) => v.parse().ok().map(Cell::Boolean),
+        _ => None,
+    }
+}
+
+/// Schema of files. All columns are optional, rows missing required values are dropped before they are written
+fn to_schema(columns: &[parquet_desc::Column]) -> Result<TypePtr, ParquetException> {
+    use parquet_desc::column::Type as ColumnType;
+    let fields = columns
+        .iter()
+        .map(|column| {
+            let (physical_type, logical_type) = match column.r#type() {
+                ColumnType::String => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+                ColumnType::Int64 => (PhysicalType::INT64, None),
+                ColumnType::Double => (PhysicalType::DOUBLE, None),
+                ColumnType::Boolean => (PhysicalType::BOOLEAN, None),
+                ColumnType::TimestampMillis => (
+                    PhysicalType::INT64,
+                    Some(LogicalType::Timestamp {
+                        is_adjusted_to_u_t_c: true,
+                        unit: TimeUnit::MILLIS(MilliSeconds {}),
+                    }),
+                ),
+            };
+            Type::primitive_type_builder(&column.name, physical_type)
+                .with_repetition(Repetition::OPTIONAL)
+                .with_logical_type(logical_type)
+                .build()
+                .map(Arc::new)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| ParquetException::InvalidSchema(err.to_string()))?;
+
+    Type::group_type_builder("schema")
+        .with_fields(fields)
+        .build()
+        .map(Arc::new)
+        .map_err(|err| ParquetException::InvalidSchema(err.to_string()))
+}
+
+/// Directories of the partition of a value, e.g. 'dt=2026-10-18/hour=03/region=eu'.
+/// The time bucket comes first, followed by a directory for each partition field.
+pub fn get_partition(
+    partitioning: &parquet_desc::Partitioning,
+    value: &TypedValue,
+    event_time: i64,
+) -> String {
+    let mut dirs = vec![];
+    if !partitioning.time_format.is_empty() {
+        let time = Utc
+            .timestamp_millis_opt(event_time)
+            .single()
+            .unwrap_or_default();
+        dirs.push(time.format(&partitioning.time_format).to_string());
+    }
+    partitioning.fields.iter().for_each(|field| {
+        let partition_value = match value {
+            TypedValue::Object(object) => match object.get(field) {
+                None | Some(TypedValue::Null) | Some(TypedValue::Invalid) => {
+                    NULL_PARTITION.to_string()
+                }
+                Some(TypedValue::String(v)) => v.clone(),
+                Some(v) => v.to_json_value().to_string(),
+            },
+            _ => NULL_PARTITION.to_string(),
+        };
+        dirs.push(format!("{}={}", field, partition_value.replace('/', "_")));
+    });
+    dirs.join("/")
+}
+
+/// A writer of Parquet files partitioned by [parquet_desc::Partitioning].
+///
+/// Each partition has an in-progress file `.<prefix>-<millis when it's opened>-<sequence>.parquet.inprogress`.
+/// Rows are buffered and written as a row group once there are `row_group_size` rows.
+/// A file is committed by writing its footer and renaming it to `<prefix>-<millis when it's opened>-<sequence>.parquet` atomically, so readers never see a partial file.
+/// Files are committed when they are rolled by [proto::common::file_desc::RotationOpts] or by [PartitionedParquetWriter::commit].
+/// The size of a file only counts row groups which have been written.
+pub struct PartitionedParquetWriter {
+    dir: PathBuf,
+    prefix: String,
+    columns: Vec<parquet_desc::Column>,
+    schema: TypePtr,
+    properties: Arc<WriterProperties>,
+    partitioning: parquet_desc::Partitioning,
+    max_bytes: u64,
+    max_millis: u64,
+    row_group_size: usize,
+    sequence: u64,
+    partitions: BTreeMap<String, PartitionFile>,
+}
+
+struct PartitionFile {
+    writer: SerializedFileWriter<std::fs::File>,
+    in_progress_path: PathBuf,
+    path: PathBuf,
+    rows: Vec<Vec<Option<Cell>>>,
+    opened_at: i64,
+}
+
+impl PartitionedParquetWriter {
+    pub fn new<P: AsRef<Path>>(
+        dir: P,
+        prefix: &str,
+        conf: &ParquetDesc,
+    ) -> Result<Self, ParquetException> {
+        let rotation = conf.get_rotation_opts();
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            prefix: prefix.to_string(),
+            columns: conf.columns.clone(),
+            schema: to_schema(&conf.columns)?,
+            properties: Arc::new(
+                WriterProperties::builder()
+                    .set_compression(Compression::SNAPPY)
+                    .build(),
+            ),
+            partitioning: conf.get_partitioning(),
+            max_bytes: rotation.max_bytes,
+            max_millis: rotation.max_millis,
+            row_group_size: if conf.row_group_size == 0 {
+                DEFAULT_ROW_GROUP_SIZE
+            } else {
+                conf.row_group_size as usize
+            },
+            sequence: 0,
+            partitions: Default::default(),
+        })
+    }
+
+    /// Write a value into its partition. Values which are not objects or miss values of required columns are dropped
+    pub fn write(&mut self, value: &TypedValue, event_time: i64) -> Result<(), ParquetException> {
+        let row = match self.to_row(value) {
+            Some(row) => row,
+            None => {
+                tracing::warn!("value {:?} is dropped by parquet schema", value);
+                return Ok(());
+            }
+        };
+        let partition = get_partition(&self.partitioning, value, event_time);
+        if !self.partitions.contains_key(&partition) {
+            let file = self.open(&partition)?;
+            self.partitions.insert(partition.clone(), file);
+        }
+
+        let should_commit = match self.partitions.get_mut(&partition) {
+            Some(file) => {
+                file.rows.push(row);
+                if file.rows.len() >= self.row_group_size {
+                    file.write_row_group(&self.columns)?;
+                }
+                file.should_roll(self.max_bytes, self.max_millis)
+            }
+            None => false,
+        };
+        if should_commit {
+            self.commit_partition(&partition)?;
+        }
+        Ok(())
+    }
+
+    /// Commit files which have been opened longer than `max_millis`
+    pub fn roll_expired(&mut self) -> Result<(), ParquetException> {
+        let expired = self
+            .partitions
+            .iter()
+            .filter(|(_, file)| file.is_expired(self.max_millis))
+            .map(|(partition, _)| partition.clone())
+            .collect::<Vec<_>>();
+        for partition in expired {
+            self.commit_partition(&partition)?;
+        }
+        Ok(())
+    }
+
+    /// Commit files of all partitions. Buffered rows are written before files are committed
+    pub fn commit(&mut self) -> Result<(), ParquetException> {
+        let partitions = self.partitions.keys().cloned().collect::<Vec<_>>();
+        for partition in partitions {
+            self.commit_partition(&partition)?;
+        }
+        Ok(())
+    }
+
+    fn to_row(&self, value: &TypedValue) -> Option<Vec<Option<Cell>>> {
+        let object = match value {
+            TypedValue::Object(object) => object,
+            _ => return None,
+        };
+        self.columns
+            .iter()
+            .map(|column| {
+                let cell = object
+                    .get(&column.name)
+                    .and_then(|value| to_cell(column.r#type(), value));
+                if column.required && cell.is_none() {
+                    None
+                } else {
+                    Some(cell)
+                }
+            })
+            .collect()
+    }
+
+    fn open(&mut self, partition: &str) -> Result<PartitionFile, ParquetException> {
+        let dir = if partition.is_empty() {
+            self.dir.clone()
+        } else {
+            self.dir.join(partition)
+        };
+        std::fs::create_dir_all(&dir)
+            .map_err(|err| ParquetException::WriteFailed(err.to_string()))?;
+
+        let opened_at = now_timestamp();
+        let name = format!(
+            "{}-{}-{}.{}",
+            &self.prefix, opened_at, self.sequence, PARQUET_EXTENSION
+        );
+        self.sequence += 1;
+        let in_progress_path = dir.join(format!(".{}.{}", &name, IN_PROGRESS_SUFFIX));
+        let file = std::fs::File::create(&in_progress_path)
+            .map_err(|err| ParquetException::WriteFailed(err.to_string()))?;
+        let writer = SerializedFileWriter::new(file, self.schema.clone(), self.properties.clone())
+            .map_err(|err| ParquetException::WriteFailed(err.to_string()))?;
+
+        Ok(PartitionFile {
+            writer,
+            in_progress_path,
+            path: dir.join(name),
+            rows: vec![],
+            opened_at,
+        })
+    }
+
+    fn commit_partition(&mut self, partition: &str) -> Result<(), ParquetException> {
+        match self.partitions.remove(partition) {
+            Some(file) => file.commit(&self.columns),
+            None => Ok(()),
+        }
+    }
+}
+
+impl PartitionFile {
+    fn should_roll(&self, max_bytes: u64, max_millis: u64) -> bool {
+        (max_bytes > 0 && self.writer.bytes_written() as u64 >= max_bytes)
+            || self.is_expired(max_millis)
+    }
+
+    fn is_expired(&self, max_millis: u64) -> bool {
+        max_millis > 0 && now_timestamp() - self.opened_at >= max_millis as i64
+    }
+
+    fn write_row_group(
+        &mut self,
+        columns: &[parquet_desc::Column],
+    ) -> Result<(), ParquetException> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let rows = std::mem::take(&mut self.rows);
+        let mut row_group = self
+            .writer
+            .next_row_group()
+            .map_err(|err| ParquetException::WriteFailed(err.to_string()))?;
+
+        let mut index = 0;
+        while let Some(mut column_writer) = row_group
+            .next_column()
+            .map_err(|err| ParquetException::WriteFailed(err.to_string()))?
+        {
+            let cells = rows.iter().map(|row| row.get(index).cloned().flatten());
+            let def_levels = rows
+                .iter()
+                .map(|row| match row.get(index) {
+                    Some(Some(_)) => 1,
+                    _ => 0,
+                })
+                .collect::<Vec<i16>>();
+            let result = match columns.get(index).map(|column| column.r#type()) {
+                Some(parquet_desc::column::Type::Int64)
+                | Some(parquet_desc::column::Type::TimestampMillis) => {
+                    let values = cells
+                        .filter_map(|cell| match cell {
+                            Some(Cell::Int64(v)) => Some(v),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+                    column_writer
+                        .typed::<Int64Type>()
+                        .write_batch(&values, Some(&def_levels), None)
+                }
+                Some(parquet_desc::column::Type::Double) => {
+                    let values = cells
+                        .filter_map(|cell| match cell {
+                            Some(Cell::Double(v)) => Some(v),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+                    column_writer.typed::<DoubleType>().write_batch(
+                        &values,
+                        Some(&def_levels),
+                        None,
+                    )
+                }
+                Some(parquet_desc::column::Type::Boolean) => {
+                    let values = cells
+                        .filter_map(|cell| match cell {
+                            Some(Cell::Boolean(v)) => Some(v),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+                    column_writer
+                        .typed::<BoolType>()
+                        .write_batch(&values, Some(&def_levels), None)
+                }
+                _ => {
+                    let values = cells
+                        .filter_map(|cell| match cell {
+                            Some(Cell::String(v)) => Some(ByteArray::from(v.into_bytes())),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+                    column_writer.typed::<ByteArrayType>().write_batch(
+                        &values,
+                        Some(&def_levels),
+                        None,
+                    )
+                }
+            };
+            result
+                .and_then(|_| column_writer.close())
+                .map_err(|err| ParquetException::WriteFailed(err.to_string()))?;
+            index += 1;
+        }
+
+        row_group
+            .close()
+            .map(|_| ())
+            .map_err(|err| ParquetException::WriteFailed(err.to_string()))
+    }
+
+    /// write buffered rows and the footer, then rename the in-progress file to its final name
+    fn commit(mut self, columns: &[parquet_desc::Column]) -> Result<(), ParquetException> {
+        self.write_row_group(columns)?;
+        self.writer
+            .close()
+            .map_err(|err| ParquetException::CommitFailed(err.to_string()))?;
+        std::fs::rename(&self.in_progress_path, &self.path)
+            .map_err(|err| ParquetException::CommitFailed(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use proto::common::parquet_desc;
+
+    use crate::types::TypedValue;
+
+    use super::{get_partition, to_cell, Cell};
+
+    #[test]
+    fn test_get_partition() {
+        let value = TypedValue::Object(BTreeMap::from([
+            (
+                "region".to_string(),
+                TypedValue::String("eu/west".to_string()),
+            ),
+            ("shard".to_string(), TypedValue::BigInt(3)),
+        ]));
+        let partitioning = parquet_desc::Partitioning {
+            time_format: "dt=%Y-%m-%d/hour=%H".to_string(),
+            fields: vec![
+                "region".to_string(),
+                "shard".to_string(),
+                "missing".to_string(),
+            ],
+        };
+
+        // 2026-10-18T03:04:05Z
+        assert_eq!(
+            get_partition(&partitioning, &value, 1792292645000),
+            "dt=2026-10-18/hour=03/region=eu_west/shard=3/missing=__HIVE_DEFAULT_PARTITION__"
+        );
+        assert_eq!(
+            get_partition(&Default::default(), &value, 1792292645000),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_to_cell() {
+        use parquet_desc::column::Type;
+
+        assert_eq!(
+            to_cell(Type::Int64, &TypedValue::String("42".to_string())),
+            Some(Cell::Int64(42))
+        );
+        assert_eq!(
+            to_cell(Type::Double, &TypedValue::BigInt(1)),
+            Some(Cell::Double(1.0))
+        );
+        assert_eq!(
+            to_cell(Type::String, &TypedValue::BigInt(1)),
+            Some(Cell::String("1".to_string()))
+        );
+        assert_eq!(to_cell(Type::Boolean, &TypedValue::Number(1.0)), None);
+        assert_eq!(to_cell(Type::String, &TypedValue::Null), None);
+    }
+}
@@ -1,117 +1,853 @@
-use std::time::Duration;
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
 
 use proto::common::RedisDesc;
-use redis::{Commands, ConnectionAddr, ConnectionInfo, RedisConnectionInfo, ToRedisArgs};
+use redis::{aio::MultiplexedConnection, FromRedisValue, RedisConnectionInfo, ToRedisArgs};
+use tokio::net::TcpStream;
+use tokio_rustls::{
+    rustls::{self, OwnedTrustAnchor, RootCertStore, ServerName},
+    TlsConnector,
+};
 
-use crate::err::RedisException;
+use crate::{err::RedisException, types::TypedValue};
 
 const REDIS_PORT: u16 = 6379;
+const REDIS_CLUSTER_SLOTS: u16 = 16384;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
 
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct NodeAddr {
+    host: String,
+    port: u16,
+}
+
+/// Async client of Redis
+///
+/// [RedisClient] connects to Redis nodes by [MultiplexedConnection], which pipelines concurrent requests in a single connection without blocking the current thread.
+/// - Connections are created lazily. A connection will be dropped if it's broken, and a new one will be created by the next request.
+/// - If `tls` is enabled, connections are encrypted by rustls with the root certificates of webpki.
+/// - If `cluster` is enabled, the slots of nodes are loaded by `CLUSTER SLOTS` from the seed nodes. Commands are routed to the node owning the slot of their keys,
+///   and the slots will be reloaded if a node replies MOVED or ASK. A single command is retried once against the node in the reply, with `ASKING` sent first for ASK.
+///   Multiple commands are sent to each node in a pipeline concurrently, and a redirected pipeline fails without retry.
 pub struct RedisClient {
-    client: redis::Client,
-    inner: Option<redis::Connection>,
+    seeds: Vec<NodeAddr>,
+    tls: bool,
+    cluster: bool,
+    redis_info: RedisConnectionInfo,
+    connections: BTreeMap<NodeAddr, MultiplexedConnection>,
+    /// slot ranges (both inclusive) and their master nodes
+    slots: Vec<(u16, u16, NodeAddr)>,
 }
 
 impl RedisClient {
     pub fn new(conf: &RedisDesc) -> Self {
-        let connection_info = to_connection_info(conf);
-        let client = redis::Client::open(connection_info);
+        let opts = conf.connection_opts.clone().unwrap_or_default();
+        let seeds = parse_nodes(&opts.host);
         Self {
-            client: client.expect("create redis client failed"),
-            inner: None,
-        }
-    }
-
-    fn connect(&mut self) -> Result<(), RedisException> {
-        if self.inner.is_none() {
-            self.client
-                .get_connection_with_timeout(Duration::from_secs(3))
-                .map(|conn| self.inner = Some(conn))
-                .map_err(|err| RedisException::ConnectFailed(format!("{}", err)))
-        } else {
-            Ok(())
+            seeds,
+            tls: opts.tls,
+            cluster: opts.cluster,
+            redis_info: RedisConnectionInfo {
+                db: opts.database,
+                username: Some(opts.username).filter(|username| !username.is_empty()),
+                password: Some(opts.password).filter(|password| !password.is_empty()),
+            },
+            connections: Default::default(),
+            slots: vec![],
         }
     }
 
-    pub fn set<K: ToRedisArgs, V: ToRedisArgs>(
+    pub async fn set<K: ToRedisArgs, V: ToRedisArgs>(
         &mut self,
         key: &K,
         value: &V,
     ) -> Result<(), RedisException> {
-        self.connect()?;
-        let conn = self.inner.as_mut().unwrap();
-        conn.set(key, value)
-            .map_err(|err| RedisException::SetValueFailed(format!("{}", err)))
+        self.query(redis::cmd("SET").arg(key).arg(value), key)
+            .await
+            .map_err(|err| RedisException::SetValueFailed(err))
     }
 
-    pub fn set_multiple<K: ToRedisArgs, V: ToRedisArgs>(
+    /// MSET can not write keys of different slots in a cluster, so keys are written by SET in pipelines
+    pub async fn set_multiple<K: ToRedisArgs, V: ToRedisArgs>(
         &mut self,
         items: &[(&K, &V)],
     ) -> Result<(), RedisException> {
-        self.connect()?;
+        let commands = items
+            .iter()
+            .map(|(key, value)| {
+                (
+                    to_key(*key),
+                    redis::cmd("SET").arg(*key).arg(*value).to_owned(),
+                )
+            })
+            .collect::<Vec<_>>();
+        self.pipeline(commands)
+            .await
+            .map_err(|err| RedisException::SetMultipleValueFailed(err))
+    }
 
-        let conn = self.inner.as_mut().unwrap();
-        conn.set_multiple(items)
-            .map_err(|err| RedisException::SetMultipleValueFailed(format!("{}", err)))
+    pub async fn get<K: ToRedisArgs>(&mut self, key: &K) -> Result<Vec<u8>, RedisException> {
+        self.query(redis::cmd("GET").arg(key), key)
+            .await
+            .map_err(|err| RedisException::GetValueFailed(err))
     }
 
-    pub fn get<K: ToRedisArgs>(&mut self, key: &K) -> Result<Vec<u8>, RedisException> {
-        self.connect()?;
-        let conn = self.inner.as_mut().unwrap();
-        conn.get(key)
-            .map_err(|err| RedisException::GetValueFailed(format!("{}", err)))
+    /// keys are deleted one by one because they may belong to different slots in a cluster
+    pub async fn del<K: ToRedisArgs>(&mut self, key: &K) -> Result<(), RedisException> {
+        let commands = key
+            .to_redis_args()
+            .into_iter()
+            .map(|key| (key.clone(), redis::cmd("DEL").arg(key).to_owned()))
+            .collect::<Vec<_>>();
+        self.pipeline(commands)
+            .await
+            .map_err(|err| RedisException::DelValueFailed(err))
     }
 
-    pub fn del<K: ToRedisArgs>(&mut self, key: &K) -> Result<(), RedisException> {
-        self.connect()?;
-        let conn = self.inner.as_mut().unwrap();
-        conn.del(key)
-            .map_err(|err| RedisException::DelValueFailed(format!("{}", err)))
+    /// Execute commands in pipelines. Commands of the same key are executed in order.
+    pub async fn execute(&mut self, commands: &[RedisCommand]) -> Result<(), RedisException> {
+        let commands = commands
+            .iter()
+            .map(|command| (to_key(command.key()), command.to_cmd()))
+            .collect::<Vec<_>>();
+        self.pipeline(commands)
+            .await
+            .map_err(|err| RedisException::ExecuteCommandsFailed(err))
     }
-}
 
-pub fn to_connection_info(conf: &RedisDesc) -> ConnectionInfo {
-    let opts = conf.connection_opts.as_ref().unwrap();
-    let db = opts.database;
-    let split: Vec<_> = opts.host.split(":").collect();
-    let (host, port) = if split.len() < 2 {
-        if split.len() == 1 {
-            (split[0].to_string(), REDIS_PORT)
-        } else {
-            ("localhost".to_string(), REDIS_PORT)
+    /// Create a consumer group of the stream starting from `start_id`. The stream will be created if it does not exist.
+    /// It's ok if the group exists already.
+    pub async fn create_group(
+        &mut self,
+        key: &str,
+        group: &str,
+        start_id: &str,
+    ) -> Result<(), RedisException> {
+        let result = self
+            .query::<_, ()>(
+                redis::cmd("XGROUP")
+                    .arg("CREATE")
+                    .arg(key)
+                    .arg(group)
+                    .arg(start_id)
+                    .arg("MKSTREAM"),
+                &key,
+            )
+            .await;
+        match result {
+            Err(err) if !err.starts_with("BUSYGROUP") => {
+                Err(RedisException::CreateGroupFailed(err))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Read entries of the stream by XREADGROUP.
+    /// `id` is `>` for new entries, or `0` for entries which are delivered to this consumer but not acked yet.
+    pub async fn read_group(
+        &mut self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        id: &str,
+        count: usize,
+        block_millis: u64,
+    ) -> Result<Vec<StreamEntry>, RedisException> {
+        let mut cmd = redis::cmd("XREADGROUP");
+        cmd.arg("GROUP")
+            .arg(group)
+            .arg(consumer)
+            .arg("COUNT")
+            .arg(count);
+        if id == ">" {
+            cmd.arg("BLOCK").arg(block_millis);
         }
-    } else {
-        (
-            split[0].to_string(),
-            u16::from_str_radix(split[1], 10).expect("invalid redis port"),
+        cmd.arg("STREAMS").arg(key).arg(id);
+
+        // the reply is [[key, entries]], or nil if it's timeout
+        self.query::<_, redis::Value>(&cmd, &key)
+            .await
+            .map(|value| match value {
+                redis::Value::Bulk(streams) => streams
+                    .iter()
+                    .flat_map(|stream| match stream {
+                        redis::Value::Bulk(items) if items.len() == 2 => {
+                            parse_stream_entries(&items[1])
+                        }
+                        _ => vec![],
+                    })
+                    .collect(),
+                _ => vec![],
+            })
+            .map_err(|err| RedisException::ReadStreamFailed(err))
+    }
+
+    /// Claim entries pending longer than `min_idle_millis` in other consumers by XAUTOCLAIM.
+    /// It returns the cursor of the next claim, which is `0-0` if all pending entries have been scanned.
+    pub async fn auto_claim(
+        &mut self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        min_idle_millis: u64,
+        cursor: &str,
+        count: usize,
+    ) -> Result<(String, Vec<StreamEntry>), RedisException> {
+        // the reply is [cursor, entries] (and deleted ids since Redis 7.0)
+        self.query::<_, redis::Value>(
+            redis::cmd("XAUTOCLAIM")
+                .arg(key)
+                .arg(group)
+                .arg(consumer)
+                .arg(min_idle_millis)
+                .arg(cursor)
+                .arg("COUNT")
+                .arg(count),
+            &key,
         )
-    };
-    let (addr, opt) = if opts.tls {
-        (
-            ConnectionAddr::TcpTls {
-                host,
-                port,
-                insecure: false,
-            },
-            RedisConnectionInfo {
-                db,
-                username: if opts.username.is_empty() {
-                    Some(opts.username.clone())
-                } else {
-                    None
-                },
-                password: Some(opts.password.clone()),
+        .await
+        .map(|value| match value {
+            redis::Value::Bulk(items) if items.len() >= 2 => (
+                String::from_redis_value(&items[0]).unwrap_or_default(),
+                parse_stream_entries(&items[1]),
+            ),
+            _ => (STREAM_START_ID.to_string(), vec![]),
+        })
+        .map_err(|err| RedisException::ReadStreamFailed(err))
+    }
+
+    /// Acknowledge processed entries by XACK
+    pub async fn ack(
+        &mut self,
+        key: &str,
+        group: &str,
+        ids: &[String],
+    ) -> Result<(), RedisException> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.query(redis::cmd("XACK").arg(key).arg(group).arg(ids), &key)
+            .await
+            .map_err(|err| RedisException::AckStreamFailed(err))
+    }
+
+    async fn query<K: ToRedisArgs, T: FromRedisValue>(
+        &mut self,
+        cmd: &redis::Cmd,
+        key: &K,
+    ) -> Result<T, String> {
+        let node = self.get_node(&to_key(key)).await?;
+        let mut conn = self.get_connection(&node).await?;
+        match cmd.query_async(&mut conn).await {
+            Ok(value) => Ok(value),
+            Err(err) => match redirect_node(&node, &err) {
+                Some(target) => {
+                    let ask = err.kind() == redis::ErrorKind::Ask;
+                    self.handle_error(&node, err);
+                    self.query_redirected(cmd, &target, ask).await
+                }
+                None => Err(self.handle_error(&node, err)),
             },
+        }
+    }
+
+    /// retry a command once against the node in a MOVED or ASK reply.
+    /// `ASKING` is pipelined before the command for ASK, so that the node serves the slot being imported
+    async fn query_redirected<T: FromRedisValue>(
+        &mut self,
+        cmd: &redis::Cmd,
+        node: &NodeAddr,
+        ask: bool,
+    ) -> Result<T, String> {
+        let mut conn = self.get_connection(node).await?;
+        let result = if ask {
+            redis::pipe()
+                .cmd("ASKING")
+                .ignore()
+                .add_command(cmd.clone())
+                .query_async::<_, (T,)>(&mut conn)
+                .await
+                .map(|(value,)| value)
+        } else {
+            cmd.query_async(&mut conn).await
+        };
+        result.map_err(|err| self.handle_error(node, err))
+    }
+
+    async fn pipeline(&mut self, commands: Vec<(Vec<u8>, redis::Cmd)>) -> Result<(), String> {
+        if commands.is_empty() {
+            return Ok(());
+        }
+
+        let mut pipelines = BTreeMap::<NodeAddr, redis::Pipeline>::new();
+        for (key, cmd) in commands {
+            let node = self.get_node(&key).await?;
+            pipelines
+                .entry(node)
+                .or_insert_with(redis::pipe)
+                .add_command(cmd)
+                .ignore();
+        }
+
+        let mut requests = vec![];
+        for (node, pipe) in pipelines {
+            let conn = self.get_connection(&node).await?;
+            requests.push(async move {
+                let mut conn = conn;
+                let result: redis::RedisResult<()> = pipe.query_async(&mut conn).await;
+                (node, result)
+            });
+        }
+
+        let mut result = Ok(());
+        for (node, node_result) in futures_util::future::join_all(requests).await {
+            if let Err(err) = node_result {
+                result = Err(self.handle_error(&node, err));
+            }
+        }
+        result
+    }
+
+    /// drop the broken connection and the stale slots, so that they will be reloaded by the next request
+    fn handle_error(&mut self, node: &NodeAddr, err: redis::RedisError) -> String {
+        if err.is_io_error() || err.is_connection_dropped() || err.is_connection_refusal() {
+            self.connections.remove(node);
+            self.slots.clear();
+        }
+        if err.redirect_node().is_some() {
+            self.slots.clear();
+        }
+        format!("{}", err)
+    }
+
+    async fn get_node(&mut self, key: &[u8]) -> Result<NodeAddr, String> {
+        if !self.cluster {
+            return self
+                .seeds
+                .first()
+                .cloned()
+                .ok_or_else(|| "no redis host".to_string());
+        }
+
+        if self.slots.is_empty() {
+            self.load_slots().await?;
+        }
+        let slot = get_slot(key);
+        self.slots
+            .iter()
+            .find(|(start, end, _)| *start <= slot && slot <= *end)
+            .map(|(_, _, node)| node.clone())
+            .ok_or_else(|| format!("slot {} is not served by any node", slot))
+    }
+
+    async fn load_slots(&mut self) -> Result<(), String> {
+        let mut err = "no redis host".to_string();
+        for seed in self.seeds.clone() {
+            let result = match self.get_connection(&seed).await {
+                Ok(mut conn) => redis::cmd("CLUSTER")
+                    .arg("SLOTS")
+                    .query_async::<_, redis::Value>(&mut conn)
+                    .await
+                    .map_err(|e| self.handle_error(&seed, e)),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(value) => {
+                    self.slots = parse_slots(&value, &seed);
+                    return Ok(());
+                }
+                Err(e) => err = e,
+            }
+        }
+        Err(err)
+    }
+
+    async fn get_connection(&mut self, node: &NodeAddr) -> Result<MultiplexedConnection, String> {
+        match self.connections.get(node) {
+            Some(conn) => Ok(conn.clone()),
+            None => {
+                let conn = self.connect(node).await.map_err(|err| {
+                    format!("connect to {}:{} failed: {}", node.host, node.port, err)
+                })?;
+                self.connections.insert(node.clone(), conn.clone());
+                Ok(conn)
+            }
+        }
+    }
+
+    async fn connect(&self, node: &NodeAddr) -> Result<MultiplexedConnection, String> {
+        let stream = tokio::time::timeout(
+            CONNECT_TIMEOUT,
+            TcpStream::connect((node.host.as_str(), node.port)),
         )
-    } else {
-        (
-            ConnectionAddr::Tcp(host, port),
-            RedisConnectionInfo {
-                db,
-                username: None,
-                password: None,
-            },
+        .await
+        .map_err(|err| format!("{}", err))?
+        .map_err(|err| format!("{}", err))?;
+
+        // the driver reads replies from the socket and completes requests, it stops when the connection is closed
+        if self.tls {
+            let server_name =
+                ServerName::try_from(node.host.as_str()).map_err(|err| format!("{}", err))?;
+            let stream = tls_connector()
+                .connect(server_name, stream)
+                .await
+                .map_err(|err| format!("{}", err))?;
+            let (conn, driver) = MultiplexedConnection::new(&self.redis_info, stream)
+                .await
+                .map_err(|err| format!("{}", err))?;
+            tokio::spawn(driver);
+            Ok(conn)
+        } else {
+            let (conn, driver) = MultiplexedConnection::new(&self.redis_info, stream)
+                .await
+                .map_err(|err| format!("{}", err))?;
+            tokio::spawn(driver);
+            Ok(conn)
+        }
+    }
+}
+
+/// The smallest id of stream entries
+pub const STREAM_START_ID: &str = "0-0";
+
+/// An entry of Redis Streams. The value is an object whose fields are the fields of the entry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamEntry {
+    pub id: String,
+    pub value: TypedValue,
+}
+
+/// Parse entries like `[[id, [field1, value1, field2, value2, ...]], ...]`. Values of fields are decoded as strings.
+/// Entries which have been deleted have nil fields, and they will be skipped.
+fn parse_stream_entries(value: &redis::Value) -> Vec<StreamEntry> {
+    match value {
+        redis::Value::Bulk(entries) => entries
+            .iter()
+            .filter_map(|entry| match entry {
+                redis::Value::Bulk(items) if items.len() == 2 => {
+                    let id = String::from_redis_value(&items[0]).ok()?;
+                    let fields = match &items[1] {
+                        redis::Value::Bulk(fields) => fields
+                            .chunks(2)
+                            .filter(|pair| pair.len() == 2)
+                            .filter_map(|pair| {
+                                Some((
+                                    String::from_redis_value(&pair[0]).ok()?,
+                                    TypedValue::String(String::from_redis_value(&pair[1]).ok()?),
+                                ))
+                            })
+                            .collect(),
+                        _ => return None,
+                    };
+                    Some(StreamEntry {
+                        id,
+                        value: TypedValue::Object(fields),
+                    })
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Write commands supported by Redis sink
+#[derive(Clone, Debug, PartialEq)]
+pub enum RedisCommand {
+    Set {
+        key: TypedValue,
+        value: TypedValue,
+    },
+    Del {
+        key: TypedValue,
+    },
+    HSet {
+        key: TypedValue,
+        field: TypedValue,
+        value: TypedValue,
+    },
+    HDel {
+        key: TypedValue,
+        field: TypedValue,
+    },
+    LPush {
+        key: TypedValue,
+        value: TypedValue,
+    },
+    RPush {
+        key: TypedValue,
+        value: TypedValue,
+    },
+    SAdd {
+        key: TypedValue,
+        member: TypedValue,
+    },
+    SRem {
+        key: TypedValue,
+        member: TypedValue,
+    },
+    ZAdd {
+        key: TypedValue,
+        member: TypedValue,
+        score: f64,
+    },
+    ZRem {
+        key: TypedValue,
+        member: TypedValue,
+    },
+    /// fields of an object value are the fields of the stream entry. Other values are written in a single field `value`
+    XAdd {
+        key: TypedValue,
+        value: TypedValue,
+    },
+    IncrBy {
+        key: TypedValue,
+        delta: i64,
+    },
+    PExpire {
+        key: TypedValue,
+        millis: u64,
+    },
+}
+
+impl RedisCommand {
+    fn key(&self) -> &TypedValue {
+        match self {
+            Self::Set { key, .. }
+            | Self::Del { key }
+            | Self::HSet { key, .. }
+            | Self::HDel { key, .. }
+            | Self::LPush { key, .. }
+            | Self::RPush { key, .. }
+            | Self::SAdd { key, .. }
+            | Self::SRem { key, .. }
+            | Self::ZAdd { key, .. }
+            | Self::ZRem { key, .. }
+            | Self::XAdd { key, .. }
+            | Self::IncrBy { key, .. }
+            | Self::PExpire { key, .. } => key,
+        }
+    }
+
+    fn to_cmd(&self) -> redis::Cmd {
+        match self {
+            Self::Set { key, value } => redis::cmd("SET").arg(key).arg(value).to_owned(),
+            Self::Del { key } => redis::cmd("DEL").arg(key).to_owned(),
+            Self::HSet { key, field, value } => {
+                redis::cmd("HSET").arg(key).arg(field).arg(value).to_owned()
+            }
+            Self::HDel { key, field } => redis::cmd("HDEL").arg(key).arg(field).to_owned(),
+            Self::LPush { key, value } => redis::cmd("LPUSH").arg(key).arg(value).to_owned(),
+            Self::RPush { key, value } => redis::cmd("RPUSH").arg(key).arg(value).to_owned(),
+            Self::SAdd { key, member } => redis::cmd("SADD").arg(key).arg(member).to_owned(),
+            Self::SRem { key, member } => redis::cmd("SREM").arg(key).arg(member).to_owned(),
+            Self::ZAdd { key, member, score } => redis::cmd("ZADD")
+                .arg(key)
+                .arg(score)
+                .arg(member)
+                .to_owned(),
+            Self::ZRem { key, member } => redis::cmd("ZREM").arg(key).arg(member).to_owned(),
+            Self::XAdd { key, value } => {
+                let mut cmd = redis::cmd("XADD");
+                cmd.arg(key).arg("*");
+                match value {
+                    TypedValue::Object(fields) => fields.iter().for_each(|(field, value)| {
+                        cmd.arg(field).arg(value);
+                    }),
+                    _ => {
+                        cmd.arg("value").arg(value);
+                    }
+                }
+                cmd
+            }
+            Self::IncrBy { key, delta } => redis::cmd("INCRBY").arg(key).arg(delta).to_owned(),
+            Self::PExpire { key, millis } => redis::cmd("PEXPIRE").arg(key).arg(millis).to_owned(),
+        }
+    }
+}
+
+fn tls_connector() -> TlsConnector {
+    let mut root_store = RootCertStore::empty();
+    root_store.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            anchor.subject,
+            anchor.spki,
+            anchor.name_constraints,
         )
+    }));
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_store)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(config))
+}
+
+/// parse nodes separated by comma. The port is 6379 by default.
+/// Hosts are validated by [RedisDesc] before deployment, a node with an invalid port is skipped here
+fn parse_nodes(hosts: &str) -> Vec<NodeAddr> {
+    hosts
+        .split(',')
+        .map(|host| host.trim())
+        .filter(|host| !host.is_empty())
+        .filter_map(|host| match host.rsplit_once(':') {
+            Some((addr, port)) => match port.parse() {
+                Ok(port) => Some(NodeAddr {
+                    host: addr.to_string(),
+                    port,
+                }),
+                Err(err) => {
+                    tracing::error!("invalid port of redis host {}: {}", host, err);
+                    None
+                }
+            },
+            None => Some(NodeAddr {
+                host: host.to_string(),
+                port: REDIS_PORT,
+            }),
+        })
+        .collect()
+}
+
+/// the node in a MOVED or ASK reply. An empty host means the host of the replier
+fn redirect_node(replier: &NodeAddr, err: &redis::RedisError) -> Option<NodeAddr> {
+    let (addr, _) = err.redirect_node()?;
+    let (host, port) = addr.rsplit_once(':')?;
+    Some(NodeAddr {
+        host: if host.is_empty() {
+            replier.host.clone()
+        } else {
+            host.to_string()
+        },
+        port: port.parse().ok()?,
+    })
+}
+
+fn to_key<K: ToRedisArgs>(key: &K) -> Vec<u8> {
+    key.to_redis_args().into_iter().next().unwrap_or_default()
+}
+
+/// Get the slot of a key in a cluster. Only the hash tag, the substring between the first `{` and the following `}`, is hashed if it exists.
+fn get_slot(key: &[u8]) -> u16 {
+    let hash_tag = key
+        .iter()
+        .position(|c| *c == b'{')
+        .and_then(|start| {
+            key[start + 1..]
+                .iter()
+                .position(|c| *c == b'}')
+                .map(|len| &key[start + 1..start + 1 + len])
+        })
+        .filter(|tag| !tag.is_empty());
+
+    crc16(hash_tag.unwrap_or(key)) % REDIS_CLUSTER_SLOTS
+}
+
+/// CRC16 of XMODEM, which is used by Redis Cluster
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, byte| {
+        (0..8).fold(crc ^ ((*byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// Parse the reply of `CLUSTER SLOTS`, each element of which is `[start, end, [host, port, id], replicas...]`.
+/// An empty host means the node has the same host as the one replying.
+fn parse_slots(value: &redis::Value, replier: &NodeAddr) -> Vec<(u16, u16, NodeAddr)> {
+    let as_int = |value: &redis::Value| match value {
+        redis::Value::Int(v) => Some(*v),
+        _ => None,
     };
-    ConnectionInfo { addr, redis: opt }
+    match value {
+        redis::Value::Bulk(ranges) => ranges
+            .iter()
+            .filter_map(|range| match range {
+                redis::Value::Bulk(items) if items.len() >= 3 => {
+                    let start = as_int(&items[0])?;
+                    let end = as_int(&items[1])?;
+                    let node = match &items[2] {
+                        redis::Value::Bulk(node) if node.len() >= 2 => {
+                            let host = match &node[0] {
+                                redis::Value::Data(host) if !host.is_empty() => {
+                                    String::from_utf8_lossy(host).to_string()
+                                }
+                                _ => replier.host.clone(),
+                            };
+                            NodeAddr {
+                                host,
+                                port: as_int(&node[1])? as u16,
+                            }
+                        }
+                        _ => return None,
+                    };
+                    Some((start as u16, end as u16, node))
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        get_slot, parse_nodes, parse_slots, parse_stream_entries, redirect_node, NodeAddr,
+        StreamEntry,
+    };
+
+    #[test]
+    fn test_get_slot() {
+        assert_eq!(get_slot(b"123456789"), 0x31C3);
+        assert_eq!(get_slot(b"foo"), 12182);
+        assert_eq!(get_slot(b"{user1000}.following"), get_slot(b"user1000"));
+        assert_eq!(get_slot(b"foo{}{bar}"), get_slot(b"foo{}{bar}"));
+        assert_ne!(get_slot(b"foo{}{bar}"), get_slot(b"bar"));
+    }
+
+    #[test]
+    fn test_parse_nodes() {
+        assert_eq!(
+            parse_nodes("localhost, 127.0.0.1:7000"),
+            vec![
+                NodeAddr {
+                    host: "localhost".to_string(),
+                    port: 6379
+                },
+                NodeAddr {
+                    host: "127.0.0.1".to_string(),
+                    port: 7000
+                }
+            ]
+        );
+        assert_eq!(
+            parse_nodes("localhost:port, 127.0.0.1:7000"),
+            vec![NodeAddr {
+                host: "127.0.0.1".to_string(),
+                port: 7000
+            }]
+        );
+    }
+
+    #[test]
+    fn test_redirect_node() {
+        let replier = NodeAddr {
+            host: "10.0.0.1".to_string(),
+            port: 7000,
+        };
+        let moved = redis::RedisError::from((
+            redis::ErrorKind::Moved,
+            "An error was signalled by the server",
+            "3999 10.0.0.2:7001".to_string(),
+        ));
+        assert_eq!(
+            redirect_node(&replier, &moved),
+            Some(NodeAddr {
+                host: "10.0.0.2".to_string(),
+                port: 7001
+            })
+        );
+        let ask = redis::RedisError::from((
+            redis::ErrorKind::Ask,
+            "An error was signalled by the server",
+            "3999 :7002".to_string(),
+        ));
+        assert_eq!(
+            redirect_node(&replier, &ask),
+            Some(NodeAddr {
+                host: "10.0.0.1".to_string(),
+                port: 7002
+            })
+        );
+        let other = redis::RedisError::from((redis::ErrorKind::IoError, "broken pipe"));
+        assert_eq!(redirect_node(&replier, &other), None);
+    }
+
+    #[test]
+    fn test_parse_slots() {
+        use redis::Value;
+
+        let replier = NodeAddr {
+            host: "10.0.0.1".to_string(),
+            port: 7000,
+        };
+        let value = Value::Bulk(vec![
+            Value::Bulk(vec![
+                Value::Int(0),
+                Value::Int(8191),
+                Value::Bulk(vec![
+                    Value::Data(b"".to_vec()),
+                    Value::Int(7000),
+                    Value::Data(b"id1".to_vec()),
+                ]),
+            ]),
+            Value::Bulk(vec![
+                Value::Int(8192),
+                Value::Int(16383),
+                Value::Bulk(vec![
+                    Value::Data(b"10.0.0.2".to_vec()),
+                    Value::Int(7001),
+                    Value::Data(b"id2".to_vec()),
+                ]),
+                Value::Bulk(vec![
+                    Value::Data(b"10.0.0.3".to_vec()),
+                    Value::Int(7002),
+                    Value::Data(b"id3".to_vec()),
+                ]),
+            ]),
+        ]);
+
+        assert_eq!(
+            parse_slots(&value, &replier),
+            vec![
+                (0, 8191, replier.clone()),
+                (
+                    8192,
+                    16383,
+                    NodeAddr {
+                        host: "10.0.0.2".to_string(),
+                        port: 7001
+                    }
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_stream_entries() {
+        use std::collections::BTreeMap;
+
+        use redis::Value;
+
+        use crate::types::TypedValue;
+
+        let value = Value::Bulk(vec![
+            Value::Bulk(vec![
+                Value::Data(b"1672502400000-0".to_vec()),
+                Value::Bulk(vec![
+                    Value::Data(b"name".to_vec()),
+                    Value::Data(b"jason".to_vec()),
+                    Value::Data(b"age".to_vec()),
+                    Value::Data(b"25".to_vec()),
+                ]),
+            ]),
+            // deleted entry
+            Value::Bulk(vec![Value::Data(b"1672502400000-1".to_vec()), Value::Nil]),
+        ]);
+
+        assert_eq!(
+            parse_stream_entries(&value),
+            vec![StreamEntry {
+                id: "1672502400000-0".to_string(),
+                value: TypedValue::Object(BTreeMap::from([
+                    ("name".to_string(), TypedValue::String("jason".to_string())),
+                    ("age".to_string(), TypedValue::String("25".to_string())),
+                ]))
+            }]
+        );
+        assert_eq!(parse_stream_entries(&Value::Nil), vec![]);
+    }
 }
@@ -13,3 +13,109 @@ impl<T> TestKit<T> {
 
     pub async fn run_async(&self) {}
 }
+
+/// A mock HTTP server for tests. It responds requests with `statuses` in order, and with 200 after they are used up.
+/// Bodies of all received requests are recorded.
+pub struct MockHttpServer {
+    addr: std::net::SocketAddr,
+    bodies: std::sync::Arc<std::sync::Mutex<Vec<Vec<u8>>>>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl MockHttpServer {
+    pub async fn start(statuses: Vec<u16>) -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock http server failed");
+        let addr = listener.local_addr().expect("get local address failed");
+        let bodies = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let statuses = std::sync::Arc::new(std::sync::Mutex::new(
+            std::collections::VecDeque::from(statuses),
+        ));
+
+        let received = bodies.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_mock_http_connection(
+                    stream,
+                    received.clone(),
+                    statuses.clone(),
+                ));
+            }
+        });
+
+        Self {
+            addr,
+            bodies,
+            handle,
+        }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    pub fn received_bodies(&self) -> Vec<Vec<u8>> {
+        self.bodies
+            .lock()
+            .map(|bodies| bodies.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for MockHttpServer {
+    fn drop(&mut self) {
+        self.handle.abort()
+    }
+}
+
+async fn serve_mock_http_connection(
+    mut stream: tokio::net::TcpStream,
+    bodies: std::sync::Arc<std::sync::Mutex<Vec<Vec<u8>>>>,
+    statuses: std::sync::Arc<std::sync::Mutex<std::collections::VecDeque<u16>>>,
+) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut buf = vec![];
+    loop {
+        // read the head of a request
+        let head_end = loop {
+            if let Some(pos) = buf.windows(4).position(|window| window == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            let mut chunk = [0u8; 1024];
+            match stream.read(&mut chunk).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            }
+        };
+        let content_length = String::from_utf8_lossy(&buf[..head_end])
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+            .unwrap_or_default();
+        while buf.len() < head_end + content_length {
+            let mut chunk = [0u8; 1024];
+            match stream.read(&mut chunk).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            }
+        }
+
+        let body = buf[head_end..head_end + content_length].to_vec();
+        buf.drain(..head_end + content_length);
+        if let Ok(mut bodies) = bodies.lock() {
+            bodies.push(body);
+        }
+        let status = statuses
+            .lock()
+            .ok()
+            .and_then(|mut statuses| statuses.pop_front())
+            .unwrap_or(200);
+        let response = format!("HTTP/1.1 {} Mock\r\ncontent-length: 0\r\n\r\n", status);
+        if stream.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
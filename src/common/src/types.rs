@@ -95,7 +95,9 @@ impl Ord for TypedValue {
 /// - set
 /// - zset
 /// However, in redis, the commands of data in these data structures is distinct.
-/// For now, Lightflus only support SET command, which means any value will be serialized as string and each key will be rewrite after call SET cmd
+/// Lightflus sink writes values by the command configured in [proto::common::RedisDesc], see [crate::redis::RedisCommand].
+/// A string is written as its bytes, a bigint or a number is written as its big-endian bytes and other values are written as their string format.
+/// Scores of ZADD and increments of INCRBY are written as decimal numbers instead
 impl ToRedisArgs for TypedValue {
     fn write_redis_args<W>(&self, out: &mut W)
     where
@@ -55,6 +55,105 @@ pub mod times {
     }
 }
 
+/// Keyed events and keyed state of an operator are partitioned into a fixed number of key groups.
+/// Each parallel instance of the operator owns a contiguous range of key groups
+pub mod key_groups {
+    use std::{hash::Hasher, ops::Range};
+
+    use twox_hash::XxHash64;
+
+    /// the number of key groups of an operator, which is also the max parallelism of it
+    pub const KEY_GROUPS: u32 = 128;
+
+    const HASH_SEED: u64 = 0;
+
+    /// XXH64 of the bytes with a constant seed. Keyed state is stored by key group,
+    /// so unlike [std::collections::hash_map::DefaultHasher], it must not change across nodes or releases
+    pub fn hash(bytes: &[u8]) -> u64 {
+        let mut hasher = XxHash64::with_seed(HASH_SEED);
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    pub fn key_group(key: &[u8]) -> u32 {
+        (hash(key) % KEY_GROUPS as u64) as u32
+    }
+
+    /// the index of the instance which owns the key group
+    pub fn instance_of(key_group: u32, parallelism: u32) -> u32 {
+        key_group * parallelism.max(1) / KEY_GROUPS
+    }
+
+    /// the key groups which the instance owns
+    pub fn range_of(index: u32, parallelism: u32) -> Range<u32> {
+        let parallelism = parallelism.max(1);
+        let start = (index * KEY_GROUPS + parallelism - 1) / parallelism;
+        let end = ((index + 1) * KEY_GROUPS + parallelism - 1) / parallelism;
+        start.min(KEY_GROUPS)..end.min(KEY_GROUPS)
+    }
+
+    /// The key groups whose owners change if the instances placed at `from` are rescaled to the ones placed at `to`.
+    /// The owner of each instance is given by its index, and the key groups are grouped by the old and new owners
+    pub fn reassign<T: PartialEq + Clone>(from: &[T], to: &[T]) -> Vec<(T, T, Vec<u32>)> {
+        let mut reassigned: Vec<(T, T, Vec<u32>)> = vec![];
+        if from.is_empty() || to.is_empty() {
+            return reassigned;
+        }
+        (0..KEY_GROUPS).for_each(|key_group| {
+            let old = &from[instance_of(key_group, from.len() as u32) as usize];
+            let new = &to[instance_of(key_group, to.len() as u32) as usize];
+            if old == new {
+                return;
+            }
+            match reassigned
+                .iter_mut()
+                .find(|(old_owner, new_owner, _)| old_owner == old && new_owner == new)
+            {
+                Some((_, _, key_groups)) => key_groups.push(key_group),
+                None => reassigned.push((old.clone(), new.clone(), vec![key_group])),
+            }
+        });
+        reassigned
+    }
+}
+
+/// Tokens of HTTP sources are redacted to their SHA-256 digests before a dataflow is returned to clients,
+/// and a token sent by a client is verified against either the plain token or its digest in constant time
+pub mod tokens {
+    use sha2::{Digest, Sha256};
+    use subtle::ConstantTimeEq;
+
+    const REDACTED_PREFIX: &str = "sha256:";
+
+    /// the digest of the token. An empty token and a redacted token are returned as they are
+    pub fn redact(token: &str) -> String {
+        if token.is_empty() || token.starts_with(REDACTED_PREFIX) {
+            return token.to_string();
+        }
+        format!(
+            "{}{}",
+            REDACTED_PREFIX,
+            to_hex(&Sha256::digest(token.as_bytes()))
+        )
+    }
+
+    /// whether `token` matches the `expected` token, which may be redacted by [redact]
+    pub fn verify(expected: &str, token: &str) -> bool {
+        let (expected, token) = match expected.strip_prefix(REDACTED_PREFIX) {
+            Some(digest) => (
+                digest.to_string(),
+                to_hex(&Sha256::digest(token.as_bytes())),
+            ),
+            None => (expected.to_string(), token.to_string()),
+        };
+        expected.as_bytes().ct_eq(token.as_bytes()).into()
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
 pub mod results {
     pub fn match_process_result<F0: Fn(E) -> T1, F1: Fn(T) -> T1, T, E, T1>(
         result: Result<T, E>,
@@ -258,6 +357,90 @@ mod tests {
         common_impl::DataflowValidateError,
     };
 
+    #[test]
+    fn test_key_group_is_stable() {
+        use super::key_groups::{hash, key_group};
+
+        // reference values of XXH64 with seed 0
+        assert_eq!(hash(b""), 0xef46db3751d8e999);
+        assert_eq!(hash(b"abc"), 0x44bc2cf5ad770999);
+
+        assert_eq!(key_group(b""), 25);
+        assert_eq!(key_group(b"abc"), 25);
+        assert_eq!(key_group(b"lightflus"), 83);
+    }
+
+    #[test]
+    fn test_key_group_ranges() {
+        use super::key_groups::{instance_of, range_of, KEY_GROUPS};
+
+        for parallelism in [1, 3, 7, KEY_GROUPS] {
+            let mut next = 0;
+            for index in 0..parallelism {
+                let range = range_of(index, parallelism);
+                assert_eq!(range.start, next);
+                assert!(!range.is_empty());
+                range
+                    .clone()
+                    .for_each(|key_group| assert_eq!(instance_of(key_group, parallelism), index));
+                next = range.end;
+            }
+            assert_eq!(next, KEY_GROUPS);
+        }
+    }
+
+    #[test]
+    fn test_tokens() {
+        use super::tokens::{redact, verify};
+
+        let redacted = redact("secret");
+        assert_eq!(
+            redacted,
+            "sha256:2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b"
+        );
+        assert_eq!(redact(&redacted), redacted);
+        assert_eq!(redact(""), "");
+
+        assert!(verify("secret", "secret"));
+        assert!(!verify("secret", "secrets"));
+        assert!(verify(&redacted, "secret"));
+        assert!(!verify(&redacted, "Secret"));
+        assert!(!verify(&redacted, &redacted));
+    }
+
+    #[test]
+    fn test_key_group_reassign() {
+        use super::key_groups::{range_of, reassign, KEY_GROUPS};
+
+        // instances on the same owners keep their key groups
+        assert!(reassign(&["a", "b"], &["a", "b"]).is_empty());
+        assert!(reassign(&["a", "a"], &["a"]).is_empty());
+
+        let moves = reassign(&["a"], &["a", "b"]);
+        assert_eq!(moves, vec![("a", "b", range_of(1, 2).collect())]);
+
+        // every key group which changes its owner is moved once
+        let moves = reassign(&["a", "b", "c"], &["b", "c"]);
+        let mut moved = moves
+            .iter()
+            .flat_map(|(from, to, key_groups)| {
+                assert_ne!(from, to);
+                key_groups.iter().copied()
+            })
+            .collect::<Vec<_>>();
+        moved.sort();
+        moved.dedup();
+        assert_eq!(
+            moved.len(),
+            moves
+                .iter()
+                .map(|(_, _, key_groups)| key_groups.len())
+                .sum::<usize>()
+        );
+        assert!(moved.iter().all(|key_group| *key_group < KEY_GROUPS));
+        assert!(moves.iter().any(|(from, to, _)| *from == "a" && *to == "b"));
+    }
+
     #[test]
     fn test_process_arg_success() {
         use std::collections::HashMap;
@@ -458,6 +641,130 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_dataflow_unparallel_source() {
+        use proto::common::{
+            source, Dataflow, DataflowMeta, GeneratorDesc, KafkaDesc, OperatorInfo, Source,
+        };
+
+        let source = |desc, parallelism| OperatorInfo {
+            operator_id: 0,
+            parallelism,
+            details: Some(Details::Source(Source { desc: Some(desc) })),
+            ..Default::default()
+        };
+        let mut dataflow = Dataflow {
+            job_id: Some(ResourceId::default()),
+            meta: vec![DataflowMeta {
+                center: 0,
+                neighbors: vec![1],
+            }],
+            nodes: vec![
+                (
+                    0,
+                    source(source::Desc::Generator(GeneratorDesc::default()), 1),
+                ),
+                (
+                    1,
+                    OperatorInfo {
+                        operator_id: 1,
+                        upstreams: vec![0],
+                        details: Some(Details::Filter(Default::default())),
+                        ..Default::default()
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            execution_id: None,
+        };
+        assert!(dataflow.validate().is_ok());
+
+        // every instance would read all the generated events
+        dataflow.nodes.insert(
+            0,
+            source(source::Desc::Generator(GeneratorDesc::default()), 2),
+        );
+        match dataflow.validate() {
+            Err(DataflowValidateError::UnparallelSource(0)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // instances of a kafka source share the consumer group
+        dataflow.nodes.insert(
+            0,
+            source(
+                source::Desc::Kafka(KafkaDesc {
+                    brokers: vec!["localhost:9092".to_string()],
+                    topic: "topic".to_string(),
+                    data_type: proto::common::DataTypeEnum::Object as i32,
+                    ..Default::default()
+                }),
+                2,
+            ),
+        );
+        assert!(dataflow.validate().is_ok());
+    }
+
+    #[test]
+    fn test_dataflow_invalid_redis_host() {
+        use proto::common::{
+            redis_desc::{ConnectionOpts, StreamOpts},
+            source, Dataflow, DataflowMeta, OperatorInfo, RedisDesc, Source,
+        };
+
+        let dataflow = |host: &str| Dataflow {
+            job_id: Some(ResourceId::default()),
+            meta: vec![DataflowMeta {
+                center: 0,
+                neighbors: vec![1],
+            }],
+            nodes: vec![
+                (
+                    0,
+                    OperatorInfo {
+                        operator_id: 0,
+                        parallelism: 1,
+                        details: Some(Details::Source(Source {
+                            desc: Some(source::Desc::Redis(RedisDesc {
+                                connection_opts: Some(ConnectionOpts {
+                                    host: host.to_string(),
+                                    ..Default::default()
+                                }),
+                                stream_opts: Some(StreamOpts {
+                                    key: "stream".to_string(),
+                                    group: "group".to_string(),
+                                    ..Default::default()
+                                }),
+                                ..Default::default()
+                            })),
+                        })),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    1,
+                    OperatorInfo {
+                        operator_id: 1,
+                        upstreams: vec![0],
+                        details: Some(Details::Filter(Default::default())),
+                        ..Default::default()
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            execution_id: None,
+        };
+        assert!(dataflow("localhost, 127.0.0.1:7000").validate().is_ok());
+        match dataflow("localhost, 127.0.0.1:port").validate() {
+            Err(DataflowValidateError::InvalidRedisHost(host)) => {
+                assert_eq!(host, "127.0.0.1:port")
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
     #[test]
     fn test_serde_env() {
         let origin = "{\"name\":\"${your.name}\", \"card\": \"${your.card}\", \"info\": {\"address\": \"${your.addr}\", \"second_address\": \"${your.addr}\"}}";
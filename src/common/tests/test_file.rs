@@ -0,0 +1,44 @@
+use common::file::{list_files, RollingFileWriter};
+use proto::common::file_desc;
+
+#[tokio::test]
+pub async fn test_rolling_file_writer() {
+    let dir = std::env::temp_dir().join("lightflus-test-rolling-file-writer");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut writer = RollingFileWriter::new(
+        &dir,
+        "job",
+        "csv",
+        Some(b"id,name\n".to_vec()),
+        &file_desc::RotationOpts {
+            max_bytes: 20,
+            max_millis: 0,
+        },
+    );
+    for line in ["1,hello\n", "2,world\n", "3,lightflus\n"] {
+        let result = writer.write(line.as_bytes()).await;
+        assert!(result.is_ok());
+    }
+    let result = writer.close().await;
+    assert!(result.is_ok());
+
+    let files =
+        list_files(dir.join("job-*.csv").to_str().unwrap_or_default()).expect("list files failed");
+    assert_eq!(files.len(), 2);
+
+    let mut contents = files
+        .iter()
+        .map(|path| std::fs::read_to_string(path).expect("read file failed"))
+        .collect::<Vec<_>>();
+    contents.sort();
+    assert_eq!(
+        contents,
+        vec![
+            "id,name\n1,hello\n2,world\n".to_string(),
+            "id,name\n3,lightflus\n".to_string()
+        ]
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use common::{err::HttpException, http::HttpClient, testutils::MockHttpServer};
+use proto::common::{http_desc, HttpDesc};
+
+fn new_http_desc(url: String, max_retries: u32) -> HttpDesc {
+    HttpDesc {
+        token: Default::default(),
+        url,
+        method: http_desc::Method::Post as i32,
+        headers: HashMap::from([("X-Lightflus".to_string(), "test".to_string())]),
+        body_template: Default::default(),
+        body_extractor: None,
+        batch: None,
+        retry: Some(http_desc::RetryOpts {
+            max_retries,
+            initial_backoff_millis: 10,
+            max_backoff_millis: 100,
+        }),
+        concurrency: 0,
+        timeout_millis: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_http_client_retry() {
+    let server = MockHttpServer::start(vec![503, 500]).await;
+    let client = HttpClient::new(&new_http_desc(server.url("/webhook"), 2))
+        .expect("create http client failed");
+
+    let result = client.send(br#"{"word":"hello"}"#.to_vec()).await;
+    assert!(result.is_ok());
+    // two failed requests are retried
+    assert_eq!(
+        server.received_bodies(),
+        vec![br#"{"word":"hello"}"#.to_vec(); 3]
+    );
+}
+
+#[tokio::test]
+async fn test_http_client_failed() {
+    let server = MockHttpServer::start(vec![503, 503, 400]).await;
+    let client = HttpClient::new(&new_http_desc(server.url("/webhook"), 1))
+        .expect("create http client failed");
+
+    let result = client.send(b"1".to_vec()).await;
+    assert!(matches!(result, Err(HttpException::UnexpectedStatus(503))));
+    assert_eq!(server.received_bodies().len(), 2);
+
+    // 4xx is not retried
+    let result = client.send(b"2".to_vec()).await;
+    assert!(matches!(result, Err(HttpException::UnexpectedStatus(400))));
+    assert_eq!(server.received_bodies().len(), 3);
+}
@@ -1,4 +1,9 @@
-use common::{db::MysqlConn, types::TypedValue};
+use std::collections::BTreeMap;
+
+use common::{
+    db::{to_typed_value, MysqlConn},
+    types::TypedValue,
+};
 use proto::common::mysql_desc;
 use sqlx::Row;
 
@@ -61,3 +66,48 @@ async fn test_mysql_execute() {
     let result = conn.execute("drop table if exists person", vec![]).await;
     assert!(result.is_ok());
 }
+
+#[tokio::test]
+async fn test_mysql_row_to_typed_value() {
+    let conn_opts = mysql_desc::ConnectionOpts {
+        host: "localhost".to_string(),
+        username: "ci".to_string(),
+        password: "123".to_string(),
+        database: "ci".to_string(),
+    };
+
+    let mut conn = MysqlConn::from(conn_opts);
+
+    let result = conn.execute("create table if not exists product (id bigint NOT NULL AUTO_INCREMENT, name varchar(36), price double, on_sale boolean, remark varchar(36), updated_at datetime, PRIMARY KEY (id))", vec![]).await;
+    assert!(result.is_ok());
+
+    let result = conn.execute("insert into product (name,price,on_sale,remark,updated_at) values ('apple',1.5,true,null,'2023-01-01 00:00:00')", vec![]).await;
+    assert!(result.is_ok());
+
+    let mut rows = vec![];
+    let result = conn
+        .try_for_each("select * from product", vec![], |row| {
+            rows.push(to_typed_value(&row));
+            futures_util::future::ready(Ok(()))
+        })
+        .await;
+    assert!(result.is_ok());
+
+    assert_eq!(
+        rows,
+        vec![TypedValue::Object(BTreeMap::from([
+            ("id".to_string(), TypedValue::BigInt(1)),
+            ("name".to_string(), TypedValue::String("apple".to_string())),
+            ("price".to_string(), TypedValue::Number(1.5)),
+            ("on_sale".to_string(), TypedValue::Boolean(true)),
+            ("remark".to_string(), TypedValue::Null),
+            (
+                "updated_at".to_string(),
+                TypedValue::String("2023-01-01 00:00:00".to_string())
+            ),
+        ]))]
+    );
+
+    let result = conn.execute("drop table if exists product", vec![]).await;
+    assert!(result.is_ok());
+}
@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+use common::{file::list_files, parquet::PartitionedParquetWriter, types::TypedValue};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use proto::common::{parquet_desc, ParquetDesc};
+
+#[test]
+pub fn test_partitioned_parquet_writer() {
+    let dir = std::env::temp_dir().join("lightflus-test-partitioned-parquet-writer");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let conf = ParquetDesc {
+        path: dir.to_str().unwrap_or_default().to_string(),
+        columns: vec![
+            parquet_desc::Column {
+                name: "id".to_string(),
+                r#type: parquet_desc::column::Type::Int64 as i32,
+                required: true,
+            },
+            parquet_desc::Column {
+                name: "name".to_string(),
+                r#type: parquet_desc::column::Type::String as i32,
+                required: false,
+            },
+        ],
+        partitioning: Some(parquet_desc::Partitioning {
+            time_format: "dt=%Y-%m-%d".to_string(),
+            fields: vec!["region".to_string()],
+        }),
+        rotation: None,
+        row_group_size: 2,
+    };
+    let mut writer =
+        PartitionedParquetWriter::new(&dir, "job", &conf).expect("create writer failed");
+
+    let new_value = |id: Option<i64>, name: Option<&str>, region: &str| {
+        let mut object =
+            BTreeMap::from([("region".to_string(), TypedValue::String(region.to_string()))]);
+        id.iter().for_each(|id| {
+            object.insert("id".to_string(), TypedValue::BigInt(*id));
+        });
+        name.iter().for_each(|name| {
+            object.insert("name".to_string(), TypedValue::String(name.to_string()));
+        });
+        TypedValue::Object(object)
+    };
+    // 2026-10-18T03:04:05Z
+    let event_time = 1792292645000;
+    for value in [
+        new_value(Some(1), Some("hello"), "eu"),
+        new_value(Some(2), None, "eu"),
+        new_value(Some(3), Some("world"), "eu"),
+        new_value(Some(4), Some("lightflus"), "us"),
+        // id is required
+        new_value(None, Some("dropped"), "us"),
+    ] {
+        let result = writer.write(&value, event_time);
+        assert!(result.is_ok());
+    }
+
+    // files are not visible before they are committed
+    let pattern = dir.join("dt=*/region=*/job-*.parquet");
+    let files = list_files(pattern.to_str().unwrap_or_default()).expect("list files failed");
+    assert!(files.is_empty());
+
+    let result = writer.commit();
+    assert!(result.is_ok());
+
+    let files = list_files(pattern.to_str().unwrap_or_default()).expect("list files failed");
+    assert_eq!(files.len(), 2);
+    assert!(files[0].starts_with(dir.join("dt=2026-10-18/region=eu")));
+    assert!(files[1].starts_with(dir.join("dt=2026-10-18/region=us")));
+
+    let rows = files
+        .iter()
+        .map(|path| {
+            let file = std::fs::File::open(path).expect("open file failed");
+            let reader = SerializedFileReader::new(file).expect("read file failed");
+            reader
+                .get_row_iter(None)
+                .expect("read rows failed")
+                .map(|row| row.expect("read row failed").to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                "{id: 1, name: \"hello\"}".to_string(),
+                "{id: 2, name: null}".to_string(),
+                "{id: 3, name: \"world\"}".to_string(),
+            ],
+            vec!["{id: 4, name: \"lightflus\"}".to_string()],
+        ]
+    );
+
+    let in_progress_files = list_files(dir.join("**/.*.inprogress").to_str().unwrap_or_default())
+        .expect("list files failed");
+    assert!(in_progress_files.is_empty());
+}
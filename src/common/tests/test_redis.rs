@@ -1,11 +1,16 @@
 use std::collections::BTreeMap;
 
 use bytes::Buf;
-use common::{redis::RedisClient, types::TypedValue, utils::get_env};
+use common::{
+    redis::{RedisClient, RedisCommand},
+    types::TypedValue,
+    utils::get_env,
+};
 use proto::common::{redis_desc, RedisDesc};
+use redis::Commands;
 
-#[test]
-pub fn test_redis_with_string_key_simple_value() {
+#[tokio::test]
+pub async fn test_redis_with_string_key_simple_value() {
     let conf = RedisDesc {
         connection_opts: Some(redis_desc::ConnectionOpts {
             host: get_env("REDIS_HOST").unwrap_or("localhost".to_string()),
@@ -13,9 +18,15 @@ pub fn test_redis_with_string_key_simple_value() {
             password: Default::default(),
             database: 0,
             tls: false,
+            cluster: false,
         }),
         key_extractor: None,
         value_extractor: None,
+        command: redis_desc::Command::Set as i32,
+        field_extractor: None,
+        score_extractor: None,
+        expire_millis: 0,
+        stream_opts: None,
     };
     let mut client = RedisClient::new(&conf);
 
@@ -23,17 +34,17 @@ pub fn test_redis_with_string_key_simple_value() {
         let key = &TypedValue::String("key".to_string());
         let value = &TypedValue::String("value".to_string());
 
-        let result = client.set(key, value);
+        let result = client.set(key, value).await;
         assert!(result.is_ok());
 
-        let result = client.get(key);
+        let result = client.get(key).await;
         assert!(result.is_ok());
 
         let result = result.expect("msg");
 
         assert_eq!(String::from_utf8(result), Ok("value".to_string()));
 
-        let result = client.del(key);
+        let result = client.del(key).await;
         assert!(result.is_ok());
     }
 
@@ -41,17 +52,17 @@ pub fn test_redis_with_string_key_simple_value() {
         let key = &TypedValue::String("key".to_string());
         let value = &TypedValue::BigInt(123456789);
 
-        let result = client.set(key, value);
+        let result = client.set(key, value).await;
         assert!(result.is_ok());
 
-        let result = client.get(key);
+        let result = client.get(key).await;
         assert!(result.is_ok());
 
         let result = result.expect("msg");
 
         assert_eq!(result.as_slice().get_i64(), 123456789);
 
-        let result = client.del(key);
+        let result = client.del(key).await;
         assert!(result.is_ok());
     }
 
@@ -59,17 +70,17 @@ pub fn test_redis_with_string_key_simple_value() {
         let key = &TypedValue::String("key".to_string());
         let value = &TypedValue::Number(123456789.123456789);
 
-        let result = client.set(key, value);
+        let result = client.set(key, value).await;
         assert!(result.is_ok());
 
-        let result = client.get(key);
+        let result = client.get(key).await;
         assert!(result.is_ok());
 
         let result = result.expect("msg");
 
         assert_eq!(result.as_slice().get_f64(), 123456789.123456789);
 
-        let result = client.del(key);
+        let result = client.del(key).await;
         assert!(result.is_ok());
     }
 
@@ -77,10 +88,10 @@ pub fn test_redis_with_string_key_simple_value() {
         let key = &TypedValue::String("key".to_string());
         let value = &TypedValue::Boolean(true);
 
-        let result = client.set(key, value);
+        let result = client.set(key, value).await;
         assert!(result.is_ok());
 
-        let result = client.get(key);
+        let result = client.get(key).await;
         assert!(result.is_ok());
 
         let result = result.expect("msg");
@@ -89,16 +100,16 @@ pub fn test_redis_with_string_key_simple_value() {
 
         let value = &TypedValue::Boolean(false);
 
-        let result = client.set(key, value);
+        let result = client.set(key, value).await;
         assert!(result.is_ok());
 
-        let result = client.get(key);
+        let result = client.get(key).await;
         assert!(result.is_ok());
 
         let result = result.expect("msg");
         assert_eq!(String::from_utf8(result), Ok("false".to_string()));
 
-        let result = client.del(key);
+        let result = client.del(key).await;
         assert!(result.is_ok());
     }
 
@@ -106,17 +117,17 @@ pub fn test_redis_with_string_key_simple_value() {
         let key = &TypedValue::String("key".to_string());
         let value = &TypedValue::Null;
 
-        let result = client.set(key, value);
+        let result = client.set(key, value).await;
         assert!(result.is_ok());
 
-        let result = client.get(key);
+        let result = client.get(key).await;
         assert!(result.is_ok());
 
         let result = result.expect("msg");
 
         assert_eq!(String::from_utf8(result), Ok("null".to_string()));
 
-        let result = client.del(key);
+        let result = client.del(key).await;
         assert!(result.is_ok());
     }
 
@@ -124,17 +135,17 @@ pub fn test_redis_with_string_key_simple_value() {
         let key = &TypedValue::String("key".to_string());
         let value = &TypedValue::Invalid;
 
-        let result = client.set(key, value);
+        let result = client.set(key, value).await;
         assert!(result.is_ok());
 
-        let result = client.get(key);
+        let result = client.get(key).await;
         assert!(result.is_ok());
 
         let result = result.expect("msg");
 
         assert_eq!(String::from_utf8(result), Ok("undefined".to_string()));
 
-        let result = client.del(key);
+        let result = client.del(key).await;
         assert!(result.is_ok());
     }
 
@@ -146,17 +157,17 @@ pub fn test_redis_with_string_key_simple_value() {
             TypedValue::Number(3.4),
         ]);
 
-        let result = client.set(key, value);
+        let result = client.set(key, value).await;
         assert!(result.is_ok());
 
-        let result = client.get(key);
+        let result = client.get(key).await;
         assert!(result.is_ok());
 
         let result = result.expect("msg");
 
         assert_eq!(String::from_utf8(result), Ok("[1.2,2.3,3.4]".to_string()));
 
-        let result = client.del(key);
+        let result = client.del(key).await;
         assert!(result.is_ok());
     }
 
@@ -168,10 +179,10 @@ pub fn test_redis_with_string_key_simple_value() {
             TypedValue::String("v3".to_string()),
         ]);
 
-        let result = client.set(key, value);
+        let result = client.set(key, value).await;
         assert!(result.is_ok());
 
-        let result = client.get(key);
+        let result = client.get(key).await;
         assert!(result.is_ok());
 
         let result = result.expect("msg");
@@ -181,7 +192,7 @@ pub fn test_redis_with_string_key_simple_value() {
             Ok("[\"v1\",\"v2\",\"v3\"]".to_string())
         );
 
-        let result = client.del(key);
+        let result = client.del(key).await;
         assert!(result.is_ok());
     }
 
@@ -189,17 +200,17 @@ pub fn test_redis_with_string_key_simple_value() {
         let key = &TypedValue::String("key".to_string());
         let value = &TypedValue::Array(vec![TypedValue::BigInt(1), TypedValue::BigInt(2)]);
 
-        let result = client.set(key, value);
+        let result = client.set(key, value).await;
         assert!(result.is_ok());
 
-        let result = client.get(key);
+        let result = client.get(key).await;
         assert!(result.is_ok());
 
         let result = result.expect("msg");
 
         assert_eq!(String::from_utf8(result), Ok("[1,2]".to_string()));
 
-        let result = client.del(key);
+        let result = client.del(key).await;
         assert!(result.is_ok());
     }
 
@@ -207,17 +218,17 @@ pub fn test_redis_with_string_key_simple_value() {
         let key = &TypedValue::String("key".to_string());
         let value = &TypedValue::Array(vec![TypedValue::Boolean(true), TypedValue::Boolean(false)]);
 
-        let result = client.set(key, value);
+        let result = client.set(key, value).await;
         assert!(result.is_ok());
 
-        let result = client.get(key);
+        let result = client.get(key).await;
         assert!(result.is_ok());
 
         let result = result.expect("msg");
 
         assert_eq!(String::from_utf8(result), Ok("[true,false]".to_string()));
 
-        let result = client.del(key);
+        let result = client.del(key).await;
         assert!(result.is_ok());
     }
 
@@ -234,17 +245,119 @@ pub fn test_redis_with_string_key_simple_value() {
         );
         let value = &TypedValue::Object(val);
 
-        let result = client.set(key, value);
+        let result = client.set(key, value).await;
         assert!(result.is_ok());
 
-        let result = client.get(key);
+        let result = client.get(key).await;
         assert!(result.is_ok());
 
         let result = result.expect("msg");
 
         assert_eq!(String::from_utf8(result), Ok("{\"k1\":\"v1\",\"k2\":123456789.1234567,\"k3\":true,\"k4\":123456789,\"k5\":[1,2]}".to_string()));
 
-        let result = client.del(key);
+        let result = client.del(key).await;
         assert!(result.is_ok());
     }
 }
+
+#[tokio::test]
+pub async fn test_redis_execute_commands() {
+    let host = get_env("REDIS_HOST").unwrap_or("localhost".to_string());
+    let conf = RedisDesc {
+        connection_opts: Some(redis_desc::ConnectionOpts {
+            host: host.clone(),
+            username: Default::default(),
+            password: Default::default(),
+            database: 0,
+            tls: false,
+            cluster: false,
+        }),
+        key_extractor: None,
+        value_extractor: None,
+        command: redis_desc::Command::Set as i32,
+        field_extractor: None,
+        score_extractor: None,
+        expire_millis: 0,
+        stream_opts: None,
+    };
+    let mut client = RedisClient::new(&conf);
+    let mut conn = redis::Client::open(format!("redis://{}", host))
+        .and_then(|client| client.get_connection())
+        .expect("connect redis failed");
+
+    let key = |key: &str| TypedValue::String(key.to_string());
+    let result = client
+        .execute(&[
+            RedisCommand::HSet {
+                key: key("user"),
+                field: key("name"),
+                value: key("jason"),
+            },
+            RedisCommand::HSet {
+                key: key("user"),
+                field: key("country"),
+                value: key("China"),
+            },
+            RedisCommand::HDel {
+                key: key("user"),
+                field: key("country"),
+            },
+            RedisCommand::ZAdd {
+                key: key("leaderboard"),
+                member: key("jason"),
+                score: 10.0,
+            },
+            RedisCommand::ZAdd {
+                key: key("leaderboard"),
+                member: key("thon"),
+                score: 5.5,
+            },
+            RedisCommand::ZRem {
+                key: key("leaderboard"),
+                member: key("thon"),
+            },
+            RedisCommand::IncrBy {
+                key: key("counter"),
+                delta: 5,
+            },
+            RedisCommand::IncrBy {
+                key: key("counter"),
+                delta: -2,
+            },
+            RedisCommand::PExpire {
+                key: key("counter"),
+                millis: 60000,
+            },
+        ])
+        .await;
+    assert!(result.is_ok());
+
+    let user: BTreeMap<String, String> = conn.hgetall("user").expect("hgetall failed");
+    assert_eq!(
+        user,
+        BTreeMap::from([("name".to_string(), "jason".to_string())])
+    );
+
+    let leaderboard: Vec<(String, f64)> = conn
+        .zrange_withscores("leaderboard", 0, -1)
+        .expect("zrange failed");
+    assert_eq!(leaderboard, vec![("jason".to_string(), 10.0)]);
+
+    let counter: i64 = conn.get("counter").expect("get failed");
+    assert_eq!(counter, 3);
+    let ttl: i64 = conn.pttl("counter").expect("pttl failed");
+    assert!(ttl > 0 && ttl <= 60000);
+
+    let result = client
+        .execute(&[
+            RedisCommand::Del { key: key("user") },
+            RedisCommand::Del {
+                key: key("leaderboard"),
+            },
+            RedisCommand::Del {
+                key: key("counter"),
+            },
+        ])
+        .await;
+    assert!(result.is_ok());
+}
@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use common::net::{
+    gateway::{coordinator::SafeCoordinatorRpcGateway, ReceiveHeartbeatRpcGateway},
+    TlsConfig,
+};
+use proto::{
+    common::{Ack, Dataflow, DataflowStates, Heartbeat, HostAddr, ResourceId, Response},
+    coordinator::{
+        coordinator_api_server::{CoordinatorApi, CoordinatorApiServer},
+        GetDataflowRequest, RescaleDataflowRequest,
+    },
+};
+use tonic::{async_trait, transport::Server, Request, Status};
+
+const CERTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/certs");
+
+struct MockCoordinator;
+
+#[async_trait]
+impl CoordinatorApi for MockCoordinator {
+    async fn create_dataflow(
+        &self,
+        _request: Request<Dataflow>,
+    ) -> Result<tonic::Response<Response>, Status> {
+        Err(Status::unimplemented("create_dataflow"))
+    }
+
+    async fn terminate_dataflow(
+        &self,
+        _request: Request<ResourceId>,
+    ) -> Result<tonic::Response<Response>, Status> {
+        Err(Status::unimplemented("terminate_dataflow"))
+    }
+
+    async fn get_dataflow(
+        &self,
+        _request: Request<GetDataflowRequest>,
+    ) -> Result<tonic::Response<DataflowStates>, Status> {
+        Err(Status::unimplemented("get_dataflow"))
+    }
+
+    async fn rescale_dataflow(
+        &self,
+        _request: Request<RescaleDataflowRequest>,
+    ) -> Result<tonic::Response<Response>, Status> {
+        Err(Status::unimplemented("rescale_dataflow"))
+    }
+
+    async fn receive_ack(
+        &self,
+        _request: Request<Ack>,
+    ) -> Result<tonic::Response<Response>, Status> {
+        Err(Status::unimplemented("receive_ack"))
+    }
+
+    async fn receive_heartbeat(
+        &self,
+        _request: Request<Heartbeat>,
+    ) -> Result<tonic::Response<Response>, Status> {
+        Ok(tonic::Response::new(Response::ok()))
+    }
+}
+
+fn tls_config(name: &str, mutual: bool) -> TlsConfig {
+    TlsConfig {
+        cert_path: format!("{}/{}.pem", CERTS_DIR, name),
+        key_path: format!("{}/{}.key", CERTS_DIR, name),
+        ca_path: format!("{}/ca.pem", CERTS_DIR),
+        mutual,
+        domain_name: None,
+    }
+}
+
+async fn setup_server(port: u16, tls: &TlsConfig) {
+    let server = Server::builder()
+        .tls_config(tls.server_config().expect("load tls config failed"))
+        .expect("invalid tls config")
+        .add_service(CoordinatorApiServer::new(MockCoordinator))
+        .serve(format!("127.0.0.1:{}", port).parse().unwrap());
+    tokio::spawn(server);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+}
+
+async fn new_gateway(port: u16, tls: Option<&TlsConfig>) -> SafeCoordinatorRpcGateway {
+    let gateway = SafeCoordinatorRpcGateway::new(&HostAddr {
+        host: "localhost".to_string(),
+        port: port as u32,
+    })
+    .await;
+    match tls {
+        Some(tls) => gateway.with_tls(tls.client_config().expect("load tls config failed")),
+        None => gateway,
+    }
+}
+
+#[tokio::test]
+async fn test_tls_config_load_failed() {
+    let mut config = tls_config("node", true);
+    config.ca_path = format!("{}/not_found.pem", CERTS_DIR);
+    assert!(config.client_config().is_err());
+    assert!(config.server_config().is_err());
+}
+
+#[tokio::test]
+async fn test_mutual_tls_success() {
+    let port = 8796;
+    setup_server(port, &tls_config("node", true)).await;
+
+    let gateway = new_gateway(port, Some(&tls_config("node", true))).await;
+    let result = gateway.receive_heartbeat(Heartbeat::default()).await;
+    assert!(result.is_ok(), "{:?}", result);
+}
+
+#[tokio::test]
+async fn test_mutual_tls_rejects_unverified_clients() {
+    let port = 8797;
+    setup_server(port, &tls_config("node", true)).await;
+
+    // the client doesn't present its certificate
+    let gateway = new_gateway(port, Some(&tls_config("node", false))).await;
+    assert!(gateway
+        .receive_heartbeat(Heartbeat::default())
+        .await
+        .is_err());
+
+    // the certificate of the client isn't signed by the CA
+    let gateway = new_gateway(port, Some(&tls_config("untrusted", true))).await;
+    assert!(gateway
+        .receive_heartbeat(Heartbeat::default())
+        .await
+        .is_err());
+
+    // plaintext
+    let gateway = new_gateway(port, None).await;
+    assert!(gateway
+        .receive_heartbeat(Heartbeat::default())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_tls_client_rejects_unverified_servers() {
+    let port = 8798;
+    setup_server(port, &tls_config("untrusted", false)).await;
+
+    let gateway = new_gateway(port, Some(&tls_config("node", false))).await;
+    assert!(gateway
+        .receive_heartbeat(Heartbeat::default())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_lazy_gateway_connects_over_tls() {
+    let port = 8801;
+    setup_server(port, &tls_config("node", true)).await;
+
+    let gateway = SafeCoordinatorRpcGateway::lazy(
+        &HostAddr {
+            host: "localhost".to_string(),
+            port: port as u32,
+        },
+        Some(
+            tls_config("node", true)
+                .client_config()
+                .expect("load tls config failed"),
+        ),
+    );
+    let result = gateway.receive_heartbeat(Heartbeat::default()).await;
+    assert!(result.is_ok(), "{:?}", result);
+}
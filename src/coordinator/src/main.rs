@@ -4,8 +4,9 @@ use actix_web::{web, App};
 
 use lightflus_core::{
     apiserver::handler::{
-        resources::{create_resource, get_resource, list_resources, overview},
-        COORDINATOR_URI_ENV, RESOURCES_HANDLER_ROOT,
+        ingest::ingest_events,
+        resources::{create_resource, get_resource, list_resources, overview, rescale_resource},
+        set_client_tls, COORDINATOR_URI_ENV, INGEST_HANDLER_ROOT, RESOURCES_HANDLER_ROOT,
     },
     coordinator::{
         api::CoordinatorApiImpl,
@@ -14,7 +15,6 @@ use lightflus_core::{
 };
 
 use proto::coordinator::coordinator_api_server::CoordinatorApiServer;
-use tonic::transport::Server;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -27,6 +27,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let addr = format!("0.0.0.0:{}", builder.port).parse()?;
     env::set_var(COORDINATOR_URI_ENV, format!("localhost:{}", builder.port));
+    // the api server connects to the coordinator and task managers as the coordinator does
+    set_client_tls(builder.tls.as_ref(), builder.cluster.tls.as_ref())?;
 
     let handler = tokio::spawn(
         actix_web::HttpServer::new(move || {
@@ -35,8 +37,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     web::scope(RESOURCES_HANDLER_ROOT)
                         .service(create_resource)
                         .service(get_resource)
+                        .service(rescale_resource)
                         .service(list_resources),
                 )
+                .service(web::scope(INGEST_HANDLER_ROOT).service(ingest_events))
                 .service(overview)
         })
         .client_disconnect_timeout(Duration::from_secs(3))
@@ -49,7 +53,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     tracing::info!("service will start at {}", builder.port);
 
-    Server::builder()
+    builder
+        .server_builder()
         .timeout(Duration::from_secs(3))
         .add_service(CoordinatorApiServer::new(CoordinatorApiImpl::new(
             coordinator,
@@ -0,0 +1,389 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use actix_web::{
+    error::{
+        ErrorBadRequest, ErrorInternalServerError, ErrorNotFound, ErrorPayloadTooLarge,
+        ErrorServiceUnavailable, ErrorUnauthorized,
+    },
+    http::header,
+    post, web, HttpRequest, HttpResponse,
+};
+use common::{
+    types::TypedValue,
+    utils::{from_pb_slice, get_env, times::now_timestamp, tokens},
+};
+use futures_util::StreamExt;
+use proto::common::{Entry, HttpDesc, KeyedDataEvent, KeyedEventSet, ResourceId, RowKind};
+
+use crate::{apiserver::types::IngestArgs, errors::apiserver::ApiError};
+
+use super::{
+    services::{get_operator_info, invalidate_operator_info, send_events_to_operator},
+    DEFAULT_INGEST_MAX_PAYLOAD_SIZE, INGEST_MAX_PAYLOAD_SIZE_ENV,
+};
+
+const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+const BEARER_PREFIX: &str = "Bearer ";
+const RETRY_AFTER_SECS: &str = "1";
+
+/// Push a batch of events to an HTTP source operator of a running dataflow.
+///
+/// The body is a protobuf [`KeyedEventSet`] if the content type is `application/x-protobuf`.
+/// Otherwise, it's a JSON array and each element is the value of an event, or a single JSON value of one event.
+///
+/// It responds 413 if the body is larger than `LIGHTFLUS_INGEST_MAX_PAYLOAD_SIZE`, 4 MiB by default.
+///
+/// It responds 429 if the queue of the operator is full and the batch should be retried later.
+/// A batch is accepted or rejected as a whole, so a retried batch is not delivered twice.
+#[post("/{namespace}/{resource_id}/{operator_id}")]
+async fn ingest_events(
+    args: web::Path<IngestArgs>,
+    req: HttpRequest,
+    mut payload: web::Payload,
+) -> actix_web::Result<HttpResponse> {
+    let job_id = args.to_resource_id();
+    let operator = get_operator_info(&job_id, args.operator_id).await?;
+    let desc = operator.get_http_source_desc().ok_or_else(|| {
+        ErrorNotFound(format!(
+            "operator {} is not an http source",
+            args.operator_id
+        ))
+    })?;
+    if !is_authorized(&req, desc) {
+        return Err(ErrorUnauthorized("invalid token"));
+    }
+    let host_addr = operator
+        .get_host_addr_ref()
+        .ok_or_else(|| ErrorServiceUnavailable("source operator is not deployed"))?;
+
+    let max_payload_size = get_env(INGEST_MAX_PAYLOAD_SIZE_ENV)
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(DEFAULT_INGEST_MAX_PAYLOAD_SIZE);
+    let mut bytes = web::BytesMut::new();
+    while let Some(item) = payload.next().await {
+        let item = item?;
+        if bytes.len() + item.len() > max_payload_size {
+            return Err(ErrorPayloadTooLarge(format!(
+                "payload is larger than {} bytes",
+                max_payload_size
+            )));
+        }
+        bytes.extend_from_slice(&item);
+    }
+
+    let events = if is_protobuf(&req) {
+        from_pb_slice::<KeyedEventSet>(&bytes)
+            .map(|event_set| event_set.events)
+            .map_err(|err| ErrorBadRequest(err))?
+    } else {
+        decode_json_events(&bytes).map_err(|err| ErrorBadRequest(err))?
+    };
+
+    match send_events_to_operator(host_addr, to_event_set(&job_id, args.operator_id, events)).await
+    {
+        Ok(_) => Ok(HttpResponse::Accepted().finish()),
+        Err(status) => match status.code() {
+            tonic::Code::ResourceExhausted => Ok(HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, RETRY_AFTER_SECS))
+                .body(ApiError::from(status).to_string())),
+            _ => {
+                // the operator may be moved or stopped, so it will be looked up again by the next request
+                invalidate_operator_info(&job_id, Some(args.operator_id));
+                Err(ErrorInternalServerError(ApiError::from(status)))
+            }
+        },
+    }
+}
+
+fn is_authorized(req: &HttpRequest, desc: &HttpDesc) -> bool {
+    if desc.token.is_empty() {
+        return true;
+    }
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix(BEARER_PREFIX))
+        .filter(|token| tokens::verify(&desc.token, token))
+        .is_some()
+}
+
+fn is_protobuf(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| value.starts_with(PROTOBUF_CONTENT_TYPE))
+        .is_some()
+}
+
+fn decode_json_events(body: &[u8]) -> Result<Vec<KeyedDataEvent>, serde_json::Error> {
+    let values = match serde_json::from_slice::<serde_json::Value>(body)? {
+        serde_json::Value::Array(values) => values,
+        value => vec![value],
+    };
+
+    Ok(values
+        .into_iter()
+        .map(|value| {
+            let value = TypedValue::from_json_value(value);
+            KeyedDataEvent {
+                data: vec![Entry {
+                    data_type: value.get_type() as i32,
+                    value: value.get_data_bytes(),
+                }],
+                row_kind: RowKind::Insert as i32,
+                ..Default::default()
+            }
+        })
+        .collect())
+}
+
+/// Events are sent to the source operator as if they were emitted by it. Missing event time and event id are filled.
+fn to_event_set(
+    job_id: &ResourceId,
+    operator_id: u32,
+    mut events: Vec<KeyedDataEvent>,
+) -> KeyedEventSet {
+    let ref mut hasher = DefaultHasher::new();
+    Hash::hash(job_id, hasher);
+    let job_id_hash = hasher.finish() as i64;
+    let now = now_timestamp();
+
+    events.iter_mut().enumerate().for_each(|(index, event)| {
+        event.job_id = Some(job_id.clone());
+        event.to_operator_id = operator_id;
+        event.from_operator_id = operator_id;
+        if event.event_time == 0 {
+            event.event_time = now;
+        }
+        if event.event_id == 0 {
+            event.event_id = job_id_hash.wrapping_add(now).wrapping_add(index as i64);
+        }
+    });
+
+    KeyedEventSet {
+        events,
+        job_id: Some(job_id.clone()),
+        to_operator_id: operator_id,
+        from_operator_id: operator_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test::TestRequest, App};
+    use common::{types::TypedValue, utils::tokens};
+    use futures_util::stream::BoxStream;
+    use proto::{
+        common::{
+            operator_info::Details, source, Ack, Heartbeat, HostAddr, HttpDesc, KeyedDataEvent,
+            KeyedEventSet, OperatorInfo, ResourceId, Response, Source, SubDataflowStates,
+        },
+        taskmanager::{
+            task_manager_api_server::{TaskManagerApi, TaskManagerApiServer},
+            BatchSendEventsToOperatorResponse, CreateSubDataflowRequest, CreateSubDataflowResponse,
+            EdgeAck, EdgeFrame, ExportKeyGroupsRequest, GetCollectedEventsRequest,
+            GetCollectedEventsResponse, KeyGroupStates, SendEventToOperatorResponse,
+            StopDataflowResponse,
+        },
+    };
+    use tonic::{transport::Server, Request, Status, Streaming};
+
+    use crate::apiserver::handler::services::cache_operator_info;
+
+    use super::{decode_json_events, ingest_events, to_event_set};
+
+    /// a TaskManager whose operators are always busy
+    struct BusyTaskManager;
+
+    #[tonic::async_trait]
+    impl TaskManagerApi for BusyTaskManager {
+        async fn send_event_to_operator(
+            &self,
+            _: Request<KeyedDataEvent>,
+        ) -> Result<tonic::Response<SendEventToOperatorResponse>, Status> {
+            Err(Status::resource_exhausted("queue is full"))
+        }
+        async fn stop_dataflow(
+            &self,
+            _: Request<ResourceId>,
+        ) -> Result<tonic::Response<StopDataflowResponse>, Status> {
+            Err(Status::unimplemented("stop_dataflow"))
+        }
+        async fn create_sub_dataflow(
+            &self,
+            _: Request<CreateSubDataflowRequest>,
+        ) -> Result<tonic::Response<CreateSubDataflowResponse>, Status> {
+            Err(Status::unimplemented("create_sub_dataflow"))
+        }
+        async fn receive_heartbeat(
+            &self,
+            _: Request<Heartbeat>,
+        ) -> Result<tonic::Response<Response>, Status> {
+            Err(Status::unimplemented("receive_heartbeat"))
+        }
+        async fn receive_ack(&self, _: Request<Ack>) -> Result<tonic::Response<Response>, Status> {
+            Err(Status::unimplemented("receive_ack"))
+        }
+        async fn batch_send_events_to_operator(
+            &self,
+            _: Request<KeyedEventSet>,
+        ) -> Result<tonic::Response<BatchSendEventsToOperatorResponse>, Status> {
+            Err(Status::resource_exhausted("queue is full"))
+        }
+        async fn get_sub_dataflow(
+            &self,
+            _: Request<ResourceId>,
+        ) -> Result<tonic::Response<SubDataflowStates>, Status> {
+            Err(Status::unimplemented("get_sub_dataflow"))
+        }
+        async fn get_collected_events(
+            &self,
+            _: Request<GetCollectedEventsRequest>,
+        ) -> Result<tonic::Response<GetCollectedEventsResponse>, Status> {
+            Err(Status::unimplemented("get_collected_events"))
+        }
+        type OpenEdgeStream = BoxStream<'static, Result<EdgeAck, Status>>;
+        async fn open_edge(
+            &self,
+            _: Request<Streaming<EdgeFrame>>,
+        ) -> Result<tonic::Response<Self::OpenEdgeStream>, Status> {
+            Err(Status::unimplemented("open_edge"))
+        }
+        async fn export_key_groups(
+            &self,
+            _: Request<ExportKeyGroupsRequest>,
+        ) -> Result<tonic::Response<KeyGroupStates>, Status> {
+            Err(Status::unimplemented("export_key_groups"))
+        }
+        async fn import_key_groups(
+            &self,
+            _: Request<KeyGroupStates>,
+        ) -> Result<tonic::Response<Response>, Status> {
+            Err(Status::unimplemented("import_key_groups"))
+        }
+    }
+
+    /// cache an http source whose token is `secret`, so that the coordinator isn't asked for it
+    fn cache_http_source(resource_id: &str, port: u32) {
+        cache_operator_info(
+            &ResourceId {
+                resource_id: resource_id.to_string(),
+                namespace_id: "default".to_string(),
+            },
+            OperatorInfo {
+                operator_id: 1,
+                host_addr: Some(HostAddr {
+                    host: "127.0.0.1".to_string(),
+                    port,
+                }),
+                details: Some(Details::Source(Source {
+                    desc: Some(source::Desc::Http(HttpDesc {
+                        token: tokens::redact("secret"),
+                        ..Default::default()
+                    })),
+                })),
+                ..Default::default()
+            },
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_ingest_events_unauthorized() {
+        cache_http_source("unauthorized", 8795);
+        let app = actix_web::test::init_service(App::new().service(ingest_events)).await;
+
+        let req = TestRequest::post()
+            .uri("/default/unauthorized/1")
+            .set_payload("[1]")
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let req = TestRequest::post()
+            .uri("/default/unauthorized/1")
+            .insert_header(("Authorization", "Bearer Secret"))
+            .set_payload("[1]")
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // the redacted token can't be used as the token
+        let req = TestRequest::post()
+            .uri("/default/unauthorized/1")
+            .insert_header((
+                "Authorization",
+                format!("Bearer {}", tokens::redact("secret")),
+            ))
+            .set_payload("[1]")
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_ingest_events_backpressure() {
+        let port = 8799;
+        tokio::spawn(
+            Server::builder()
+                .add_service(TaskManagerApiServer::new(BusyTaskManager))
+                .serve(format!("127.0.0.1:{}", port).parse().unwrap()),
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        cache_http_source("backpressure", port);
+        let app = actix_web::test::init_service(App::new().service(ingest_events)).await;
+
+        let req = TestRequest::post()
+            .uri("/default/backpressure/1")
+            .insert_header(("Authorization", "Bearer secret"))
+            .set_payload(r#"[{"id": 1}, {"id": 2}]"#)
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            resp.headers()
+                .get(actix_web::http::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok()),
+            Some("1")
+        );
+
+        // the payload is rejected before it's sent to the TaskManager
+        let req = TestRequest::post()
+            .uri("/default/backpressure/1")
+            .insert_header(("Authorization", "Bearer secret"))
+            .set_payload(vec![b' '; super::DEFAULT_INGEST_MAX_PAYLOAD_SIZE + 1])
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_decode_json_events() {
+        let events = decode_json_events(br#"[{"id": 1}, "hello"]"#).expect("decode failed");
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            TypedValue::from_slice(&events[1].data[0].value),
+            TypedValue::String("hello".to_string())
+        );
+
+        let events = decode_json_events(br#"{"id": 1}"#).expect("decode failed");
+        assert_eq!(events.len(), 1);
+
+        assert!(decode_json_events(b"{").is_err());
+
+        let job_id = ResourceId {
+            resource_id: "resource_id".to_string(),
+            namespace_id: "namespace_id".to_string(),
+        };
+        let event_set = to_event_set(&job_id, 1, events);
+        assert_eq!(event_set.to_operator_id, 1);
+        event_set.events.iter().for_each(|event| {
+            assert_eq!(event.job_id.as_ref(), Some(&job_id));
+            assert_eq!(event.to_operator_id, 1);
+            assert!(event.event_time > 0);
+            assert_ne!(event.event_id, 0);
+        });
+    }
+}
@@ -1,5 +1,46 @@
+use std::sync::OnceLock;
+
+use common::net::TlsConfig;
+use tonic::transport::ClientTlsConfig;
+
+pub mod ingest;
 pub mod resources;
 mod services;
 
 pub const RESOURCES_HANDLER_ROOT: &str = "/resources";
+pub const INGEST_HANDLER_ROOT: &str = "/ingest";
 pub const COORDINATOR_URI_ENV: &str = "LIGHTFLUS_COORDINATOR_URI";
+/// the max size in bytes of a batch of events pushed to an HTTP source
+pub const INGEST_MAX_PAYLOAD_SIZE_ENV: &str = "LIGHTFLUS_INGEST_MAX_PAYLOAD_SIZE";
+pub const DEFAULT_INGEST_MAX_PAYLOAD_SIZE: usize = 4 * 1024 * 1024;
+
+/// TLS of the connections from the API server to the coordinator and TaskManagers
+#[derive(Default)]
+struct ClientTls {
+    coordinator: Option<ClientTlsConfig>,
+    taskmanager: Option<ClientTlsConfig>,
+}
+
+static CLIENT_TLS: OnceLock<ClientTls> = OnceLock::new();
+
+/// Connect to the coordinator and TaskManagers over TLS, with the same configs as the coordinator serves and connects to TaskManagers with.
+/// It should be called before the API server starts. Connections are plaintext if it's not called
+pub fn set_client_tls(
+    coordinator: Option<&TlsConfig>,
+    taskmanager: Option<&TlsConfig>,
+) -> std::io::Result<()> {
+    let tls = ClientTls {
+        coordinator: coordinator.map(|tls| tls.client_config()).transpose()?,
+        taskmanager: taskmanager.map(|tls| tls.client_config()).transpose()?,
+    };
+    CLIENT_TLS.set(tls).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "tls of the api server has been set",
+        )
+    })
+}
+
+fn client_tls() -> &'static ClientTls {
+    CLIENT_TLS.get_or_init(Default::default)
+}
@@ -1,14 +1,16 @@
 use actix_web::{error::ErrorBadRequest, get, post, web, HttpResponse};
 use common::utils::{from_pb_slice, pb_to_bytes_mut};
 use futures_util::StreamExt;
-use proto::apiserver::{CreateResourceRequest, CreateResourceResponse, ResourceTypeEnum};
+use proto::apiserver::{
+    CreateResourceRequest, CreateResourceResponse, RescaleResourceRequest, ResourceTypeEnum,
+};
 
 use crate::apiserver::{
     handler::services::create_dataflow,
     types::{GetResourceArgs, ListResourcesArgs},
 };
 
-use super::services::get_dataflow;
+use super::services::{get_dataflow, rescale_dataflow};
 
 #[post("/create")]
 async fn create_resource(mut req: web::Payload) -> actix_web::Result<HttpResponse> {
@@ -31,6 +33,23 @@ async fn create_resource(mut req: web::Payload) -> actix_web::Result<HttpRespons
     }
 }
 
+/// Change the parallelism of operators of a running dataflow without losing its state
+#[post("/rescale")]
+async fn rescale_resource(mut req: web::Payload) -> actix_web::Result<HttpResponse> {
+    let mut bytes = web::BytesMut::new();
+    while let Some(item) = req.next().await {
+        let item = item?;
+        bytes.extend_from_slice(&item);
+    }
+
+    match from_pb_slice::<RescaleResourceRequest>(bytes.iter().as_slice()) {
+        Ok(req) => rescale_dataflow(req)
+            .await
+            .map(|resp| HttpResponse::Ok().body(pb_to_bytes_mut(resp))),
+        Err(err) => Err(ErrorBadRequest(err)),
+    }
+}
+
 #[get("/get/{namespace}/{resource_type}/{resource_id}")]
 async fn get_resource(args: web::Path<GetResourceArgs>) -> actix_web::Result<HttpResponse> {
     match ResourceTypeEnum::from_i32(args.resource_type) {
@@ -1,19 +1,48 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, MutexGuard, OnceLock},
+    time::{Duration, Instant},
+};
+
 use actix_web::{
-    error::{ErrorBadRequest, ErrorInternalServerError},
+    error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound},
     HttpResponse,
 };
-use common::utils::pb_to_bytes_mut;
+use common::{
+    net::{
+        cluster::NodeBuilder,
+        gateway::{coordinator::SafeCoordinatorRpcGateway, taskmanager::SafeTaskManagerRpcGateway},
+    },
+    utils::pb_to_bytes_mut,
+};
 use proto::{
     apiserver::{
-        CreateResourceRequest, CreateResourceResponse, GetResourceResponse, Resource,
-        ResourceStatusEnum, ResourceTypeEnum,
+        CreateResourceRequest, CreateResourceResponse, GetResourceResponse, RescaleResourceRequest,
+        RescaleResourceResponse, Resource, ResourceStatusEnum, ResourceTypeEnum,
     },
-    coordinator::{coordinator_api_client::CoordinatorApiClient, GetDataflowRequest},
+    common::{HostAddr, KeyedEventSet, OperatorInfo, ResourceId},
+    coordinator::{GetDataflowRequest, RescaleDataflowRequest},
 };
 
 use crate::{apiserver::types::GetResourceArgs, errors::apiserver::ApiError};
 
-use super::COORDINATOR_URI_ENV;
+use super::{client_tls, COORDINATOR_URI_ENV};
+
+/// The gateway to the coordinator at [COORDINATOR_URI_ENV], which is `host:port`. It connects over TLS if the API server has a TLS config
+fn coordinator_gateway() -> SafeCoordinatorRpcGateway {
+    let uri = common::utils::get_env(COORDINATOR_URI_ENV).unwrap_or_default();
+    let node = NodeBuilder::from(
+        uri.trim_start_matches("http://")
+            .trim_start_matches("https://"),
+    );
+    SafeCoordinatorRpcGateway::lazy(
+        &HostAddr {
+            host: node.host,
+            port: node.port as u32,
+        },
+        client_tls().coordinator.clone(),
+    )
+}
 
 pub(crate) async fn create_dataflow(
     req: CreateResourceRequest,
@@ -24,57 +53,155 @@ pub(crate) async fn create_dataflow(
         return Err(ErrorBadRequest("empty dataflow"));
     }
 
-    let uri = common::utils::get_env(COORDINATOR_URI_ENV);
-    let ref mut cli = CoordinatorApiClient::connect(uri.unwrap_or_default()).await;
-
-    match cli {
-        Ok(client) => {
-            let result = client
-                .create_dataflow(tonic::Request::new(req.get_dataflow()))
-                .await;
-            result
-                .map_err(|err| ErrorInternalServerError(ApiError::from(err)))
-                .map(|_| {
-                    let mut response = CreateResourceResponse::default();
-                    response.set_status(ResourceStatusEnum::Starting);
-                    response
-                })
-        }
-        Err(err) => Err(ErrorInternalServerError(ApiError::from(err))),
-    }
+    coordinator_gateway()
+        .create_dataflow(req.get_dataflow())
+        .await
+        .map_err(|err| ErrorInternalServerError(ApiError::from(err)))
+        .map(|_| {
+            let mut response = CreateResourceResponse::default();
+            response.set_status(ResourceStatusEnum::Starting);
+            response
+        })
+}
+
+pub(crate) async fn rescale_dataflow(
+    req: RescaleResourceRequest,
+) -> Result<RescaleResourceResponse, actix_web::Error> {
+    let job_id = match req.resource_id.as_ref() {
+        Some(job_id) => job_id,
+        None => return Err(ErrorBadRequest("empty resource id")),
+    };
+
+    coordinator_gateway()
+        .rescale_dataflow(RescaleDataflowRequest {
+            job_id: Some(job_id.clone()),
+            parallelism: req.parallelism.clone(),
+        })
+        .await
+        .map_err(|err| match err.code() {
+            tonic::Code::NotFound => ErrorNotFound(ApiError::from(err)),
+            tonic::Code::InvalidArgument => ErrorBadRequest(ApiError::from(err)),
+            _ => ErrorInternalServerError(ApiError::from(err)),
+        })
+        .map(|_| {
+            invalidate_operator_info(job_id, None);
+            RescaleResourceResponse {
+                resource: Some(Resource {
+                    resource_id: Some(job_id.clone()),
+                    resource_type: ResourceTypeEnum::Dataflow as i32,
+                    status: ResourceStatusEnum::Starting as i32,
+                    ..Default::default()
+                }),
+            }
+        })
 }
 
 pub(crate) async fn get_dataflow(args: &GetResourceArgs) -> actix_web::Result<HttpResponse> {
     let mut resp = HttpResponse::Ok();
-    let uri = common::utils::get_env(COORDINATOR_URI_ENV).unwrap();
-    let mut cli = CoordinatorApiClient::connect(uri).await;
-
-    match cli
-        .as_mut()
+    let mut req = GetDataflowRequest::default();
+    req.job_id = Some(args.to_resource_id());
+    coordinator_gateway()
+        .get_dataflow(req)
+        .await
         .map_err(|err| ErrorInternalServerError(ApiError::from(err)))
+        .and_then(|states| {
+            let mut response = GetResourceResponse::default();
+            let mut resource = Resource::default();
+            match states.graph {
+                Some(dataflow) => {
+                    resource.resource_id = dataflow.job_id.clone();
+                    resource.set_resource_type(ResourceTypeEnum::Dataflow);
+                    response.resource = Some(resource);
+                    Ok(response)
+                }
+                None => Err(ErrorBadRequest("empty graph response")),
+            }
+        })
+        .map(|response| resp.body(pb_to_bytes_mut(response)))
+}
+
+/// the key of an operator in [OPERATORS], which is the namespace, the resource id and the operator id
+type OperatorKey = (String, String, u32);
+
+/// Operators looked up by ingestion, so that the coordinator isn't asked for the dataflow on each request.
+/// An operator is looked up again after [OPERATOR_CACHE_TTL], or after it's invalidated by [invalidate_operator_info]
+static OPERATORS: OnceLock<Mutex<BTreeMap<OperatorKey, (Instant, OperatorInfo)>>> = OnceLock::new();
+
+const OPERATOR_CACHE_TTL: Duration = Duration::from_secs(10);
+
+fn to_operator_key(job_id: &ResourceId, operator_id: u32) -> OperatorKey {
+    (
+        job_id.namespace_id.clone(),
+        job_id.resource_id.clone(),
+        operator_id,
+    )
+}
+
+fn get_operators() -> MutexGuard<'static, BTreeMap<OperatorKey, (Instant, OperatorInfo)>> {
+    OPERATORS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+}
+
+pub(crate) fn cache_operator_info(job_id: &ResourceId, operator: OperatorInfo) {
+    get_operators().insert(
+        to_operator_key(job_id, operator.operator_id),
+        (Instant::now(), operator),
+    );
+}
+
+/// Remove the cached operator, or all operators of the job if `operator_id` is [None],
+/// e.g. after the operator can not be reached or the dataflow is rescaled
+pub(crate) fn invalidate_operator_info(job_id: &ResourceId, operator_id: Option<u32>) {
+    get_operators().retain(|(namespace_id, resource_id, id), _| {
+        namespace_id != &job_id.namespace_id
+            || resource_id != &job_id.resource_id
+            || operator_id
+                .filter(|operator_id| operator_id != id)
+                .is_some()
+    })
+}
+
+pub(crate) async fn get_operator_info(
+    job_id: &ResourceId,
+    operator_id: u32,
+) -> actix_web::Result<OperatorInfo> {
+    if let Some((_, operator)) = get_operators()
+        .get(&to_operator_key(job_id, operator_id))
+        .filter(|(cached_at, _)| cached_at.elapsed() < OPERATOR_CACHE_TTL)
     {
-        Ok(client) => {
-            let mut req = GetDataflowRequest::default();
-            req.job_id = Some(args.to_resource_id());
-            client
-                .get_dataflow(tonic::Request::new(req))
-                .await
-                .map_err(|err| ErrorInternalServerError(ApiError::from(err)))
-                .and_then(|resp| {
-                    let mut response = GetResourceResponse::default();
-                    let mut resource = Resource::default();
-                    match resp.into_inner().graph {
-                        Some(dataflow) => {
-                            resource.resource_id = dataflow.job_id.clone();
-                            resource.set_resource_type(ResourceTypeEnum::Dataflow);
-                            response.resource = Some(resource);
-                            Ok(response)
-                        }
-                        None => Err(ErrorBadRequest("empty graph response")),
-                    }
-                })
-                .map(|response| resp.body(pb_to_bytes_mut(response)))
-        }
-        Err(err) => Err(err),
+        return Ok(operator.clone());
+    }
+
+    let mut req = GetDataflowRequest::default();
+    req.job_id = Some(job_id.clone());
+    let states = coordinator_gateway()
+        .get_dataflow(req)
+        .await
+        .map_err(|err| match err.code() {
+            tonic::Code::NotFound => ErrorNotFound(ApiError::from(err)),
+            _ => ErrorInternalServerError(ApiError::from(err)),
+        })?;
+
+    let operator = states
+        .graph
+        .and_then(|mut dataflow| dataflow.nodes.remove(&operator_id))
+        .ok_or_else(|| ErrorNotFound(format!("operator {} not found", operator_id)))?;
+    cache_operator_info(job_id, operator.clone());
+    Ok(operator)
+}
+
+pub(crate) async fn send_events_to_operator(
+    host_addr: &HostAddr,
+    event_set: KeyedEventSet,
+) -> Result<(), tonic::Status> {
+    let gateway = SafeTaskManagerRpcGateway::new(host_addr);
+    match client_tls().taskmanager.clone() {
+        Some(tls) => gateway.with_tls(tls),
+        None => gateway,
     }
+    .batch_send_events_to_operator(event_set)
+    .await
+    .map(|_| ())
 }
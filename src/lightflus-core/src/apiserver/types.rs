@@ -21,3 +21,19 @@ pub(crate) struct ListResourcesArgs {
     pub resource_type: i32,
     pub namespace: String,
 }
+
+#[derive(serde::Deserialize)]
+pub(crate) struct IngestArgs {
+    pub namespace: String,
+    pub resource_id: String,
+    pub operator_id: u32,
+}
+
+impl IngestArgs {
+    pub fn to_resource_id(&self) -> ResourceId {
+        let mut resource_id = ResourceId::default();
+        resource_id.namespace_id = self.namespace.clone();
+        resource_id.resource_id = self.resource_id.clone();
+        resource_id
+    }
+}
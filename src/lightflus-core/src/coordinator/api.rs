@@ -4,7 +4,7 @@ use super::coord;
 use proto::common::{Ack, Dataflow, DataflowStates, Heartbeat, ResourceId, Response};
 
 use proto::coordinator::coordinator_api_server::CoordinatorApi;
-use proto::coordinator::GetDataflowRequest;
+use proto::coordinator::{GetDataflowRequest, RescaleDataflowRequest};
 
 use tonic::async_trait;
 
@@ -67,4 +67,13 @@ impl CoordinatorApi for CoordinatorApiImpl {
             .await
             .and_then(|dataflow| Ok(new_rpc_response(dataflow)))
     }
+    async fn rescale_dataflow(
+        &self,
+        request: tonic::Request<RescaleDataflowRequest>,
+    ) -> Result<tonic::Response<Response>, tonic::Status> {
+        self.coordinator
+            .rescale_dataflow(request.get_ref())
+            .await
+            .map(|_| tonic::Response::new(Response::ok()))
+    }
 }
@@ -3,7 +3,9 @@ use std::fs;
 use common::net::cluster;
 use common::net::AckResponderBuilder;
 use common::net::HeartbeatBuilder;
+use common::net::TlsConfig;
 use common::utils;
+use common::utils::tokens;
 use proto::common::Ack;
 use proto::common::Dataflow;
 use proto::common::DataflowStates;
@@ -12,6 +14,8 @@ use proto::common::DataflowStatus;
 use proto::common::Heartbeat;
 use proto::common::NodeType;
 use proto::common::ResourceId;
+use proto::coordinator::RescaleDataflowRequest;
+use tonic::transport::Server;
 
 use super::managers::Dispatcher;
 use super::storage::DataflowStorageBuilder;
@@ -30,6 +34,9 @@ pub struct CoordinatorBuilder {
     pub heartbeat: HeartbeatBuilder,
     // ack responder builder
     pub ack: AckResponderBuilder,
+    /// TLS of the coordinator server
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
 }
 
 impl CoordinatorBuilder {
@@ -44,6 +51,16 @@ impl CoordinatorBuilder {
             ),
         }
     }
+
+    /// The server which serves over TLS if it's configured
+    pub fn server_builder(&self) -> Server {
+        match &self.tls {
+            Some(tls) => Server::builder()
+                .tls_config(tls.server_config().expect("load tls config failed: "))
+                .expect("invalid tls config: "),
+            None => Server::builder(),
+        }
+    }
 }
 
 pub fn load_builder() -> CoordinatorBuilder {
@@ -112,9 +129,34 @@ impl Coordinator {
         self.dispatcher
             .get_dataflow(job_id)
             .await
+            .map(|mut states| {
+                // tokens of http sources are never returned in plain text
+                states.graph.iter_mut().for_each(|dataflow| {
+                    dataflow.nodes.values_mut().for_each(|operator| {
+                        if let Some(desc) = operator.get_http_source_desc_mut() {
+                            desc.token = tokens::redact(&desc.token);
+                        }
+                    })
+                });
+                states
+            })
             .map_err(|err| err.to_tonic_status())
     }
 
+    pub(crate) async fn rescale_dataflow(
+        &self,
+        request: &RescaleDataflowRequest,
+    ) -> Result<(), tonic::Status> {
+        match request.job_id.as_ref() {
+            Some(job_id) => self
+                .dispatcher
+                .rescale_dataflow(job_id, &request.parallelism)
+                .await
+                .map_err(|err| err.to_tonic_status()),
+            None => Err(tonic::Status::invalid_argument("job id is not provided")),
+        }
+    }
+
     pub(crate) async fn receive_heartbeart(&self, heartbeat: &Heartbeat) {
         self.dispatcher
             .update_task_manager_heartbeat_status(heartbeat)
@@ -69,17 +69,19 @@ impl VertexExecution {
         operator: &OperatorInfo,
         ack_builder: &AckResponderBuilder,
         heartbeat_builder: &HeartbeatBuilder,
+        worker_gateway: &SafeTaskManagerRpcGateway,
     ) -> Self {
         let host_addr = operator.get_host_addr();
+        // gateways of acks and heartbeats share the compression and TLS configs of the worker's gateway
         let (ack, sender) = ack_builder.build(&host_addr, |addr, connect_timeout, rpc_timout| {
-            SafeTaskManagerRpcGateway::with_timeout(addr, connect_timeout, rpc_timout)
+            worker_gateway.connect_to(addr, connect_timeout, rpc_timout)
         });
 
         let mut heartbeat = heartbeat_builder.build(
             &host_addr,
             executor_id,
             |host_addr, connect_timeout, rpc_timeout| {
-                SafeTaskManagerRpcGateway::with_timeout(host_addr, connect_timeout, rpc_timeout)
+                worker_gateway.connect_to(host_addr, connect_timeout, rpc_timeout)
             },
         );
         heartbeat.update_execution_id(execution_id.clone());
@@ -95,6 +97,12 @@ impl VertexExecution {
     }
 }
 
+impl Drop for VertexExecution {
+    fn drop(&mut self) {
+        self.heartbeat_handler.abort()
+    }
+}
+
 /// A [`SubdataflowDeploymentPlan`] represents a description for a subdataflow [`Dataflow`] deployment. It may contains following properties:
 /// - the structure of subdataflow
 /// - the execution id of the subdataflow
@@ -194,18 +202,26 @@ impl SubdataflowExecution {
         ack: &AckResponderBuilder,
         heartbeat: &HeartbeatBuilder,
     ) -> Self {
+        let vertexes = subdataflow
+            .nodes
+            .iter()
+            .map(|(executor_id, info)| {
+                (
+                    *executor_id,
+                    VertexExecution::new(
+                        &execution_id,
+                        *executor_id,
+                        info,
+                        ack,
+                        heartbeat,
+                        worker.get_gateway(),
+                    ),
+                )
+            })
+            .collect();
         Self {
             worker,
-            vertexes: subdataflow
-                .nodes
-                .iter()
-                .map(|(executor_id, info)| {
-                    (
-                        *executor_id,
-                        VertexExecution::new(&execution_id, *executor_id, info, ack, heartbeat),
-                    )
-                })
-                .collect(),
+            vertexes,
             execution_id,
         }
     }
@@ -214,6 +230,16 @@ impl SubdataflowExecution {
         todo!()
     }
 
+    /// stop the subdataflow on the TaskManager
+    pub(crate) async fn stop(&self) -> Result<(), SubdataflowError> {
+        self.worker
+            .get_gateway()
+            .stop_dataflow(self.get_execution_id().get_job_id())
+            .await
+            .map(|_| ())
+            .map_err(SubdataflowError::RpcError)
+    }
+
     pub(crate) fn get_execution_id(&self) -> &SubDataflowId {
         &self.execution_id
     }
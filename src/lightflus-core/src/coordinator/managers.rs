@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use common::net::{
     cluster::{self, ClusterBuilder},
     local, AckResponderBuilder, HeartbeatBuilder,
@@ -6,13 +8,15 @@ use crossbeam_skiplist::SkipMap;
 use proto::common::{
     Ack, Dataflow, DataflowStates, DataflowStatus, Heartbeat, HostAddr, ResourceId,
 };
+use tokio::sync::RwLock;
 
 use crate::errors::coordinator::{
-    not_found_dataflow, task_deployment_err, unexpected_dataflow_staus,
+    invalid_rescale, not_found_dataflow, task_deployment_err, unexpected_dataflow_staus,
 };
 
 use super::{
     executions::{SubdataflowDeploymentPlan, TaskDeploymentException},
+    rescale::StateMove,
     scheduler::Scheduler,
     storage::{DataflowStorage, DataflowStorageBuilder},
 };
@@ -54,7 +58,103 @@ impl JobManager {
     ) -> Result<(), TaskDeploymentException> {
         let _ = self.storage.save(&self.dataflow);
         cluster.partition_dataflow(&mut self.dataflow);
+        self.deploy_subdataflows(cluster, heartbeat_builder, ack_builder)
+            .await
+    }
+
+    /// Rescale the deployed dataflow with the new parallelism of its operators, and place it onto the TaskManagers which are available now.
+    /// A state which can't be moved, e.g. the memory state, is rejected before the subdataflows are stopped.
+    /// The executors drain the events they have read and terminate before the state is moved, so that the state doesn't change while it's moved.
+    /// Key groups are copied to their new TaskManagers before they are removed from the old ones. If a copy fails, the dataflow is deployed again
+    /// as it was, so a failed rescaling can be retried without losing state
+    async fn rescale_dataflow(
+        &mut self,
+        parallelism: &HashMap<u32, u32>,
+        cluster: &cluster::Cluster,
+        heartbeat_builder: &HeartbeatBuilder,
+        ack_builder: &AckResponderBuilder,
+    ) -> Result<(), DispatcherException> {
+        // the submitted dataflow
+        let mut logical_dataflow = match self.storage.get(&self.job_id) {
+            Some(dataflow) => dataflow,
+            None => return Err(DispatcherException::NotFoundDataflow(self.job_id.clone())),
+        };
+        for (operator_id, parallelism) in parallelism {
+            match logical_dataflow.nodes.get_mut(operator_id) {
+                Some(operator) if *parallelism > 0 => operator.parallelism = *parallelism,
+                Some(_) => {
+                    return Err(DispatcherException::Tonic(
+                        invalid_rescale(&format!(
+                            "parallelism of operator {} must be positive",
+                            operator_id
+                        ))
+                        .into_tonic_status(),
+                    ))
+                }
+                None => {
+                    return Err(DispatcherException::Tonic(
+                        invalid_rescale(&format!("operator {} not found", operator_id))
+                            .into_tonic_status(),
+                    ))
+                }
+            }
+        }
+        if let Err(err) = logical_dataflow.validate() {
+            return Err(DispatcherException::Tonic(
+                invalid_rescale(&format!("{:?}", err)).into_tonic_status(),
+            ));
+        }
+        let mut dataflow = logical_dataflow.clone();
+        cluster.partition_dataflow(&mut dataflow);
 
+        let state_moves = StateMove::plan(&self.dataflow, &dataflow);
+        for state_move in &state_moves {
+            state_move
+                .check(&self.job_id, cluster)
+                .await
+                .map_err(DispatcherException::Tonic)?;
+        }
+
+        self.scheduler
+            .stop_dataflow()
+            .await
+            .map_err(|err| DispatcherException::Tonic(err.to_tonic_status()))?;
+
+        for state_move in &state_moves {
+            if let Err(status) = state_move.copy(&self.job_id, cluster).await {
+                // nothing has been removed from the old TaskManagers yet
+                if let Err(err) = self
+                    .deploy_subdataflows(cluster, heartbeat_builder, ack_builder)
+                    .await
+                {
+                    tracing::error!(
+                        "deploy dataflow {:?} again after the failed rescaling failed: {}",
+                        self.job_id,
+                        DispatcherException::DeploymentError(err).to_tonic_status()
+                    )
+                }
+                return Err(DispatcherException::Tonic(status));
+            }
+        }
+        for state_move in &state_moves {
+            if let Err(err) = state_move.clear(&self.job_id, cluster, &state_moves).await {
+                tracing::warn!("remove moved state {:?} failed: {}", state_move, err)
+            }
+        }
+
+        let _ = self.storage.save(&logical_dataflow);
+        self.dataflow = dataflow;
+        self.deploy_subdataflows(cluster, heartbeat_builder, ack_builder)
+            .await
+            .map_err(DispatcherException::DeploymentError)
+    }
+
+    async fn deploy_subdataflows(
+        &mut self,
+        cluster: &cluster::Cluster,
+        heartbeat_builder: &HeartbeatBuilder,
+        ack_builder: &AckResponderBuilder,
+    ) -> Result<(), TaskDeploymentException> {
         let mut subdataflow = cluster.split_into_subdataflow(&self.dataflow);
         let executions = subdataflow.iter_mut().map(|pair| {
             let host_addr = pair.0;
@@ -109,7 +209,7 @@ impl JobManager {
 /// - job recovery
 /// - heartbeat of remote cluster
 pub(crate) struct Dispatcher {
-    managers: SkipMap<ResourceId, JobManager>,
+    managers: SkipMap<ResourceId, RwLock<JobManager>>,
     cluster: cluster::Cluster,
     location: HostAddr,
     heartbeat: HeartbeatBuilder,
@@ -146,17 +246,35 @@ impl Dispatcher {
             .deploy_dataflow(&self.cluster, &self.heartbeat, &self.ack)
             .await
             .map_err(|err| DispatcherException::DeploymentError(err));
-        self.managers.insert(job_id, job_manager);
+        self.managers.insert(job_id, RwLock::new(job_manager));
 
         result
     }
 
+    pub(crate) async fn rescale_dataflow(
+        &self,
+        job_id: &ResourceId,
+        parallelism: &HashMap<u32, u32>,
+    ) -> Result<(), DispatcherException> {
+        match self.managers.get(job_id) {
+            Some(entry) => {
+                entry
+                    .value()
+                    .write()
+                    .await
+                    .rescale_dataflow(parallelism, &self.cluster, &self.heartbeat, &self.ack)
+                    .await
+            }
+            None => Err(DispatcherException::NotFoundDataflow(job_id.clone())),
+        }
+    }
+
     pub(crate) async fn terminate_dataflow(
         &self,
         job_id: &ResourceId,
     ) -> Result<DataflowStatus, DispatcherException> {
         match self.managers.get(job_id) {
-            Some(manager) => match manager.value().terminate_dataflow().await {
+            Some(manager) => match manager.value().read().await.terminate_dataflow().await {
                 Ok(status) => match &status {
                     DataflowStatus::Initialized => {
                         Err(DispatcherException::UnexpectedDataflowStatus(status))
@@ -181,7 +299,7 @@ impl Dispatcher {
         job_id: &ResourceId,
     ) -> Result<DataflowStates, DispatcherException> {
         match self.managers.get(job_id) {
-            Some(entry) => Ok(entry.value().get_dataflow().await),
+            Some(entry) => Ok(entry.value().read().await.get_dataflow().await),
             None => Err(DispatcherException::NotFoundDataflow(job_id.clone())),
         }
     }
@@ -193,7 +311,14 @@ impl Dispatcher {
             .and_then(|execution_id| execution_id.job_id.as_ref())
             .and_then(|resource_id| self.managers.get(resource_id))
         {
-            Some(entry) => entry.value().update_heartbeat_status(heartbeat).await,
+            Some(entry) => {
+                entry
+                    .value()
+                    .read()
+                    .await
+                    .update_heartbeat_status(heartbeat)
+                    .await
+            }
             None => {}
         }
     }
@@ -205,7 +330,7 @@ impl Dispatcher {
             .and_then(|execution_id| execution_id.job_id.as_ref())
             .and_then(|resource_id| self.managers.get(resource_id))
         {
-            Some(manager) => manager.value().ack_from_execution(&ack),
+            Some(manager) => manager.value().read().await.ack_from_execution(&ack),
             None => {}
         }
     }
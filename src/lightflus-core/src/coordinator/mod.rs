@@ -2,5 +2,6 @@ pub mod api;
 pub mod coord;
 pub mod executions;
 pub mod managers;
+pub mod rescale;
 pub mod scheduler;
 pub mod storage;
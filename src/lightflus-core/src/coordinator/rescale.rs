@@ -0,0 +1,388 @@
+use std::collections::BTreeMap;
+
+use common::{
+    net::{cluster::Cluster, gateway::taskmanager::SafeTaskManagerRpcGateway},
+    types::ExecutorId,
+    utils::key_groups,
+};
+use proto::{
+    common::{Dataflow, HostAddr, ResourceId},
+    taskmanager::{ExportKeyGroupsRequest, KeyGroupState, KeyGroupStates},
+};
+
+use crate::errors::coordinator::unavailable_task_manager;
+
+/// The key groups of a state which are moved from one TaskManager to another when a dataflow is rescaled
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StateMove {
+    /// the operator in the submitted dataflow, or the source executor if it's the state of a source
+    operator_id: ExecutorId,
+    /// the executor which takes over the positions of a source. It's `operator_id` for keyed state
+    to_operator_id: ExecutorId,
+    /// the positions recorded by a source
+    source: bool,
+    from: HostAddr,
+    to: HostAddr,
+    key_groups: Vec<u32>,
+}
+
+impl StateMove {
+    /// The moves of states if the deployed dataflow `from` is rescaled to `to`.
+    /// Keyed state follows the owners of its key groups. The positions recorded by an instance of a source are taken over by the instance
+    /// with the same index, whose executor id may change if the instances are numbered again. The positions of the instances
+    /// which are removed by the rescaling are dropped
+    pub(crate) fn plan(from: &Dataflow, to: &Dataflow) -> Vec<StateMove> {
+        let old_hosts = keyed_instance_hosts(from);
+        let mut moves = keyed_instance_hosts(to)
+            .into_iter()
+            .flat_map(|(operator_id, new_hosts)| {
+                key_groups::reassign(
+                    old_hosts
+                        .get(&operator_id)
+                        .map(|hosts| hosts.as_slice())
+                        .unwrap_or_default(),
+                    &new_hosts,
+                )
+                .into_iter()
+                .map(move |(from, to, key_groups)| StateMove {
+                    operator_id,
+                    to_operator_id: operator_id,
+                    source: false,
+                    from,
+                    to,
+                    key_groups,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let old_sources = from
+            .nodes
+            .values()
+            .filter(|operator| operator.has_source())
+            .map(|operator| {
+                (
+                    (
+                        operator.get_logical_operator_id(),
+                        operator.get_instance_index(),
+                    ),
+                    operator,
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+        to.nodes
+            .values()
+            .filter(|operator| operator.has_source())
+            .for_each(|operator| {
+                if let Some(old) = old_sources
+                    .get(&(
+                        operator.get_logical_operator_id(),
+                        operator.get_instance_index(),
+                    ))
+                    .filter(|old| {
+                        old.operator_id != operator.operator_id
+                            || old.get_host_addr() != operator.get_host_addr()
+                    })
+                {
+                    moves.push(StateMove {
+                        operator_id: old.operator_id,
+                        to_operator_id: operator.operator_id,
+                        source: true,
+                        from: old.get_host_addr(),
+                        to: operator.get_host_addr(),
+                        key_groups: (0..key_groups::KEY_GROUPS).collect(),
+                    })
+                }
+            });
+
+        moves.retain(|state_move| state_move.from.is_valid() && state_move.to.is_valid());
+        moves
+    }
+
+    /// Probe both TaskManagers with requests of no key groups, so that a state which can't be moved is rejected before the dataflow is stopped
+    pub(crate) async fn check(
+        &self,
+        job_id: &ResourceId,
+        cluster: &Cluster,
+    ) -> Result<(), tonic::Status> {
+        get_gateway(cluster, &self.from)
+            .ok_or_else(|| unavailable_task_manager(&self.from).into_tonic_status())?
+            .export_key_groups(ExportKeyGroupsRequest {
+                job_id: Some(job_id.clone()),
+                operator_id: self.operator_id,
+                source: self.source,
+                key_groups: vec![],
+            })
+            .await?;
+        get_gateway(cluster, &self.to)
+            .ok_or_else(|| unavailable_task_manager(&self.to).into_tonic_status())?
+            .import_key_groups(KeyGroupStates {
+                job_id: Some(job_id.clone()),
+                operator_id: self.to_operator_id,
+                source: self.source,
+                key_groups: vec![],
+            })
+            .await
+            .map(|_| ())
+    }
+
+    /// copy the key groups to the new TaskManager
+    pub(crate) async fn copy(
+        &self,
+        job_id: &ResourceId,
+        cluster: &Cluster,
+    ) -> Result<(), tonic::Status> {
+        let mut states = get_gateway(cluster, &self.from)
+            .ok_or_else(|| unavailable_task_manager(&self.from).into_tonic_status())?
+            .export_key_groups(ExportKeyGroupsRequest {
+                job_id: Some(job_id.clone()),
+                operator_id: self.operator_id,
+                source: self.source,
+                key_groups: self.key_groups.clone(),
+            })
+            .await?;
+        states.operator_id = self.to_operator_id;
+        get_gateway(cluster, &self.to)
+            .ok_or_else(|| unavailable_task_manager(&self.to).into_tonic_status())?
+            .import_key_groups(states)
+            .await
+            .map(|_| ())
+    }
+
+    /// remove the key groups from the old TaskManager once all the moves are copied
+    pub(crate) async fn clear(
+        &self,
+        job_id: &ResourceId,
+        cluster: &Cluster,
+        moves: &[StateMove],
+    ) -> Result<(), tonic::Status> {
+        let key_groups = self.cleared_key_groups(moves);
+        if key_groups.is_empty() {
+            return Ok(());
+        }
+        get_gateway(cluster, &self.from)
+            .ok_or_else(|| unavailable_task_manager(&self.from).into_tonic_status())?
+            .import_key_groups(KeyGroupStates {
+                job_id: Some(job_id.clone()),
+                operator_id: self.operator_id,
+                source: self.source,
+                key_groups: key_groups
+                    .into_iter()
+                    .map(|key_group| KeyGroupState {
+                        key_group,
+                        entries: vec![],
+                    })
+                    .collect(),
+            })
+            .await
+            .map(|_| ())
+    }
+
+    /// the moved key groups, except the ones which other moves have copied to the same state.
+    /// A source instance may take over the executor id of another one on the same TaskManager
+    fn cleared_key_groups(&self, moves: &[StateMove]) -> Vec<u32> {
+        self.key_groups
+            .iter()
+            .filter(|key_group| {
+                !moves.iter().any(|other| {
+                    other.to == self.from
+                        && other.to_operator_id == self.operator_id
+                        && other.source == self.source
+                        && other.key_groups.contains(key_group)
+                })
+            })
+            .copied()
+            .collect()
+    }
+}
+
+/// the hosts of the instances of each operator which has keyed state, ordered by the indexes of the instances
+fn keyed_instance_hosts(dataflow: &Dataflow) -> BTreeMap<ExecutorId, Vec<HostAddr>> {
+    let mut instances = BTreeMap::<ExecutorId, Vec<(u32, HostAddr)>>::new();
+    dataflow
+        .nodes
+        .values()
+        .filter(|operator| operator.has_keyed_state())
+        .for_each(|operator| {
+            instances
+                .entry(operator.get_logical_operator_id())
+                .or_default()
+                .push((operator.get_instance_index(), operator.get_host_addr()))
+        });
+    instances
+        .into_iter()
+        .map(|(operator_id, mut hosts)| {
+            hosts.sort_by_key(|(index, _)| *index);
+            (
+                operator_id,
+                hosts.into_iter().map(|(_, host)| host).collect(),
+            )
+        })
+        .collect()
+}
+
+fn get_gateway<'a>(
+    cluster: &'a Cluster,
+    host_addr: &HostAddr,
+) -> Option<&'a SafeTaskManagerRpcGateway> {
+    cluster.get_node(host_addr).map(|node| node.get_gateway())
+}
+
+#[cfg(test)]
+mod tests {
+    use common::utils::key_groups::{range_of, KEY_GROUPS};
+    use proto::common::{
+        operator_info::Details, Dataflow, HostAddr, OperatorInfo, OperatorInstance, Reducer, Source,
+    };
+
+    use super::StateMove;
+
+    fn host(port: u32) -> HostAddr {
+        HostAddr {
+            host: "198.0.0.1".to_string(),
+            port,
+        }
+    }
+
+    fn instance(
+        operator_id: u32,
+        logical_operator_id: u32,
+        index: u32,
+        port: u32,
+        details: Details,
+    ) -> (u32, OperatorInfo) {
+        (
+            operator_id,
+            OperatorInfo {
+                operator_id,
+                host_addr: Some(host(port)),
+                instance: Some(OperatorInstance {
+                    operator_id: logical_operator_id,
+                    index,
+                }),
+                details: Some(details),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_plan_state_moves() {
+        let reducer = || Details::Reducer(Reducer::default());
+        let source = || Details::Source(Source::default());
+        let from = Dataflow {
+            nodes: vec![
+                instance(0, 0, 0, 8080, source()),
+                instance(1, 1, 0, 8080, reducer()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let to = Dataflow {
+            nodes: vec![
+                instance(0, 0, 0, 8081, source()),
+                instance(1, 1, 0, 8080, reducer()),
+                instance(2, 1, 1, 8081, reducer()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let moves = StateMove::plan(&from, &to);
+        assert_eq!(
+            moves,
+            vec![
+                StateMove {
+                    operator_id: 1,
+                    to_operator_id: 1,
+                    source: false,
+                    from: host(8080),
+                    to: host(8081),
+                    key_groups: range_of(1, 2).collect(),
+                },
+                StateMove {
+                    operator_id: 0,
+                    to_operator_id: 0,
+                    source: true,
+                    from: host(8080),
+                    to: host(8081),
+                    key_groups: (0..KEY_GROUPS).collect(),
+                }
+            ]
+        );
+
+        // nothing is moved if the placement doesn't change
+        assert!(StateMove::plan(&to, &to).is_empty());
+    }
+
+    #[test]
+    fn test_plan_state_moves_of_renumbered_sources() {
+        let reducer = || Details::Reducer(Reducer::default());
+        let source = || Details::Source(Source::default());
+        let from = Dataflow {
+            nodes: vec![
+                instance(0, 0, 0, 8080, reducer()),
+                instance(1, 1, 0, 8080, source()),
+                instance(2, 1, 1, 8081, source()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        // the second instance of the reducer takes the id of the second instance of the source
+        let to = Dataflow {
+            nodes: vec![
+                instance(0, 0, 0, 8080, reducer()),
+                instance(2, 0, 1, 8081, reducer()),
+                instance(1, 1, 0, 8080, source()),
+                instance(3, 1, 1, 8081, source()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let moves = StateMove::plan(&from, &to);
+        assert_eq!(
+            moves,
+            vec![
+                StateMove {
+                    operator_id: 0,
+                    to_operator_id: 0,
+                    source: false,
+                    from: host(8080),
+                    to: host(8081),
+                    key_groups: range_of(1, 2).collect(),
+                },
+                StateMove {
+                    operator_id: 2,
+                    to_operator_id: 3,
+                    source: true,
+                    from: host(8081),
+                    to: host(8081),
+                    key_groups: (0..KEY_GROUPS).collect(),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cleared_key_groups() {
+        let state_move = |operator_id, to_operator_id| StateMove {
+            operator_id,
+            to_operator_id,
+            source: true,
+            from: host(8080),
+            to: host(8080),
+            key_groups: (0..KEY_GROUPS).collect(),
+        };
+        // the positions of executor 3 are taken over by executor 4, and the ones of executor 2 by executor 3
+        let moves = vec![state_move(3, 4), state_move(2, 3)];
+        assert_eq!(moves[0].cleared_key_groups(&moves), Vec::<u32>::new());
+        assert_eq!(
+            moves[1].cleared_key_groups(&moves),
+            (0..KEY_GROUPS).collect::<Vec<_>>()
+        );
+    }
+}
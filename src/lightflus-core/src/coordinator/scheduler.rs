@@ -1,4 +1,5 @@
 use crossbeam_skiplist::SkipMap;
+use futures_util::future::join_all;
 use proto::common::{
     Ack, Dataflow, DataflowStates, DataflowStatus, Heartbeat, SubDataflowId, SubdataflowInfo,
 };
@@ -39,6 +40,32 @@ impl Scheduler {
         Ok(DataflowStatus::Closing)
     }
 
+    /// Stop all subdataflows and drop their executions. Subdataflows which fail to stop are kept
+    pub(crate) async fn stop_dataflow(&self) -> Result<(), TaskExecutionException> {
+        // subdataflows are stopped concurrently, because their executors wait for the upstreams on other TaskManagers to terminate
+        let results = join_all(
+            self.executions
+                .iter()
+                .map(|entry| async move { entry.value().stop().await.map(|_| entry) }),
+        )
+        .await;
+        let mut errors = vec![];
+        for result in results {
+            match result {
+                Ok(entry) => {
+                    entry.remove();
+                }
+                Err(err) => errors.push(err),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(TaskExecutionException::SubdataflowErrors(errors))
+        }
+    }
+
     pub(crate) async fn receive_heartbeat(&self, heartbeat: &Heartbeat) {
         match heartbeat
             .get_subdataflow_id()
@@ -93,6 +120,11 @@ pub(crate) enum TaskExecutionException {
 
 impl TaskExecutionException {
     pub(crate) fn to_tonic_status(&self) -> tonic::Status {
-        todo!()
+        match self {
+            TaskExecutionException::SubdataflowErrors(errors) => match errors.as_slice() {
+                [SubdataflowError::RpcError(status)] => status.clone(),
+                _ => tonic::Status::internal(format!("{:?}", errors)),
+            },
+        }
     }
 }
@@ -38,6 +38,53 @@ pub mod taskmanager {
         }
     }
 
+    pub fn no_found_collect_sink() -> RpcError {
+        RpcError {
+            biz_err: BizError {
+                biz_code: TASK_MANAGER_BIZ_CODE,
+                error_code: 10,
+                message: "no collect sink found".to_string(),
+            },
+            status: tonic::Status::not_found("no collect sink found"),
+        }
+    }
+
+    pub fn edge_handshake_unprovided() -> RpcError {
+        RpcError {
+            biz_err: BizError {
+                biz_code: TASK_MANAGER_BIZ_CODE,
+                error_code: 11,
+                message: "the first frame of edge stream should be handshake".to_string(),
+            },
+            status: tonic::Status::invalid_argument(
+                "the first frame of edge stream should be handshake",
+            ),
+        }
+    }
+
+    pub fn no_found_operator() -> RpcError {
+        RpcError {
+            biz_err: BizError {
+                biz_code: TASK_MANAGER_BIZ_CODE,
+                error_code: 12,
+                message: "no operator found".to_string(),
+            },
+            status: tonic::Status::not_found("no operator found"),
+        }
+    }
+
+    pub fn unmovable_state() -> RpcError {
+        let message = "key groups of the memory state can't be moved, the key-value state should be configured for rescaling";
+        RpcError {
+            biz_err: BizError {
+                biz_code: TASK_MANAGER_BIZ_CODE,
+                error_code: 14,
+                message: message.to_string(),
+            },
+            status: tonic::Status::failed_precondition(message),
+        }
+    }
+
     #[derive(Debug)]
     pub enum TaskWorkerError {
         DataflowValidateError(DataflowValidateError),
@@ -45,6 +92,8 @@ pub mod taskmanager {
         ChannelEmpty,
         ExecutionError(String),
         EventSendFailure(String),
+        QueueFull(String),
+        LegacyState(String),
     }
 
     impl From<TryRecvError> for TaskWorkerError {
@@ -96,6 +145,17 @@ pub mod taskmanager {
                     rpc_err.biz_err.error_code = 8;
                     rpc_err.biz_err.message = format!("event sent error: {:?}", err);
                 }
+                TaskWorkerError::QueueFull(err) => {
+                    rpc_err.status =
+                        tonic::Status::resource_exhausted(format!("queue is full: {:?}", err));
+                    rpc_err.biz_err.error_code = 9;
+                    rpc_err.biz_err.message = format!("queue is full: {:?}", err);
+                }
+                TaskWorkerError::LegacyState(err) => {
+                    rpc_err.status = tonic::Status::failed_precondition(err);
+                    rpc_err.biz_err.error_code = 13;
+                    rpc_err.biz_err.message = err.clone();
+                }
             }
             rpc_err.into_tonic_status()
         }
@@ -104,7 +164,7 @@ pub mod taskmanager {
 
 pub mod coordinator {
     use common::err::{BizCode, BizError, RpcError};
-    use proto::common::{DataflowStatus, ResourceId};
+    use proto::common::{DataflowStatus, HostAddr, ResourceId};
 
     pub const COORDINATOR_BIZ_CODE: BizCode = 100;
 
@@ -142,6 +202,29 @@ pub mod coordinator {
             status: tonic::Status::not_found(message),
         }
     }
+
+    pub fn invalid_rescale(message: &str) -> RpcError {
+        RpcError {
+            biz_err: BizError {
+                biz_code: COORDINATOR_BIZ_CODE,
+                error_code: 4,
+                message: message.to_string(),
+            },
+            status: tonic::Status::invalid_argument(message),
+        }
+    }
+
+    pub fn unavailable_task_manager(host_addr: &HostAddr) -> RpcError {
+        let message = format!("task manager {:?} is unavailable", host_addr);
+        RpcError {
+            biz_err: BizError {
+                biz_code: COORDINATOR_BIZ_CODE,
+                error_code: 5,
+                message: message.clone(),
+            },
+            status: tonic::Status::unavailable(message),
+        }
+    }
 }
 
 pub mod apiserver {
@@ -1,6 +1,9 @@
 use std::fs;
 
-use common::utils;
+use common::{
+    net::{RpcCompression, TlsConfig},
+    utils,
+};
 use crossbeam_skiplist::SkipMap;
 use proto::{
     common::{
@@ -8,16 +11,31 @@ use proto::{
         SubDataflowStates,
     },
     taskmanager::{
+        edge_frame::Frame,
         task_manager_api_server::{TaskManagerApi, TaskManagerApiServer},
         BatchSendEventsToOperatorResponse, CreateSubDataflowRequest, CreateSubDataflowResponse,
+        EdgeAck, EdgeFrame, ExportKeyGroupsRequest, GetCollectedEventsRequest,
+        GetCollectedEventsResponse, KeyGroupEntry, KeyGroupState, KeyGroupStates,
         SendEventToOperatorResponse, StopDataflowResponse,
     },
 };
+use stream::state::{
+    close_state, new_source_state_mgt, new_state_mgt, state_mgt_type, StateManager, StateMangerType,
+};
 
-use tonic::async_trait;
+use tonic::{
+    async_trait,
+    codec::CompressionEncoding,
+    codegen::BoxStream,
+    transport::{ClientTlsConfig, Server},
+    Streaming,
+};
 
 use crate::{
-    errors::taskmanager::{execution_id_unprovided, no_found_worker, resource_id_unprovided},
+    errors::taskmanager::{
+        edge_handshake_unprovided, execution_id_unprovided, no_found_collect_sink,
+        no_found_operator, no_found_worker, resource_id_unprovided, unmovable_state,
+    },
     new_rpc_response,
     taskmanager::taskworker::{TaskWorker, TaskWorkerBuilder},
     RpcRequest, RpcResponse,
@@ -29,6 +47,12 @@ pub struct TaskManagerBuilder {
     pub port: usize,
     // max available number of jobs
     pub max_job_nums: usize,
+    // compression of the messages sent to other nodes, "none" or "gzip". It should be the same as the one of the coordinator
+    #[serde(default)]
+    pub compression: RpcCompression,
+    // TLS of the server and the connections to other task managers
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
 }
 
 pub fn load_builder() -> TaskManagerBuilder {
@@ -49,14 +73,71 @@ pub fn load_builder() -> TaskManagerBuilder {
 }
 
 impl TaskManagerBuilder {
+    /// The server always accepts compressed requests, and it compresses responses if compression is enabled and the client accepts it
     pub fn build(&self) -> TaskManagerApiServer<TaskManager> {
         let workers = SkipMap::new();
-        TaskManagerApiServer::new(TaskManager { workers })
+        let server = TaskManagerApiServer::new(TaskManager {
+            workers,
+            compression: self.compression,
+            tls: self
+                .tls
+                .as_ref()
+                .map(|tls| tls.client_config().expect("load tls config failed: ")),
+        })
+        .accept_compressed(CompressionEncoding::Gzip);
+        match self.compression.encoding() {
+            Some(encoding) => server.send_compressed(encoding),
+            None => server,
+        }
+    }
+
+    /// The server which serves over TLS if it's configured
+    pub fn server_builder(&self) -> Server {
+        match &self.tls {
+            Some(tls) => Server::builder()
+                .tls_config(tls.server_config().expect("load tls config failed: "))
+                .expect("invalid tls config: "),
+            None => Server::builder(),
+        }
     }
 }
 
+/// the entries of the key groups in the state
+fn export_key_groups(state: impl StateManager, key_groups: &[u32]) -> Vec<KeyGroupState> {
+    key_groups
+        .iter()
+        .map(|key_group| KeyGroupState {
+            key_group: *key_group,
+            entries: state
+                .export_key_group(*key_group)
+                .into_iter()
+                .map(|(key, value)| KeyGroupEntry { key, value })
+                .collect(),
+        })
+        .collect()
+}
+
+/// each key group replaces the one in the state
+fn import_key_groups(state: impl StateManager, key_groups: Vec<KeyGroupState>) {
+    key_groups.into_iter().for_each(|key_group| {
+        state.remove_key_group(key_group.key_group);
+        if !key_group.entries.is_empty() {
+            state.import_key_group(
+                key_group.key_group,
+                key_group
+                    .entries
+                    .into_iter()
+                    .map(|entry| (entry.key, entry.value))
+                    .collect(),
+            )
+        }
+    })
+}
+
 pub struct TaskManager {
     workers: SkipMap<ResourceId, TaskWorker>,
+    compression: RpcCompression,
+    tls: Option<ClientTlsConfig>,
 }
 
 #[async_trait]
@@ -73,10 +154,10 @@ impl TaskManagerApi for TaskManager {
             Some(worker) => worker
                 .value()
                 .send_event_to_operator(event)
-                .await
-                .map(|status| {
+                .map(|(status, credits)| {
                     new_rpc_response(SendEventToOperatorResponse {
                         status: status as i32,
+                        credits,
                     })
                 })
                 .map_err(|err| err.into_grpc_status()),
@@ -88,12 +169,12 @@ impl TaskManagerApi for TaskManager {
         &self,
         request: RpcRequest<ResourceId>,
     ) -> RpcResponse<StopDataflowResponse> {
+        // the events which have been read are written before the state is closed, so that it can be exported
         match self.workers.remove(request.get_ref()) {
-            Some(entry) => {
-                entry.remove();
-            }
+            Some(entry) => entry.value().stop().await,
             None => {}
         };
+        close_state(request.get_ref());
         Ok(new_rpc_response(StopDataflowResponse::default()))
     }
 
@@ -103,15 +184,17 @@ impl TaskManagerApi for TaskManager {
     ) -> RpcResponse<CreateSubDataflowResponse> {
         let request = request.into_inner();
         let opt = request.dataflow.as_ref();
-        opt.and_then(|dataflow| dataflow.job_id.as_ref())
+        if let Some(entry) = opt
+            .and_then(|dataflow| dataflow.job_id.as_ref())
             .and_then(|job_id| self.workers.remove(job_id))
-            .iter()
-            .for_each(|entry| {
-                entry.remove();
-            });
+        {
+            entry.value().stop().await;
+        }
         match opt {
             Some(dataflow) => {
-                let worker_builder = TaskWorkerBuilder::new(dataflow);
+                let worker_builder = TaskWorkerBuilder::new(dataflow)
+                    .with_compression(self.compression)
+                    .with_tls(self.tls.clone());
                 match worker_builder.build().await {
                     Ok(worker) => {
                         match dataflow.job_id.as_ref() {
@@ -182,10 +265,11 @@ impl TaskManagerApi for TaskManager {
             Some(worker) => worker
                 .value()
                 .batch_send_event_to_operator(event_set)
-                .await
-                .map(|_status| new_rpc_response(BatchSendEventsToOperatorResponse {}))
+                .map(|(_, credits)| new_rpc_response(BatchSendEventsToOperatorResponse { credits }))
                 .map_err(|err| err.into_grpc_status()),
-            None => Ok(new_rpc_response(BatchSendEventsToOperatorResponse {})),
+            None => Ok(new_rpc_response(
+                BatchSendEventsToOperatorResponse::default(),
+            )),
         }
     }
     async fn get_sub_dataflow(
@@ -199,4 +283,114 @@ impl TaskManagerApi for TaskManager {
             None => Err(no_found_worker().into_tonic_status()),
         }
     }
+
+    async fn get_collected_events(
+        &self,
+        request: RpcRequest<GetCollectedEventsRequest>,
+    ) -> RpcResponse<GetCollectedEventsResponse> {
+        let request = request.into_inner();
+        let worker = match request
+            .job_id
+            .as_ref()
+            .and_then(|job_id| self.workers.get(job_id))
+        {
+            Some(entry) => entry,
+            None => return Err(no_found_worker().into_tonic_status()),
+        };
+        match worker
+            .value()
+            .get_collected_events(request.operator_id)
+            .await
+        {
+            Some(events) => Ok(new_rpc_response(GetCollectedEventsResponse { events })),
+            None => Err(no_found_collect_sink().into_tonic_status()),
+        }
+    }
+
+    type OpenEdgeStream = BoxStream<EdgeAck>;
+
+    async fn open_edge(
+        &self,
+        request: RpcRequest<Streaming<EdgeFrame>>,
+    ) -> RpcResponse<Self::OpenEdgeStream> {
+        let mut frames = request.into_inner();
+        let handshake = match frames.message().await? {
+            Some(EdgeFrame {
+                frame: Some(Frame::Handshake(handshake)),
+                ..
+            }) => handshake,
+            _ => return Err(edge_handshake_unprovided().into_tonic_status()),
+        };
+        let worker = match handshake
+            .job_id
+            .as_ref()
+            .and_then(|job_id| self.workers.get(job_id))
+        {
+            Some(entry) => entry,
+            None => return Err(no_found_worker().into_tonic_status()),
+        };
+        match worker.value().get_remote_in_edge(handshake.to_operator_id) {
+            Some(in_edge) => Ok(new_rpc_response(in_edge.serve(handshake, frames))),
+            None => Err(no_found_operator().into_tonic_status()),
+        }
+    }
+
+    async fn export_key_groups(
+        &self,
+        request: RpcRequest<ExportKeyGroupsRequest>,
+    ) -> RpcResponse<KeyGroupStates> {
+        // the memory state of a TaskManager is dropped with the executors, so it has nothing to export
+        if !matches!(state_mgt_type(), StateMangerType::KeyValue) {
+            return Err(unmovable_state().into_tonic_status());
+        }
+        let request = request.into_inner();
+        let job_id = match request.job_id.as_ref() {
+            Some(job_id) => job_id,
+            None => return Err(resource_id_unprovided().into_tonic_status()),
+        };
+        let key_groups = if request.source {
+            export_key_groups(
+                new_source_state_mgt(job_id, request.operator_id),
+                &request.key_groups,
+            )
+        } else {
+            export_key_groups(
+                new_state_mgt(job_id, request.operator_id),
+                &request.key_groups,
+            )
+        };
+        Ok(new_rpc_response(KeyGroupStates {
+            job_id: request.job_id.clone(),
+            operator_id: request.operator_id,
+            source: request.source,
+            key_groups,
+        }))
+    }
+
+    async fn import_key_groups(
+        &self,
+        request: RpcRequest<KeyGroupStates>,
+    ) -> RpcResponse<Response> {
+        // the executors read a fresh memory state once they are deployed, so the imported key groups would be lost
+        if !matches!(state_mgt_type(), StateMangerType::KeyValue) {
+            return Err(unmovable_state().into_tonic_status());
+        }
+        let request = request.into_inner();
+        let job_id = match request.job_id.as_ref() {
+            Some(job_id) => job_id,
+            None => return Err(resource_id_unprovided().into_tonic_status()),
+        };
+        if request.source {
+            import_key_groups(
+                new_source_state_mgt(job_id, request.operator_id),
+                request.key_groups,
+            )
+        } else {
+            import_key_groups(
+                new_state_mgt(job_id, request.operator_id),
+                request.key_groups,
+            )
+        }
+        Ok(new_rpc_response(Response::ok()))
+    }
 }
@@ -1,25 +1,42 @@
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
+use std::time::Duration;
+use std::time::Instant;
+
+use common::consts::default_configs::DEFAULT_TASK_TERMINATE_TIMEOUT_MILLIS;
+use common::consts::env_keys::TASK_TERMINATE_TIMEOUT;
 
 use common::event::LocalEvent;
+use common::net::RpcCompression;
 use common::types::ExecutorId;
+use common::utils::get_env;
 use common::utils::is_remote_operator;
+use proto::common::operator_info::Details;
 use proto::common::Ack;
 use proto::common::Dataflow;
+use proto::common::DataflowMeta;
 use proto::common::Heartbeat;
 use proto::common::KeyedDataEvent;
 
 use proto::common::KeyedEventSet;
 use proto::common::NodeType;
+use proto::common::OperatorInfo;
 
 use proto::common::SubDataflowId;
 use proto::common::SubdataflowInfo;
 use proto::taskmanager::SendEventToOperatorStatusEnum;
 
+use stream::connector::CollectedEvents;
 use stream::connector::SinkImpl;
+use stream::edge::RemoteInEdge;
+use stream::err::TaskError;
+use stream::partition::OutEdgePartitioner;
+use stream::state::upgrade_legacy_state;
 use stream::task::EdgeBuilder;
 
 use stream::task::Task;
+use tonic::transport::ClientTlsConfig;
 
 use crate::errors::taskmanager::TaskWorkerError;
 
@@ -27,18 +44,51 @@ use crate::errors::taskmanager::TaskWorkerError;
 pub struct TaskWorker {
     tasks: HashMap<ExecutorId, Task>,
     subdataflow_id: SubDataflowId,
+    // events kept by collect sinks
+    collected_events: HashMap<ExecutorId, CollectedEvents>,
+    // chained operators and the operators whose tasks run them
+    chained_operators: HashMap<ExecutorId, ExecutorId>,
 }
 
 pub(crate) struct TaskWorkerBuilder<'a> {
     dataflow: &'a Dataflow,
+    // compression of the events sent to remote operators
+    compression: RpcCompression,
+    // TLS of the connections to remote operators
+    tls: Option<ClientTlsConfig>,
 }
 
 impl<'a> TaskWorkerBuilder<'a> {
     pub(crate) fn new(dataflow: &'a Dataflow) -> Self {
-        Self { dataflow }
+        Self {
+            dataflow,
+            compression: Default::default(),
+            tls: None,
+        }
+    }
+
+    pub(crate) fn with_compression(mut self, compression: RpcCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub(crate) fn with_tls(mut self, tls: Option<ClientTlsConfig>) -> Self {
+        self.tls = tls;
+        self
     }
 
     pub(crate) async fn build(&self) -> Result<TaskWorker, TaskWorkerError> {
+        if let Some(job_id) = self.dataflow.job_id.as_ref() {
+            upgrade_legacy_state(
+                job_id,
+                self.dataflow
+                    .nodes
+                    .values()
+                    .filter(|operator_info| operator_info.has_source())
+                    .map(|operator_info| operator_info.operator_id),
+            )
+            .map_err(TaskWorkerError::LegacyState)?;
+        }
         self.dataflow
             .validate()
             .map(|_| {
@@ -54,7 +104,31 @@ impl<'a> TaskWorkerBuilder<'a> {
 
                 let job_id = self.dataflow.job_id.as_ref().unwrap();
                 let info_set = &self.dataflow.nodes;
+                let chains = self.build_chains();
+                chains.iter().for_each(|(head, chain)| {
+                    chain.iter().for_each(|operator_id| {
+                        worker.chained_operators.insert(*operator_id, *head);
+                    })
+                });
+
                 self.dataflow.meta.iter().for_each(|meta| {
+                    if worker.chained_operators.contains_key(&meta.center) {
+                        return;
+                    }
+                    // the task of a chain writes to the downstreams of the last chained operator
+                    let meta = match chains.get(&meta.center).and_then(|chain| chain.last()) {
+                        Some(tail) => DataflowMeta {
+                            center: meta.center,
+                            neighbors: self
+                                .dataflow
+                                .meta
+                                .iter()
+                                .find(|meta| meta.center == *tail)
+                                .map(|meta| meta.neighbors.clone())
+                                .unwrap_or_default(),
+                        },
+                        None => meta.clone(),
+                    };
                     let info = info_set.get(&meta.center).unwrap();
                     let task = Task::new(job_id, &meta);
                     edge_builders.insert(meta.center, EdgeBuilder::local(info));
@@ -64,7 +138,14 @@ impl<'a> TaskWorkerBuilder<'a> {
                         if is_remote_operator(neighbor_info)
                             && !edge_builders.contains_key(neighbor_id)
                         {
-                            edge_builders.insert(*neighbor_id, EdgeBuilder::remote(neighbor_info));
+                            edge_builders.insert(
+                                *neighbor_id,
+                                EdgeBuilder::remote(
+                                    neighbor_info,
+                                    self.compression,
+                                    self.tls.as_ref(),
+                                ),
+                            );
                         } else if !edge_builders.contains_key(neighbor_id) {
                             edge_builders.insert(*neighbor_id, EdgeBuilder::local(info));
                         }
@@ -79,22 +160,45 @@ impl<'a> TaskWorkerBuilder<'a> {
                         let operator_info = info_set.get(&executor_id).unwrap();
 
                         let mut executor = task.create_stream_executor(operator_info);
+                        chains.get(&executor_id).iter().for_each(|chain| {
+                            chain.iter().for_each(|operator_id| {
+                                executor.chain_operator(info_set.get(operator_id).unwrap())
+                            })
+                        });
                         task.get_downstream_id_iter().for_each(|dowstream_id| {
                             edge_builders.get(dowstream_id).iter().for_each(|builder| {
                                 let out_edge = (*builder).build_out_edge();
                                 executor.add_out_edge(*dowstream_id, out_edge);
                             });
                         });
-
-                        // if operator is not Source, it should create an out-edge for [`TaskWorker`] to send operator
-                        if !operator_info.has_source() {
+                        // the last operator of a chain decides how its results are partitioned
+                        let output_info = chains
+                            .get(&executor_id)
+                            .and_then(|chain| chain.last())
+                            .and_then(|tail| info_set.get(tail))
+                            .unwrap_or(operator_info);
+                        executor.set_partitioner(OutEdgePartitioner::new(
+                            output_info,
+                            task.get_downstream_id_iter()
+                                .filter_map(|downstream_id| info_set.get(downstream_id)),
+                        ));
+
+                        // if operator is not Source or is a push Source, it should create an out-edge for [`TaskWorker`] to send operator
+                        if !operator_info.has_source() || operator_info.has_push_source() {
                             let builder = edge_builders.remove(&executor_id).unwrap();
-                            task.set_in_edge(builder.build_out_edge());
+                            builder
+                                .build_task_in_edge()
+                                .into_iter()
+                                .for_each(|in_edge| task.set_in_edge(in_edge));
                             executor.set_in_edge(builder.build_in_edge())
                         }
 
                         if operator_info.has_sink() {
-                            executor.add_external_sink(SinkImpl::from((job_id, operator_info)))
+                            let sink = SinkImpl::from((job_id, operator_info));
+                            if let Some(events) = sink.get_collected_events() {
+                                worker.collected_events.insert(executor_id, events);
+                            }
+                            executor.add_external_sink(sink)
                         }
 
                         task.start(executor);
@@ -107,22 +211,102 @@ impl<'a> TaskWorkerBuilder<'a> {
             })
             .map_err(|err| TaskWorkerError::DataflowValidateError(err))
     }
+
+    /// Consecutive one-to-one operators on this TaskManager are chained into one executor, which passes the events between them directly.
+    /// An operator is chained after its upstream if it's the only downstream of the upstream and the upstream is its only upstream.
+    /// It returns the chained operators of each chain's first operator in order
+    fn build_chains(&self) -> BTreeMap<ExecutorId, Vec<ExecutorId>> {
+        let metas = self
+            .dataflow
+            .meta
+            .iter()
+            .map(|meta| (meta.center, meta))
+            .collect::<BTreeMap<_, _>>();
+        // the sub-dataflow only has the metas of local operators, so upstreams on other TaskManagers are counted by the ones of operators
+        let mut upstream_counts = BTreeMap::<ExecutorId, usize>::new();
+        metas
+            .values()
+            .flat_map(|meta| {
+                meta.neighbors
+                    .iter()
+                    .map(|neighbor_id| (meta.center, *neighbor_id))
+            })
+            .chain(self.dataflow.nodes.values().flat_map(|info| {
+                info.upstreams
+                    .iter()
+                    .map(|upstream_id| (*upstream_id, info.operator_id))
+            }))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .for_each(|(_, operator_id)| *upstream_counts.entry(operator_id).or_default() += 1);
+
+        let is_chainable = |operator_id: &ExecutorId| {
+            metas.contains_key(operator_id)
+                && self
+                    .dataflow
+                    .nodes
+                    .get(operator_id)
+                    .filter(|info| is_chainable_operator(info))
+                    .is_some()
+        };
+        let next_chained = |operator_id: &ExecutorId| match metas.get(operator_id) {
+            Some(meta) if is_chainable(operator_id) => match meta.neighbors.as_slice() {
+                [next] if upstream_counts.get(next) == Some(&1) && is_chainable(next) => {
+                    Some(*next)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let chained = metas
+            .keys()
+            .filter_map(|operator_id| next_chained(operator_id))
+            .collect::<BTreeSet<_>>();
+
+        metas
+            .keys()
+            .filter(|operator_id| !chained.contains(*operator_id))
+            .filter_map(|head| {
+                let mut chain = vec![];
+                let mut current = *head;
+                while let Some(next) = next_chained(&current) {
+                    chain.push(next);
+                    current = next;
+                }
+                if chain.is_empty() {
+                    None
+                } else {
+                    Some((*head, chain))
+                }
+            })
+            .collect()
+    }
+}
+
+/// stateless operators which produce events for a single downstream
+fn is_chainable_operator(info: &OperatorInfo) -> bool {
+    !is_remote_operator(info)
+        && match &info.details {
+            Some(Details::Mapper(_)) | Some(Details::Filter(_)) | Some(Details::FlatMap(_)) => true,
+            _ => false,
+        }
 }
 
 impl TaskWorker {
+    /// It returns the credits granted to the sender with the status
     #[inline]
-    pub async fn send_event_to_operator(
+    pub fn send_event_to_operator(
         &self,
         event: KeyedDataEvent,
-    ) -> Result<SendEventToOperatorStatusEnum, TaskWorkerError> {
+    ) -> Result<(SendEventToOperatorStatusEnum, u32), TaskWorkerError> {
         let executor_id = event.to_operator_id;
         match self.tasks.get(&executor_id) {
             Some(task) => task
                 .send_event_to_operator(LocalEvent::KeyedDataStreamEvent(event))
-                .await
-                .map(|_| SendEventToOperatorStatusEnum::Done)
-                .map_err(|err| TaskWorkerError::EventSendFailure(err.to_string())),
-            None => Ok(SendEventToOperatorStatusEnum::Done),
+                .map(|credits| (SendEventToOperatorStatusEnum::Done, credits))
+                .map_err(|err| into_send_event_error(err)),
+            None => Ok((SendEventToOperatorStatusEnum::Done, 0)),
         }
     }
 
@@ -148,18 +332,52 @@ impl TaskWorker {
         }
     }
 
+    /// It returns the credits granted to the sender with the status
     #[inline]
-    pub async fn batch_send_event_to_operator(
+    pub fn batch_send_event_to_operator(
         &self,
         event_set: KeyedEventSet,
-    ) -> Result<SendEventToOperatorStatusEnum, TaskWorkerError> {
+    ) -> Result<(SendEventToOperatorStatusEnum, u32), TaskWorkerError> {
         match self.tasks.get(&event_set.to_operator_id) {
             Some(task) => task
                 .batch_send_event_to_operator(event_set)
-                .await
-                .map(|_| SendEventToOperatorStatusEnum::Done)
-                .map_err(|err| TaskWorkerError::EventSendFailure(err.to_string())),
-            None => Ok(SendEventToOperatorStatusEnum::Done),
+                .map(|credits| (SendEventToOperatorStatusEnum::Done, credits))
+                .map_err(|err| into_send_event_error(err)),
+            None => Ok((SendEventToOperatorStatusEnum::Done, 0)),
+        }
+    }
+
+    /// the endpoint which serves the streams of remote edges to the operator
+    pub fn get_remote_in_edge(&self, operator_id: ExecutorId) -> Option<RemoteInEdge> {
+        self.tasks
+            .get(&operator_id)
+            .and_then(|task| task.get_remote_in_edge())
+    }
+
+    /// events kept by the collect sink of the operator. It's [None] if the operator is not a collect sink
+    pub async fn get_collected_events(
+        &self,
+        operator_id: ExecutorId,
+    ) -> Option<Vec<KeyedDataEvent>> {
+        match self.collected_events.get(&operator_id) {
+            Some(events) => Some(events.get_events().await),
+            None => None,
+        }
+    }
+
+    /// Stop the sources and wait until the executors terminate, so that the events which have been read are written to the downstream operators.
+    /// Executors which haven't terminated in the timeout are aborted
+    pub async fn stop(&self) {
+        self.tasks.values().for_each(|task| task.stop());
+        let deadline = Instant::now()
+            + Duration::from_millis(
+                get_env(TASK_TERMINATE_TIMEOUT)
+                    .and_then(|timeout| timeout.parse().ok())
+                    .unwrap_or(DEFAULT_TASK_TERMINATE_TIMEOUT_MILLIS),
+            );
+        for task in self.tasks.values() {
+            task.wait_terminated(deadline.saturating_duration_since(Instant::now()))
+                .await;
         }
     }
 
@@ -174,15 +392,36 @@ impl TaskWorker {
                 .insert(*executor_id, task.get_state().await);
         }
 
+        // chained operators share the state of the task which runs them
+        for (executor_id, head) in &self.chained_operators {
+            if let Some(task) = self.tasks.get(head) {
+                let mut state = task.get_state().await;
+                state.executor_id = *executor_id;
+                info.executors_info.insert(*executor_id, state);
+            }
+        }
+
         info
     }
 }
 
+/// the sender should send the events again once it has credits if the operator has no room for them
+fn into_send_event_error(err: TaskError) -> TaskWorkerError {
+    match &err {
+        TaskError::OutEdgeError(edge_err) if edge_err.is_queue_full() => {
+            TaskWorkerError::QueueFull(err.to_string())
+        }
+        _ => TaskWorkerError::EventSendFailure(err.to_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
 
-    use proto::common::{Dataflow, OperatorInfo, ResourceId, SubDataflowId};
+    use proto::common::{
+        operator_info::Details, Dataflow, DataflowMeta, OperatorInfo, ResourceId, SubDataflowId,
+    };
 
     use super::TaskWorkerBuilder;
 
@@ -208,6 +447,78 @@ mod tests {
         assert!(result.is_ok())
     }
 
+    #[test]
+    fn test_task_worker_build_chains() {
+        let operator = |operator_id: u32, details: Details| OperatorInfo {
+            operator_id,
+            details: Some(details),
+            ..Default::default()
+        };
+        let meta = |center: u32, neighbors: Vec<u32>| DataflowMeta { center, neighbors };
+
+        // 0 -> 1 -> 2 -> 3 -> 4 -> 5, 4 -> 6, 6 -> 7 and 5 -> 7
+        let dataflow = Dataflow {
+            job_id: Some(ResourceId {
+                resource_id: "resource_id".to_string(),
+                namespace_id: "namespace_id".to_string(),
+            }),
+            meta: vec![
+                meta(0, vec![1]),
+                meta(1, vec![2]),
+                meta(2, vec![3]),
+                meta(3, vec![4]),
+                meta(4, vec![5, 6]),
+                meta(5, vec![7]),
+                meta(6, vec![7]),
+                meta(7, vec![]),
+            ],
+            nodes: HashMap::from_iter(vec![
+                (0, operator(0, Details::Source(Default::default()))),
+                (1, operator(1, Details::Mapper(Default::default()))),
+                (2, operator(2, Details::Filter(Default::default()))),
+                (3, operator(3, Details::Mapper(Default::default()))),
+                (4, operator(4, Details::FlatMap(Default::default()))),
+                (5, operator(5, Details::Mapper(Default::default()))),
+                (6, operator(6, Details::Mapper(Default::default()))),
+                (7, operator(7, Details::Mapper(Default::default()))),
+            ]),
+            execution_id: None,
+        };
+
+        let chains = TaskWorkerBuilder::new(&dataflow).build_chains();
+        assert_eq!(chains, BTreeMap::from_iter([(1, vec![2, 3, 4])]));
+    }
+
+    #[test]
+    fn test_task_worker_build_chains_with_remote_upstream() {
+        let operator = |operator_id: u32, upstreams: Vec<u32>| OperatorInfo {
+            operator_id,
+            upstreams,
+            details: Some(Details::Mapper(Default::default())),
+            ..Default::default()
+        };
+        let meta = |center: u32, neighbors: Vec<u32>| DataflowMeta { center, neighbors };
+
+        // 1 -> 2 -> 3 on this TaskManager, and 0 -> 2 on another one
+        let dataflow = Dataflow {
+            job_id: Some(ResourceId {
+                resource_id: "resource_id".to_string(),
+                namespace_id: "namespace_id".to_string(),
+            }),
+            meta: vec![meta(1, vec![2]), meta(2, vec![3]), meta(3, vec![])],
+            nodes: HashMap::from_iter(vec![
+                (1, operator(1, vec![])),
+                (2, operator(2, vec![0, 1])),
+                (3, operator(3, vec![2])),
+            ]),
+            execution_id: None,
+        };
+
+        // 2 keeps its own task to receive the events of 0
+        let chains = TaskWorkerBuilder::new(&dataflow).build_chains();
+        assert_eq!(chains, BTreeMap::from_iter([(2, vec![3])]));
+    }
+
     #[tokio::test]
     async fn test_edge_builder_build_out_edge() {}
 }
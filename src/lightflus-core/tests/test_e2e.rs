@@ -1,10 +1,16 @@
-use std::{collections::HashMap, sync::Once, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Once,
+    time::Duration,
+};
 
 use common::{
+    generator::{get_input_key, GeneratorInputs},
     kafka::run_producer,
     net::{
-        cluster::ClusterBuilder, gateway::coordinator::SafeCoordinatorRpcGateway,
-        AckResponderBuilder, HeartbeatBuilder,
+        cluster::ClusterBuilder,
+        gateway::{coordinator::SafeCoordinatorRpcGateway, taskmanager::SafeTaskManagerRpcGateway},
+        AckResponderBuilder, HeartbeatBuilder, RpcCompression,
     },
     redis::RedisClient,
     types::TypedValue,
@@ -17,11 +23,14 @@ use lightflus_core::{
 };
 use proto::{
     common::{
-        flat_map, kafka_desc, key_by, operator_info, redis_desc::ConnectionOpts, reducer, sink,
-        source, DataTypeEnum, Dataflow, DataflowMeta, DeliveryGuarentee, FlatMap, Func, HostAddr,
-        KafkaDesc, KeyBy, OperatorInfo, RedisDesc, Reducer, ResourceId, Sink, Source,
+        flat_map, kafka_desc, key_by, operator_info,
+        redis_desc::{self, ConnectionOpts},
+        reducer, sink, source, CollectDesc, DataTypeEnum, Dataflow, DataflowMeta,
+        DeliveryGuarentee, FlatMap, Func, GeneratorDesc, HostAddr, KafkaDesc, KeyBy, OperatorInfo,
+        RedisDesc, Reducer, ResourceId, Sink, Source,
     },
-    coordinator::coordinator_api_server::CoordinatorApiServer,
+    coordinator::{coordinator_api_server::CoordinatorApiServer, RescaleDataflowRequest},
+    taskmanager::GetCollectedEventsRequest,
 };
 use stream::initialize_v8;
 use tokio::{task::JoinHandle, time};
@@ -33,6 +42,11 @@ pub fn setup() {
     ONCE.call_once(|| {
         tracing_subscriber::fmt::init();
         initialize_v8();
+        // operators keep their state in a fresh key-value store, which is kept while the dataflows are rescaled
+        let dir = std::env::temp_dir().join("lightflus-test-e2e-state");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::env::set_var("KEY_VALUE_STATE_PATH", dir.to_str().unwrap_or_default());
+        std::env::set_var("STATE_MANAGER", "key_value");
     })
 }
 
@@ -74,6 +88,9 @@ fn setup_wordcount_dataflow(worker_port: u32) -> Dataflow {
                         port: worker_port,
                     }),
                     upstreams: vec![],
+                    out_edge_batch: None,
+                    parallelism: 0,
+                    instance: None,
                     details: Some(operator_info::Details::Source(Source {
                         desc: Some(source::Desc::Kafka(KafkaDesc {
                             brokers: vec!["localhost:9092".to_string()],
@@ -83,6 +100,7 @@ fn setup_wordcount_dataflow(worker_port: u32) -> Dataflow {
                                 partition: None,
                             }),
                             data_type: DataTypeEnum::String as i32,
+                            format: kafka_desc::MessageFormat::Plain as i32,
                         })),
                     })),
                 },
@@ -96,6 +114,9 @@ fn setup_wordcount_dataflow(worker_port: u32) -> Dataflow {
                         port: worker_port,
                     }),
                     upstreams: vec![0],
+                    out_edge_batch: None,
+                    parallelism: 0,
+                    instance: None,
                     details: Some(operator_info::Details::FlatMap(FlatMap {
                         value: Some(flat_map::Value::Func(Func {
                             function: [
@@ -119,6 +140,9 @@ fn setup_wordcount_dataflow(worker_port: u32) -> Dataflow {
                         port: worker_port,
                     }),
                     upstreams: vec![1],
+                    out_edge_batch: None,
+                    parallelism: 0,
+                    instance: None,
                     details: Some(operator_info::Details::KeyBy(KeyBy {
                         value: Some(key_by::Value::Func(Func {
                             function: [
@@ -139,6 +163,9 @@ fn setup_wordcount_dataflow(worker_port: u32) -> Dataflow {
                         port: worker_port,
                     }),
                     upstreams: vec![2],
+                    out_edge_batch: None,
+                    parallelism: 0,
+                    instance: None,
                     details: Some(operator_info::Details::Reducer(Reducer {
                         value: Some(reducer::Value::Func(Func {
                             function: [
@@ -159,6 +186,9 @@ fn setup_wordcount_dataflow(worker_port: u32) -> Dataflow {
                         port: worker_port,
                     }),
                     upstreams: vec![3],
+                    out_edge_batch: None,
+                    parallelism: 0,
+                    instance: None,
                     details: Some(operator_info::Details::Sink(Sink {
                         delivery_guarentee: DeliveryGuarentee::None as i32,
                         desc: Some(sink::Desc::Redis(RedisDesc {
@@ -168,6 +198,7 @@ fn setup_wordcount_dataflow(worker_port: u32) -> Dataflow {
                                 password: "".to_string(),
                                 database: 0,
                                 tls: false,
+                                cluster: false,
                             }),
                             key_extractor: Some(Func {
                                 function: "function redis_extractor(a) { return a.t1 }".to_string(),
@@ -176,6 +207,11 @@ fn setup_wordcount_dataflow(worker_port: u32) -> Dataflow {
                                 function: "function redis_extractor(a) { return a.t0.toString() }"
                                     .to_string(),
                             }),
+                            command: redis_desc::Command::Set as i32,
+                            field_extractor: None,
+                            score_extractor: None,
+                            expire_millis: 0,
+                            stream_opts: None,
                         })),
                     })),
                 },
@@ -185,10 +221,109 @@ fn setup_wordcount_dataflow(worker_port: u32) -> Dataflow {
     }
 }
 
+/// word count of keys generated by a generator source. Counts are kept by a collect sink
+fn setup_generator_dataflow(worker_port: u32, generator: GeneratorDesc) -> Dataflow {
+    let operator = |operator_id: u32, upstreams: Vec<u32>, details| OperatorInfo {
+        operator_id,
+        host_addr: Some(HostAddr {
+            host: "localhost".to_string(),
+            port: worker_port,
+        }),
+        upstreams,
+        out_edge_batch: None,
+        parallelism: 0,
+        instance: None,
+        details: Some(details),
+    };
+
+    Dataflow {
+        job_id: Some(ResourceId {
+            resource_id: "generator".to_string(),
+            namespace_id: "nsId".to_string(),
+        }),
+        meta: vec![
+            DataflowMeta {
+                center: 0,
+                neighbors: vec![1],
+            },
+            DataflowMeta {
+                center: 1,
+                neighbors: vec![2],
+            },
+            DataflowMeta {
+                center: 2,
+                neighbors: vec![3],
+            },
+            DataflowMeta {
+                center: 3,
+                neighbors: vec![],
+            },
+        ],
+        nodes: HashMap::from_iter([
+            (
+                0,
+                operator(
+                    0,
+                    vec![],
+                    operator_info::Details::Source(Source {
+                        desc: Some(source::Desc::Generator(generator)),
+                    }),
+                ),
+            ),
+            (
+                1,
+                operator(
+                    1,
+                    vec![0],
+                    operator_info::Details::KeyBy(KeyBy {
+                        value: Some(key_by::Value::Func(Func {
+                            function: [
+                                format!("function _operator_{}_process(v) ", "keyBy"),
+                                "{ return v.t1 }".to_string(),
+                            ]
+                            .concat(),
+                        })),
+                    }),
+                ),
+            ),
+            (
+                2,
+                operator(
+                    2,
+                    vec![1],
+                    operator_info::Details::Reducer(Reducer {
+                        value: Some(reducer::Value::Func(Func {
+                            function: [
+                                format!("function _operator_{}_process(v1, v2) ", "reduce"),
+                                "{ return { t1: v1.t1, t0: v1.t0 + v2.t0 }; }".to_string(),
+                            ]
+                            .concat(),
+                        })),
+                    }),
+                ),
+            ),
+            (
+                3,
+                operator(
+                    3,
+                    vec![2],
+                    operator_info::Details::Sink(Sink {
+                        delivery_guarentee: DeliveryGuarentee::None as i32,
+                        desc: Some(sink::Desc::Collect(CollectDesc { capacity: 0 })),
+                    }),
+                ),
+            ),
+        ]),
+        execution_id: None,
+    }
+}
+
 fn setup_builder(port: usize) -> TaskManagerBuilder {
     TaskManagerBuilder {
         port,
         max_job_nums: 10,
+        compression: RpcCompression::Gzip,
+        tls: None,
     }
 }
 
@@ -199,16 +334,19 @@ fn setup_server(port: usize) -> JoinHandle<Result<(), Error>> {
     tokio::spawn(Server::builder().add_service(server).serve(addr))
 }
 
-#[tokio::test(flavor = "multi_thread", worker_threads = 20)]
-async fn test_e2e() {
-    setup();
-    let taskmanager_port = 9999;
+/// start a coordinator and a taskmanager, and connect to the coordinator
+async fn setup_cluster(
+    coordinator_port: usize,
+    taskmanager_port: usize,
+) -> SafeCoordinatorRpcGateway {
     let builder = CoordinatorBuilder {
-        port: 8888,
+        port: coordinator_port,
         cluster: ClusterBuilder {
             nodes: format!("localhost:{}", taskmanager_port),
             rpc_timeout: 5,
             connect_timeout: 5,
+            compression: RpcCompression::Gzip,
+            tls: None,
         },
         storage: DataflowStorageBuilder::Memory,
         heartbeat: HeartbeatBuilder {
@@ -222,6 +360,7 @@ async fn test_e2e() {
             connect_timeout: 5,
             rpc_timeout: 5,
         },
+        tls: None,
     };
 
     let addr = format!("0.0.0.0:{}", builder.port).parse().expect("msg");
@@ -235,13 +374,20 @@ async fn test_e2e() {
             .serve(addr),
     );
 
-    let _ = setup_server(taskmanager_port as usize);
+    let _ = setup_server(taskmanager_port);
 
-    let gateway = SafeCoordinatorRpcGateway::new(&HostAddr {
+    SafeCoordinatorRpcGateway::new(&HostAddr {
         host: "localhost".to_string(),
-        port: builder.port as u32,
+        port: coordinator_port as u32,
     })
-    .await;
+    .await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 20)]
+async fn test_e2e() {
+    setup();
+    let taskmanager_port = 9999;
+    let gateway = setup_cluster(8888, taskmanager_port).await;
     let producer = run_producer("localhost:9092", "topic-1", "", 0).expect("msg");
 
     let r = time::timeout(
@@ -268,6 +414,7 @@ async fn test_e2e() {
             password: "".to_string(),
             database: 0,
             tls: false,
+            cluster: false,
         }),
         key_extractor: Some(Func {
             function: "function redis_extractor(a) { return a.t1 }".to_string(),
@@ -275,18 +422,23 @@ async fn test_e2e() {
         value_extractor: Some(Func {
             function: "function redis_extractor(a) { return a.t0.toString() }".to_string(),
         }),
+        command: redis_desc::Command::Set as i32,
+        field_extractor: None,
+        score_extractor: None,
+        expire_millis: 0,
+        stream_opts: None,
     };
     let mut redis = RedisClient::new(&desc);
     let _ = tokio::time::sleep(Duration::from_secs(3)).await;
 
-    let r = redis.get(&TypedValue::String("word".to_string()));
+    let r = redis.get(&TypedValue::String("word".to_string())).await;
 
     assert!(r.is_ok());
     let r = r.expect("msg");
     let v = TypedValue::from_slice_with_type(&r, DataTypeEnum::String);
     assert_eq!(v, TypedValue::String("3".to_string()));
 
-    let r = redis.get(&TypedValue::String("count".to_string()));
+    let r = redis.get(&TypedValue::String("count".to_string())).await;
 
     assert!(r.is_ok());
     let r = r.expect("msg");
@@ -295,3 +447,120 @@ async fn test_e2e() {
     println!("success sink");
     return;
 }
+
+/// The same word count as [test_e2e] without external services
+#[tokio::test(flavor = "multi_thread", worker_threads = 20)]
+async fn test_e2e_generator() {
+    setup();
+    let taskmanager_port = 9998;
+    let gateway = setup_cluster(8887, taskmanager_port).await;
+
+    let generator = GeneratorDesc {
+        rate_per_sec: 0,
+        count: 20,
+        key_cardinality: 3,
+        seed: 2026,
+        function: None,
+        template: r#"{"t0": 1, "t1": {{key}}}"#.to_string(),
+    };
+    // keys are generated deterministically by the seed
+    let mut expected = BTreeMap::new();
+    GeneratorInputs::new(&generator).for_each(|input| {
+        *expected.entry(get_input_key(&input)).or_insert(0i64) += 1;
+    });
+
+    let dataflow = setup_generator_dataflow(taskmanager_port as u32, generator);
+    let job_id = dataflow.job_id.clone();
+    let r = gateway.create_dataflow(dataflow).await;
+    assert!(r.is_ok());
+    let _ = tokio::time::sleep(Duration::from_secs(3)).await;
+
+    let taskmanager = SafeTaskManagerRpcGateway::new(&HostAddr {
+        host: "localhost".to_string(),
+        port: taskmanager_port as u32,
+    });
+    assert_eq!(get_counts(&taskmanager, job_id).await, expected);
+}
+
+/// the last count of each word kept by the collect sink, which is the total
+async fn get_counts(
+    taskmanager: &SafeTaskManagerRpcGateway,
+    job_id: Option<ResourceId>,
+) -> BTreeMap<TypedValue, i64> {
+    let r = taskmanager
+        .get_collected_events(GetCollectedEventsRequest {
+            job_id,
+            operator_id: 3,
+        })
+        .await;
+    assert!(r.is_ok());
+
+    let mut counts = BTreeMap::new();
+    r.expect("msg")
+        .events
+        .iter()
+        .map(|event| TypedValue::from_slice(&event.data[0].value))
+        .for_each(|value| match value {
+            TypedValue::Object(object) => {
+                let count = match object.get("t0") {
+                    Some(TypedValue::BigInt(count)) => *count,
+                    Some(TypedValue::Number(count)) => *count as i64,
+                    count => panic!("unexpected count {:?}", count),
+                };
+                counts.insert(object.get("t1").cloned().unwrap_or_default(), count);
+            }
+            value => panic!("unexpected value {:?}", value),
+        });
+    counts
+}
+
+/// The counts are kept in the key-value state while the reducer is rescaled. The generator starts over once the dataflow is deployed again,
+/// so each word is counted twice
+#[tokio::test(flavor = "multi_thread", worker_threads = 20)]
+async fn test_e2e_rescale() {
+    setup();
+    let taskmanager_port = 9997;
+    let gateway = setup_cluster(8886, taskmanager_port).await;
+
+    let generator = GeneratorDesc {
+        rate_per_sec: 0,
+        count: 20,
+        key_cardinality: 3,
+        seed: 2026,
+        function: None,
+        template: r#"{"t0": 1, "t1": {{key}}}"#.to_string(),
+    };
+    let mut expected = BTreeMap::new();
+    GeneratorInputs::new(&generator).for_each(|input| {
+        *expected.entry(get_input_key(&input)).or_insert(0i64) += 1;
+    });
+
+    let mut dataflow = setup_generator_dataflow(taskmanager_port as u32, generator);
+    dataflow.job_id = Some(ResourceId {
+        resource_id: "rescale".to_string(),
+        namespace_id: "nsId".to_string(),
+    });
+    let job_id = dataflow.job_id.clone();
+    let r = gateway.create_dataflow(dataflow).await;
+    assert!(r.is_ok());
+    let _ = tokio::time::sleep(Duration::from_secs(3)).await;
+
+    let taskmanager = SafeTaskManagerRpcGateway::new(&HostAddr {
+        host: "localhost".to_string(),
+        port: taskmanager_port as u32,
+    });
+    assert_eq!(get_counts(&taskmanager, job_id.clone()).await, expected);
+
+    let r = gateway
+        .rescale_dataflow(RescaleDataflowRequest {
+            job_id: job_id.clone(),
+            parallelism: HashMap::from([(2, 2)]),
+        })
+        .await;
+    assert!(r.is_ok());
+    let _ = tokio::time::sleep(Duration::from_secs(3)).await;
+
+    // the counts continue from the ones before rescaling
+    expected.values_mut().for_each(|count| *count *= 2);
+    assert_eq!(get_counts(&taskmanager, job_id).await, expected);
+}
@@ -0,0 +1,244 @@
+//! Rescaling with the default memory state, which can't be moved. The state manager is read from the environment of the process,
+//! so these tests run apart from the end-to-end ones which keep their state in a key-value store
+use std::{collections::HashMap, sync::Once, time::Duration};
+
+use common::net::{
+    cluster::ClusterBuilder, gateway::coordinator::SafeCoordinatorRpcGateway, AckResponderBuilder,
+    HeartbeatBuilder, RpcCompression,
+};
+use lightflus_core::{
+    coordinator::{
+        api::CoordinatorApiImpl, coord::CoordinatorBuilder, storage::DataflowStorageBuilder,
+    },
+    taskmanager::rpc::TaskManagerBuilder,
+};
+use proto::{
+    common::{
+        key_by, operator_info, reducer, sink, source, CollectDesc, Dataflow, DataflowMeta,
+        DeliveryGuarentee, Func, HostAddr, HttpDesc, KeyBy, OperatorInfo, Reducer, ResourceId,
+        Sink, Source,
+    },
+    coordinator::{
+        coordinator_api_server::CoordinatorApiServer, GetDataflowRequest, RescaleDataflowRequest,
+    },
+};
+use stream::initialize_v8;
+use tonic::transport::Server;
+
+static ONCE: Once = Once::new();
+
+pub fn setup() {
+    ONCE.call_once(|| {
+        tracing_subscriber::fmt::init();
+        initialize_v8();
+        std::env::remove_var("STATE_MANAGER");
+    })
+}
+
+/// start a coordinator and a taskmanager, and connect to the coordinator
+async fn setup_cluster(
+    coordinator_port: usize,
+    taskmanager_port: usize,
+) -> SafeCoordinatorRpcGateway {
+    let builder = CoordinatorBuilder {
+        port: coordinator_port,
+        cluster: ClusterBuilder {
+            nodes: format!("localhost:{}", taskmanager_port),
+            rpc_timeout: 5,
+            connect_timeout: 5,
+            compression: RpcCompression::Gzip,
+            tls: None,
+        },
+        storage: DataflowStorageBuilder::Memory,
+        heartbeat: HeartbeatBuilder {
+            period: 3,
+            connect_timeout: 3,
+            rpc_timeout: 3,
+        },
+        ack: AckResponderBuilder {
+            delay: 3,
+            buf_size: 10,
+            connect_timeout: 5,
+            rpc_timeout: 5,
+        },
+        tls: None,
+    };
+
+    let addr = format!("0.0.0.0:{}", builder.port).parse().expect("msg");
+    let coordinator = builder.build();
+    tokio::spawn(
+        Server::builder()
+            .timeout(Duration::from_secs(3))
+            .add_service(CoordinatorApiServer::new(CoordinatorApiImpl::new(
+                coordinator,
+            )))
+            .serve(addr),
+    );
+
+    let taskmanager = TaskManagerBuilder {
+        port: taskmanager_port,
+        max_job_nums: 10,
+        compression: RpcCompression::Gzip,
+        tls: None,
+    };
+    let addr = format!("0.0.0.0:{}", taskmanager.port)
+        .parse()
+        .expect("msg");
+    tokio::spawn(
+        Server::builder()
+            .add_service(taskmanager.build())
+            .serve(addr),
+    );
+
+    SafeCoordinatorRpcGateway::new(&HostAddr {
+        host: "localhost".to_string(),
+        port: coordinator_port as u32,
+    })
+    .await
+}
+
+/// Two HTTP sources count the pushed events by key. The second one has two instances,
+/// and the id of its second instance changes if the first source is rescaled
+fn setup_http_dataflow(worker_port: u32) -> Dataflow {
+    let operator = |operator_id: u32, upstreams: Vec<u32>, parallelism, details| OperatorInfo {
+        operator_id,
+        host_addr: Some(HostAddr {
+            host: "localhost".to_string(),
+            port: worker_port,
+        }),
+        upstreams,
+        out_edge_batch: None,
+        parallelism,
+        instance: None,
+        details: Some(details),
+    };
+    let http = || {
+        operator_info::Details::Source(Source {
+            desc: Some(source::Desc::Http(HttpDesc::default())),
+        })
+    };
+
+    Dataflow {
+        job_id: Some(ResourceId {
+            resource_id: "rescale-memory".to_string(),
+            namespace_id: "nsId".to_string(),
+        }),
+        meta: vec![
+            DataflowMeta {
+                center: 0,
+                neighbors: vec![2],
+            },
+            DataflowMeta {
+                center: 1,
+                neighbors: vec![2],
+            },
+            DataflowMeta {
+                center: 2,
+                neighbors: vec![3],
+            },
+            DataflowMeta {
+                center: 3,
+                neighbors: vec![4],
+            },
+            DataflowMeta {
+                center: 4,
+                neighbors: vec![],
+            },
+        ],
+        nodes: HashMap::from_iter([
+            (0, operator(0, vec![], 1, http())),
+            (1, operator(1, vec![], 2, http())),
+            (
+                2,
+                operator(
+                    2,
+                    vec![0, 1],
+                    1,
+                    operator_info::Details::KeyBy(KeyBy {
+                        value: Some(key_by::Value::Func(Func {
+                            function: [
+                                format!("function _operator_{}_process(v) ", "keyBy"),
+                                "{ return v.t1 }".to_string(),
+                            ]
+                            .concat(),
+                        })),
+                    }),
+                ),
+            ),
+            (
+                3,
+                operator(
+                    3,
+                    vec![2],
+                    1,
+                    operator_info::Details::Reducer(Reducer {
+                        value: Some(reducer::Value::Func(Func {
+                            function: [
+                                format!("function _operator_{}_process(v1, v2) ", "reduce"),
+                                "{ return { t1: v1.t1, t0: v1.t0 + v2.t0 }; }".to_string(),
+                            ]
+                            .concat(),
+                        })),
+                    }),
+                ),
+            ),
+            (
+                4,
+                operator(
+                    4,
+                    vec![3],
+                    1,
+                    operator_info::Details::Sink(Sink {
+                        delivery_guarentee: DeliveryGuarentee::None as i32,
+                        desc: Some(sink::Desc::Collect(CollectDesc { capacity: 0 })),
+                    }),
+                ),
+            ),
+        ]),
+        execution_id: None,
+    }
+}
+
+/// The positions of the renumbered source instance can't be moved in the memory state,
+/// so the rescaling is rejected and the dataflow keeps running as it was
+#[tokio::test(flavor = "multi_thread", worker_threads = 20)]
+async fn test_rescale_unmovable_state() {
+    setup();
+    let taskmanager_port = 9996;
+    let gateway = setup_cluster(8885, taskmanager_port).await;
+
+    let dataflow = setup_http_dataflow(taskmanager_port as u32);
+    let job_id = dataflow.job_id.clone();
+    let r = gateway.create_dataflow(dataflow).await;
+    assert!(r.is_ok());
+    let _ = tokio::time::sleep(Duration::from_secs(3)).await;
+
+    let r = gateway
+        .rescale_dataflow(RescaleDataflowRequest {
+            job_id: job_id.clone(),
+            parallelism: HashMap::from([(0, 2)]),
+        })
+        .await;
+    assert_eq!(
+        r.expect_err("rescaled the memory state").code(),
+        tonic::Code::FailedPrecondition
+    );
+
+    let r = gateway
+        .get_dataflow(GetDataflowRequest {
+            job_id: job_id.clone(),
+        })
+        .await;
+    let states = r.expect("msg");
+    // the subdataflow has not been stopped, and the instances are numbered as before
+    assert_eq!(states.subdataflow_infos.len(), 1);
+    let mut operator_ids = states
+        .graph
+        .expect("msg")
+        .nodes
+        .keys()
+        .copied()
+        .collect::<Vec<_>>();
+    operator_ids.sort();
+    assert_eq!(operator_ids, vec![0, 1, 2, 3, 4, 5]);
+}
@@ -1,15 +1,25 @@
-use std::{collections::HashMap, sync::Once};
+use std::{collections::HashMap, sync::Once, time::Duration};
 
-use common::net::gateway::taskmanager::SafeTaskManagerRpcGateway;
+use common::{
+    event::LocalEvent,
+    net::{gateway::taskmanager::SafeTaskManagerRpcGateway, RpcCompression},
+    utils::key_groups,
+};
 use lightflus_core::taskmanager::rpc::TaskManagerBuilder;
 use proto::{
     common::{
-        mapper, operator_info, Dataflow, DataflowMeta, ExecutorStatus, Func, HostAddr, Mapper,
-        OperatorInfo, ResourceId,
+        mapper, operator_info, sink, CollectDesc, Dataflow, DataflowMeta, DeliveryGuarentee,
+        ExecutorStatus, Func, HostAddr, KeyedDataEvent, Mapper, OperatorInfo, ResourceId, Sink,
+    },
+    taskmanager::{
+        CreateSubDataflowRequest, ExportKeyGroupsRequest, GetCollectedEventsRequest, KeyGroupEntry,
+        KeyGroupState, KeyGroupStates,
     },
-    taskmanager::CreateSubDataflowRequest,
 };
-use stream::initialize_v8;
+use stream::{
+    edge::{OutEdge, RemoteOutEdge},
+    initialize_v8,
+};
 use tokio::task::JoinHandle;
 use tonic::transport::{Error, Server};
 
@@ -17,6 +27,8 @@ fn setup_builder(port: usize) -> TaskManagerBuilder {
     TaskManagerBuilder {
         port,
         max_job_nums: 10,
+        compression: RpcCompression::Gzip,
+        tls: None,
     }
 }
 
@@ -29,7 +41,6 @@ pub fn setup() {
     })
 }
 
-
 fn setup_server(port: usize) -> JoinHandle<Result<(), Error>> {
     let builder = setup_builder(port);
     let server = builder.build();
@@ -56,6 +67,9 @@ fn setup_dataflow(resource_id: ResourceId, server_port: usize) -> Dataflow {
                     port: server_port as u32,
                 }),
                 upstreams: vec![],
+                out_edge_batch: None,
+                parallelism: 0,
+                instance: None,
                 details: Some(operator_info::Details::Mapper(Mapper {
                     value: Some(mapper::Value::Func(Func {
                         function: format!("_operator_{}_process", "map"),
@@ -72,6 +86,9 @@ fn setup_dataflow(resource_id: ResourceId, server_port: usize) -> Dataflow {
                     port: server_port as u32,
                 }),
                 upstreams: vec![0],
+                out_edge_batch: None,
+                parallelism: 0,
+                instance: None,
                 details: Some(operator_info::Details::Mapper(Mapper {
                     value: Some(mapper::Value::Func(Func {
                         function: format!("_operator_{}_process", "map"),
@@ -141,4 +158,143 @@ async fn test_taskmanager_deploy_and_terminate() {
     assert!(r.is_ok());
 
     server_1.abort();
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_taskmanager_remote_edge_stream() {
+    setup();
+    let server_port = 8794;
+    let server = setup_server(server_port);
+    let host_addr = HostAddr {
+        host: "localhost".to_string(),
+        port: server_port as u32,
+    };
+    let gateway = SafeTaskManagerRpcGateway::new(&host_addr);
+    let job_id = ResourceId {
+        resource_id: "remote_edge".to_string(),
+        namespace_id: "ns_id".to_string(),
+    };
+
+    let mut dataflow = setup_dataflow(job_id.clone(), server_port);
+    dataflow.nodes.get_mut(&1).unwrap().details = Some(operator_info::Details::Sink(Sink {
+        delivery_guarentee: DeliveryGuarentee::None as i32,
+        desc: Some(sink::Desc::Collect(CollectDesc { capacity: 0 })),
+    }));
+    let r = gateway
+        .create_sub_dataflow(CreateSubDataflowRequest {
+            job_id: Some(job_id.clone()),
+            dataflow: Some(dataflow),
+        })
+        .await;
+    assert!(r.is_ok());
+
+    // operator 0 sends events to operator 1 on the stream of the edge
+    let out_edge = RemoteOutEdge::new(SafeTaskManagerRpcGateway::new(&host_addr));
+    let events = |ids: std::ops::Range<i64>| {
+        ids.map(|event_id| {
+            LocalEvent::KeyedDataStreamEvent(KeyedDataEvent {
+                job_id: Some(job_id.clone()),
+                to_operator_id: 1,
+                from_operator_id: 0,
+                event_id,
+                ..Default::default()
+            })
+        })
+        .collect::<Vec<_>>()
+    };
+    let r = out_edge
+        .batch_write(&Some(job_id.clone()), 1, 0, events(0..5))
+        .await;
+    assert!(r.is_ok());
+    let r = out_edge
+        .batch_write(&Some(job_id.clone()), 1, 0, events(5..10))
+        .await;
+    assert!(r.is_ok());
+    assert!(out_edge.credits() > 0);
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let r = gateway
+        .get_collected_events(GetCollectedEventsRequest {
+            job_id: Some(job_id.clone()),
+            operator_id: 1,
+        })
+        .await;
+    assert!(r.is_ok());
+    // events arrive in the order they are sent
+    let event_ids = r
+        .unwrap()
+        .events
+        .iter()
+        .map(|event| event.event_id)
+        .collect::<Vec<_>>();
+    assert_eq!(event_ids, (0..10).collect::<Vec<_>>());
+
+    let r = gateway.stop_dataflow(job_id).await;
+    assert!(r.is_ok());
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_taskmanager_export_and_import_key_groups() {
+    setup();
+    let server_port = 8795;
+    let server = setup_server(server_port);
+    let gateway = SafeTaskManagerRpcGateway::new(&HostAddr {
+        host: "localhost".to_string(),
+        port: server_port as u32,
+    });
+    let job_id = ResourceId {
+        resource_id: "key_groups".to_string(),
+        namespace_id: "ns_id".to_string(),
+    };
+    let key_group = key_groups::key_group(b"word");
+    let states = |entries: Vec<KeyGroupEntry>| KeyGroupStates {
+        job_id: Some(job_id.clone()),
+        operator_id: 1,
+        source: false,
+        key_groups: vec![KeyGroupState { key_group, entries }],
+    };
+    let export = || ExportKeyGroupsRequest {
+        job_id: Some(job_id.clone()),
+        operator_id: 1,
+        source: false,
+        key_groups: vec![key_group],
+    };
+
+    // the memory state is dropped with the executors, so it can't be moved
+    std::env::remove_var("STATE_MANAGER");
+    let r = gateway.export_key_groups(export()).await;
+    assert_eq!(
+        r.map_err(|status| status.code()),
+        Err(tonic::Code::FailedPrecondition)
+    );
+    let r = gateway.import_key_groups(states(vec![])).await;
+    assert_eq!(
+        r.map_err(|status| status.code()),
+        Err(tonic::Code::FailedPrecondition)
+    );
+
+    let dir = std::env::temp_dir().join("lightflus-test-taskmanager-key-groups");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::env::set_var("KEY_VALUE_STATE_PATH", dir.to_str().unwrap_or_default());
+    std::env::set_var("STATE_MANAGER", "key_value");
+
+    let entries = vec![KeyGroupEntry {
+        key: b"word".to_vec(),
+        value: b"3".to_vec(),
+    }];
+    let r = gateway.import_key_groups(states(entries.clone())).await;
+    assert!(r.is_ok());
+    let r = gateway.export_key_groups(export()).await;
+    assert_eq!(r.expect("msg").key_groups, states(entries).key_groups);
+
+    // the key groups are cleared once they are moved
+    let r = gateway.import_key_groups(states(vec![])).await;
+    assert!(r.is_ok());
+    let r = gateway.export_key_groups(export()).await;
+    assert_eq!(r.expect("msg").key_groups, states(vec![]).key_groups);
+
+    std::env::remove_var("STATE_MANAGER");
+    server.abort();
+}
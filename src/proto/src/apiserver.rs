@@ -37,6 +37,21 @@ pub struct CreateDataflowOptions {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RescaleResourceRequest {
+    #[prost(message, optional, tag = "1")]
+    pub resource_id: ::core::option::Option<super::common::ResourceId>,
+    /// the new parallelism of operators, keyed by the ids of the operators in the dataflow
+    #[prost(map = "uint32, uint32", tag = "2")]
+    pub parallelism: ::std::collections::HashMap<u32, u32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RescaleResourceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub resource: ::core::option::Option<Resource>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ListResourcesRequest {
     /// namespace
     #[prost(string, tag = "1")]
@@ -165,6 +165,23 @@ pub struct ExecutorInfo {
     pub executor_id: u32,
     #[prost(enumeration = "ExecutorStatus", tag = "2")]
     pub status: i32,
+    /// spill queues of the remote out-edges, keyed by the downstream operator id
+    #[prost(map = "uint32, message", tag = "3")]
+    pub spills: ::std::collections::HashMap<u32, SpillMetrics>,
+}
+/// size of the spill queue of a remote out-edge, which keeps the events that failed to be sent
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SpillMetrics {
+    /// events kept in memory
+    #[prost(uint64, tag = "1")]
+    pub memory_events: u64,
+    /// events kept on local disk
+    #[prost(uint64, tag = "2")]
+    pub disk_events: u64,
+    /// bytes of the spill file on local disk
+    #[prost(uint64, tag = "3")]
+    pub disk_bytes: u64,
 }
 /// Enum of Data Type. each one corresponds to a primitive type in JavaScript
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
@@ -366,6 +383,9 @@ pub struct KeyedDataEvent {
     /// event id, generated by source
     #[prost(int64, tag = "9")]
     pub event_id: i64,
+    /// changelog kind of this event, default is insert
+    #[prost(enumeration = "RowKind", tag = "10")]
+    pub row_kind: i32,
 }
 /// Nested message and enum types in `KeyedDataEvent`.
 pub mod keyed_data_event {
@@ -407,6 +427,46 @@ pub struct KeyedEventSet {
     #[prost(uint32, tag = "4")]
     pub from_operator_id: u32,
 }
+/// RowKind describes the changelog semantics of a KeyedDataEvent.
+/// In accumulating mode, every event is an insert. In retracting mode, an operator like Reduce
+/// retracts its previous result with UPDATE_BEFORE and emits the new one with UPDATE_AFTER,
+/// so that downstream sinks can keep materialized tables correct.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum RowKind {
+    /// append a new row
+    Insert = 0,
+    /// retraction of a previously emitted row
+    UpdateBefore = 1,
+    /// the new content of an updated row
+    UpdateAfter = 2,
+    /// delete a row by its key
+    Delete = 3,
+}
+impl RowKind {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            RowKind::Insert => "ROW_KIND_INSERT",
+            RowKind::UpdateBefore => "ROW_KIND_UPDATE_BEFORE",
+            RowKind::UpdateAfter => "ROW_KIND_UPDATE_AFTER",
+            RowKind::Delete => "ROW_KIND_DELETE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "ROW_KIND_INSERT" => Some(Self::Insert),
+            "ROW_KIND_UPDATE_BEFORE" => Some(Self::UpdateBefore),
+            "ROW_KIND_UPDATE_AFTER" => Some(Self::UpdateAfter),
+            "ROW_KIND_DELETE" => Some(Self::Delete),
+            _ => None,
+        }
+    }
+}
 /// *
 /// StreamGraph metadata, it stores the structural information of a stream graph
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -432,6 +492,17 @@ pub struct OperatorInfo {
     /// upstreams operator_id
     #[prost(uint32, repeated, tag = "3")]
     pub upstreams: ::prost::alloc::vec::Vec<u32>,
+    /// micro-batching of the out-edges, events are written immediately if it's absent
+    #[prost(message, optional, tag = "13")]
+    pub out_edge_batch: ::core::option::Option<EdgeBatch>,
+    /// the number of parallel instances of the operator, 0 is treated as 1.
+    /// Only Kafka and HTTP sources can be parallel: instances of a Kafka source share the consumer group, and events of an HTTP source are pushed to instance 0.
+    /// Other sources would be read by every instance, so a dataflow with parallel ones is rejected
+    #[prost(uint32, tag = "14")]
+    pub parallelism: u32,
+    /// set by the coordinator on each parallel instance of the operator
+    #[prost(message, optional, tag = "15")]
+    pub instance: ::core::option::Option<OperatorInstance>,
     /// optional for different operator type
     #[prost(oneof = "operator_info::Details", tags = "5, 6, 7, 8, 9, 10, 11, 12")]
     pub details: ::core::option::Option<operator_info::Details>,
@@ -463,6 +534,29 @@ pub mod operator_info {
         Window(super::Window),
     }
 }
+/// A parallel instance of an operator. Instance 0 keeps the id of the operator, and the others get new ids
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OperatorInstance {
+    /// id of the operator in the submitted dataflow
+    #[prost(uint32, tag = "1")]
+    pub operator_id: u32,
+    /// index of the instance, in [0, parallelism)
+    #[prost(uint32, tag = "2")]
+    pub index: u32,
+}
+/// Events are written to each out-edge in batches, which trades a little latency for throughput.
+/// Batches are also flushed when the operator terminates. Operators don't propagate watermarks, so there's no flush on watermark
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EdgeBatch {
+    /// a batch is flushed once it has this number of events
+    #[prost(uint32, tag = "1")]
+    pub max_batch_size: u32,
+    /// a batch is flushed this number of milliseconds after its first event, even if it's not full
+    #[prost(uint64, tag = "2")]
+    pub max_linger_millis: u64,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Reducer {
@@ -572,7 +666,7 @@ pub mod key_by {
 pub struct Sink {
     #[prost(enumeration = "DeliveryGuarentee", tag = "4")]
     pub delivery_guarentee: i32,
-    #[prost(oneof = "sink::Desc", tags = "1, 2, 3")]
+    #[prost(oneof = "sink::Desc", tags = "1, 2, 3, 5, 6, 7, 8, 9, 10, 11")]
     pub desc: ::core::option::Option<sink::Desc>,
 }
 /// Nested message and enum types in `Sink`.
@@ -586,6 +680,20 @@ pub mod sink {
         Mysql(super::MysqlDesc),
         #[prost(message, tag = "3")]
         Redis(super::RedisDesc),
+        #[prost(message, tag = "5")]
+        Postgres(super::PostgresDesc),
+        #[prost(message, tag = "6")]
+        Mqtt(super::MqttDesc),
+        #[prost(message, tag = "7")]
+        File(super::FileDesc),
+        #[prost(message, tag = "8")]
+        Http(super::HttpDesc),
+        #[prost(message, tag = "9")]
+        Parquet(super::ParquetDesc),
+        #[prost(message, tag = "10")]
+        Stdout(super::StdoutDesc),
+        #[prost(message, tag = "11")]
+        Collect(super::CollectDesc),
     }
 }
 /// *
@@ -603,7 +711,7 @@ pub struct ConstOp {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Source {
-    #[prost(oneof = "source::Desc", tags = "3")]
+    #[prost(oneof = "source::Desc", tags = "3, 4, 5, 6, 7, 8, 9")]
     pub desc: ::core::option::Option<source::Desc>,
 }
 /// Nested message and enum types in `Source`.
@@ -613,6 +721,19 @@ pub mod source {
     pub enum Desc {
         #[prost(message, tag = "3")]
         Kafka(super::KafkaDesc),
+        #[prost(message, tag = "4")]
+        Mysql(super::MysqlDesc),
+        #[prost(message, tag = "5")]
+        Redis(super::RedisDesc),
+        #[prost(message, tag = "6")]
+        Mqtt(super::MqttDesc),
+        #[prost(message, tag = "7")]
+        File(super::FileDesc),
+        /// events are pushed by HTTP requests to the apiserver instead of being pulled by the source
+        #[prost(message, tag = "8")]
+        Http(super::HttpDesc),
+        #[prost(message, tag = "9")]
+        Generator(super::GeneratorDesc),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -626,6 +747,9 @@ pub struct KafkaDesc {
     pub opts: ::core::option::Option<kafka_desc::KafkaOptions>,
     #[prost(enumeration = "DataTypeEnum", tag = "4")]
     pub data_type: i32,
+    /// format of message payload, only available for source
+    #[prost(enumeration = "kafka_desc::MessageFormat", tag = "5")]
+    pub format: i32,
 }
 /// Nested message and enum types in `KafkaDesc`.
 pub mod kafka_desc {
@@ -637,14 +761,63 @@ pub mod kafka_desc {
         #[prost(uint32, optional, tag = "2")]
         pub partition: ::core::option::Option<u32>,
     }
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum MessageFormat {
+        /// payload is decoded by data_type
+        Plain = 0,
+        /// payload is a Debezium change event envelope in JSON with before, after and op fields.
+        /// Each envelope will be decoded as insert, update or delete events
+        DebeziumJson = 1,
+    }
+    impl MessageFormat {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                MessageFormat::Plain => "MESSAGE_FORMAT_PLAIN",
+                MessageFormat::DebeziumJson => "MESSAGE_FORMAT_DEBEZIUM_JSON",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "MESSAGE_FORMAT_PLAIN" => Some(Self::Plain),
+                "MESSAGE_FORMAT_DEBEZIUM_JSON" => Some(Self::DebeziumJson),
+                _ => None,
+            }
+        }
+    }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct MysqlDesc {
     #[prost(message, optional, tag = "1")]
     pub connection_opts: ::core::option::Option<mysql_desc::ConnectionOpts>,
+    /// statement applied to insert and update-after rows. Use INSERT ... ON DUPLICATE KEY UPDATE for upserts
     #[prost(message, optional, tag = "2")]
     pub statement: ::core::option::Option<mysql_desc::Statement>,
+    /// optional statement applied to delete rows
+    #[prost(message, optional, tag = "3")]
+    pub delete_statement: ::core::option::Option<mysql_desc::Statement>,
+    /// options of polling source, only available for source
+    #[prost(message, optional, tag = "4")]
+    pub polling_opts: ::core::option::Option<mysql_desc::PollingOpts>,
+    /// max number of rows written in one transaction by sink, 500 by default
+    #[prost(uint32, tag = "5")]
+    pub max_batch_size: u32,
 }
 /// Nested message and enum types in `MysqlDesc`.
 pub mod mysql_desc {
@@ -679,6 +852,75 @@ pub mod mysql_desc {
             pub extractor: ::prost::alloc::string::String,
         }
     }
+    /// MySQL source polls a query incrementally with a monotonically increasing column.
+    /// The largest value of the column it has read, called high-water mark, is kept in state
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct PollingOpts {
+        /// query with a single placeholder '?' which will be bound with high-water mark,
+        /// e.g. 'select * from t where id > ? order by id limit 1000'
+        #[prost(string, tag = "1")]
+        pub query: ::prost::alloc::string::String,
+        /// name of the monotonically increasing column, e.g. 'id' or 'updated_at'
+        #[prost(string, tag = "2")]
+        pub incrementing_column: ::prost::alloc::string::String,
+        /// interval between two polls in milliseconds
+        #[prost(uint64, tag = "3")]
+        pub interval_millis: u64,
+        /// high-water mark of the first poll, 0 by default
+        #[prost(string, optional, tag = "4")]
+        pub start_offset: ::core::option::Option<::prost::alloc::string::String>,
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PostgresDesc {
+    #[prost(message, optional, tag = "1")]
+    pub connection_opts: ::core::option::Option<postgres_desc::ConnectionOpts>,
+    /// statement applied to insert and update-after rows with placeholders $1, $2, ...
+    /// Use INSERT ... ON CONFLICT (...) DO UPDATE SET ... for upserts
+    #[prost(message, optional, tag = "2")]
+    pub statement: ::core::option::Option<postgres_desc::Statement>,
+    /// optional statement applied to delete rows
+    #[prost(message, optional, tag = "3")]
+    pub delete_statement: ::core::option::Option<postgres_desc::Statement>,
+    /// max number of rows written in one transaction, 500 by default
+    #[prost(uint32, tag = "4")]
+    pub max_batch_size: u32,
+}
+/// Nested message and enum types in `PostgresDesc`.
+pub mod postgres_desc {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct ConnectionOpts {
+        #[prost(string, tag = "1")]
+        pub host: ::prost::alloc::string::String,
+        #[prost(string, tag = "2")]
+        pub username: ::prost::alloc::string::String,
+        #[prost(string, tag = "3")]
+        pub password: ::prost::alloc::string::String,
+        #[prost(string, tag = "4")]
+        pub database: ::prost::alloc::string::String,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Statement {
+        #[prost(string, tag = "1")]
+        pub statement: ::prost::alloc::string::String,
+        #[prost(message, repeated, tag = "2")]
+        pub extractors: ::prost::alloc::vec::Vec<statement::Extractor>,
+    }
+    /// Nested message and enum types in `Statement`.
+    pub mod statement {
+        #[allow(clippy::derive_partial_eq_without_eq)]
+        #[derive(Clone, PartialEq, ::prost::Message)]
+        pub struct Extractor {
+            #[prost(uint32, tag = "1")]
+            pub index: u32,
+            #[prost(string, tag = "2")]
+            pub extractor: ::prost::alloc::string::String,
+        }
+    }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -687,8 +929,23 @@ pub struct RedisDesc {
     pub connection_opts: ::core::option::Option<redis_desc::ConnectionOpts>,
     #[prost(message, optional, tag = "2")]
     pub key_extractor: ::core::option::Option<Func>,
+    /// value of SET, HSET, LPUSH, RPUSH and XADD, member of SADD and ZADD, increment of INCRBY
     #[prost(message, optional, tag = "3")]
     pub value_extractor: ::core::option::Option<Func>,
+    #[prost(enumeration = "redis_desc::Command", tag = "4")]
+    pub command: i32,
+    /// field of HSET, required if command is HSET
+    #[prost(message, optional, tag = "5")]
+    pub field_extractor: ::core::option::Option<Func>,
+    /// score of ZADD, required if command is ZADD
+    #[prost(message, optional, tag = "6")]
+    pub score_extractor: ::core::option::Option<Func>,
+    /// expiry of written keys in milliseconds. Keys never expire if it's 0
+    #[prost(uint64, tag = "7")]
+    pub expire_millis: u64,
+    /// options of Redis Streams source, only available for source
+    #[prost(message, optional, tag = "8")]
+    pub stream_opts: ::core::option::Option<redis_desc::StreamOpts>,
 }
 /// Nested message and enum types in `RedisDesc`.
 pub mod redis_desc {
@@ -705,6 +962,494 @@ pub mod redis_desc {
         pub database: i64,
         #[prost(bool, tag = "5")]
         pub tls: bool,
+        /// connect to a Redis Cluster. host is a list of seed nodes separated by comma, e.g. 'host1:6379,host2:6379'
+        #[prost(bool, tag = "6")]
+        pub cluster: bool,
+    }
+    /// Redis Streams source reads entries of a stream by a consumer group
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct StreamOpts {
+        /// key of the stream
+        #[prost(string, tag = "1")]
+        pub key: ::prost::alloc::string::String,
+        /// name of the consumer group. The group will be created if it does not exist
+        #[prost(string, tag = "2")]
+        pub group: ::prost::alloc::string::String,
+        /// name of the consumer, '<job id>-<source id>' by default
+        #[prost(string, tag = "3")]
+        pub consumer: ::prost::alloc::string::String,
+        /// id which a new group starts from, '$' (only new entries) by default
+        #[prost(string, tag = "4")]
+        pub start_id: ::prost::alloc::string::String,
+        /// max number of entries read at a time, 100 by default
+        #[prost(uint32, tag = "5")]
+        pub count: u32,
+        /// max blocking time of a read in milliseconds, 1000 by default
+        #[prost(uint64, tag = "6")]
+        pub block_millis: u64,
+        /// on start, entries pending longer than it in other consumers are claimed by this consumer. 0 means not to claim
+        #[prost(uint64, tag = "7")]
+        pub claim_min_idle_millis: u64,
+    }
+    /// command used by sink to write values
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum Command {
+        Set = 0,
+        Hset = 1,
+        Lpush = 2,
+        Rpush = 3,
+        Sadd = 4,
+        Zadd = 5,
+        Xadd = 6,
+        Incrby = 7,
+    }
+    impl Command {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Command::Set => "COMMAND_SET",
+                Command::Hset => "COMMAND_HSET",
+                Command::Lpush => "COMMAND_LPUSH",
+                Command::Rpush => "COMMAND_RPUSH",
+                Command::Sadd => "COMMAND_SADD",
+                Command::Zadd => "COMMAND_ZADD",
+                Command::Xadd => "COMMAND_XADD",
+                Command::Incrby => "COMMAND_INCRBY",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "COMMAND_SET" => Some(Self::Set),
+                "COMMAND_HSET" => Some(Self::Hset),
+                "COMMAND_LPUSH" => Some(Self::Lpush),
+                "COMMAND_RPUSH" => Some(Self::Rpush),
+                "COMMAND_SADD" => Some(Self::Sadd),
+                "COMMAND_ZADD" => Some(Self::Zadd),
+                "COMMAND_XADD" => Some(Self::Xadd),
+                "COMMAND_INCRBY" => Some(Self::Incrby),
+                _ => None,
+            }
+        }
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MqttDesc {
+    #[prost(message, optional, tag = "1")]
+    pub connection_opts: ::core::option::Option<mqtt_desc::ConnectionOpts>,
+    /// topic filters subscribed by source, wildcards '+' and '#' are supported. Only available for source
+    #[prost(string, repeated, tag = "2")]
+    pub topic_filters: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// topic published by sink. Only available for sink
+    #[prost(string, tag = "3")]
+    pub topic: ::prost::alloc::string::String,
+    /// QoS of subscriptions and published messages
+    #[prost(enumeration = "mqtt_desc::Qos", tag = "4")]
+    pub qos: i32,
+    /// payload is decoded by data_type, only available for source
+    #[prost(enumeration = "DataTypeEnum", tag = "5")]
+    pub data_type: i32,
+    /// keep the session when disconnecting, so that the broker redelivers messages of QoS 1 and 2 not acked yet
+    #[prost(bool, tag = "6")]
+    pub persistent_session: bool,
+}
+/// Nested message and enum types in `MqttDesc`.
+pub mod mqtt_desc {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct ConnectionOpts {
+        /// address of the broker, e.g. 'localhost:1883'. Port is 1883 by default
+        #[prost(string, tag = "1")]
+        pub broker: ::prost::alloc::string::String,
+        /// '<job id>-<connector id>' by default
+        #[prost(string, tag = "2")]
+        pub client_id: ::prost::alloc::string::String,
+        #[prost(string, tag = "3")]
+        pub username: ::prost::alloc::string::String,
+        #[prost(string, tag = "4")]
+        pub password: ::prost::alloc::string::String,
+        /// interval of keep alive pings in seconds, 60 by default
+        #[prost(uint64, tag = "5")]
+        pub keep_alive_secs: u64,
+    }
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum Qos {
+        AtMostOnce = 0,
+        AtLeastOnce = 1,
+        ExactlyOnce = 2,
+    }
+    impl Qos {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Qos::AtMostOnce => "QOS_AT_MOST_ONCE",
+                Qos::AtLeastOnce => "QOS_AT_LEAST_ONCE",
+                Qos::ExactlyOnce => "QOS_EXACTLY_ONCE",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "QOS_AT_MOST_ONCE" => Some(Self::AtMostOnce),
+                "QOS_AT_LEAST_ONCE" => Some(Self::AtLeastOnce),
+                "QOS_EXACTLY_ONCE" => Some(Self::ExactlyOnce),
+                _ => None,
+            }
+        }
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FileDesc {
+    /// for source, it's a path or a glob pattern of input files, e.g. '/data/orders-*.jsonl'.
+    /// for sink, it's the directory which output files are written into
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(enumeration = "file_desc::Format", tag = "2")]
+    pub format: i32,
+    #[prost(message, optional, tag = "3")]
+    pub csv_opts: ::core::option::Option<file_desc::CsvOpts>,
+    /// keep reading appended data and new files matching the pattern instead of terminating at the end of files. Only available for source
+    #[prost(bool, tag = "4")]
+    pub follow: bool,
+    /// interval of checking appended data in milliseconds, 1000 by default. Only available for source
+    #[prost(uint64, tag = "5")]
+    pub poll_interval_millis: u64,
+    /// rotation of output files, only available for sink. Output files are never rolled if it's not set
+    #[prost(message, optional, tag = "6")]
+    pub rotation: ::core::option::Option<file_desc::RotationOpts>,
+}
+/// Nested message and enum types in `FileDesc`.
+pub mod file_desc {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct CsvOpts {
+        /// delimiter of fields, ',' by default
+        #[prost(string, tag = "1")]
+        pub delimiter: ::prost::alloc::string::String,
+        /// the first line of each file is the header. Sink writes the header at the beginning of each file
+        #[prost(bool, tag = "2")]
+        pub has_header: bool,
+        /// names of columns. Source uses them if files have no header, and fields are named by their indexes if both are missing.
+        /// Sink writes fields of objects in this order, or in the order of field names by default
+        #[prost(string, repeated, tag = "3")]
+        pub columns: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct RotationOpts {
+        /// roll to a new file once the current file is larger than it. 0 means no limit
+        #[prost(uint64, tag = "1")]
+        pub max_bytes: u64,
+        /// roll to a new file once the current file has been opened longer than it. 0 means no limit
+        #[prost(uint64, tag = "2")]
+        pub max_millis: u64,
+    }
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum Format {
+        /// each line is a JSON value
+        JsonLines = 0,
+        /// each line is a record of CSV. Quoted fields can not contain line breaks
+        Csv = 1,
+    }
+    impl Format {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Format::JsonLines => "FORMAT_JSON_LINES",
+                Format::Csv => "FORMAT_CSV",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "FORMAT_JSON_LINES" => Some(Self::JsonLines),
+                "FORMAT_CSV" => Some(Self::Csv),
+                _ => None,
+            }
+        }
+    }
+}
+/// a source generating synthetic events without external services, for tests and benchmarks.
+/// Each event is generated from an input object {index, key, random}:
+/// - index: the index of the event, starting from 0;
+/// - key: one of `key_cardinality` distinct string keys, or null if `key_cardinality` is 0. It's also the key of the event;
+/// - random: a pseudo-random number in [0, 1).
+/// The same seed always generates the same sequence of inputs
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GeneratorDesc {
+    /// events generated per second. Events are generated as fast as possible if it's 0
+    #[prost(uint64, tag = "1")]
+    pub rate_per_sec: u64,
+    /// total number of events. The source terminates after all events are generated. It's unbounded if it's 0
+    #[prost(uint64, tag = "2")]
+    pub count: u64,
+    /// number of distinct keys
+    #[prost(uint64, tag = "3")]
+    pub key_cardinality: u64,
+    /// seed of the pseudo-random sequence
+    #[prost(uint64, tag = "4")]
+    pub seed: u64,
+    /// a JS function named `generate` which takes the input and returns the value of the event
+    #[prost(message, optional, tag = "5")]
+    pub function: ::core::option::Option<Func>,
+    /// a JSON template of the value with placeholders {{index}}, {{key}} and {{random}}, such as '{"id": {{index}}, "user": {{key}}}'.
+    /// It's used if `function` is not set, and the value is the input itself if both are not set
+    #[prost(string, tag = "6")]
+    pub template: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ParquetDesc {
+    /// the directory which partitions are written into
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    /// schema of files. Fields of object values which are not declared are not written
+    #[prost(message, repeated, tag = "2")]
+    pub columns: ::prost::alloc::vec::Vec<parquet_desc::Column>,
+    /// files are written into 'path' directly if it's not set
+    #[prost(message, optional, tag = "3")]
+    pub partitioning: ::core::option::Option<parquet_desc::Partitioning>,
+    /// a file of a partition is committed and a new file is opened when it's rolled. Files are rolled only when the sink is closed if it's not set
+    #[prost(message, optional, tag = "4")]
+    pub rotation: ::core::option::Option<file_desc::RotationOpts>,
+    /// max number of rows of a row group, 10000 by default
+    #[prost(uint32, tag = "5")]
+    pub row_group_size: u32,
+}
+/// Nested message and enum types in `ParquetDesc`.
+pub mod parquet_desc {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Column {
+        /// name of the column and the field of object values
+        #[prost(string, tag = "1")]
+        pub name: ::prost::alloc::string::String,
+        #[prost(enumeration = "column::Type", tag = "2")]
+        pub r#type: i32,
+        /// rows whose field of this column is missing or null are dropped if it's true
+        #[prost(bool, tag = "3")]
+        pub required: bool,
+    }
+    /// Nested message and enum types in `Column`.
+    pub mod column {
+        #[derive(
+            Clone,
+            Copy,
+            Debug,
+            PartialEq,
+            Eq,
+            Hash,
+            PartialOrd,
+            Ord,
+            ::prost::Enumeration
+        )]
+        #[repr(i32)]
+        pub enum Type {
+            String = 0,
+            Int64 = 1,
+            Double = 2,
+            Boolean = 3,
+            /// milliseconds since epoch
+            TimestampMillis = 4,
+        }
+        impl Type {
+            /// String value of the enum field names used in the ProtoBuf definition.
+            ///
+            /// The values are not transformed in any way and thus are considered stable
+            /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+            pub fn as_str_name(&self) -> &'static str {
+                match self {
+                    Type::String => "TYPE_STRING",
+                    Type::Int64 => "TYPE_INT64",
+                    Type::Double => "TYPE_DOUBLE",
+                    Type::Boolean => "TYPE_BOOLEAN",
+                    Type::TimestampMillis => "TYPE_TIMESTAMP_MILLIS",
+                }
+            }
+            /// Creates an enum from field names used in the ProtoBuf definition.
+            pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+                match value {
+                    "TYPE_STRING" => Some(Self::String),
+                    "TYPE_INT64" => Some(Self::Int64),
+                    "TYPE_DOUBLE" => Some(Self::Double),
+                    "TYPE_BOOLEAN" => Some(Self::Boolean),
+                    "TYPE_TIMESTAMP_MILLIS" => Some(Self::TimestampMillis),
+                    _ => None,
+                }
+            }
+        }
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Partitioning {
+        /// a directory of the time bucket of event time in UTC, formatted by strftime, e.g. 'dt=%Y-%m-%d/hour=%H'
+        #[prost(string, tag = "1")]
+        pub time_format: ::prost::alloc::string::String,
+        /// a directory '<field>=<value>' for each field of object values, nested after the time bucket
+        #[prost(string, repeated, tag = "2")]
+        pub fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    }
+}
+/// a sink for debugging which logs events by `tracing` at INFO level
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StdoutDesc {
+    /// prefix of each logged line, which helps to tell sinks apart
+    #[prost(string, tag = "1")]
+    pub prefix: ::prost::alloc::string::String,
+}
+/// a sink for debugging and tests which keeps events in memory of the TaskManager.
+/// Kept events can be fetched by the `GetCollectedEvents` RPC of the TaskManager
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CollectDesc {
+    /// max number of kept events. The oldest events are dropped once it's exceeded. 1000 by default
+    #[prost(uint32, tag = "1")]
+    pub capacity: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HttpDesc {
+    /// token which requests pushing events must carry in the header 'Authorization: Bearer <token>'.
+    /// Requests are not authenticated if it's empty. Only available for source
+    #[prost(string, tag = "1")]
+    pub token: ::prost::alloc::string::String,
+    /// url which sink sends requests to. Only available for sink
+    #[prost(string, tag = "2")]
+    pub url: ::prost::alloc::string::String,
+    /// method of requests, POST by default. Only available for sink
+    #[prost(enumeration = "http_desc::Method", tag = "3")]
+    pub method: i32,
+    /// headers of requests. 'Content-Type: application/json' is set if it's not specified. Only available for sink
+    #[prost(map = "string, string", tag = "4")]
+    pub headers: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    /// template of the body of each value, e.g. '{"text": {{value.message}}}'. Only available for sink.
+    /// '{{value}}' is replaced by the JSON of the value and '{{value.<field>}}' by the JSON of a field of an object value
+    #[prost(string, tag = "5")]
+    pub body_template: ::prost::alloc::string::String,
+    /// function which extracts the body of each value. It overwrites body_template. Only available for sink
+    #[prost(message, optional, tag = "6")]
+    pub body_extractor: ::core::option::Option<Func>,
+    #[prost(message, optional, tag = "7")]
+    pub batch: ::core::option::Option<http_desc::BatchOpts>,
+    #[prost(message, optional, tag = "8")]
+    pub retry: ::core::option::Option<http_desc::RetryOpts>,
+    /// max number of concurrent requests, 1 by default. Only available for sink
+    #[prost(uint32, tag = "9")]
+    pub concurrency: u32,
+    /// timeout of each request in milliseconds, 3000 by default. Only available for sink
+    #[prost(uint64, tag = "10")]
+    pub timeout_millis: u64,
+}
+/// Nested message and enum types in `HttpDesc`.
+pub mod http_desc {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct BatchOpts {
+        /// max number of values in the JSON array of a request. Each value is sent by a single request if it's 0 or 1
+        #[prost(uint32, tag = "1")]
+        pub max_size: u32,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct RetryOpts {
+        /// max retries of a request which fails with 5xx status or a connection error. 0 means no retry
+        #[prost(uint32, tag = "1")]
+        pub max_retries: u32,
+        /// backoff of the first retry in milliseconds, 100 by default. It's doubled after each retry
+        #[prost(uint64, tag = "2")]
+        pub initial_backoff_millis: u64,
+        /// max backoff in milliseconds, 10000 by default
+        #[prost(uint64, tag = "3")]
+        pub max_backoff_millis: u64,
+    }
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum Method {
+        Post = 0,
+        Put = 1,
+        Patch = 2,
+    }
+    impl Method {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Method::Post => "METHOD_POST",
+                Method::Put => "METHOD_PUT",
+                Method::Patch => "METHOD_PATCH",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "METHOD_POST" => Some(Self::Post),
+                "METHOD_PUT" => Some(Self::Put),
+                "METHOD_PATCH" => Some(Self::Patch),
+                _ => None,
+            }
+        }
     }
 }
 /// An union linked-list structure of the description of Dataflow.
@@ -1,14 +1,18 @@
+use std::collections::BTreeMap;
+
 use chrono::Duration;
 
 use crate::common::{
+    file_desc, http_desc, kafka_desc, mqtt_desc,
     mysql_desc::{self, Statement},
     operator_info::Details,
-    sink, source,
+    parquet_desc, postgres_desc, redis_desc, sink, source,
     trigger::Watermark,
     window::{self, FixedWindow, SessionWindow, SlidingWindow},
-    Ack, DataTypeEnum, Dataflow, Entry, SubDataflowId, Func, Heartbeat, HostAddr, KafkaDesc,
-    KeyedDataEvent, MysqlDesc, OperatorInfo, RedisDesc, ResourceId, Response, Sink, Source, Time,
-    Trigger, Window,
+    Ack, DataTypeEnum, Dataflow, DataflowMeta, Entry, FileDesc, Func, Heartbeat, HostAddr,
+    HttpDesc, KafkaDesc, KeyedDataEvent, MqttDesc, MysqlDesc, OperatorInfo, OperatorInstance,
+    ParquetDesc, PostgresDesc, RedisDesc, ResourceId, Response, RowKind, Sink, Source,
+    SubDataflowId, Time, Trigger, Window,
 };
 
 pub const SUCCESS_RPC_RESPONSE: &str = "success";
@@ -93,6 +97,10 @@ const KEYED_DATA_EVENT_SCHEMA: &str = r#"{
             "name": "event_id", 
             "type": "long"
         }
+        {
+            "name": "row_kind", 
+            "type": "int"
+        }
     ]
 }"#;
 
@@ -117,6 +125,31 @@ impl OperatorInfo {
             .is_some()
     }
 
+    /// Events of a push source are sent to the operator by `BatchSendEventsToOperator` instead of being pulled by the source connector
+    pub fn has_push_source(&self) -> bool {
+        self.get_http_source_desc().is_some()
+    }
+
+    pub fn get_http_source_desc(&self) -> Option<&HttpDesc> {
+        self.details.as_ref().and_then(|details| match details {
+            Details::Source(source) => match source.desc.as_ref() {
+                Some(source::Desc::Http(desc)) => Some(desc),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    pub fn get_http_source_desc_mut(&mut self) -> Option<&mut HttpDesc> {
+        self.details.as_mut().and_then(|details| match details {
+            Details::Source(source) => match source.desc.as_mut() {
+                Some(source::Desc::Http(desc)) => Some(desc),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
     pub fn get_host_addr(&self) -> HostAddr {
         self.host_addr
             .as_ref()
@@ -167,6 +200,49 @@ impl OperatorInfo {
             })
             .unwrap_or_default()
     }
+
+    pub fn has_key_by(&self) -> bool {
+        self.details
+            .as_ref()
+            .map(|details| match details {
+                Details::KeyBy(_) => true,
+                _ => false,
+            })
+            .unwrap_or_default()
+    }
+
+    /// the operators whose keyed state is partitioned into key groups
+    pub fn has_keyed_state(&self) -> bool {
+        self.details
+            .as_ref()
+            .map(|details| match details {
+                Details::Reducer(_) => true,
+                _ => false,
+            })
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn get_parallelism(&self) -> u32 {
+        self.parallelism.max(1)
+    }
+
+    /// the id of the operator in the submitted dataflow, which is shared by all its parallel instances
+    #[inline]
+    pub fn get_logical_operator_id(&self) -> u32 {
+        self.instance
+            .as_ref()
+            .map(|instance| instance.operator_id)
+            .unwrap_or(self.operator_id)
+    }
+
+    #[inline]
+    pub fn get_instance_index(&self) -> u32 {
+        self.instance
+            .as_ref()
+            .map(|instance| instance.index)
+            .unwrap_or_default()
+    }
 }
 
 impl Window {
@@ -183,7 +259,9 @@ impl KafkaDesc {
     pub(crate) fn check(&self) -> Result<(), DataflowValidateError> {
         if self.brokers.is_empty() {
             Err(DataflowValidateError::MissingKafkaBrokers)
-        } else if self.data_type() == DataTypeEnum::Unspecified {
+        } else if self.data_type() == DataTypeEnum::Unspecified
+            && self.format() == kafka_desc::MessageFormat::Plain
+        {
             Err(DataflowValidateError::MissingKafkaDataType)
         } else if self.topic.is_empty() {
             Err(DataflowValidateError::MissingKafkaTopic)
@@ -215,6 +293,12 @@ impl MysqlDesc {
             .unwrap_or_default()
     }
 
+    pub fn get_mysql_delete_statement(&self) -> Option<Statement> {
+        self.delete_statement
+            .as_ref()
+            .map(|statement| statement.clone())
+    }
+
     pub(crate) fn check(&self) -> Result<(), DataflowValidateError> {
         if self.connection_opts.is_none() {
             Err(DataflowValidateError::MissingMysqlConnectionOpts)
@@ -231,6 +315,70 @@ impl MysqlDesc {
             Ok(())
         }
     }
+
+    pub fn get_polling_opts(&self) -> mysql_desc::PollingOpts {
+        self.polling_opts
+            .as_ref()
+            .map(|opts| opts.clone())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn check_source(&self) -> Result<(), DataflowValidateError> {
+        if self.connection_opts.is_none() {
+            Err(DataflowValidateError::MissingMysqlConnectionOpts)
+        } else if self
+            .polling_opts
+            .as_ref()
+            .filter(|opts| !opts.query.is_empty() && !opts.incrementing_column.is_empty())
+            .is_none()
+        {
+            Err(DataflowValidateError::MissingMysqlPollingOpts)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl PostgresDesc {
+    pub fn get_postgres_statement(&self) -> postgres_desc::Statement {
+        self.statement
+            .as_ref()
+            .map(|statement| statement.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn get_postgres_delete_statement(&self) -> Option<postgres_desc::Statement> {
+        self.delete_statement
+            .as_ref()
+            .map(|statement| statement.clone())
+    }
+
+    pub(crate) fn check(&self) -> Result<(), DataflowValidateError> {
+        if self.connection_opts.is_none() {
+            Err(DataflowValidateError::MissingPostgresConnectionOpts)
+        } else if self
+            .statement
+            .as_ref()
+            .filter(|statement| !statement.statement.is_empty())
+            .is_none()
+        {
+            Err(DataflowValidateError::MissingPostgresStatement)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// the first of the comma-separated redis hosts whose port is not a number
+fn invalid_redis_host(hosts: &str) -> Option<String> {
+    hosts
+        .split(',')
+        .map(|host| host.trim())
+        .find(|host| match host.rsplit_once(':') {
+            Some((_, port)) => port.parse::<u16>().is_err(),
+            None => false,
+        })
+        .map(|host| host.to_string())
 }
 
 impl RedisDesc {
@@ -239,6 +387,10 @@ impl RedisDesc {
             Err(DataflowValidateError::MissingRedisKeyExtractor)
         } else if self.value_extractor.is_none() {
             Err(DataflowValidateError::MissingRedisValueExtractor)
+        } else if self.command() == redis_desc::Command::Hset && self.field_extractor.is_none() {
+            Err(DataflowValidateError::MissingRedisFieldExtractor)
+        } else if self.command() == redis_desc::Command::Zadd && self.score_extractor.is_none() {
+            Err(DataflowValidateError::MissingRedisScoreExtractor)
         } else if self.connection_opts.is_none() {
             Err(DataflowValidateError::MissingRedisConnectionOpts)
         } else {
@@ -246,6 +398,8 @@ impl RedisDesc {
                 Some(opts) => {
                     if opts.host.is_empty() {
                         Err(DataflowValidateError::MissingRedisHost)
+                    } else if let Some(host) = invalid_redis_host(&opts.host) {
+                        Err(DataflowValidateError::InvalidRedisHost(host))
                     } else if opts.tls {
                         if opts.username.is_empty() || opts.password.is_empty() {
                             Err(DataflowValidateError::MissingRedisTlsConfig)
@@ -260,6 +414,150 @@ impl RedisDesc {
             }
         }
     }
+
+    pub fn get_stream_opts(&self) -> redis_desc::StreamOpts {
+        self.stream_opts
+            .as_ref()
+            .map(|opts| opts.clone())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn check_source(&self) -> Result<(), DataflowValidateError> {
+        match self.connection_opts.as_ref() {
+            Some(opts) if opts.host.is_empty() => Err(DataflowValidateError::MissingRedisHost),
+            Some(opts) => {
+                if let Some(host) = invalid_redis_host(&opts.host) {
+                    Err(DataflowValidateError::InvalidRedisHost(host))
+                } else if self
+                    .stream_opts
+                    .as_ref()
+                    .filter(|opts| !opts.key.is_empty() && !opts.group.is_empty())
+                    .is_none()
+                {
+                    Err(DataflowValidateError::MissingRedisStreamOpts)
+                } else {
+                    Ok(())
+                }
+            }
+            None => Err(DataflowValidateError::MissingRedisConnectionOpts),
+        }
+    }
+}
+
+impl MqttDesc {
+    pub(crate) fn check_source(&self) -> Result<(), DataflowValidateError> {
+        if self.topic_filters.is_empty() {
+            Err(DataflowValidateError::MissingMqttTopicFilters)
+        } else if self.data_type() == DataTypeEnum::Unspecified {
+            Err(DataflowValidateError::MissingMqttDataType)
+        } else {
+            self.check_connection_opts()
+        }
+    }
+
+    pub(crate) fn check(&self) -> Result<(), DataflowValidateError> {
+        if self.topic.is_empty() {
+            Err(DataflowValidateError::MissingMqttTopic)
+        } else {
+            self.check_connection_opts()
+        }
+    }
+
+    fn check_connection_opts(&self) -> Result<(), DataflowValidateError> {
+        match self.connection_opts.as_ref() {
+            Some(opts) if opts.broker.is_empty() => Err(DataflowValidateError::MissingMqttBroker),
+            Some(_) => Ok(()),
+            None => Err(DataflowValidateError::MissingMqttConnectionOpts),
+        }
+    }
+
+    pub fn get_connection_opts(&self) -> mqtt_desc::ConnectionOpts {
+        self.connection_opts
+            .as_ref()
+            .map(|opts| opts.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl FileDesc {
+    pub(crate) fn check(&self) -> Result<(), DataflowValidateError> {
+        if self.path.is_empty() {
+            Err(DataflowValidateError::MissingFilePath)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn get_csv_opts(&self) -> file_desc::CsvOpts {
+        self.csv_opts
+            .as_ref()
+            .map(|opts| opts.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn get_rotation_opts(&self) -> file_desc::RotationOpts {
+        self.rotation
+            .as_ref()
+            .map(|opts| opts.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl ParquetDesc {
+    pub(crate) fn check(&self) -> Result<(), DataflowValidateError> {
+        if self.path.is_empty() {
+            Err(DataflowValidateError::MissingParquetPath)
+        } else if self.columns.is_empty() {
+            Err(DataflowValidateError::MissingParquetColumns)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn get_partitioning(&self) -> parquet_desc::Partitioning {
+        self.partitioning
+            .as_ref()
+            .map(|partitioning| partitioning.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn get_rotation_opts(&self) -> file_desc::RotationOpts {
+        self.rotation
+            .as_ref()
+            .map(|opts| opts.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl HttpDesc {
+    pub(crate) fn check(&self) -> Result<(), DataflowValidateError> {
+        if self.url.is_empty() {
+            Err(DataflowValidateError::MissingHttpUrl)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn get_batch_opts(&self) -> http_desc::BatchOpts {
+        self.batch
+            .as_ref()
+            .map(|opts| opts.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn get_retry_opts(&self) -> http_desc::RetryOpts {
+        self.retry
+            .as_ref()
+            .map(|opts| opts.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl file_desc::CsvOpts {
+    /// delimiter of fields, ',' if it's not specified
+    pub fn get_delimiter(&self) -> u8 {
+        self.delimiter.as_bytes().first().copied().unwrap_or(b',')
+    }
 }
 
 impl Dataflow {
@@ -277,14 +575,17 @@ impl Dataflow {
             }
 
             for neighbor in &meta.neighbors {
-                if neighbor < &meta.center {
-                    return Err(DataflowValidateError::CyclicDataflow);
-                }
-
                 let result = self.check_operator(*neighbor);
                 if result.is_err() {
                     return result;
                 }
+
+                // parallel instances are ordered by the ids of their operators
+                if self.get_logical_operator_id(*neighbor)
+                    < self.get_logical_operator_id(meta.center)
+                {
+                    return Err(DataflowValidateError::CyclicDataflow);
+                }
             }
         }
 
@@ -302,7 +603,13 @@ impl Dataflow {
 
             match operator.details.as_ref() {
                 Some(detail) => match detail {
-                    Details::Source(source) => source.check(),
+                    Details::Source(source) => source.check().and_then(|_| {
+                        if operator.get_parallelism() > 1 && !source.is_parallel() {
+                            Err(DataflowValidateError::UnparallelSource(node_id))
+                        } else {
+                            Ok(())
+                        }
+                    }),
                     Details::Sink(sink) => sink.check(),
                     _ => Ok(()),
                 },
@@ -321,6 +628,86 @@ impl Dataflow {
     pub fn get_execution_id_ref(&self) -> Option<&SubDataflowId> {
         self.execution_id.as_ref()
     }
+
+    fn get_logical_operator_id(&self, operator_id: u32) -> u32 {
+        self.nodes
+            .get(&operator_id)
+            .map(|operator| operator.get_logical_operator_id())
+            .unwrap_or(operator_id)
+    }
+
+    /// Replace each operator with its parallel instances. Instance 0 keeps the id of the operator, and the other instances get ids after the largest one.
+    /// Every instance is connected to all instances of the neighbors of the operator, and the upstream instances choose which ones they send events to.
+    /// A dataflow which has been expanded is left unchanged
+    pub fn expand_parallelism(&mut self) {
+        if self
+            .nodes
+            .values()
+            .any(|operator| operator.instance.is_some())
+        {
+            return;
+        }
+
+        let mut operator_ids = self.nodes.keys().copied().collect::<Vec<_>>();
+        operator_ids.sort();
+        let mut next_id = operator_ids.last().map(|id| id + 1).unwrap_or_default();
+        let instances = operator_ids
+            .iter()
+            .map(|operator_id| {
+                let parallelism = self.nodes[operator_id].get_parallelism();
+                let mut ids = vec![*operator_id];
+                (1..parallelism).for_each(|_| {
+                    ids.push(next_id);
+                    next_id += 1;
+                });
+                (*operator_id, ids)
+            })
+            .collect::<BTreeMap<_, _>>();
+        let instances_of = |operator_ids: &[u32]| {
+            operator_ids
+                .iter()
+                .flat_map(|operator_id| {
+                    instances
+                        .get(operator_id)
+                        .cloned()
+                        .unwrap_or_else(|| vec![*operator_id])
+                })
+                .collect::<Vec<_>>()
+        };
+
+        self.meta = self
+            .meta
+            .iter()
+            .flat_map(|meta| {
+                let neighbors = instances_of(&meta.neighbors);
+                instances_of(&[meta.center])
+                    .into_iter()
+                    .map(move |center| DataflowMeta {
+                        center,
+                        neighbors: neighbors.clone(),
+                    })
+            })
+            .collect();
+        self.nodes = self
+            .nodes
+            .values()
+            .flat_map(|operator| {
+                let upstreams = instances_of(&operator.upstreams);
+                instances[&operator.operator_id].iter().enumerate().map(
+                    move |(index, instance_id)| {
+                        let mut instance = operator.clone();
+                        instance.operator_id = *instance_id;
+                        instance.upstreams = upstreams.clone();
+                        instance.instance = Some(OperatorInstance {
+                            operator_id: operator.operator_id,
+                            index: index as u32,
+                        });
+                        (*instance_id, instance)
+                    },
+                )
+            })
+            .collect();
+    }
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -338,10 +725,28 @@ pub enum DataflowValidateError {
     MissingMysqlConnectionOpts,
     MissingMysqlStatement,
     MissingRedisHost,
+    InvalidRedisHost(String),
     MissingRedisTlsConfig,
     MissingKafkaBrokers,
     MissingKafkaDataType,
     MissingKafkaTopic,
+    MissingMysqlPollingOpts,
+    MissingPostgresConnectionOpts,
+    MissingPostgresStatement,
+    MissingRedisFieldExtractor,
+    MissingRedisScoreExtractor,
+    MissingRedisStreamOpts,
+    MissingMqttConnectionOpts,
+    MissingMqttBroker,
+    MissingMqttTopicFilters,
+    MissingMqttTopic,
+    MissingMqttDataType,
+    MissingFilePath,
+    MissingHttpUrl,
+    MissingParquetPath,
+    MissingParquetColumns,
+    // parallelism of a source which can't be read by parallel instances is larger than 1
+    UnparallelSource(u32),
 }
 
 impl Source {
@@ -349,10 +754,26 @@ impl Source {
         match self.desc.as_ref() {
             Some(desc) => match desc {
                 source::Desc::Kafka(kafka) => kafka.check(),
+                source::Desc::Mysql(mysql) => mysql.check_source(),
+                source::Desc::Redis(redis) => redis.check_source(),
+                source::Desc::Mqtt(mqtt) => mqtt.check_source(),
+                source::Desc::File(file) => file.check(),
+                source::Desc::Http(_) => Ok(()),
+                source::Desc::Generator(_) => Ok(()),
             },
             None => Err(DataflowValidateError::MissingSourceDesc),
         }
     }
+
+    /// Whether the parallel instances of the source split the events instead of reading all of them.
+    /// Instances of a Kafka source are in the same consumer group, and events of an HTTP source are pushed to one instance.
+    /// The others are read by every instance, so that each event would be duplicated
+    pub fn is_parallel(&self) -> bool {
+        matches!(
+            self.desc.as_ref(),
+            Some(source::Desc::Kafka(_)) | Some(source::Desc::Http(_))
+        )
+    }
 }
 
 impl Sink {
@@ -362,6 +783,12 @@ impl Sink {
                 sink::Desc::Redis(redis) => redis.check(),
                 sink::Desc::Kafka(kafka) => kafka.check(),
                 sink::Desc::Mysql(mysql) => mysql.check(),
+                sink::Desc::Postgres(postgres) => postgres.check(),
+                sink::Desc::Mqtt(mqtt) => mqtt.check(),
+                sink::Desc::File(file) => file.check(),
+                sink::Desc::Http(http) => http.check(),
+                sink::Desc::Parquet(parquet) => parquet.check(),
+                sink::Desc::Stdout(_) | sink::Desc::Collect(_) => Ok(()),
             },
             None => Err(DataflowValidateError::MissingSinkDesc),
         }
@@ -392,6 +819,15 @@ impl KeyedDataEvent {
     pub fn get_event_time(&self) -> i64 {
         self.event_time
     }
+
+    /// an event with kind UPDATE_BEFORE or DELETE retracts a row emitted before
+    #[inline]
+    pub fn is_retraction(&self) -> bool {
+        match self.row_kind() {
+            RowKind::UpdateBefore | RowKind::Delete => true,
+            _ => false,
+        }
+    }
 }
 
 impl FixedWindow {
@@ -458,6 +894,32 @@ impl mysql_desc::ConnectionOpts {
     }
 }
 
+impl postgres_desc::ConnectionOpts {
+    pub fn get_uri(&self) -> String {
+        let db = &self.database;
+        let user = &self.username;
+        let password = &self.password;
+        let host = &self.host;
+
+        format!("postgres://{user}:{password}@{host}/{db}")
+    }
+}
+
+impl mqtt_desc::ConnectionOpts {
+    /// host and port of the broker. Port is 1883 if it's not specified
+    pub fn get_host_and_port(&self) -> (String, u16) {
+        match self.broker.rsplit_once(':') {
+            Some((host, port)) => match port.parse() {
+                Ok(port) => (host.to_string(), port),
+                Err(_) => (self.broker.clone(), DEFAULT_MQTT_PORT),
+            },
+            None => (self.broker.clone(), DEFAULT_MQTT_PORT),
+        }
+    }
+}
+
+const DEFAULT_MQTT_PORT: u16 = 1883;
+
 impl Response {
     pub fn ok() -> Self {
         Self {
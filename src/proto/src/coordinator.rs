@@ -4,6 +4,15 @@ pub struct GetDataflowRequest {
     #[prost(message, optional, tag = "1")]
     pub job_id: ::core::option::Option<super::common::ResourceId>,
 }
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RescaleDataflowRequest {
+    #[prost(message, optional, tag = "1")]
+    pub job_id: ::core::option::Option<super::common::ResourceId>,
+    /// the new parallelism of operators, keyed by the ids of the operators in the submitted dataflow
+    #[prost(map = "uint32, uint32", tag = "2")]
+    pub parallelism: ::std::collections::HashMap<u32, u32>,
+}
 /// Generated client implementations.
 pub mod coordinator_api_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
@@ -141,6 +150,27 @@ pub mod coordinator_api_client {
             );
             self.inner.unary(request.into_request(), path, codec).await
         }
+        /// / Change the parallelism of operators of a running dataflow, or place it onto the TaskManagers which are available now.
+        /// / The keyed state is redistributed to the new instances by key groups and sources resume from the positions they have recorded
+        pub async fn rescale_dataflow(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RescaleDataflowRequest>,
+        ) -> Result<tonic::Response<super::super::common::Response>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/coordinator.CoordinatorApi/RescaleDataflow",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
         /// / Receive ack
         pub async fn receive_ack(
             &mut self,
@@ -212,6 +242,12 @@ pub mod coordinator_api_server {
             tonic::Response<super::super::common::DataflowStates>,
             tonic::Status,
         >;
+        /// / Change the parallelism of operators of a running dataflow, or place it onto the TaskManagers which are available now.
+        /// / The keyed state is redistributed to the new instances by key groups and sources resume from the positions they have recorded
+        async fn rescale_dataflow(
+            &self,
+            request: tonic::Request<super::RescaleDataflowRequest>,
+        ) -> Result<tonic::Response<super::super::common::Response>, tonic::Status>;
         /// / Receive ack
         async fn receive_ack(
             &self,
@@ -403,6 +439,46 @@ pub mod coordinator_api_server {
                     };
                     Box::pin(fut)
                 }
+                "/coordinator.CoordinatorApi/RescaleDataflow" => {
+                    #[allow(non_camel_case_types)]
+                    struct RescaleDataflowSvc<T: CoordinatorApi>(pub Arc<T>);
+                    impl<
+                        T: CoordinatorApi,
+                    > tonic::server::UnaryService<super::RescaleDataflowRequest>
+                    for RescaleDataflowSvc<T> {
+                        type Response = super::super::common::Response;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RescaleDataflowRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).rescale_dataflow(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RescaleDataflowSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/coordinator.CoordinatorApi/ReceiveAck" => {
                     #[allow(non_camel_case_types)]
                     struct ReceiveAckSvc<T: CoordinatorApi>(pub Arc<T>);
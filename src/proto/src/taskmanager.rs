@@ -3,10 +3,17 @@
 pub struct SendEventToOperatorResponse {
     #[prost(enumeration = "SendEventToOperatorStatusEnum", tag = "1")]
     pub status: i32,
+    /// the number of events the sender can send to the operator without being rejected
+    #[prost(uint32, tag = "2")]
+    pub credits: u32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct BatchSendEventsToOperatorResponse {}
+pub struct BatchSendEventsToOperatorResponse {
+    /// the number of events the sender can send to the operator without being rejected
+    #[prost(uint32, tag = "1")]
+    pub credits: u32,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct StopDataflowResponse {
@@ -27,6 +34,117 @@ pub struct CreateSubDataflowResponse {
     #[prost(enumeration = "super::common::DataflowStatus", tag = "1")]
     pub status: i32,
 }
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetCollectedEventsRequest {
+    #[prost(message, optional, tag = "1")]
+    pub job_id: ::core::option::Option<super::common::ResourceId>,
+    /// id of the collect sink operator
+    #[prost(uint32, tag = "2")]
+    pub operator_id: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetCollectedEventsResponse {
+    /// kept events in the order they arrived
+    #[prost(message, repeated, tag = "1")]
+    pub events: ::prost::alloc::vec::Vec<super::common::KeyedDataEvent>,
+}
+/// A frame sent on the stream of an edge
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EdgeFrame {
+    /// sequence number of the frame on the edge, starting from 1. It's 0 for a handshake
+    #[prost(uint64, tag = "1")]
+    pub sequence: u64,
+    #[prost(oneof = "edge_frame::Frame", tags = "2, 3, 5")]
+    pub frame: ::core::option::Option<edge_frame::Frame>,
+}
+/// Nested message and enum types in `EdgeFrame`.
+pub mod edge_frame {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Frame {
+        #[prost(message, tag = "2")]
+        Handshake(super::EdgeHandshake),
+        #[prost(message, tag = "3")]
+        Events(super::super::common::KeyedEventSet),
+        #[prost(message, tag = "5")]
+        Terminate(super::EdgeTerminate),
+    }
+}
+/// The first frame of the stream, which identifies the edge
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EdgeHandshake {
+    #[prost(message, optional, tag = "1")]
+    pub job_id: ::core::option::Option<super::common::ResourceId>,
+    #[prost(uint32, tag = "2")]
+    pub from_operator_id: u32,
+    #[prost(uint32, tag = "3")]
+    pub to_operator_id: u32,
+}
+/// Control message that terminates the downstream operator
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EdgeTerminate {
+    #[prost(int64, tag = "1")]
+    pub event_time: i64,
+}
+/// Acks are cumulative. The handshake is acked with the last sequence accepted on the edge, so that a reconnected
+/// upstream operator can resume from the next frame
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EdgeAck {
+    /// the last sequence which has been accepted by the operator
+    #[prost(uint64, tag = "1")]
+    pub sequence: u64,
+    /// the number of events the operator can receive without waiting, when the frame is accepted
+    #[prost(uint32, tag = "2")]
+    pub credits: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportKeyGroupsRequest {
+    #[prost(message, optional, tag = "1")]
+    pub job_id: ::core::option::Option<super::common::ResourceId>,
+    /// the operator in the submitted dataflow, or the source executor if it's the state of a source
+    #[prost(uint32, tag = "2")]
+    pub operator_id: u32,
+    /// the positions recorded by a source, which are kept apart from the keyed state of operators
+    #[prost(bool, tag = "3")]
+    pub source: bool,
+    #[prost(uint32, repeated, tag = "4")]
+    pub key_groups: ::prost::alloc::vec::Vec<u32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct KeyGroupStates {
+    #[prost(message, optional, tag = "1")]
+    pub job_id: ::core::option::Option<super::common::ResourceId>,
+    #[prost(uint32, tag = "2")]
+    pub operator_id: u32,
+    #[prost(bool, tag = "3")]
+    pub source: bool,
+    #[prost(message, repeated, tag = "4")]
+    pub key_groups: ::prost::alloc::vec::Vec<KeyGroupState>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct KeyGroupState {
+    #[prost(uint32, tag = "1")]
+    pub key_group: u32,
+    #[prost(message, repeated, tag = "2")]
+    pub entries: ::prost::alloc::vec::Vec<KeyGroupEntry>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct KeyGroupEntry {
+    #[prost(bytes = "vec", tag = "1")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub value: ::prost::alloc::vec::Vec<u8>,
+}
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
 pub enum SendEventToOperatorStatusEnum {
@@ -272,6 +390,90 @@ pub mod task_manager_api_client {
             );
             self.inner.unary(request.into_request(), path, codec).await
         }
+        /// / Get events kept by a collect sink
+        pub async fn get_collected_events(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetCollectedEventsRequest>,
+        ) -> Result<tonic::Response<super::GetCollectedEventsResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/taskmanager.TaskManagerApi/GetCollectedEvents",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// / Open a long-lived stream of an edge between two operators. The first frame must be a handshake.
+        /// / Frames are delivered to the downstream operator in order, and the operator acks them with credits
+        pub async fn open_edge(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::EdgeFrame>,
+        ) -> Result<
+            tonic::Response<tonic::codec::Streaming<super::EdgeAck>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/taskmanager.TaskManagerApi/OpenEdge",
+            );
+            self.inner.streaming(request.into_streaming_request(), path, codec).await
+        }
+        /// / Export the keyed state of an operator in the key groups
+        pub async fn export_key_groups(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ExportKeyGroupsRequest>,
+        ) -> Result<tonic::Response<super::KeyGroupStates>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/taskmanager.TaskManagerApi/ExportKeyGroups",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// / Import the keyed state of an operator. Each key group replaces the one on the TaskManager, so importing an empty key group removes it
+        pub async fn import_key_groups(
+            &mut self,
+            request: impl tonic::IntoRequest<super::KeyGroupStates>,
+        ) -> Result<tonic::Response<super::super::common::Response>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/taskmanager.TaskManagerApi/ImportKeyGroups",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -322,6 +524,33 @@ pub mod task_manager_api_server {
             tonic::Response<super::super::common::SubDataflowStates>,
             tonic::Status,
         >;
+        /// / Get events kept by a collect sink
+        async fn get_collected_events(
+            &self,
+            request: tonic::Request<super::GetCollectedEventsRequest>,
+        ) -> Result<tonic::Response<super::GetCollectedEventsResponse>, tonic::Status>;
+        /// Server streaming response type for the OpenEdge method.
+        type OpenEdgeStream: futures_core::Stream<
+                Item = Result<super::EdgeAck, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// / Open a long-lived stream of an edge between two operators. The first frame must be a handshake.
+        /// / Frames are delivered to the downstream operator in order, and the operator acks them with credits
+        async fn open_edge(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::EdgeFrame>>,
+        ) -> Result<tonic::Response<Self::OpenEdgeStream>, tonic::Status>;
+        /// / Export the keyed state of an operator in the key groups
+        async fn export_key_groups(
+            &self,
+            request: tonic::Request<super::ExportKeyGroupsRequest>,
+        ) -> Result<tonic::Response<super::KeyGroupStates>, tonic::Status>;
+        /// / Import the keyed state of an operator. Each key group replaces the one on the TaskManager, so importing an empty key group removes it
+        async fn import_key_groups(
+            &self,
+            request: tonic::Request<super::KeyGroupStates>,
+        ) -> Result<tonic::Response<super::super::common::Response>, tonic::Status>;
     }
     /// / RPC Api for Task Manager
     #[derive(Debug)]
@@ -661,6 +890,165 @@ pub mod task_manager_api_server {
                     };
                     Box::pin(fut)
                 }
+                "/taskmanager.TaskManagerApi/GetCollectedEvents" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetCollectedEventsSvc<T: TaskManagerApi>(pub Arc<T>);
+                    impl<
+                        T: TaskManagerApi,
+                    > tonic::server::UnaryService<super::GetCollectedEventsRequest>
+                    for GetCollectedEventsSvc<T> {
+                        type Response = super::GetCollectedEventsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetCollectedEventsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_collected_events(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetCollectedEventsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/taskmanager.TaskManagerApi/OpenEdge" => {
+                    #[allow(non_camel_case_types)]
+                    struct OpenEdgeSvc<T: TaskManagerApi>(pub Arc<T>);
+                    impl<
+                        T: TaskManagerApi,
+                    > tonic::server::StreamingService<super::EdgeFrame>
+                    for OpenEdgeSvc<T> {
+                        type Response = super::EdgeAck;
+                        type ResponseStream = T::OpenEdgeStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<tonic::Streaming<super::EdgeFrame>>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).open_edge(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = OpenEdgeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/taskmanager.TaskManagerApi/ExportKeyGroups" => {
+                    #[allow(non_camel_case_types)]
+                    struct ExportKeyGroupsSvc<T: TaskManagerApi>(pub Arc<T>);
+                    impl<
+                        T: TaskManagerApi,
+                    > tonic::server::UnaryService<super::ExportKeyGroupsRequest>
+                    for ExportKeyGroupsSvc<T> {
+                        type Response = super::KeyGroupStates;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ExportKeyGroupsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).export_key_groups(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ExportKeyGroupsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/taskmanager.TaskManagerApi/ImportKeyGroups" => {
+                    #[allow(non_camel_case_types)]
+                    struct ImportKeyGroupsSvc<T: TaskManagerApi>(pub Arc<T>);
+                    impl<
+                        T: TaskManagerApi,
+                    > tonic::server::UnaryService<super::KeyGroupStates>
+                    for ImportKeyGroupsSvc<T> {
+                        type Response = super::super::common::Response;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::KeyGroupStates>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).import_key_groups(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ImportKeyGroupsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
@@ -1,23 +1,47 @@
 use std::{
-    collections::{hash_map::DefaultHasher, BTreeMap},
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, VecDeque},
+    future::Future,
     hash::{Hash, Hasher},
+    pin::Pin,
+    sync::Arc,
     task::Poll,
+    time::Duration,
 };
 
 use common::{
-    db::MysqlConn,
+    cdc::{decode_debezium_json, decode_debezium_json_key},
+    consts::{default_configs::DEFAULT_CHANNEL_SIZE, env_keys::CHANNEL_SIZE},
+    db::{
+        advance_high_water_mark, to_multi_row_statement, to_postgres_conflict_arguments,
+        to_postgres_multi_row_statement, to_typed_value, MysqlConn, PostgresConn,
+    },
+    err::{FileException, HttpException, ParquetException},
     event::{LocalEvent, StreamEvent},
+    file::{
+        decode_json_line, encode_csv_header, encode_csv_line, encode_json_line, get_file_extension,
+        list_files, parse_csv_line, to_csv_object, RollingFileWriter,
+    },
+    generator::{get_input_key, render_template, GeneratorInputs},
+    http::{render_body_template, HttpClient},
     kafka::{run_consumer, run_producer, KafkaConsumer, KafkaMessage, KafkaProducer},
-    redis::RedisClient,
+    mqtt::{run_publisher, run_subscriber, MqttMessage, MqttPublisher, MqttSubscriber},
+    parquet::PartitionedParquetWriter,
+    redis::{RedisClient, RedisCommand, STREAM_START_ID},
     types::{ExecutorId, SinkId, SourceId, TypedValue},
-    utils::times::{now, now_timestamp},
+    utils::{
+        get_env,
+        times::{now, now_timestamp},
+    },
 };
+use futures_util::StreamExt;
 use prost::Message;
 
 use proto::common::{
+    file_desc, kafka_desc, mysql_desc,
     operator_info::{self, Details},
-    sink, source, Entry, KafkaDesc, KeyedDataEvent, KeyedEventSet, MysqlDesc, OperatorInfo,
-    RedisDesc, ResourceId,
+    postgres_desc, redis_desc, sink, source, CollectDesc, DataTypeEnum, Entry, FileDesc, Func,
+    GeneratorDesc, HttpDesc, KafkaDesc, KeyedDataEvent, KeyedEventSet, MqttDesc, MysqlDesc,
+    OperatorInfo, ParquetDesc, PostgresDesc, RedisDesc, ResourceId, RowKind, StdoutDesc,
 };
 
 use tokio::sync::mpsc::error::TryRecvError;
@@ -26,6 +50,7 @@ use tonic::async_trait;
 use crate::{
     err::{BatchSinkException, SinkException},
     new_event_channel,
+    state::{new_source_state_mgt, StateManager},
     v8_runtime::RuntimeEngine,
     Receiver, Sender,
 };
@@ -63,6 +88,16 @@ pub trait Source {
 
     fn poll_next(&mut self, cx: &mut std::task::Context<'_>)
         -> std::task::Poll<Option<LocalEvent>>;
+
+    /// Stop reading from the external system. It returns `true` if the events which have been read are still polled
+    /// until `poll_next` returns `Ready(None)`, otherwise they are dropped
+    fn stop(&mut self) -> bool {
+        false
+    }
+
+    /// Acknowledge the events which have been polled. The executor calls it once they have been written to the out-edges,
+    /// so that the unacked ones are delivered again after a restart
+    fn ack(&mut self) {}
 }
 
 #[async_trait]
@@ -85,10 +120,24 @@ pub trait Sink {
      * Gracefully close sink
      */
     fn close_sink(&mut self);
+
+    /**
+     * Close the sink once the executor terminates. Sinks which should flush data without blocking the executor override it
+     */
+    async fn close(&mut self) {
+        self.close_sink()
+    }
 }
 
 pub enum SourceImpl {
     Kafka(Kafka, Sender<LocalEvent>, Receiver<LocalEvent>),
+    Mysql(Mysql, Sender<LocalEvent>, Receiver<LocalEvent>),
+    Redis(Redis, Sender<LocalEvent>, Receiver<LocalEvent>),
+    Mqtt(Mqtt, Sender<LocalEvent>, Receiver<LocalEvent>),
+    File(File, Sender<LocalEvent>, Receiver<LocalEvent>),
+    Generator(Generator, Sender<LocalEvent>, Receiver<LocalEvent>),
+    /// A push source. Events pushed by HTTP requests arrive at the in-edge of the executor, so it only receives the terminate event
+    Http(SourceId, Sender<LocalEvent>, Receiver<LocalEvent>),
     Empty(SourceId, Sender<LocalEvent>, Receiver<LocalEvent>),
 }
 
@@ -97,6 +146,12 @@ impl Source for SourceImpl {
     fn source_id(&self) -> SourceId {
         match self {
             SourceImpl::Kafka(source, _, _) => source.source_id(),
+            SourceImpl::Mysql(source, _, _) => source.source_id(),
+            SourceImpl::Redis(source, _, _) => source.source_id(),
+            SourceImpl::Mqtt(source, _, _) => source.source_id(),
+            SourceImpl::File(source, _, _) => source.source_id(),
+            SourceImpl::Generator(source, _, _) => source.source_id(),
+            SourceImpl::Http(source_id, _, _) => *source_id,
             SourceImpl::Empty(source_id, _, _) => *source_id,
         }
     }
@@ -117,6 +172,62 @@ impl Source for SourceImpl {
                     },
                 }
             }
+            Self::Mysql(source, _, terminator_rx) => match terminator_rx.try_recv() {
+                Ok(message) => Some(message),
+                Err(err) => match err {
+                    TryRecvError::Disconnected => Some(LocalEvent::Terminate {
+                        job_id: source.job_id.clone(),
+                        to: source.source_id(),
+                        event_time: now().timestamp_millis(),
+                    }),
+                    _ => source.next().await,
+                },
+            },
+            Self::Redis(source, _, terminator_rx) => match terminator_rx.try_recv() {
+                Ok(message) => Some(message),
+                Err(err) => match err {
+                    TryRecvError::Disconnected => Some(LocalEvent::Terminate {
+                        job_id: source.job_id.clone(),
+                        to: source.source_id(),
+                        event_time: now().timestamp_millis(),
+                    }),
+                    _ => source.next().await,
+                },
+            },
+            Self::Mqtt(source, _, terminator_rx) => match terminator_rx.try_recv() {
+                Ok(message) => Some(message),
+                Err(err) => match err {
+                    TryRecvError::Disconnected => Some(LocalEvent::Terminate {
+                        job_id: source.job_id.clone(),
+                        to: source.source_id(),
+                        event_time: now().timestamp_millis(),
+                    }),
+                    _ => source.next().await,
+                },
+            },
+            Self::File(source, _, terminator_rx) => match terminator_rx.try_recv() {
+                Ok(message) => Some(message),
+                Err(err) => match err {
+                    TryRecvError::Disconnected => Some(LocalEvent::Terminate {
+                        job_id: source.job_id.clone(),
+                        to: source.source_id(),
+                        event_time: now().timestamp_millis(),
+                    }),
+                    _ => source.next().await,
+                },
+            },
+            Self::Generator(source, _, terminator_rx) => match terminator_rx.try_recv() {
+                Ok(message) => Some(message),
+                Err(err) => match err {
+                    TryRecvError::Disconnected => Some(LocalEvent::Terminate {
+                        job_id: source.job_id.clone(),
+                        to: source.source_id(),
+                        event_time: now().timestamp_millis(),
+                    }),
+                    _ => source.next().await,
+                },
+            },
+            Self::Http(.., terminator_rx) => terminator_rx.recv().await,
             Self::Empty(.., terminator_rx) => terminator_rx.recv().await,
         }
     }
@@ -127,16 +238,61 @@ impl Source for SourceImpl {
     ) -> std::task::Poll<Option<LocalEvent>> {
         match self {
             Self::Kafka(source, _, _) => source.poll_next(cx),
+            Self::Mysql(source, _, _) => source.poll_next(cx),
+            Self::Redis(source, _, _) => source.poll_next(cx),
+            Self::Mqtt(source, _, _) => source.poll_next(cx),
+            Self::File(source, _, _) => source.poll_next(cx),
+            Self::Generator(source, _, _) => source.poll_next(cx),
+            Self::Http(.., terminator_rx) => terminator_rx.poll_recv(cx),
             Self::Empty(.., terminator_rx) => terminator_rx.poll_recv(cx),
         }
     }
 
+    fn stop(&mut self) -> bool {
+        match self {
+            Self::Mysql(source, _, _) => source.stop(),
+            _ => false,
+        }
+    }
+
+    fn ack(&mut self) {
+        match self {
+            Self::Redis(source, _, _) => source.ack(),
+            Self::Mqtt(source, _, _) => source.ack(),
+            _ => {}
+        }
+    }
+
     async fn close_source(&mut self) {
         match self {
             Self::Kafka(kafka, tx, rx) => {
                 rx.close();
                 tokio::join!(kafka.close_source(), tx.closed());
             }
+            Self::Mysql(mysql, tx, rx) => {
+                rx.close();
+                tokio::join!(mysql.close_source(), tx.closed());
+            }
+            Self::Redis(redis, tx, rx) => {
+                rx.close();
+                tokio::join!(redis.close_source(), tx.closed());
+            }
+            Self::Mqtt(mqtt, tx, rx) => {
+                rx.close();
+                tokio::join!(mqtt.close_source(), tx.closed());
+            }
+            Self::File(file, tx, rx) => {
+                rx.close();
+                tokio::join!(file.close_source(), tx.closed());
+            }
+            Self::Generator(generator, tx, rx) => {
+                rx.close();
+                tokio::join!(generator.close_source(), tx.closed());
+            }
+            Self::Http(_, tx, rx) => {
+                rx.close();
+                tx.closed().await;
+            }
             Self::Empty(id, tx, rx) => {
                 drop(id);
                 rx.close();
@@ -155,6 +311,24 @@ impl From<(&ResourceId, SourceId, &operator_info::Details)> for SourceImpl {
                     source::Desc::Kafka(conf) => {
                         SourceImpl::Kafka(Kafka::with_source_config(args.0, args.1, conf), tx, rx)
                     }
+                    source::Desc::Mysql(conf) => {
+                        SourceImpl::Mysql(Mysql::with_source_config(args.0, args.1, conf), tx, rx)
+                    }
+                    source::Desc::Redis(conf) => {
+                        SourceImpl::Redis(Redis::with_source_config(args.0, args.1, conf), tx, rx)
+                    }
+                    source::Desc::Mqtt(conf) => {
+                        SourceImpl::Mqtt(Mqtt::with_source_config(args.0, args.1, conf), tx, rx)
+                    }
+                    source::Desc::File(conf) => {
+                        SourceImpl::File(File::with_source_config(args.0, args.1, conf), tx, rx)
+                    }
+                    source::Desc::Http(_) => SourceImpl::Http(args.1, tx, rx),
+                    source::Desc::Generator(conf) => SourceImpl::Generator(
+                        Generator::with_source_config(args.0, args.1, conf),
+                        tx,
+                        rx,
+                    ),
                 },
                 None => SourceImpl::Empty(args.1, tx, rx),
             },
@@ -167,6 +341,13 @@ pub enum SinkImpl {
     Kafka(Kafka),
     Mysql(Mysql),
     Redis(Redis),
+    Postgres(Postgres),
+    Mqtt(Mqtt),
+    File(File),
+    Http(Http),
+    Parquet(Parquet),
+    Stdout(Stdout),
+    Collect(Collect),
     Empty(SinkId),
 }
 
@@ -178,6 +359,13 @@ impl Sink for SinkImpl {
             Self::Mysql(mysql) => mysql.sink_id(),
             Self::Empty(sink_id) => *sink_id,
             Self::Redis(redis) => redis.sink_id(),
+            Self::Postgres(postgres) => postgres.sink_id(),
+            Self::Mqtt(mqtt) => mqtt.sink_id(),
+            Self::File(file) => file.sink_id(),
+            Self::Http(http) => http.sink_id(),
+            Self::Parquet(parquet) => parquet.sink_id(),
+            Self::Stdout(stdout) => stdout.sink_id(),
+            Self::Collect(collect) => collect.sink_id(),
         }
     }
 
@@ -187,6 +375,13 @@ impl Sink for SinkImpl {
             Self::Mysql(sink) => sink.sink(msg).await,
             Self::Empty(_) => Ok(()),
             Self::Redis(redis) => redis.sink(msg).await,
+            Self::Postgres(sink) => sink.sink(msg).await,
+            Self::Mqtt(sink) => sink.sink(msg).await,
+            Self::File(sink) => sink.sink(msg).await,
+            Self::Http(sink) => sink.sink(msg).await,
+            Self::Parquet(sink) => sink.sink(msg).await,
+            Self::Stdout(sink) => sink.sink(msg).await,
+            Self::Collect(sink) => sink.sink(msg).await,
         }
     }
 
@@ -195,16 +390,47 @@ impl Sink for SinkImpl {
             Self::Kafka(sink) => sink.close_sink(),
             Self::Mysql(sink) => sink.close_sink(),
             Self::Redis(sink) => sink.close_sink(),
+            Self::Postgres(sink) => sink.close_sink(),
+            Self::Mqtt(sink) => sink.close_sink(),
+            Self::File(sink) => sink.close_sink(),
+            Self::Http(sink) => sink.close_sink(),
+            Self::Parquet(sink) => sink.close_sink(),
+            Self::Stdout(sink) => sink.close_sink(),
+            Self::Collect(sink) => sink.close_sink(),
             Self::Empty(id) => drop(id),
         }
     }
 
+    async fn close(&mut self) {
+        match self {
+            Self::Parquet(sink) => sink.close().await,
+            _ => self.close_sink(),
+        }
+    }
+
     async fn batch_sink(&mut self, event_set: KeyedEventSet) -> Result<(), BatchSinkException> {
         match self {
             Self::Kafka(sink) => sink.batch_sink(event_set).await,
             Self::Mysql(sink) => sink.batch_sink(event_set).await,
             Self::Empty(_) => Ok(()),
             Self::Redis(redis) => redis.batch_sink(event_set).await,
+            Self::Postgres(sink) => sink.batch_sink(event_set).await,
+            Self::Mqtt(sink) => sink.batch_sink(event_set).await,
+            Self::File(sink) => sink.batch_sink(event_set).await,
+            Self::Http(sink) => sink.batch_sink(event_set).await,
+            Self::Parquet(sink) => sink.batch_sink(event_set).await,
+            Self::Stdout(sink) => sink.batch_sink(event_set).await,
+            Self::Collect(sink) => sink.batch_sink(event_set).await,
+        }
+    }
+}
+
+impl SinkImpl {
+    /// events kept by a collect sink. It's [None] for other sinks
+    pub fn get_collected_events(&self) -> Option<CollectedEvents> {
+        match self {
+            Self::Collect(collect) => Some(collect.get_collected_events()),
+            _ => None,
         }
     }
 }
@@ -226,6 +452,33 @@ impl From<(&ResourceId, &OperatorInfo)> for SinkImpl {
                         sink::Desc::Redis(desc) => {
                             SinkImpl::Redis(Redis::with_config(info.operator_id, desc))
                         }
+                        sink::Desc::Postgres(desc) => {
+                            SinkImpl::Postgres(Postgres::with_config(info.operator_id, desc))
+                        }
+                        sink::Desc::Mqtt(desc) => SinkImpl::Mqtt(Mqtt::with_sink_config(
+                            resource_id,
+                            info.operator_id,
+                            desc,
+                        )),
+                        sink::Desc::File(desc) => SinkImpl::File(File::with_sink_config(
+                            resource_id,
+                            info.operator_id,
+                            desc,
+                        )),
+                        sink::Desc::Http(desc) => {
+                            SinkImpl::Http(Http::with_sink_config(info.operator_id, desc))
+                        }
+                        sink::Desc::Parquet(desc) => {
+                            let parquet =
+                                Parquet::with_sink_config(resource_id, info.operator_id, desc);
+                            SinkImpl::Parquet(parquet)
+                        }
+                        sink::Desc::Stdout(desc) => {
+                            SinkImpl::Stdout(Stdout::with_config(info.operator_id, desc))
+                        }
+                        sink::Desc::Collect(desc) => {
+                            SinkImpl::Collect(Collect::with_config(info.operator_id, desc))
+                        }
                     },
                     None => Self::Empty(info.operator_id),
                 },
@@ -244,6 +497,8 @@ pub struct Kafka {
     consumer: Option<KafkaConsumer>,
     producer: Option<KafkaProducer>,
     job_id_hash: u64,
+    /// events decoded but not consumed yet. One Kafka message may be decoded as multiple events in CDC format
+    pending_events: VecDeque<LocalEvent>,
 }
 
 impl Kafka {
@@ -263,6 +518,7 @@ impl Kafka {
             consumer: None,
             producer: None,
             job_id_hash,
+            pending_events: Default::default(),
         };
         match run_consumer(
             config
@@ -298,6 +554,7 @@ impl Kafka {
             consumer: None,
             producer: None,
             job_id_hash,
+            pending_events: Default::default(),
         };
         match run_producer(
             config
@@ -318,13 +575,76 @@ impl Kafka {
         this
     }
 
-    fn process(&self, message: KafkaMessage) -> LocalEvent {
+    fn process(&self, message: KafkaMessage) -> Vec<LocalEvent> {
+        match self.conf.format() {
+            kafka_desc::MessageFormat::Plain => vec![self.process_plain(message)],
+            kafka_desc::MessageFormat::DebeziumJson => self.process_debezium_json(message),
+        }
+    }
+
+    fn process_plain(&self, message: KafkaMessage) -> LocalEvent {
         let data_type = self.conf.data_type();
         let key = TypedValue::from_slice(&message.key);
         let val = TypedValue::from_slice_with_type(&message.payload, data_type);
-        let event_id = self.generate_new_event_id();
+        // a tombstone deletes the row of its key
+        let row_kind = if message.is_tombstone() {
+            RowKind::Delete
+        } else {
+            RowKind::Insert
+        };
+
+        self.new_event(
+            &key,
+            &val,
+            data_type,
+            row_kind,
+            message.timestamp.unwrap_or_else(|| now_timestamp()),
+        )
+    }
+
+    fn process_debezium_json(&self, message: KafkaMessage) -> Vec<LocalEvent> {
+        let key = match decode_debezium_json_key(&message.key) {
+            Ok(key) => key,
+            Err(err) => {
+                tracing::error!("decode debezium key {:?} failed: {}", &message.key, err);
+                return vec![];
+            }
+        };
+        let event_time = message.timestamp.unwrap_or_else(|| now_timestamp());
+
+        match decode_debezium_json(&message.payload) {
+            Ok(rows) => rows
+                .iter()
+                .map(|row| {
+                    self.new_event(
+                        &key,
+                        &row.value,
+                        row.value.get_type(),
+                        row.row_kind,
+                        event_time,
+                    )
+                })
+                .collect(),
+            Err(err) => {
+                tracing::error!(
+                    "decode debezium envelope {:?} failed: {}",
+                    &message.payload,
+                    err
+                );
+                vec![]
+            }
+        }
+    }
 
-        let result = LocalEvent::KeyedDataStreamEvent(KeyedDataEvent {
+    fn new_event(
+        &self,
+        key: &TypedValue,
+        val: &TypedValue,
+        data_type: DataTypeEnum,
+        row_kind: RowKind,
+        event_time: i64,
+    ) -> LocalEvent {
+        LocalEvent::KeyedDataStreamEvent(KeyedDataEvent {
             job_id: Some(self.job_id.clone()),
             key: Some(Entry {
                 data_type: key.get_type() as i32,
@@ -332,28 +652,31 @@ impl Kafka {
             }),
             to_operator_id: 0,
             data: vec![Entry {
-                data_type: self.conf.data_type() as i32,
+                data_type: data_type as i32,
                 value: val.get_data_bytes(),
             }],
 
-            event_time: message.timestamp.unwrap_or_else(|| now_timestamp()),
+            event_time,
             from_operator_id: self.connector_id,
             window: None,
-            event_id,
-        });
-
-        result
+            event_id: self.generate_new_event_id(),
+            row_kind: row_kind as i32,
+        })
     }
 
     fn generate_new_event_id(&self) -> i64 {
-        const EPOCH: i64 = 1640966400;
-
-        let timestamp = now_timestamp();
-        let diff = timestamp - EPOCH;
-        diff + (self.job_id_hash as i64)
+        generate_event_id(self.job_id_hash)
     }
 }
 
+fn generate_event_id(job_id_hash: u64) -> i64 {
+    const EPOCH: i64 = 1640966400;
+
+    let timestamp = now_timestamp();
+    let diff = timestamp - EPOCH;
+    diff + (job_id_hash as i64)
+}
+
 #[async_trait]
 impl Source for Kafka {
     fn source_id(&self) -> SourceId {
@@ -371,18 +694,25 @@ impl Source for Kafka {
     }
 
     async fn next(&mut self) -> Option<LocalEvent> {
-        match &self.consumer {
-            Some(consumer) => consumer.fetch(|message| self.process(message)).await,
-            None => None,
+        if self.pending_events.is_empty() {
+            let events = match &self.consumer {
+                Some(consumer) => consumer.fetch(|message| self.process(message)).await,
+                None => None,
+            };
+            self.pending_events.extend(events.unwrap_or_default());
         }
+        self.pending_events.pop_front()
     }
 
     fn poll_next(&mut self, _cx: &mut std::task::Context<'_>) -> Poll<Option<LocalEvent>> {
-        Poll::Ready(
-            self.consumer
+        if self.pending_events.is_empty() {
+            let events = self
+                .consumer
                 .as_ref()
-                .and_then(|consumer| consumer.blocking_fetch(|message| self.process(message))),
-        )
+                .and_then(|consumer| consumer.blocking_fetch(|message| self.process(message)));
+            self.pending_events.extend(events.unwrap_or_default());
+        }
+        Poll::Ready(self.pending_events.pop_front())
     }
 }
 
@@ -399,7 +729,11 @@ impl Sink for Kafka {
                 match result.map_err(|err| err.into()) {
                     Ok(messages) => {
                         for msg in messages {
-                            let send_result = producer.send(&msg.key, &msg.payload).await;
+                            let send_result = if msg.is_tombstone() {
+                                producer.send_tombstone(&msg.key).await
+                            } else {
+                                producer.send(&msg.key, &msg.payload).await
+                            };
                             if send_result.is_err() {
                                 return send_result.map_err(|err| err.into());
                             }
@@ -435,7 +769,12 @@ impl Sink for Kafka {
                     match kafka_msg {
                         Ok(messages) => {
                             for msg in messages {
-                                match producer.send(&msg.key, &msg.payload).await {
+                                let send_result = if msg.is_tombstone() {
+                                    producer.send_tombstone(&msg.key).await
+                                } else {
+                                    producer.send(&msg.key, &msg.payload).await
+                                };
+                                match send_result {
                                     Err(err) => {
                                         tracing::error!(
                                             "sink [{:?}] to kafka failed: {}",
@@ -465,25 +804,48 @@ impl Sink for Kafka {
 }
 
 /// An unified implementation for Mysql Source and Sink
+///
+/// Mysql sink applies events by their [RowKind]:
+/// - INSERT and UPDATE_AFTER rows are applied by `statement`, which is recommended to be an upsert statement like `INSERT ... ON DUPLICATE KEY UPDATE`;
+/// - DELETE rows are applied by `delete_statement`. They will be ignored if `delete_statement` is not configured;
+/// - UPDATE_BEFORE rows are ignored because the following UPDATE_AFTER row will overwrite them;
+///
+/// A batch of events is written in transactions of at most `max_batch_size` rows. Consecutive rows of an INSERT or REPLACE statement
+/// are merged into a multi-row statement, so that a transaction usually takes only a few round trips.
+///
+/// Mysql source polls the query in [mysql_desc::PollingOpts] incrementally in a background task:
+/// - the query is bound with the high-water mark, the largest value of the incrementing column it has read;
+/// - the high-water mark starts from `start_offset`, or `0` if it's not set. It's converted to the type of the incrementing column when compared with rows, see [advance_high_water_mark];
+/// - each row is emitted as an object [TypedValue];
+/// - the high-water mark is kept in state so that a restarted source will continue from it;
+/// - the query is polled again at once while the high-water mark moves, otherwise after `interval_millis`. Rows are polled again
+///   if it doesn't move, e.g. the incrementing column is misnamed or the query selects the rows at the mark;
+///
+/// Rows are compared with the high-water mark strictly, so rows committed later with the same value of the incrementing column may be missed.
 pub struct Mysql {
     connector_id: SinkId,
     statement: String,
     extractors: Vec<String>,
+    delete_statement: Option<String>,
+    delete_extractors: Vec<String>,
+    max_batch_size: usize,
     conn: MysqlConn,
+    job_id: ResourceId,
+    connection_opts: mysql_desc::ConnectionOpts,
+    polling_opts: mysql_desc::PollingOpts,
+    polling_handle: Option<tokio::task::JoinHandle<()>>,
+    polling_rx: Option<Receiver<LocalEvent>>,
 }
 impl Mysql {
     pub fn with_config(connector_id: u32, conf: &MysqlDesc) -> Mysql {
-        let mut statement = conf.get_mysql_statement();
-        statement
-            .extractors
-            .sort_by(|v1, v2| v1.index.cmp(&v2.index));
-        let extractors = statement
-            .extractors
-            .iter()
-            .map(|e| e.extractor.clone())
-            .collect();
-
-        let statement = statement.statement;
+        let (statement, extractors) = Self::split_statement(conf.get_mysql_statement());
+        let (delete_statement, delete_extractors) = conf
+            .get_mysql_delete_statement()
+            .map(|statement| {
+                let (statement, extractors) = Self::split_statement(statement);
+                (Some(statement), extractors)
+            })
+            .unwrap_or_default();
 
         let connection_opts = conf
             .connection_opts
@@ -491,19 +853,114 @@ impl Mysql {
             .map(|opts| opts.clone())
             .unwrap_or_default();
 
-        let conn = MysqlConn::from(connection_opts);
+        let conn = MysqlConn::from(connection_opts.clone());
 
         Mysql {
             connector_id,
             statement,
             extractors,
+            delete_statement,
+            delete_extractors,
+            max_batch_size: if conf.max_batch_size == 0 {
+                DEFAULT_MYSQL_MAX_BATCH_SIZE
+            } else {
+                conf.max_batch_size as usize
+            },
             conn,
+            job_id: Default::default(),
+            connection_opts,
+            polling_opts: Default::default(),
+            polling_handle: None,
+            polling_rx: None,
+        }
+    }
+
+    pub fn with_source_config(
+        job_id: &ResourceId,
+        executor_id: ExecutorId,
+        conf: &MysqlDesc,
+    ) -> Mysql {
+        let mut this = Self::with_config(executor_id, conf);
+        this.job_id = job_id.clone();
+        this.polling_opts = conf.get_polling_opts();
+        this
+    }
+
+    /// start polling in background if it has not started. It must be called in the context of tokio runtime
+    fn start_polling(&mut self) {
+        if self.polling_rx.is_some() {
+            return;
         }
+        let (tx, rx) = new_event_channel(
+            get_env(CHANNEL_SIZE)
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(DEFAULT_CHANNEL_SIZE),
+        );
+        self.polling_rx = Some(rx);
+        self.polling_handle = Some(tokio::spawn(poll_mysql(
+            self.job_id.clone(),
+            self.connector_id,
+            self.connection_opts.clone(),
+            self.polling_opts.clone(),
+            tx,
+        )));
+    }
+
+    fn split_statement(mut statement: mysql_desc::Statement) -> (String, Vec<String>) {
+        statement
+            .extractors
+            .sort_by(|v1, v2| v1.index.cmp(&v2.index));
+        let extractors = statement
+            .extractors
+            .iter()
+            .map(|e| e.extractor.clone())
+            .collect();
+
+        (statement.statement, extractors)
     }
 
     fn get_arguments(&self, msg: &LocalEvent) -> Vec<Vec<TypedValue>> {
         extract_arguments(self.extractors.as_slice(), msg, "mysql_extractor")
     }
+
+    /// get the statement and its arguments which should be applied for the event
+    fn get_statement_and_arguments(
+        &self,
+        msg: &LocalEvent,
+    ) -> Option<(&str, Vec<Vec<TypedValue>>)> {
+        match msg {
+            LocalEvent::Terminate { .. } => None,
+            LocalEvent::KeyedDataStreamEvent(event) => match event.row_kind() {
+                RowKind::Insert | RowKind::UpdateAfter => {
+                    Some((self.statement.as_str(), self.get_arguments(msg)))
+                }
+                RowKind::Delete => self.delete_statement.as_ref().map(|statement| {
+                    (
+                        statement.as_str(),
+                        extract_arguments(
+                            self.delete_extractors.as_slice(),
+                            msg,
+                            "mysql_extractor",
+                        ),
+                    )
+                }),
+                RowKind::UpdateBefore => None,
+            },
+        }
+    }
+
+    async fn execute_event(&mut self, msg: &LocalEvent) -> Result<(), sqlx::Error> {
+        match self.get_statement_and_arguments(msg) {
+            Some((statement, row_arguments)) => {
+                let statement = statement.to_string();
+                for arguments in row_arguments {
+                    self.conn.execute(&statement, arguments).await?;
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
 }
 
 #[async_trait]
@@ -513,183 +970,2209 @@ impl Sink for Mysql {
     }
 
     async fn sink(&mut self, msg: LocalEvent) -> Result<(), SinkException> {
-        let row_arguments = self.get_arguments(&msg);
-        for arguments in row_arguments {
-            let result = self
-                .conn
-                .execute(&self.statement, arguments)
-                .await
-                .map_err(|err| err.into());
-            if result.is_err() {
-                return result.map(|_| {});
-            }
-        }
-
-        Ok(())
+        self.execute_event(&msg).await.map_err(|err| err.into())
     }
 
     fn close_sink(&mut self) {
         self.conn.close();
         self.extractors.clear();
+        self.delete_extractors.clear();
         drop(self.connector_id);
         self.statement.clear();
+        self.delete_statement = None;
     }
 
     async fn batch_sink(&mut self, event_set: KeyedEventSet) -> Result<(), BatchSinkException> {
-        let row_arguments = event_set
-            .events
-            .into_iter()
-            .map(|event| LocalEvent::KeyedDataStreamEvent(event))
-            .map(|event| self.get_arguments(&event))
-            .flatten()
-            .flatten()
+        // events have to be applied in order, otherwise a delete may be overwritten by an earlier upsert
+        let mut rows = vec![];
+        for event in event_set.events {
+            let event_id = event.event_id;
+            let msg = LocalEvent::KeyedDataStreamEvent(event);
+            if let Some((statement, row_arguments)) = self.get_statement_and_arguments(&msg) {
+                row_arguments
+                    .into_iter()
+                    .for_each(|arguments| rows.push((event_id, statement, arguments)));
+            }
+        }
+
+        let batches = rows
+            .chunks(self.max_batch_size)
+            .map(|chunk| {
+                (
+                    chunk[0].0,
+                    to_batch_statements(
+                        chunk
+                            .iter()
+                            .map(|(_, statement, arguments)| (*statement, arguments.clone())),
+                        to_multi_row_statement,
+                    ),
+                )
+            })
             .collect::<Vec<_>>();
 
-        self.conn
-            .execute(&self.statement, row_arguments)
-            .await
-            .map(|_| {})
-            .map_err(|err| {
-                tracing::error!("execute mysql statement failed: {}", err);
-                BatchSinkException::from(err)
-            })?;
+        for (event_id, statements) in batches {
+            self.conn
+                .execute_in_transaction(statements)
+                .await
+                .map_err(|err| {
+                    tracing::error!("execute mysql statements failed: {}", err);
+                    let mut exception = BatchSinkException::from(err);
+                    exception.event_id = event_id as u64;
+                    exception
+                })?;
+        }
 
         Ok(())
     }
 }
 
-/// An unified implement for Redis Source and Sink
-pub struct Redis {
-    connector_id: SinkId,
-    key_extractor: String,
-    value_extractor: String,
-    client: RedisClient,
-}
-
-impl Redis {
-    pub fn with_config(connector_id: SinkId, conf: &RedisDesc) -> Self {
-        let client = RedisClient::new(&conf);
-        let key_extractor = conf
-            .key_extractor
-            .as_ref()
-            .map(|func| func.function.clone())
-            .unwrap_or_default();
-        let value_extractor = conf
-            .value_extractor
-            .as_ref()
-            .map(|func| func.function.clone())
-            .unwrap_or_default();
-        Self {
-            connector_id,
-            key_extractor,
-            value_extractor,
-            client,
-        }
-    }
-}
-const REDIS_EXTRACTOR_FUN_NAME: &str = "redis_extractor";
 #[async_trait]
-impl Sink for Redis {
-    fn sink_id(&self) -> SinkId {
+impl Source for Mysql {
+    fn source_id(&self) -> SourceId {
         self.connector_id
     }
 
-    async fn sink(&mut self, msg: LocalEvent) -> Result<(), SinkException> {
-        let key_values = extract_arguments(
-            &[self.key_extractor.clone(), self.value_extractor.clone()],
-            &msg,
-            REDIS_EXTRACTOR_FUN_NAME,
-        );
-        let kvs = Vec::from_iter(key_values.iter().map(|kv| (&kv[0], &kv[1])));
-        self.client
-            .set_multiple(kvs.as_slice())
-            .map_err(|err| err.into())
+    async fn close_source(&mut self) {
+        self.polling_handle
+            .take()
+            .iter()
+            .for_each(|handle| handle.abort());
+        self.polling_rx.iter_mut().for_each(|rx| rx.close());
+        self.polling_rx = None;
+        self.conn.close();
+        self.connection_opts.clear();
+        self.polling_opts.clear();
+        self.job_id.clear();
     }
 
-    fn close_sink(&mut self) {
-        drop(self.connector_id);
-        self.key_extractor.clear();
-        self.value_extractor.clear();
+    async fn next(&mut self) -> Option<LocalEvent> {
+        self.start_polling();
+        match &mut self.polling_rx {
+            Some(rx) => rx.recv().await,
+            None => None,
+        }
     }
 
-    async fn batch_sink(&mut self, mut event_set: KeyedEventSet) -> Result<(), BatchSinkException> {
-        let mut kv_set = BTreeMap::new();
-        event_set.events.sort_by_key(|event| event.event_time);
-        let isolate = &mut v8::Isolate::new(Default::default());
-        let scope = &mut v8::HandleScope::new(isolate);
-
-        event_set.events.into_iter().for_each(|event| {
-            extract_arguments_scope(
-                &[self.key_extractor.clone(), self.value_extractor.clone()],
-                &LocalEvent::KeyedDataStreamEvent(event),
-                REDIS_EXTRACTOR_FUN_NAME,
-                scope,
-            )
-            .into_iter()
-            .filter(|kv| !kv.is_empty())
-            .map(|kv| (kv[0].clone(), kv[1].clone()))
-            .for_each(|(key, value)| {
-                kv_set.insert(key, value);
-            })
-        });
-        self.client
-            .set_multiple(kv_set.iter().collect::<Vec<_>>().as_slice())
-            .map_err(|err| err.into())
+    fn poll_next(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Option<LocalEvent>> {
+        self.start_polling();
+        match &mut self.polling_rx {
+            Some(rx) => rx.poll_recv(cx),
+            None => Poll::Ready(None),
+        }
     }
-}
 
-fn extract_arguments_scope(
-    extractors: &[String],
-    event: &LocalEvent,
-    fn_name: &str,
-    scope: &mut v8::HandleScope<'_, ()>,
-) -> Vec<Vec<TypedValue>> {
-    match event {
-        LocalEvent::Terminate { .. } => vec![],
-        LocalEvent::KeyedDataStreamEvent(e) => Vec::from_iter(e.data.iter().map(|entry| {
-            let val = TypedValue::from_slice(&entry.value);
-            extractors
-                .iter()
-                .map(|extractor| {
-                    let mut rt_engine = RuntimeEngine::new(extractor.as_str(), fn_name, scope);
-                    rt_engine.call_one_arg(&val).unwrap_or_default()
-                })
-                .collect::<Vec<TypedValue>>()
-        })),
+    /// The polling is aborted while the rows it has sent are left in the channel. The high-water mark is written after the rows of a batch are sent,
+    /// so the rows of an aborted batch are polled again after the job restarts
+    fn stop(&mut self) -> bool {
+        match &self.polling_handle {
+            Some(handle) => handle.abort(),
+            // the polling has not started, and nothing is polled
+            None => {
+                let (_, rx) = new_event_channel(1);
+                self.polling_rx = Some(rx);
+            }
+        }
+        true
     }
 }
 
-fn extract_arguments(
-    extractors: &[String],
-    event: &LocalEvent,
-    fn_name: &str,
-) -> Vec<Vec<TypedValue>> {
-    let isolate = &mut v8::Isolate::new(Default::default());
-    let scope = &mut v8::HandleScope::new(isolate);
-    match event {
-        LocalEvent::Terminate { .. } => vec![],
-        LocalEvent::KeyedDataStreamEvent(e) => Vec::from_iter(e.data.iter().map(|entry| {
-            let val = TypedValue::from_slice(&entry.value);
-            extractors
-                .iter()
-                .map(|extractor| {
-                    let mut rt_engine = RuntimeEngine::new(extractor.as_str(), fn_name, scope);
-                    rt_engine.call_one_arg(&val).unwrap_or_default()
-                })
-                .collect::<Vec<TypedValue>>()
-        })),
+/// Merge consecutive rows of the same INSERT or REPLACE statement into multi-row statements by `to_multi_row_statement`.
+/// Other rows are executed one by one.
+fn to_batch_statements<'a>(
+    rows: impl Iterator<Item = (&'a str, Vec<TypedValue>)>,
+    to_multi_row_statement: fn(&str, usize) -> Option<String>,
+) -> Vec<(String, Vec<TypedValue>)> {
+    let mut groups: Vec<(&str, Vec<Vec<TypedValue>>)> = vec![];
+    for (statement, arguments) in rows {
+        match groups.last_mut() {
+            Some((last, group)) if *last == statement => group.push(arguments),
+            _ => groups.push((statement, vec![arguments])),
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use proto::common::{
-        mysql_desc, redis_desc, Entry, Func, KafkaDesc, MysqlDesc, RedisDesc, ResourceId,
-    };
+    groups
+        .into_iter()
+        .flat_map(
+            |(statement, group)| match to_multi_row_statement(statement, group.len()) {
+                Some(multi_row_statement) if group.len() > 1 => {
+                    vec![(multi_row_statement, group.concat())]
+                }
+                _ => group
+                    .into_iter()
+                    .map(|arguments| (statement.to_string(), arguments))
+                    .collect(),
+            },
+        )
+        .collect()
+}
 
-    use crate::{new_event_channel, MOD_TEST_START};
+const DEFAULT_MYSQL_POLLING_INTERVAL_MILLIS: u64 = 1000;
+const DEFAULT_MYSQL_MAX_BATCH_SIZE: usize = 500;
 
-    use super::{Sink, SinkImpl, Source, SourceImpl};
+/// the key of high-water mark in the state of a mysql source
+fn get_mysql_high_water_mark_key(connector_id: SourceId) -> Vec<u8> {
+    format!("mysql-source-{}-high-water-mark", connector_id)
+        .as_bytes()
+        .to_vec()
+}
+
+async fn poll_mysql(
+    job_id: ResourceId,
+    connector_id: SourceId,
+    connection_opts: mysql_desc::ConnectionOpts,
+    polling_opts: mysql_desc::PollingOpts,
+    tx: Sender<LocalEvent>,
+) {
+    let ref mut hasher = DefaultHasher::new();
+    Hash::hash(&job_id, hasher);
+    let job_id_hash = hasher.finish();
+
+    let mut conn = MysqlConn::from(connection_opts);
+    let state_manager = new_source_state_mgt(&job_id, connector_id);
+    let state_key = get_mysql_high_water_mark_key(connector_id);
+    let interval = std::time::Duration::from_millis(if polling_opts.interval_millis == 0 {
+        DEFAULT_MYSQL_POLLING_INTERVAL_MILLIS
+    } else {
+        polling_opts.interval_millis
+    });
+
+    loop {
+        let state = state_manager.get_keyed_state(&state_key);
+        let high_water_mark = if state.is_empty() {
+            polling_opts
+                .start_offset
+                .as_ref()
+                .map(|offset| TypedValue::String(offset.clone()))
+                .unwrap_or(TypedValue::BigInt(0))
+        } else {
+            TypedValue::from_vec(&state)
+        };
+
+        let mut rows = vec![];
+        let result = conn
+            .try_for_each(&polling_opts.query, vec![high_water_mark.clone()], |row| {
+                rows.push(to_typed_value(&row));
+                futures_util::future::ready(Ok(()))
+            })
+            .await;
+        if let Err(err) = result {
+            tracing::error!("poll mysql source {} failed: {}", connector_id, err);
+        }
+
+        let polled = rows.len();
+        let mut new_high_water_mark = high_water_mark.clone();
+        for row in rows {
+            match &row {
+                TypedValue::Object(fields) => fields
+                    .get(&polling_opts.incrementing_column)
+                    .and_then(|value| advance_high_water_mark(&new_high_water_mark, value))
+                    .into_iter()
+                    .for_each(|value| new_high_water_mark = value),
+                _ => {}
+            }
+
+            let event = LocalEvent::KeyedDataStreamEvent(KeyedDataEvent {
+                job_id: Some(job_id.clone()),
+                key: None,
+                to_operator_id: 0,
+                data: vec![Entry {
+                    data_type: row.get_type() as i32,
+                    value: row.get_data_bytes(),
+                }],
+                event_time: now_timestamp(),
+                from_operator_id: connector_id,
+                window: None,
+                event_id: generate_event_id(job_id_hash),
+                row_kind: RowKind::Insert as i32,
+            });
+            if tx.send(event).await.is_err() {
+                return;
+            }
+        }
+
+        // poll again immediately only if the mark has moved, as there may be more rows after it
+        if new_high_water_mark != high_water_mark {
+            state_manager.set_key_state(&state_key, &new_high_water_mark.get_data_bytes());
+        } else {
+            if polled > 0 {
+                tracing::warn!(
+                    "high-water mark {:?} of mysql source {} doesn't advance after polling {} rows. The query should only select rows whose `{}` is greater than the mark",
+                    high_water_mark,
+                    connector_id,
+                    polled,
+                    polling_opts.incrementing_column
+                );
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+/// Sink of PostgreSQL.
+///
+/// Same as Mysql sink, Postgres sink applies events by their [RowKind]:
+/// - INSERT and UPDATE_AFTER rows are applied by `statement`, which is recommended to be an upsert statement like `INSERT ... ON CONFLICT (...) DO UPDATE SET ...`;
+/// - DELETE rows are applied by `delete_statement`. They will be ignored if `delete_statement` is not configured;
+/// - UPDATE_BEFORE rows are ignored because the following UPDATE_AFTER row will overwrite them;
+///
+/// A batch of events is written in transactions of at most `max_batch_size` rows, and consecutive rows of an INSERT statement
+/// are merged into a multi-row statement with renumbered placeholders. If `statement` is an upsert, only the last of the merged rows
+/// with the same conflict target is written, because Postgres rejects a multi-row upsert which updates a row twice.
+pub struct Postgres {
+    connector_id: SinkId,
+    statement: String,
+    extractors: Vec<String>,
+    /// the arguments of `statement` bound to the conflict target, see [to_postgres_conflict_arguments]
+    conflict_arguments: Option<Vec<usize>>,
+    delete_statement: Option<String>,
+    delete_extractors: Vec<String>,
+    max_batch_size: usize,
+    conn: PostgresConn,
+}
+
+impl Postgres {
+    pub fn with_config(connector_id: SinkId, conf: &PostgresDesc) -> Postgres {
+        let (statement, extractors) = Self::split_statement(conf.get_postgres_statement());
+        let (delete_statement, delete_extractors) = conf
+            .get_postgres_delete_statement()
+            .map(|statement| {
+                let (statement, extractors) = Self::split_statement(statement);
+                (Some(statement), extractors)
+            })
+            .unwrap_or_default();
+
+        let conn = PostgresConn::from(
+            conf.connection_opts
+                .as_ref()
+                .map(|opts| opts.clone())
+                .unwrap_or_default(),
+        );
+
+        Postgres {
+            connector_id,
+            conflict_arguments: to_postgres_conflict_arguments(&statement),
+            statement,
+            extractors,
+            delete_statement,
+            delete_extractors,
+            max_batch_size: if conf.max_batch_size == 0 {
+                DEFAULT_POSTGRES_MAX_BATCH_SIZE
+            } else {
+                conf.max_batch_size as usize
+            },
+            conn,
+        }
+    }
+
+    fn split_statement(mut statement: postgres_desc::Statement) -> (String, Vec<String>) {
+        statement
+            .extractors
+            .sort_by(|v1, v2| v1.index.cmp(&v2.index));
+        let extractors = statement
+            .extractors
+            .iter()
+            .map(|e| e.extractor.clone())
+            .collect();
+
+        (statement.statement, extractors)
+    }
+
+    /// get the statement and its arguments which should be applied for the event
+    fn get_statement_and_arguments(
+        &self,
+        msg: &LocalEvent,
+    ) -> Option<(&str, Vec<Vec<TypedValue>>)> {
+        match msg {
+            LocalEvent::Terminate { .. } => None,
+            LocalEvent::KeyedDataStreamEvent(event) => match event.row_kind() {
+                RowKind::Insert | RowKind::UpdateAfter => Some((
+                    self.statement.as_str(),
+                    extract_arguments(self.extractors.as_slice(), msg, "postgres_extractor"),
+                )),
+                RowKind::Delete => self.delete_statement.as_ref().map(|statement| {
+                    (
+                        statement.as_str(),
+                        extract_arguments(
+                            self.delete_extractors.as_slice(),
+                            msg,
+                            "postgres_extractor",
+                        ),
+                    )
+                }),
+                RowKind::UpdateBefore => None,
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl Sink for Postgres {
+    fn sink_id(&self) -> SinkId {
+        self.connector_id
+    }
+
+    async fn sink(&mut self, msg: LocalEvent) -> Result<(), SinkException> {
+        match self.get_statement_and_arguments(&msg) {
+            Some((statement, row_arguments)) => {
+                let statement = statement.to_string();
+                for arguments in row_arguments {
+                    self.conn.execute(&statement, arguments).await?;
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn close_sink(&mut self) {
+        self.conn.close();
+        self.extractors.clear();
+        self.delete_extractors.clear();
+        self.statement.clear();
+        self.delete_statement = None;
+    }
+
+    async fn batch_sink(&mut self, event_set: KeyedEventSet) -> Result<(), BatchSinkException> {
+        // events have to be applied in order, otherwise a delete may be overwritten by an earlier upsert
+        let mut rows = vec![];
+        for event in event_set.events {
+            let event_id = event.event_id;
+            let msg = LocalEvent::KeyedDataStreamEvent(event);
+            if let Some((statement, row_arguments)) = self.get_statement_and_arguments(&msg) {
+                row_arguments
+                    .into_iter()
+                    .for_each(|arguments| rows.push((event_id, statement, arguments)));
+            }
+        }
+
+        let batches = rows
+            .chunks(self.max_batch_size)
+            .map(|chunk| {
+                let rows = chunk
+                    .iter()
+                    .map(|(_, statement, arguments)| (*statement, arguments.clone()));
+                (
+                    chunk[0].0,
+                    to_batch_statements(
+                        match &self.conflict_arguments {
+                            Some(conflict_arguments) => {
+                                dedup_upserts(rows, &self.statement, conflict_arguments)
+                            }
+                            None => rows.collect(),
+                        }
+                        .into_iter(),
+                        to_postgres_multi_row_statement,
+                    ),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for (event_id, statements) in batches {
+            self.conn
+                .execute_in_transaction(statements)
+                .await
+                .map_err(|err| {
+                    tracing::error!("execute postgres statements failed: {}", err);
+                    let mut exception = BatchSinkException::from(err);
+                    exception.event_id = event_id as u64;
+                    exception
+                })?;
+        }
+
+        Ok(())
+    }
+}
+
+const DEFAULT_POSTGRES_MAX_BATCH_SIZE: usize = 500;
+
+/// Keep only the last row of each conflict target in every run of consecutive rows of the upsert `statement`,
+/// so that a run can be merged into a multi-row upsert. Rows of other statements keep their orders.
+fn dedup_upserts<'a>(
+    rows: impl Iterator<Item = (&'a str, Vec<TypedValue>)>,
+    statement: &str,
+    conflict_arguments: &[usize],
+) -> Vec<(&'a str, Vec<TypedValue>)> {
+    let mut result: Vec<(&'a str, Vec<TypedValue>)> = vec![];
+    let mut run = vec![];
+    let flush_run = |run: &mut Vec<(&'a str, Vec<TypedValue>)>,
+                     result: &mut Vec<(&'a str, Vec<TypedValue>)>| {
+        let mut conflict_keys = BTreeSet::new();
+        let mut deduped = run
+            .drain(..)
+            .rev()
+            .filter(|(_, arguments)| {
+                conflict_keys.insert(
+                    conflict_arguments
+                        .iter()
+                        .map(|index| {
+                            arguments
+                                .get(*index)
+                                .map(|argument| argument.get_data_bytes())
+                                .unwrap_or_default()
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        deduped.reverse();
+        result.extend(deduped);
+    };
+
+    for row in rows {
+        if row.0 == statement {
+            run.push(row);
+        } else {
+            flush_run(&mut run, &mut result);
+            result.push(row);
+        }
+    }
+    flush_run(&mut run, &mut result);
+
+    result
+}
+
+/// An unified implement for Redis Source and Sink
+///
+/// Redis sink writes values by the command in [RedisDesc] and applies events by their [RowKind]:
+/// - SET: DELETE rows delete the key. UPDATE_BEFORE rows are ignored because the following UPDATE_AFTER row will overwrite them;
+/// - HSET, SADD and ZADD: DELETE and UPDATE_BEFORE rows remove the field or the member by HDEL, SREM and ZREM;
+/// - INCRBY: DELETE and UPDATE_BEFORE rows are retracted by incrementing the negative value;
+/// - LPUSH, RPUSH and XADD: DELETE and UPDATE_BEFORE rows are ignored because lists and streams are append-only;
+///
+/// If `expire_millis` is set, the expiry of a key will be refreshed after it's written.
+///
+/// Redis source reads entries of Redis Streams by a consumer group in a background task, see [consume_redis_stream].
+/// Each entry is emitted as an object [TypedValue] whose fields are the fields of the entry.
+pub struct Redis {
+    connector_id: SinkId,
+    key_extractor: String,
+    value_extractor: String,
+    field_extractor: String,
+    score_extractor: String,
+    command: redis_desc::Command,
+    expire_millis: u64,
+    client: RedisClient,
+    job_id: ResourceId,
+    conf: RedisDesc,
+    consuming_handle: Option<tokio::task::JoinHandle<()>>,
+    entry_rx: Option<tokio::sync::mpsc::Receiver<(String, LocalEvent)>>,
+    ack_tx: Option<tokio::sync::mpsc::UnboundedSender<String>>,
+    /// ids of the entries which have been polled and not acked yet
+    unacked_entry_ids: Vec<String>,
+}
+
+impl Redis {
+    pub fn with_config(connector_id: SinkId, conf: &RedisDesc) -> Self {
+        let client = RedisClient::new(&conf);
+        let get_function = |func: &Option<Func>| {
+            func.as_ref()
+                .map(|func| func.function.clone())
+                .unwrap_or_default()
+        };
+        Self {
+            connector_id,
+            key_extractor: get_function(&conf.key_extractor),
+            value_extractor: get_function(&conf.value_extractor),
+            field_extractor: get_function(&conf.field_extractor),
+            score_extractor: get_function(&conf.score_extractor),
+            command: conf.command(),
+            expire_millis: conf.expire_millis,
+            client,
+            job_id: Default::default(),
+            conf: Default::default(),
+            consuming_handle: None,
+            entry_rx: None,
+            ack_tx: None,
+            unacked_entry_ids: vec![],
+        }
+    }
+
+    pub fn with_source_config(
+        job_id: &ResourceId,
+        executor_id: ExecutorId,
+        conf: &RedisDesc,
+    ) -> Self {
+        let mut this = Self::with_config(executor_id, conf);
+        this.job_id = job_id.clone();
+        this.conf = conf.clone();
+        this
+    }
+
+    /// start consuming the stream in background if it has not started. It must be called in the context of tokio runtime
+    fn start_consuming(&mut self) {
+        if self.consuming_handle.is_some() {
+            return;
+        }
+        let (entry_tx, entry_rx) = tokio::sync::mpsc::channel(
+            get_env(CHANNEL_SIZE)
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(DEFAULT_CHANNEL_SIZE),
+        );
+        let (ack_tx, ack_rx) = tokio::sync::mpsc::unbounded_channel();
+        self.entry_rx = Some(entry_rx);
+        self.ack_tx = Some(ack_tx);
+        self.consuming_handle = Some(tokio::spawn(consume_redis_stream(
+            self.job_id.clone(),
+            self.connector_id,
+            self.conf.clone(),
+            entry_tx,
+            ack_rx,
+        )));
+    }
+
+    /// extractors of a row: key, value and the field of HSET or the score of ZADD
+    fn get_extractors(&self) -> Vec<String> {
+        let mut extractors = vec![self.key_extractor.clone(), self.value_extractor.clone()];
+        match self.command {
+            redis_desc::Command::Hset => extractors.push(self.field_extractor.clone()),
+            redis_desc::Command::Zadd => extractors.push(self.score_extractor.clone()),
+            _ => {}
+        }
+        extractors
+    }
+
+    fn to_commands(&self, row_kind: RowKind, row: Vec<TypedValue>) -> Vec<RedisCommand> {
+        let mut row = row.into_iter();
+        let (key, value) = match (row.next(), row.next()) {
+            (Some(key), Some(value)) => (key, value),
+            _ => return vec![],
+        };
+        let extra = row.next().unwrap_or_default();
+        let is_retraction = matches!(row_kind, RowKind::Delete | RowKind::UpdateBefore);
+        let expire_key = key.clone();
+
+        let commands = match self.command {
+            redis_desc::Command::Set => match row_kind {
+                RowKind::Delete => vec![RedisCommand::Del { key }],
+                RowKind::UpdateBefore => vec![],
+                _ => vec![RedisCommand::Set { key, value }],
+            },
+            redis_desc::Command::Hset if is_retraction => {
+                vec![RedisCommand::HDel { key, field: extra }]
+            }
+            redis_desc::Command::Hset => vec![RedisCommand::HSet {
+                key,
+                field: extra,
+                value,
+            }],
+            redis_desc::Command::Sadd if is_retraction => {
+                vec![RedisCommand::SRem { key, member: value }]
+            }
+            redis_desc::Command::Sadd => vec![RedisCommand::SAdd { key, member: value }],
+            redis_desc::Command::Zadd if is_retraction => {
+                vec![RedisCommand::ZRem { key, member: value }]
+            }
+            redis_desc::Command::Zadd => match to_f64(&extra) {
+                Some(score) => vec![RedisCommand::ZAdd {
+                    key,
+                    member: value,
+                    score,
+                }],
+                None => {
+                    tracing::error!("invalid score of ZADD: {:?}", extra);
+                    vec![]
+                }
+            },
+            redis_desc::Command::Incrby => match to_f64(&value) {
+                Some(delta) => vec![RedisCommand::IncrBy {
+                    key,
+                    delta: if is_retraction {
+                        -(delta as i64)
+                    } else {
+                        delta as i64
+                    },
+                }],
+                None => {
+                    tracing::error!("invalid increment of INCRBY: {:?}", value);
+                    vec![]
+                }
+            },
+            _ if is_retraction => vec![],
+            redis_desc::Command::Lpush => vec![RedisCommand::LPush { key, value }],
+            redis_desc::Command::Rpush => vec![RedisCommand::RPush { key, value }],
+            redis_desc::Command::Xadd => vec![RedisCommand::XAdd { key, value }],
+        };
+
+        self.with_expiry(expire_key, commands)
+    }
+
+    /// refresh the expiry of the key after it's written
+    fn with_expiry(&self, key: TypedValue, mut commands: Vec<RedisCommand>) -> Vec<RedisCommand> {
+        let is_written = commands
+            .iter()
+            .any(|command| !matches!(command, RedisCommand::Del { .. }));
+        if self.expire_millis > 0 && is_written {
+            commands.push(RedisCommand::PExpire {
+                key,
+                millis: self.expire_millis,
+            });
+        }
+        commands
+    }
+}
+
+fn to_f64(value: &TypedValue) -> Option<f64> {
+    match value {
+        TypedValue::Number(v) => Some(*v),
+        TypedValue::BigInt(v) => Some(*v as f64),
+        TypedValue::String(v) => v.parse().ok(),
+        _ => None,
+    }
+}
+
+const REDIS_EXTRACTOR_FUN_NAME: &str = "redis_extractor";
+#[async_trait]
+impl Sink for Redis {
+    fn sink_id(&self) -> SinkId {
+        self.connector_id
+    }
+
+    async fn sink(&mut self, msg: LocalEvent) -> Result<(), SinkException> {
+        let row_kind = match &msg {
+            LocalEvent::Terminate { .. } => return Ok(()),
+            LocalEvent::KeyedDataStreamEvent(event) => event.row_kind(),
+        };
+        let commands = extract_arguments(&self.get_extractors(), &msg, REDIS_EXTRACTOR_FUN_NAME)
+            .into_iter()
+            .flat_map(|row| self.to_commands(row_kind, row))
+            .collect::<Vec<_>>();
+        self.client
+            .execute(&commands)
+            .await
+            .map_err(|err| err.into())
+    }
+
+    fn close_sink(&mut self) {
+        drop(self.connector_id);
+        self.key_extractor.clear();
+        self.value_extractor.clear();
+        self.field_extractor.clear();
+        self.score_extractor.clear();
+    }
+
+    async fn batch_sink(&mut self, mut event_set: KeyedEventSet) -> Result<(), BatchSinkException> {
+        event_set.events.sort_by_key(|event| event.event_time);
+        let extractors = self.get_extractors();
+
+        let mut commands = vec![];
+        // the isolate must be dropped before awaiting because it's not Send
+        {
+            let isolate = &mut v8::Isolate::new(Default::default());
+            let scope = &mut v8::HandleScope::new(isolate);
+            event_set.events.into_iter().for_each(|event| {
+                let row_kind = event.row_kind();
+                extract_arguments_scope(
+                    &extractors,
+                    &LocalEvent::KeyedDataStreamEvent(event),
+                    REDIS_EXTRACTOR_FUN_NAME,
+                    scope,
+                )
+                .into_iter()
+                .for_each(|row| commands.extend(self.to_commands(row_kind, row)))
+            });
+        }
+
+        if self.command == redis_desc::Command::Set {
+            commands = Self::compact_set_commands(commands);
+        }
+
+        self.client
+            .execute(&commands)
+            .await
+            .map_err(|err| err.into())
+    }
+}
+
+impl Redis {
+    /// For SET, the latest change of each key wins, so only the last SET or DEL of a key is kept
+    fn compact_set_commands(commands: Vec<RedisCommand>) -> Vec<RedisCommand> {
+        let mut latest = BTreeMap::new();
+        let mut expiries = BTreeMap::new();
+        commands.into_iter().for_each(|command| match &command {
+            RedisCommand::Set { key, .. } | RedisCommand::Del { key } => {
+                if matches!(command, RedisCommand::Del { .. }) {
+                    expiries.remove(key);
+                }
+                latest.insert(key.clone(), command);
+            }
+            RedisCommand::PExpire { key, .. } => {
+                expiries.insert(key.clone(), command);
+            }
+            _ => {}
+        });
+
+        latest.into_values().chain(expiries.into_values()).collect()
+    }
+}
+
+#[async_trait]
+impl Source for Redis {
+    fn source_id(&self) -> SourceId {
+        self.connector_id
+    }
+
+    async fn close_source(&mut self) {
+        self.consuming_handle
+            .take()
+            .iter()
+            .for_each(|handle| handle.abort());
+        self.entry_rx.iter_mut().for_each(|rx| rx.close());
+        self.entry_rx = None;
+        self.ack_tx = None;
+        self.unacked_entry_ids.clear();
+        self.conf.clear();
+        self.job_id.clear();
+    }
+
+    /// the caller processes an event before reading the next one, so the entries which have been read are acked
+    async fn next(&mut self) -> Option<LocalEvent> {
+        self.start_consuming();
+        self.ack();
+        match &mut self.entry_rx {
+            Some(rx) => rx.recv().await.map(|(id, event)| {
+                self.unacked_entry_ids.push(id);
+                event
+            }),
+            None => None,
+        }
+    }
+
+    fn poll_next(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Option<LocalEvent>> {
+        self.start_consuming();
+        match &mut self.entry_rx {
+            Some(rx) => rx.poll_recv(cx).map(|entry| {
+                entry.map(|(id, event)| {
+                    self.unacked_entry_ids.push(id);
+                    event
+                })
+            }),
+            None => Poll::Ready(None),
+        }
+    }
+
+    fn ack(&mut self) {
+        match self.ack_tx.as_ref() {
+            Some(ack_tx) => self.unacked_entry_ids.drain(..).for_each(|id| {
+                let _ = ack_tx.send(id);
+            }),
+            None => self.unacked_entry_ids.clear(),
+        }
+    }
+}
+
+const DEFAULT_REDIS_STREAM_START_ID: &str = "$";
+const DEFAULT_REDIS_STREAM_COUNT: usize = 100;
+const DEFAULT_REDIS_STREAM_BLOCK_MILLIS: u64 = 1000;
+
+/// Consume the stream by the consumer group in [redis_desc::StreamOpts]:
+/// 1. create the group if it does not exist;
+/// 2. claim entries pending longer than `claim_min_idle_millis` in other consumers, e.g. consumers of a crashed TaskManager;
+/// 3. read entries which are delivered to this consumer but not acked yet, including the claimed ones;
+/// 4. read new entries;
+///
+/// Entries are acked after their events have been written to the out-edges of the source, so an entry may be delivered again if the source restarts before acking it.
+async fn consume_redis_stream(
+    job_id: ResourceId,
+    connector_id: SourceId,
+    conf: RedisDesc,
+    tx: tokio::sync::mpsc::Sender<(String, LocalEvent)>,
+    mut ack_rx: tokio::sync::mpsc::UnboundedReceiver<String>,
+) {
+    let ref mut hasher = DefaultHasher::new();
+    Hash::hash(&job_id, hasher);
+    let job_id_hash = hasher.finish();
+
+    let opts = conf.get_stream_opts();
+    let consumer = if opts.consumer.is_empty() {
+        format!("{}-{}", &job_id.resource_id, connector_id)
+    } else {
+        opts.consumer.clone()
+    };
+    let start_id = if opts.start_id.is_empty() {
+        DEFAULT_REDIS_STREAM_START_ID
+    } else {
+        opts.start_id.as_str()
+    };
+    let count = if opts.count == 0 {
+        DEFAULT_REDIS_STREAM_COUNT
+    } else {
+        opts.count as usize
+    };
+    let block_millis = if opts.block_millis == 0 {
+        DEFAULT_REDIS_STREAM_BLOCK_MILLIS
+    } else {
+        opts.block_millis
+    };
+    let mut client = RedisClient::new(&conf);
+
+    while let Err(err) = client.create_group(&opts.key, &opts.group, start_id).await {
+        tracing::error!(
+            "create consumer group of redis source {} failed: {:?}",
+            connector_id,
+            err
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(block_millis)).await;
+    }
+
+    if opts.claim_min_idle_millis > 0 {
+        let mut cursor = STREAM_START_ID.to_string();
+        loop {
+            match client
+                .auto_claim(
+                    &opts.key,
+                    &opts.group,
+                    &consumer,
+                    opts.claim_min_idle_millis,
+                    &cursor,
+                    count,
+                )
+                .await
+            {
+                Ok((next_cursor, _))
+                    if next_cursor != STREAM_START_ID && !next_cursor.is_empty() =>
+                {
+                    cursor = next_cursor
+                }
+                Ok(_) => break,
+                Err(err) => {
+                    tracing::error!(
+                        "claim pending entries of redis source {} failed: {:?}",
+                        connector_id,
+                        err
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
+    // the id of the last pending entry which has been read, it becomes None after all pending entries are read
+    let mut pending_id = Some(STREAM_START_ID.to_string());
+    loop {
+        let mut ids = vec![];
+        while let Ok(id) = ack_rx.try_recv() {
+            ids.push(id);
+        }
+        if let Err(err) = client.ack(&opts.key, &opts.group, &ids).await {
+            tracing::error!(
+                "ack entries of redis source {} failed: {:?}",
+                connector_id,
+                err
+            );
+        }
+
+        let id = pending_id.clone().unwrap_or(">".to_string());
+        let entries = match client
+            .read_group(&opts.key, &opts.group, &consumer, &id, count, block_millis)
+            .await
+        {
+            Ok(entries) => entries,
+            Err(err) => {
+                tracing::error!("read redis source {} failed: {:?}", connector_id, err);
+                tokio::time::sleep(std::time::Duration::from_millis(block_millis)).await;
+                continue;
+            }
+        };
+        if pending_id.is_some() {
+            pending_id = entries.last().map(|entry| entry.id.clone());
+        }
+
+        for entry in entries {
+            // the id of an entry is like '<milliseconds>-<sequence>'
+            let event_time = entry
+                .id
+                .split('-')
+                .next()
+                .and_then(|millis| millis.parse().ok())
+                .unwrap_or_else(now_timestamp);
+            let event = LocalEvent::KeyedDataStreamEvent(KeyedDataEvent {
+                job_id: Some(job_id.clone()),
+                key: None,
+                to_operator_id: 0,
+                data: vec![Entry {
+                    data_type: entry.value.get_type() as i32,
+                    value: entry.value.get_data_bytes(),
+                }],
+                event_time,
+                from_operator_id: connector_id,
+                window: None,
+                event_id: generate_event_id(job_id_hash),
+                row_kind: RowKind::Insert as i32,
+            });
+            if tx.send((entry.id, event)).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// An unified implementation for MQTT Source and Sink
+///
+/// MQTT source subscribes `topic_filters` in a background eventloop:
+/// - the key of an event is the topic of the message, so that messages of topic filters with wildcards can be told apart;
+/// - the payload is decoded by `data_type` like Kafka source;
+/// - messages of QoS 1 and 2 are acked after their events have been written to the out-edges of the source. With `persistent_session`, the broker will redeliver messages not acked after reconnecting;
+///
+/// MQTT sink publishes values of events to `topic` in JSON like Kafka sink. DELETE and UPDATE_BEFORE events are ignored because MQTT has no tombstone.
+///
+/// The client id is `<job id>-<connector id>` if it's not configured.
+pub struct Mqtt {
+    connector_id: SourceId,
+    conf: MqttDesc,
+    job_id: ResourceId,
+    job_id_hash: u64,
+    subscriber: Option<MqttSubscriber>,
+    publisher: Option<MqttPublisher>,
+    /// the messages which have been polled and not acked yet
+    unacked_messages: Vec<MqttMessage>,
+}
+
+impl Mqtt {
+    fn new(job_id: &ResourceId, executor_id: ExecutorId, config: &MqttDesc) -> Mqtt {
+        let ref mut hasher = DefaultHasher::new();
+        Hash::hash(job_id, hasher);
+        let job_id_hash = hasher.finish();
+
+        Mqtt {
+            connector_id: executor_id,
+            conf: config.clone(),
+            job_id: job_id.clone(),
+            job_id_hash,
+            subscriber: None,
+            publisher: None,
+            unacked_messages: vec![],
+        }
+    }
+
+    pub fn with_source_config(
+        job_id: &ResourceId,
+        executor_id: ExecutorId,
+        config: &MqttDesc,
+    ) -> Mqtt {
+        Self::new(job_id, executor_id, config)
+    }
+
+    pub fn with_sink_config(
+        job_id: &ResourceId,
+        executor_id: ExecutorId,
+        config: &MqttDesc,
+    ) -> Mqtt {
+        Self::new(job_id, executor_id, config)
+    }
+
+    fn client_id(&self) -> String {
+        format!("{}-{}", &self.job_id.resource_id, self.connector_id)
+    }
+
+    /// subscribe topic filters if it has not subscribed. It must be called in the context of tokio runtime
+    fn start_subscribing(&mut self) {
+        if self.subscriber.is_none() {
+            self.subscriber = Some(run_subscriber(
+                &self.conf,
+                &self.client_id(),
+                get_env(CHANNEL_SIZE)
+                    .and_then(|size| size.parse().ok())
+                    .unwrap_or(DEFAULT_CHANNEL_SIZE),
+            ));
+        }
+    }
+
+    fn process(&mut self, message: MqttMessage) -> LocalEvent {
+        let data_type = self.conf.data_type();
+        let key = TypedValue::String(message.topic.clone());
+        let val = TypedValue::from_slice_with_type(&message.payload, data_type);
+        let event = LocalEvent::KeyedDataStreamEvent(KeyedDataEvent {
+            job_id: Some(self.job_id.clone()),
+            key: Some(Entry {
+                data_type: key.get_type() as i32,
+                value: key.get_data_bytes(),
+            }),
+            to_operator_id: 0,
+            data: vec![Entry {
+                data_type: data_type as i32,
+                value: val.get_data_bytes(),
+            }],
+            event_time: now_timestamp(),
+            from_operator_id: self.connector_id,
+            window: None,
+            event_id: generate_event_id(self.job_id_hash),
+            row_kind: RowKind::Insert as i32,
+        });
+        self.unacked_messages.push(message);
+        event
+    }
+
+    async fn publish(&mut self, msg: &LocalEvent) -> Result<(), SinkException> {
+        if self.publisher.is_none() {
+            self.publisher = Some(run_publisher(&self.conf, &self.client_id()));
+        }
+        match self.publisher.as_ref() {
+            Some(publisher) => {
+                for message in msg.to_kafka_message()? {
+                    // MQTT has no tombstone, deletions are ignored
+                    if !message.is_tombstone() {
+                        publisher.publish(&message.payload).await?;
+                    }
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl Source for Mqtt {
+    fn source_id(&self) -> SourceId {
+        self.connector_id
+    }
+
+    async fn close_source(&mut self) {
+        self.unacked_messages.clear();
+        if let Some(mut subscriber) = self.subscriber.take() {
+            subscriber.close().await
+        }
+        self.conf.clear();
+        self.job_id.clear();
+    }
+
+    /// the caller processes an event before reading the next one, so the messages which have been read are acked
+    async fn next(&mut self) -> Option<LocalEvent> {
+        self.start_subscribing();
+        self.ack();
+        let message = match self.subscriber.as_mut() {
+            Some(subscriber) => subscriber.recv().await,
+            None => None,
+        };
+        message.map(|message| self.process(message))
+    }
+
+    fn poll_next(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Option<LocalEvent>> {
+        self.start_subscribing();
+        match self.subscriber.as_mut() {
+            Some(subscriber) => subscriber
+                .poll_recv(cx)
+                .map(|message| message.map(|message| self.process(message))),
+            None => Poll::Ready(None),
+        }
+    }
+
+    fn ack(&mut self) {
+        let messages = std::mem::take(&mut self.unacked_messages);
+        if let Some(subscriber) = self.subscriber.as_ref() {
+            messages.iter().for_each(|message| {
+                if let Err(err) = subscriber.ack(message) {
+                    tracing::error!(
+                        "ack mqtt message of topic {} failed: {:?}",
+                        &message.topic,
+                        err
+                    )
+                }
+            })
+        }
+    }
+}
+
+#[async_trait]
+impl Sink for Mqtt {
+    fn sink_id(&self) -> SinkId {
+        self.connector_id
+    }
+
+    async fn sink(&mut self, msg: LocalEvent) -> Result<(), SinkException> {
+        self.publish(&msg).await
+    }
+
+    fn close_sink(&mut self) {
+        self.publisher
+            .iter_mut()
+            .for_each(|publisher| publisher.close());
+        self.publisher = None;
+        self.conf.clear();
+        self.job_id.clear();
+    }
+
+    async fn batch_sink(&mut self, event_set: KeyedEventSet) -> Result<(), BatchSinkException> {
+        for event in event_set.events {
+            let event_id = event.event_id;
+            let event = LocalEvent::KeyedDataStreamEvent(event);
+            if let Err(err) = self.publish(&event).await {
+                return Err(BatchSinkException {
+                    err,
+                    event_id: event_id as u64,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An unified implementation for File Source and Sink
+///
+/// File source reads lines of files matching `path` in the order of their paths in a background task:
+/// - each line of JSON Lines is decoded as a [TypedValue] of the JSON value;
+/// - each line of CSV is decoded as an object of string fields named by the header or `columns` of [file_desc::CsvOpts];
+/// - without `follow`, [LocalEvent::Terminate] is emitted after all files have been read to the end;
+/// - with `follow`, it keeps checking appended data and new files every `poll_interval_millis`. A truncated file will be read from the beginning again;
+///
+/// File sink appends values of events as lines to files in the directory `path`, and rolls files by [file_desc::RotationOpts].
+/// Files are append-only, so DELETE and UPDATE_BEFORE events are ignored.
+pub struct File {
+    connector_id: SourceId,
+    conf: FileDesc,
+    job_id: ResourceId,
+    reading_handle: Option<tokio::task::JoinHandle<()>>,
+    reading_rx: Option<Receiver<LocalEvent>>,
+    writer: Option<RollingFileWriter>,
+    columns: Vec<String>,
+}
+
+impl File {
+    fn new(job_id: &ResourceId, executor_id: ExecutorId, config: &FileDesc) -> File {
+        File {
+            connector_id: executor_id,
+            conf: config.clone(),
+            job_id: job_id.clone(),
+            reading_handle: None,
+            reading_rx: None,
+            writer: None,
+            columns: config.get_csv_opts().columns,
+        }
+    }
+
+    pub fn with_source_config(
+        job_id: &ResourceId,
+        executor_id: ExecutorId,
+        config: &FileDesc,
+    ) -> File {
+        Self::new(job_id, executor_id, config)
+    }
+
+    pub fn with_sink_config(
+        job_id: &ResourceId,
+        executor_id: ExecutorId,
+        config: &FileDesc,
+    ) -> File {
+        Self::new(job_id, executor_id, config)
+    }
+
+    /// start reading in background if it has not started. It must be called in the context of tokio runtime
+    fn start_reading(&mut self) {
+        if self.reading_handle.is_some() {
+            return;
+        }
+        let (tx, rx) = new_event_channel(
+            get_env(CHANNEL_SIZE)
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(DEFAULT_CHANNEL_SIZE),
+        );
+        self.reading_rx = Some(rx);
+        self.reading_handle = Some(tokio::spawn(read_files(
+            self.job_id.clone(),
+            self.connector_id,
+            self.conf.clone(),
+            tx,
+        )));
+    }
+
+    fn encode(&mut self, value: &TypedValue) -> Result<Vec<u8>, FileException> {
+        match self.conf.format() {
+            file_desc::Format::JsonLines => encode_json_line(value),
+            file_desc::Format::Csv => {
+                let delimiter = self.conf.get_csv_opts().get_delimiter();
+                // the order of fields is fixed by the first object if columns are not configured
+                match value {
+                    TypedValue::Object(object) if self.columns.is_empty() => {
+                        self.columns = object.keys().map(|key| key.clone()).collect()
+                    }
+                    _ => {}
+                }
+                encode_csv_line(value, &self.columns, delimiter)
+            }
+        }
+    }
+
+    /// create the writer if it has not been created. The header of CSV is written at the beginning of each file
+    fn get_writer(&mut self) -> Result<&mut RollingFileWriter, FileException> {
+        if self.writer.is_none() {
+            let csv_opts = self.conf.get_csv_opts();
+            let header = if self.conf.format() == file_desc::Format::Csv && csv_opts.has_header {
+                Some(encode_csv_header(&self.columns, csv_opts.get_delimiter())?)
+            } else {
+                None
+            };
+            self.writer = Some(RollingFileWriter::new(
+                &self.conf.path,
+                &format!("{}-{}", &self.job_id.resource_id, self.connector_id),
+                get_file_extension(self.conf.format()),
+                header,
+                &self.conf.get_rotation_opts(),
+            ));
+        }
+        Ok(self.writer.as_mut().unwrap())
+    }
+
+    async fn write_event(&mut self, event: &KeyedDataEvent) -> Result<(), FileException> {
+        match event.row_kind() {
+            RowKind::Delete | RowKind::UpdateBefore => return Ok(()),
+            _ => {}
+        }
+        for entry in &event.data {
+            let line = self.encode(&TypedValue::from_slice(&entry.value))?;
+            self.get_writer()?.write(&line).await?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), FileException> {
+        match self.writer.as_mut() {
+            Some(writer) => writer.flush().await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl Source for File {
+    fn source_id(&self) -> SourceId {
+        self.connector_id
+    }
+
+    async fn close_source(&mut self) {
+        self.reading_handle
+            .take()
+            .iter()
+            .for_each(|handle| handle.abort());
+        self.reading_rx.iter_mut().for_each(|rx| rx.close());
+        self.reading_rx = None;
+        self.conf.clear();
+        self.job_id.clear();
+    }
+
+    async fn next(&mut self) -> Option<LocalEvent> {
+        self.start_reading();
+        match &mut self.reading_rx {
+            Some(rx) => rx.recv().await,
+            None => None,
+        }
+    }
+
+    fn poll_next(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Option<LocalEvent>> {
+        self.start_reading();
+        match &mut self.reading_rx {
+            Some(rx) => rx.poll_recv(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+#[async_trait]
+impl Sink for File {
+    fn sink_id(&self) -> SinkId {
+        self.connector_id
+    }
+
+    async fn sink(&mut self, msg: LocalEvent) -> Result<(), SinkException> {
+        match &msg {
+            LocalEvent::Terminate { .. } => Ok(()),
+            LocalEvent::KeyedDataStreamEvent(event) => {
+                self.write_event(event).await?;
+                self.flush().await.map_err(|err| err.into())
+            }
+        }
+    }
+
+    fn close_sink(&mut self) {
+        // lines have been flushed after each sink
+        self.writer = None;
+        self.columns.clear();
+        self.conf.clear();
+        self.job_id.clear();
+    }
+
+    async fn batch_sink(&mut self, event_set: KeyedEventSet) -> Result<(), BatchSinkException> {
+        for event in &event_set.events {
+            if let Err(err) = self.write_event(event).await {
+                return Err(BatchSinkException {
+                    err: err.into(),
+                    event_id: event.event_id as u64,
+                });
+            }
+        }
+        self.flush().await.map_err(|err| BatchSinkException {
+            err: err.into(),
+            event_id: 0,
+        })
+    }
+}
+
+const DEFAULT_FILE_POLL_INTERVAL_MILLIS: u64 = 1000;
+
+/// the position which a file has been read to
+struct FileCursor {
+    offset: u64,
+    /// names of CSV columns
+    columns: Vec<String>,
+}
+
+async fn read_files(
+    job_id: ResourceId,
+    connector_id: SourceId,
+    conf: FileDesc,
+    tx: Sender<LocalEvent>,
+) {
+    let ref mut hasher = DefaultHasher::new();
+    Hash::hash(&job_id, hasher);
+    let job_id_hash = hasher.finish();
+
+    let interval = std::time::Duration::from_millis(if conf.poll_interval_millis == 0 {
+        DEFAULT_FILE_POLL_INTERVAL_MILLIS
+    } else {
+        conf.poll_interval_millis
+    });
+    let mut cursors: BTreeMap<std::path::PathBuf, FileCursor> = Default::default();
+
+    loop {
+        let files = list_files(&conf.path).unwrap_or_else(|err| {
+            tracing::error!(
+                "list files of file source {} failed: {:?}",
+                connector_id,
+                err
+            );
+            vec![]
+        });
+
+        for path in files {
+            let cursor = cursors.entry(path.clone()).or_insert_with(|| FileCursor {
+                offset: 0,
+                columns: conf.get_csv_opts().columns,
+            });
+            let result = read_file(&path, cursor, &conf, |value| {
+                tx.send(LocalEvent::KeyedDataStreamEvent(KeyedDataEvent {
+                    job_id: Some(job_id.clone()),
+                    key: None,
+                    to_operator_id: 0,
+                    data: vec![Entry {
+                        data_type: value.get_type() as i32,
+                        value: value.get_data_bytes(),
+                    }],
+                    event_time: now_timestamp(),
+                    from_operator_id: connector_id,
+                    window: None,
+                    event_id: generate_event_id(job_id_hash),
+                    row_kind: RowKind::Insert as i32,
+                }))
+            })
+            .await;
+            match result {
+                Ok(true) => {}
+                Ok(false) => return,
+                Err(err) => tracing::error!("read file {:?} failed: {}", &path, err),
+            }
+        }
+
+        if !conf.follow {
+            let _ = tx
+                .send(LocalEvent::Terminate {
+                    job_id: job_id.clone(),
+                    to: connector_id,
+                    event_time: now_timestamp(),
+                })
+                .await;
+            return;
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Read lines of a file from the cursor and emit decoded values. It returns false if the receiver has been closed.
+///
+/// If `follow` is set, an incomplete line at the end of the file will be read again after it's completed.
+async fn read_file<F, Fut, E>(
+    path: &std::path::Path,
+    cursor: &mut FileCursor,
+    conf: &FileDesc,
+    emit: F,
+) -> std::io::Result<bool>
+where
+    F: Fn(TypedValue) -> Fut,
+    Fut: std::future::Future<Output = Result<(), E>>,
+{
+    use tokio::io::{AsyncBufReadExt, AsyncSeekExt};
+
+    let mut file = tokio::fs::File::open(path).await?;
+    if file.metadata().await?.len() < cursor.offset {
+        // the file has been truncated
+        cursor.offset = 0;
+        cursor.columns = conf.get_csv_opts().columns;
+    }
+    file.seek(std::io::SeekFrom::Start(cursor.offset)).await?;
+
+    let csv_opts = conf.get_csv_opts();
+    let mut reader = tokio::io::BufReader::new(file);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let size = reader.read_line(&mut line).await?;
+        if size == 0 || (conf.follow && !line.ends_with('\n')) {
+            return Ok(true);
+        }
+        let is_header = cursor.offset == 0 && csv_opts.has_header;
+        cursor.offset += size as u64;
+
+        let line = line.trim_end_matches(|c| c == '\n' || c == '\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value = match conf.format() {
+            file_desc::Format::JsonLines => decode_json_line(line),
+            file_desc::Format::Csv => match parse_csv_line(line, csv_opts.get_delimiter()) {
+                Ok(record) if is_header => {
+                    cursor.columns = record;
+                    continue;
+                }
+                Ok(record) => Ok(to_csv_object(&cursor.columns, record)),
+                Err(err) => Err(err),
+            },
+        };
+        match value {
+            Ok(value) => {
+                if emit(value).await.is_err() {
+                    return Ok(false);
+                }
+            }
+            Err(err) => tracing::error!("decode line {} of {:?} failed: {:?}", line, path, err),
+        }
+    }
+}
+
+/// HTTP sink sends values of events to `url` of [HttpDesc]:
+/// - the body of a value is extracted by `body_extractor`, or rendered by `body_template`, or the JSON of the value by default;
+/// - with `batch`, up to `max_size` bodies are sent as a JSON array by one request. A body which is not JSON is sent as a JSON string;
+/// - requests failing with 5xx or a connection error are retried by `retry`, and at most `concurrency` requests are sent at the same time;
+///
+/// DELETE and UPDATE_BEFORE events are ignored because requests can not be retracted.
+pub struct Http {
+    connector_id: SinkId,
+    body_extractor: String,
+    body_template: String,
+    batch_size: usize,
+    concurrency: usize,
+    client: Option<HttpClient>,
+}
+
+impl Http {
+    pub fn with_sink_config(executor_id: ExecutorId, conf: &HttpDesc) -> Self {
+        let client = match HttpClient::new(conf) {
+            Ok(client) => Some(client),
+            Err(err) => {
+                tracing::error!(
+                    "create http client of sink {} failed: {:?}",
+                    executor_id,
+                    err
+                );
+                None
+            }
+        };
+        Self {
+            connector_id: executor_id,
+            body_extractor: conf
+                .body_extractor
+                .as_ref()
+                .map(|func| func.function.clone())
+                .unwrap_or_default(),
+            body_template: conf.body_template.clone(),
+            batch_size: conf.get_batch_opts().max_size.max(1) as usize,
+            concurrency: conf.concurrency.max(1) as usize,
+            client,
+        }
+    }
+
+    /// bodies of values in events. Retractions are skipped
+    fn to_bodies(&self, events: Vec<KeyedDataEvent>) -> Vec<String> {
+        let events = events
+            .into_iter()
+            .filter(|event| !event.is_retraction())
+            .map(|event| LocalEvent::KeyedDataStreamEvent(event));
+
+        if !self.body_extractor.is_empty() {
+            let isolate = &mut v8::Isolate::new(Default::default());
+            let scope = &mut v8::HandleScope::new(isolate);
+            let extractors = [self.body_extractor.clone()];
+            return events
+                .flat_map(|event| {
+                    extract_arguments_scope(&extractors, &event, HTTP_EXTRACTOR_FUN_NAME, scope)
+                })
+                .flat_map(|row| row.into_iter())
+                .map(|body| match body {
+                    TypedValue::String(body) => body,
+                    body => body.to_json_value().to_string(),
+                })
+                .collect();
+        }
+
+        events
+            .flat_map(|event| match event {
+                LocalEvent::KeyedDataStreamEvent(event) => event.data,
+                LocalEvent::Terminate { .. } => vec![],
+            })
+            .map(|entry| {
+                let value = TypedValue::from_slice(&entry.value);
+                if self.body_template.is_empty() {
+                    value.to_json_value().to_string()
+                } else {
+                    render_body_template(&self.body_template, &value)
+                }
+            })
+            .collect()
+    }
+
+    /// bodies of requests. Bodies are batched into JSON arrays if the batch size is larger than 1
+    fn to_requests(&self, bodies: Vec<String>) -> Vec<Vec<u8>> {
+        if self.batch_size <= 1 {
+            return bodies.into_iter().map(|body| body.into_bytes()).collect();
+        }
+        bodies
+            .chunks(self.batch_size)
+            .map(|chunk| {
+                serde_json::Value::Array(
+                    chunk
+                        .iter()
+                        .map(|body| {
+                            serde_json::from_str(body)
+                                .unwrap_or_else(|_| serde_json::Value::String(body.clone()))
+                        })
+                        .collect(),
+                )
+                .to_string()
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    async fn send(&self, bodies: Vec<String>) -> Result<(), SinkException> {
+        let client = match self.client.as_ref() {
+            Some(client) => client,
+            None => {
+                return Err(
+                    HttpException::RequestFailed("http client is not created".to_string()).into(),
+                )
+            }
+        };
+        futures_util::stream::iter(self.to_requests(bodies))
+            .map(|body| client.send(body))
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .map(|_| ())
+            .map_err(|err| err.into())
+    }
+}
+
+const HTTP_EXTRACTOR_FUN_NAME: &str = "http_extractor";
+
+#[async_trait]
+impl Sink for Http {
+    fn sink_id(&self) -> SinkId {
+        self.connector_id
+    }
+
+    async fn sink(&mut self, msg: LocalEvent) -> Result<(), SinkException> {
+        match msg {
+            LocalEvent::Terminate { .. } => Ok(()),
+            LocalEvent::KeyedDataStreamEvent(event) => {
+                let bodies = self.to_bodies(vec![event]);
+                self.send(bodies).await
+            }
+        }
+    }
+
+    fn close_sink(&mut self) {
+        self.client = None;
+        self.body_extractor.clear();
+        self.body_template.clear();
+    }
+
+    async fn batch_sink(&mut self, event_set: KeyedEventSet) -> Result<(), BatchSinkException> {
+        let bodies = self.to_bodies(event_set.events);
+        self.send(bodies)
+            .await
+            .map_err(|err| BatchSinkException { err, event_id: 0 })
+    }
+}
+
+/// Generator source produces synthetic events from inputs of [GeneratorDesc] without external services:
+/// - values are returned by the JS function `generate(input)`, or rendered by `template`, or the inputs themselves by default;
+/// - the key of an event is the `key` of its input if `key_cardinality` is set;
+/// - events are generated at `rate_per_sec`, or as fast as possible if it's 0;
+/// - [LocalEvent::Terminate] is emitted after `count` events. It never terminates if `count` is 0;
+///
+/// Inputs are a deterministic sequence of `seed`, so the same config always generates the same values.
+/// Note that `index` is a bigint in JS, e.g. `Number(input.index)` converts it to a number.
+pub struct Generator {
+    connector_id: SourceId,
+    job_id: ResourceId,
+    job_id_hash: u64,
+    function: String,
+    template: String,
+    inputs: GeneratorInputs,
+    started_at: Option<tokio::time::Instant>,
+    sleep: Option<Pin<Box<tokio::time::Sleep>>>,
+    generated: VecDeque<LocalEvent>,
+    terminated: bool,
+}
+
+const GENERATOR_FUN_NAME: &str = "generate";
+const MAX_GENERATED_BATCH_SIZE: u64 = 1000;
+
+impl Generator {
+    pub fn with_source_config(
+        job_id: &ResourceId,
+        executor_id: ExecutorId,
+        conf: &GeneratorDesc,
+    ) -> Self {
+        let ref mut hasher = DefaultHasher::new();
+        Hash::hash(job_id, hasher);
+        let job_id_hash = hasher.finish();
+
+        Self {
+            connector_id: executor_id,
+            job_id: job_id.clone(),
+            job_id_hash,
+            function: conf
+                .function
+                .as_ref()
+                .map(|func| func.function.clone())
+                .unwrap_or_default(),
+            template: conf.template.clone(),
+            inputs: GeneratorInputs::new(conf),
+            started_at: None,
+            sleep: None,
+            generated: Default::default(),
+            terminated: false,
+        }
+    }
+
+    /// generate the next `n` events into the queue. The JS function is evaluated by one isolate for all of them
+    fn generate(&mut self, n: u64) {
+        let inputs = (&mut self.inputs).take(n as usize).collect::<Vec<_>>();
+        let values = if !self.function.is_empty() {
+            let isolate = &mut v8::Isolate::new(Default::default());
+            let scope = &mut v8::HandleScope::new(isolate);
+            let mut rt_engine =
+                RuntimeEngine::new(self.function.as_str(), GENERATOR_FUN_NAME, scope);
+            inputs
+                .iter()
+                .map(|input| rt_engine.call_one_arg(input).unwrap_or_default())
+                .collect::<Vec<_>>()
+        } else if !self.template.is_empty() {
+            inputs
+                .iter()
+                .map(|input| render_template(&self.template, input))
+                .collect()
+        } else {
+            inputs.clone()
+        };
+
+        let event_time = now_timestamp();
+        inputs.iter().zip(values).for_each(|(input, value)| {
+            let key = match get_input_key(input) {
+                TypedValue::Null => None,
+                key => Some(Entry {
+                    data_type: key.get_type() as i32,
+                    value: key.get_data_bytes(),
+                }),
+            };
+            self.generated
+                .push_back(LocalEvent::KeyedDataStreamEvent(KeyedDataEvent {
+                    job_id: Some(self.job_id.clone()),
+                    key,
+                    to_operator_id: 0,
+                    data: vec![Entry {
+                        data_type: value.get_type() as i32,
+                        value: value.get_data_bytes(),
+                    }],
+                    event_time,
+                    from_operator_id: self.connector_id,
+                    window: None,
+                    event_id: generate_event_id(self.job_id_hash),
+                    row_kind: RowKind::Insert as i32,
+                }))
+        });
+    }
+}
+
+#[async_trait]
+impl Source for Generator {
+    fn source_id(&self) -> SourceId {
+        self.connector_id
+    }
+
+    async fn close_source(&mut self) {
+        self.sleep = None;
+        self.generated.clear();
+        self.function.clear();
+        self.template.clear();
+        self.job_id.clear();
+    }
+
+    async fn next(&mut self) -> Option<LocalEvent> {
+        std::future::poll_fn(|cx| self.poll_next(cx)).await
+    }
+
+    fn poll_next(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Option<LocalEvent>> {
+        loop {
+            if let Some(event) = self.generated.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+            if self.inputs.is_finished() {
+                if self.terminated {
+                    return Poll::Ready(None);
+                }
+                self.terminated = true;
+                return Poll::Ready(Some(LocalEvent::Terminate {
+                    job_id: self.job_id.clone(),
+                    to: self.connector_id,
+                    event_time: now_timestamp(),
+                }));
+            }
+
+            let started_at = *self
+                .started_at
+                .get_or_insert_with(|| tokio::time::Instant::now());
+            let due = self
+                .inputs
+                .get_due_count(started_at.elapsed().as_millis() as u64)
+                .saturating_sub(self.inputs.generated())
+                .min(MAX_GENERATED_BATCH_SIZE);
+            if due > 0 {
+                self.generate(due);
+                continue;
+            }
+
+            // wait until the next event is due
+            let deadline = started_at + Duration::from_millis(self.inputs.get_next_due_millis());
+            let sleep = self
+                .sleep
+                .get_or_insert_with(|| Box::pin(tokio::time::sleep_until(deadline)));
+            sleep.as_mut().reset(deadline);
+            if sleep.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+/// Parquet sink writes object values of events as rows of `columns` to Parquet files in the directory `path`:
+/// - files are partitioned into sub-directories by the event time and fields of values, such as `dt=2026-10-18/hour=03`;
+/// - rows are buffered and written by row groups of `row_group_size`;
+/// - a file is written as a hidden in-progress file, and renamed to its final name once it's committed. Readers never see a partial file;
+///
+/// Files are committed when they roll by [file_desc::RotationOpts] or the sink is closed. Files exceeding `max_millis` are rolled by a timer
+/// even if no event arrives, and the remaining files are committed once the executor terminates.
+/// [PartitionedParquetWriter::commit] commits all of them at once, so it's where checkpoints hook in.
+/// Files are append-only, so DELETE and UPDATE_BEFORE events are ignored.
+///
+/// Files are written on the blocking thread pool, so that the executor isn't blocked by disk I/O.
+pub struct Parquet {
+    connector_id: SinkId,
+    writer: Arc<std::sync::Mutex<Option<PartitionedParquetWriter>>>,
+    rolling_handle: Option<tokio::task::JoinHandle<()>>,
+}
+
+/// the max interval of checking whether files of a parquet sink exceed `max_millis`
+const PARQUET_ROLLING_INTERVAL_MILLIS: u64 = 1000;
+
+impl Parquet {
+    /// It must be called in the context of tokio runtime
+    pub fn with_sink_config(
+        job_id: &ResourceId,
+        executor_id: ExecutorId,
+        conf: &ParquetDesc,
+    ) -> Self {
+        let writer = match PartitionedParquetWriter::new(
+            &conf.path,
+            &format!("{}-{}", &job_id.resource_id, executor_id),
+            conf,
+        ) {
+            Ok(writer) => Some(writer),
+            Err(err) => {
+                tracing::error!(
+                    "create parquet writer of sink {} failed: {:?}",
+                    executor_id,
+                    err
+                );
+                None
+            }
+        };
+        let writer = Arc::new(std::sync::Mutex::new(writer));
+
+        let max_millis = conf.get_rotation_opts().max_millis;
+        let rolling_handle = if max_millis > 0 {
+            let writer = writer.clone();
+            let interval = Duration::from_millis(max_millis.min(PARQUET_ROLLING_INTERVAL_MILLIS));
+            Some(tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                loop {
+                    ticker.tick().await;
+                    if let Err(err) =
+                        Self::with_writer(&writer, |writer| writer.roll_expired()).await
+                    {
+                        tracing::error!(
+                            "roll parquet files of sink {} failed: {:?}",
+                            executor_id,
+                            err
+                        );
+                    }
+                }
+            }))
+        } else {
+            None
+        };
+
+        Self {
+            connector_id: executor_id,
+            writer,
+            rolling_handle,
+        }
+    }
+
+    /// run `f` with the writer on the blocking thread pool. `f` isn't called if the writer can't be created or is closed
+    async fn with_writer<T, F>(
+        writer: &Arc<std::sync::Mutex<Option<PartitionedParquetWriter>>>,
+        f: F,
+    ) -> Result<Option<T>, ParquetException>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut PartitionedParquetWriter) -> Result<T, ParquetException> + Send + 'static,
+    {
+        let writer = writer.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut guard = writer.lock().unwrap_or_else(|err| err.into_inner());
+            guard.as_mut().map(f).transpose()
+        })
+        .await
+        .map_err(|err| ParquetException::WriteFailed(err.to_string()))?
+    }
+
+    /// the values and event times of the event which should be written
+    fn to_rows(event: &KeyedDataEvent) -> Vec<(TypedValue, i64)> {
+        match event.row_kind() {
+            RowKind::Delete | RowKind::UpdateBefore => return vec![],
+            _ => {}
+        }
+        let event_time = if event.event_time == 0 {
+            now_timestamp()
+        } else {
+            event.event_time
+        };
+        event
+            .data
+            .iter()
+            .map(|entry| (TypedValue::from_slice(&entry.value), event_time))
+            .collect()
+    }
+
+    fn stop_rolling(&mut self) {
+        if let Some(handle) = self.rolling_handle.take() {
+            handle.abort();
+        }
+    }
+}
+
+impl Drop for Parquet {
+    fn drop(&mut self) {
+        self.stop_rolling()
+    }
+}
+
+#[async_trait]
+impl Sink for Parquet {
+    fn sink_id(&self) -> SinkId {
+        self.connector_id
+    }
+
+    async fn sink(&mut self, msg: LocalEvent) -> Result<(), SinkException> {
+        match &msg {
+            LocalEvent::Terminate { .. } => Ok(()),
+            LocalEvent::KeyedDataStreamEvent(event) => {
+                let rows = Self::to_rows(event);
+                Self::with_writer(&self.writer, move |writer| {
+                    rows.iter()
+                        .try_for_each(|(value, event_time)| writer.write(value, *event_time))
+                })
+                .await
+                .map(|_| ())
+                .map_err(|err| err.into())
+            }
+        }
+    }
+
+    /// Commit files synchronously. [Sink::close] commits them on the blocking thread pool instead
+    fn close_sink(&mut self) {
+        self.stop_rolling();
+        let writer = self
+            .writer
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .take();
+        if let Some(mut writer) = writer {
+            if let Err(err) = writer.commit() {
+                tracing::error!(
+                    "commit parquet files of sink {} failed: {:?}",
+                    self.connector_id,
+                    err
+                );
+            }
+        }
+    }
+
+    async fn close(&mut self) {
+        self.stop_rolling();
+        if let Err(err) = Self::with_writer(&self.writer, |writer| writer.commit()).await {
+            tracing::error!(
+                "commit parquet files of sink {} failed: {:?}",
+                self.connector_id,
+                err
+            );
+        }
+        self.writer
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .take();
+    }
+
+    async fn batch_sink(&mut self, event_set: KeyedEventSet) -> Result<(), BatchSinkException> {
+        let rows = event_set
+            .events
+            .iter()
+            .map(|event| (event.event_id, Self::to_rows(event)))
+            .collect::<Vec<_>>();
+        Self::with_writer(&self.writer, move |writer| {
+            for (event_id, rows) in rows {
+                for (value, event_time) in rows {
+                    if let Err(err) = writer.write(&value, event_time) {
+                        return Ok(Err((event_id, err)));
+                    }
+                }
+            }
+            Ok(Ok(()))
+        })
+        .await
+        .map_err(|err| BatchSinkException {
+            err: err.into(),
+            event_id: 0,
+        })
+        .and_then(|result| match result {
+            Some(Err((event_id, err))) => Err(BatchSinkException {
+                err: err.into(),
+                event_id: event_id as u64,
+            }),
+            _ => Ok(()),
+        })
+    }
+}
+
+/// Stdout sink logs events by `tracing` for debugging. Each line contains the row kind, the key, values and the event time of an event
+pub struct Stdout {
+    connector_id: SinkId,
+    prefix: String,
+}
+
+impl Stdout {
+    pub fn with_config(executor_id: ExecutorId, conf: &StdoutDesc) -> Self {
+        Self {
+            connector_id: executor_id,
+            prefix: conf.prefix.clone(),
+        }
+    }
+
+    fn format_event(&self, event: &KeyedDataEvent) -> String {
+        let to_json = |entry: &Entry| TypedValue::from_slice(&entry.value).to_json_value();
+        let prefix = if self.prefix.is_empty() {
+            Default::default()
+        } else {
+            format!("[{}] ", &self.prefix)
+        };
+        format!(
+            "{}sink {} {} key: {} values: {} event_time: {}",
+            prefix,
+            self.connector_id,
+            event.row_kind().as_str_name(),
+            event
+                .key
+                .as_ref()
+                .map(to_json)
+                .unwrap_or(serde_json::Value::Null),
+            serde_json::Value::Array(event.data.iter().map(to_json).collect()),
+            event.event_time
+        )
+    }
+}
+
+#[async_trait]
+impl Sink for Stdout {
+    fn sink_id(&self) -> SinkId {
+        self.connector_id
+    }
+
+    async fn sink(&mut self, msg: LocalEvent) -> Result<(), SinkException> {
+        match &msg {
+            LocalEvent::Terminate { .. } => {}
+            LocalEvent::KeyedDataStreamEvent(event) => {
+                tracing::info!("{}", self.format_event(event))
+            }
+        }
+        Ok(())
+    }
+
+    fn close_sink(&mut self) {
+        self.prefix.clear();
+    }
+
+    async fn batch_sink(&mut self, event_set: KeyedEventSet) -> Result<(), BatchSinkException> {
+        event_set
+            .events
+            .iter()
+            .for_each(|event| tracing::info!("{}", self.format_event(event)));
+        Ok(())
+    }
+}
+
+const DEFAULT_COLLECT_CAPACITY: usize = 1000;
+
+/// Events kept by a [Collect] sink. Clones share the same events, so they can be read while the sink is running
+#[derive(Clone)]
+pub struct CollectedEvents {
+    capacity: usize,
+    events: Arc<tokio::sync::RwLock<VecDeque<KeyedDataEvent>>>,
+}
+
+impl CollectedEvents {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            events: Arc::new(tokio::sync::RwLock::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    /// keep events and drop the oldest ones if `capacity` is exceeded
+    async fn extend<T: IntoIterator<Item = KeyedDataEvent>>(&self, events: T) {
+        let mut guard = self.events.write().await;
+        guard.extend(events);
+        while guard.len() > self.capacity {
+            guard.pop_front();
+        }
+    }
+
+    /// kept events in the order they arrived
+    pub async fn get_events(&self) -> Vec<KeyedDataEvent> {
+        self.events.read().await.iter().cloned().collect()
+    }
+}
+
+/// Collect sink keeps the latest `capacity` events in memory for debugging and tests.
+/// Events of all row kinds are kept as they are, and they can be read by [SinkImpl::get_collected_events]
+pub struct Collect {
+    connector_id: SinkId,
+    events: CollectedEvents,
+}
+
+impl Collect {
+    pub fn with_config(executor_id: ExecutorId, conf: &CollectDesc) -> Self {
+        let capacity = if conf.capacity == 0 {
+            DEFAULT_COLLECT_CAPACITY
+        } else {
+            conf.capacity as usize
+        };
+        Self {
+            connector_id: executor_id,
+            events: CollectedEvents::new(capacity),
+        }
+    }
+
+    pub fn get_collected_events(&self) -> CollectedEvents {
+        self.events.clone()
+    }
+}
+
+#[async_trait]
+impl Sink for Collect {
+    fn sink_id(&self) -> SinkId {
+        self.connector_id
+    }
+
+    async fn sink(&mut self, msg: LocalEvent) -> Result<(), SinkException> {
+        match msg {
+            LocalEvent::Terminate { .. } => {}
+            LocalEvent::KeyedDataStreamEvent(event) => self.events.extend([event]).await,
+        }
+        Ok(())
+    }
+
+    // events are still readable after the sink is closed
+    fn close_sink(&mut self) {}
+
+    async fn batch_sink(&mut self, event_set: KeyedEventSet) -> Result<(), BatchSinkException> {
+        self.events.extend(event_set.events).await;
+        Ok(())
+    }
+}
+
+fn extract_arguments_scope(
+    extractors: &[String],
+    event: &LocalEvent,
+    fn_name: &str,
+    scope: &mut v8::HandleScope<'_, ()>,
+) -> Vec<Vec<TypedValue>> {
+    match event {
+        LocalEvent::Terminate { .. } => vec![],
+        LocalEvent::KeyedDataStreamEvent(e) => Vec::from_iter(e.data.iter().map(|entry| {
+            let val = TypedValue::from_slice(&entry.value);
+            extractors
+                .iter()
+                .map(|extractor| {
+                    let mut rt_engine = RuntimeEngine::new(extractor.as_str(), fn_name, scope);
+                    rt_engine.call_one_arg(&val).unwrap_or_default()
+                })
+                .collect::<Vec<TypedValue>>()
+        })),
+    }
+}
+
+fn extract_arguments(
+    extractors: &[String],
+    event: &LocalEvent,
+    fn_name: &str,
+) -> Vec<Vec<TypedValue>> {
+    let isolate = &mut v8::Isolate::new(Default::default());
+    let scope = &mut v8::HandleScope::new(isolate);
+    match event {
+        LocalEvent::Terminate { .. } => vec![],
+        LocalEvent::KeyedDataStreamEvent(e) => Vec::from_iter(e.data.iter().map(|entry| {
+            let val = TypedValue::from_slice(&entry.value);
+            extractors
+                .iter()
+                .map(|extractor| {
+                    let mut rt_engine = RuntimeEngine::new(extractor.as_str(), fn_name, scope);
+                    rt_engine.call_one_arg(&val).unwrap_or_default()
+                })
+                .collect::<Vec<TypedValue>>()
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proto::common::{
+        mysql_desc, redis_desc, Entry, Func, KafkaDesc, MysqlDesc, RedisDesc, ResourceId,
+    };
+
+    use crate::{new_event_channel, MOD_TEST_START};
+
+    use super::{Sink, SinkImpl, Source, SourceImpl};
 
     struct SetupGuard {}
 
@@ -744,6 +3227,9 @@ mod tests {
                     },
                 ],
             }),
+            delete_statement: None,
+            polling_opts: None,
+            max_batch_size: 0,
         };
 
         let mysql = Mysql::with_config(1, &desc);
@@ -783,6 +3269,7 @@ mod tests {
             topic: "topic".to_string(),
             opts: None,
             data_type: 6,
+            format: 0,
         };
         let (tx, rx) = new_event_channel(1);
         let mut kafka_source = SourceImpl::Kafka(
@@ -805,6 +3292,7 @@ mod tests {
                         topic: Default::default(),
                         opts: None,
                         data_type: 0,
+                        format: 0,
                     }
                 );
                 assert!(tx.is_closed());
@@ -826,6 +3314,7 @@ mod tests {
                         topic: Default::default(),
                         opts: None,
                         data_type: 0,
+                        format: 0,
                     }
                 );
             }
@@ -833,6 +3322,62 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_kafka_process_debezium_json() {
+        use common::{event::LocalEvent, kafka::KafkaMessage, types::TypedValue};
+        use proto::common::{kafka_desc, RowKind};
+        use std::collections::BTreeMap;
+
+        let job_id = ResourceId {
+            resource_id: "resource_id".to_string(),
+            namespace_id: "ns_id".to_string(),
+        };
+        let desc = KafkaDesc {
+            brokers: vec!["localhost:9092".to_string()],
+            topic: "topic".to_string(),
+            opts: None,
+            data_type: 0,
+            format: kafka_desc::MessageFormat::DebeziumJson as i32,
+        };
+        let kafka = super::Kafka::with_source_config(&job_id, 0, &desc);
+
+        let events = kafka.process(KafkaMessage {
+            key: bytes::Bytes::from_static(br#"{"id": 1}"#),
+            payload: bytes::Bytes::from_static(
+                br#"{"before": {"id": 1, "v": "a"}, "after": {"id": 1, "v": "b"}, "op": "u"}"#,
+            ),
+            timestamp: Some(100),
+        });
+
+        assert_eq!(events.len(), 2);
+        let row_kinds = events
+            .iter()
+            .map(|event| match event {
+                LocalEvent::KeyedDataStreamEvent(event) => {
+                    assert_eq!(
+                        TypedValue::from(event.key.as_ref().unwrap()),
+                        TypedValue::Object(BTreeMap::from([(
+                            "id".to_string(),
+                            TypedValue::BigInt(1)
+                        )]))
+                    );
+                    assert_eq!(event.event_time, 100);
+                    event.row_kind()
+                }
+                _ => panic!("unexpected event {:?}", event),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(row_kinds, vec![RowKind::UpdateBefore, RowKind::UpdateAfter]);
+
+        // tombstone after delete
+        let events = kafka.process(KafkaMessage {
+            key: bytes::Bytes::from_static(br#"{"id": 1}"#),
+            payload: bytes::Bytes::new(),
+            timestamp: Some(100),
+        });
+        assert!(events.is_empty());
+    }
+
     #[test]
     fn test_redis_source_sink_close() {
         let desc = RedisDesc {
@@ -842,6 +3387,7 @@ mod tests {
                 password: Default::default(),
                 database: 0,
                 tls: false,
+                cluster: false,
             }),
             key_extractor: Some(Func {
                 function: "key_extractor".to_string(),
@@ -849,6 +3395,11 @@ mod tests {
             value_extractor: Some(Func {
                 function: "value_extractor".to_string(),
             }),
+            command: redis_desc::Command::Set as i32,
+            field_extractor: None,
+            score_extractor: None,
+            expire_millis: 0,
+            stream_opts: None,
         };
         let mut redis_sink = SinkImpl::Redis(super::Redis::with_config(0, &desc));
 
@@ -862,6 +3413,128 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_redis_to_commands() {
+        use common::{redis::RedisCommand, types::TypedValue};
+        use proto::common::RowKind;
+
+        let desc = |command: redis_desc::Command, expire_millis: u64| RedisDesc {
+            connection_opts: Some(redis_desc::ConnectionOpts {
+                host: "localhost".to_string(),
+                username: Default::default(),
+                password: Default::default(),
+                database: 0,
+                tls: false,
+                cluster: false,
+            }),
+            key_extractor: None,
+            value_extractor: None,
+            command: command as i32,
+            field_extractor: None,
+            score_extractor: None,
+            expire_millis,
+            stream_opts: None,
+        };
+        let key = TypedValue::String("key".to_string());
+        let value = TypedValue::String("value".to_string());
+
+        let redis = super::Redis::with_config(0, &desc(redis_desc::Command::Set, 1000));
+        assert_eq!(
+            redis.to_commands(RowKind::Insert, vec![key.clone(), value.clone()]),
+            vec![
+                RedisCommand::Set {
+                    key: key.clone(),
+                    value: value.clone()
+                },
+                RedisCommand::PExpire {
+                    key: key.clone(),
+                    millis: 1000
+                }
+            ]
+        );
+        assert_eq!(
+            redis.to_commands(RowKind::UpdateBefore, vec![key.clone(), value.clone()]),
+            vec![]
+        );
+        assert_eq!(
+            redis.to_commands(RowKind::Delete, vec![key.clone(), value.clone()]),
+            vec![RedisCommand::Del { key: key.clone() }]
+        );
+
+        let field = TypedValue::String("field".to_string());
+        let redis = super::Redis::with_config(0, &desc(redis_desc::Command::Hset, 0));
+        assert_eq!(
+            redis.to_commands(
+                RowKind::UpdateAfter,
+                vec![key.clone(), value.clone(), field.clone()]
+            ),
+            vec![RedisCommand::HSet {
+                key: key.clone(),
+                field: field.clone(),
+                value: value.clone()
+            }]
+        );
+        assert_eq!(
+            redis.to_commands(
+                RowKind::UpdateBefore,
+                vec![key.clone(), value.clone(), field.clone()]
+            ),
+            vec![RedisCommand::HDel {
+                key: key.clone(),
+                field
+            }]
+        );
+
+        let redis = super::Redis::with_config(0, &desc(redis_desc::Command::Zadd, 0));
+        assert_eq!(
+            redis.to_commands(
+                RowKind::Insert,
+                vec![key.clone(), value.clone(), TypedValue::BigInt(10)]
+            ),
+            vec![RedisCommand::ZAdd {
+                key: key.clone(),
+                member: value.clone(),
+                score: 10.0
+            }]
+        );
+        assert_eq!(
+            redis.to_commands(
+                RowKind::Insert,
+                vec![key.clone(), value.clone(), TypedValue::Null]
+            ),
+            vec![]
+        );
+
+        let redis = super::Redis::with_config(0, &desc(redis_desc::Command::Incrby, 0));
+        assert_eq!(
+            redis.to_commands(RowKind::Delete, vec![key.clone(), TypedValue::BigInt(3)]),
+            vec![RedisCommand::IncrBy {
+                key: key.clone(),
+                delta: -3
+            }]
+        );
+
+        let redis = super::Redis::with_config(0, &desc(redis_desc::Command::Rpush, 0));
+        assert_eq!(
+            redis.to_commands(RowKind::Delete, vec![key.clone(), value.clone()]),
+            vec![]
+        );
+        assert_eq!(
+            super::Redis::compact_set_commands(vec![
+                RedisCommand::Set {
+                    key: key.clone(),
+                    value: value.clone()
+                },
+                RedisCommand::PExpire {
+                    key: key.clone(),
+                    millis: 1000
+                },
+                RedisCommand::Del { key: key.clone() },
+            ]),
+            vec![RedisCommand::Del { key }]
+        );
+    }
+
     #[test]
     fn test_mysql_sink_close() {
         let ref conf = MysqlDesc {
@@ -878,6 +3551,9 @@ mod tests {
                     extractor: "extrator".to_string(),
                 }],
             }),
+            delete_statement: None,
+            polling_opts: None,
+            max_batch_size: 0,
         };
         let mut mysql_sink = SinkImpl::Mysql(super::Mysql::with_config(0, conf));
         mysql_sink.close_sink();
@@ -889,4 +3565,123 @@ mod tests {
             _ => {}
         }
     }
+
+    #[test]
+    fn test_mysql_to_batch_statements() {
+        use common::types::TypedValue;
+
+        let upsert = "insert into t (a, b) values (?, ?) on duplicate key update b = values(b)";
+        let delete = "delete from t where a = ?";
+        let row = |a: &str, b: i64| vec![TypedValue::String(a.to_string()), TypedValue::BigInt(b)];
+
+        let statements = super::to_batch_statements(
+            vec![
+                (upsert, row("a", 1)),
+                (upsert, row("b", 2)),
+                (delete, vec![TypedValue::String("a".to_string())]),
+                (delete, vec![TypedValue::String("b".to_string())]),
+                (upsert, row("c", 3)),
+            ]
+            .into_iter(),
+            super::to_multi_row_statement,
+        );
+
+        assert_eq!(
+            statements,
+            vec![
+                (
+                    "insert into t (a, b) values (?, ?), (?, ?) on duplicate key update b = values(b)"
+                        .to_string(),
+                    [row("a", 1), row("b", 2)].concat()
+                ),
+                (delete.to_string(), vec![TypedValue::String("a".to_string())]),
+                (delete.to_string(), vec![TypedValue::String("b".to_string())]),
+                (upsert.to_string(), row("c", 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_postgres_dedup_upserts() {
+        use common::types::TypedValue;
+
+        let upsert =
+            "insert into t (a, b) values ($1, $2) on conflict (a) do update set b = excluded.b";
+        let delete = "delete from t where a = $1";
+        let row = |a: &str, b: i64| vec![TypedValue::String(a.to_string()), TypedValue::BigInt(b)];
+
+        let rows = super::dedup_upserts(
+            vec![
+                (upsert, row("a", 1)),
+                (upsert, row("b", 1)),
+                (upsert, row("a", 2)),
+                (delete, vec![TypedValue::String("a".to_string())]),
+                (upsert, row("a", 3)),
+                (upsert, row("a", 4)),
+            ]
+            .into_iter(),
+            upsert,
+            &[0],
+        );
+
+        assert_eq!(
+            rows,
+            vec![
+                (upsert, row("b", 1)),
+                (upsert, row("a", 2)),
+                (delete, vec![TypedValue::String("a".to_string())]),
+                (upsert, row("a", 4)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generator_function() {
+        use std::collections::BTreeMap;
+
+        use common::{event::LocalEvent, types::TypedValue};
+        use proto::common::GeneratorDesc;
+
+        use super::Generator;
+
+        let _setup_guard = setup_v8();
+
+        let conf = GeneratorDesc {
+            rate_per_sec: 0,
+            count: 3,
+            key_cardinality: 2,
+            seed: 7,
+            function: Some(Func {
+                function: "function generate(input) { return { id: Number(input.index) * 10, user: input.key } }"
+                    .to_string(),
+            }),
+            template: Default::default(),
+        };
+
+        let mut generator = Generator::with_source_config(&ResourceId::default(), 1, &conf);
+        for index in 0..3 {
+            match generator.next().await {
+                Some(LocalEvent::KeyedDataStreamEvent(event)) => {
+                    let key = event
+                        .key
+                        .as_ref()
+                        .map(|key| TypedValue::from_slice(&key.value));
+                    assert!(key.is_some());
+                    assert_eq!(
+                        TypedValue::from_slice(&event.data[0].value),
+                        TypedValue::Object(BTreeMap::from([
+                            ("id".to_string(), TypedValue::Number((index * 10) as f64)),
+                            ("user".to_string(), key.unwrap_or_default()),
+                        ]))
+                    );
+                }
+                event => panic!("unexpected event {:?}", event),
+            }
+        }
+        match generator.next().await {
+            Some(LocalEvent::Terminate { to, .. }) => assert_eq!(to, 1),
+            event => panic!("unexpected event {:?}", event),
+        }
+        assert!(generator.next().await.is_none());
+    }
 }
@@ -2,7 +2,7 @@ use std::{cell::RefCell, collections::BTreeMap};
 
 use common::types::{ExecutorId, NodeIdx, TypedValue};
 
-use proto::common::{operator_info::Details, Entry, KeyedDataEvent};
+use proto::common::{operator_info::Details, Entry, KeyedDataEvent, RowKind};
 use v8::HandleScope;
 
 use crate::{err::ExecutionError, state, v8_runtime::RuntimeEngine};
@@ -174,10 +174,11 @@ impl<S: state::StateManager> IOperator for KeyByOperator<S> {
                     key_entry.set_data_type(key.get_type());
                     key_entry.value = key.get_data_bytes();
 
-                    let mut event = KeyedDataEvent::default();
-                    event.from_operator_id = self.operator_id;
-                    event.key = Some(key_entry);
-                    new_events.insert(key.clone(), event);
+                    let mut new_event = KeyedDataEvent::default();
+                    new_event.from_operator_id = self.operator_id;
+                    new_event.key = Some(key_entry);
+                    new_event.row_kind = event.row_kind;
+                    new_events.insert(key.clone(), new_event);
                 }
 
                 match new_events.get_mut(&key) {
@@ -194,6 +195,11 @@ impl<S: state::StateManager> IOperator for KeyByOperator<S> {
     }
 }
 
+/// The result of a key is emitted as an INSERT, and each later result as an UPDATE_BEFORE of the previous one followed by an UPDATE_AFTER,
+/// even if the result doesn't change.
+/// A reduce function can not be inverted, so a DELETE or UPDATE_BEFORE from upstream retracts the whole result of its key:
+/// the state of the key is cleared and the previous result is emitted as a DELETE. It's correct if each key has at most one row upstream,
+/// such as the primary key of a changelog source, and the following UPDATE_AFTER of an update is accumulated from scratch
 impl<S: state::StateManager> IOperator for ReduceOperator<S> {
     fn call_fn<'p, 'i>(
         &self,
@@ -203,43 +209,73 @@ impl<S: state::StateManager> IOperator for ReduceOperator<S> {
     where
         'p: 'i,
     {
-        let key = get_operator_state_key(self.operator_id, "reduce", &event.get_key().value);
-        let state = self.state_manager.get_keyed_state(key.as_slice());
+        // the state manager belongs to the operator, and it partitions the state by the key of the event
+        let key = event.get_key().value;
+        let state = self.state_manager.get_keyed_state(&key);
 
-        let accum = if state.is_empty() {
-            event
-                .data
-                .iter()
-                .map(|entry| TypedValue::from(entry))
-                .reduce(|prev, next| {
-                    rt_engine
-                        .borrow_mut()
-                        .call_two_args((&prev, &next))
-                        .unwrap_or(TypedValue::Invalid)
-                })
-                .unwrap_or(TypedValue::Invalid)
+        let values = event.data.iter().map(|entry| TypedValue::from(entry));
+
+        let prev = if state.is_empty() {
+            None
         } else {
-            let accum = TypedValue::from_vec(&state);
+            Some(TypedValue::from_vec(&state))
+        };
 
-            event.data.iter().fold(accum, |accum, entry| {
-                let val = TypedValue::from_slice(&entry.value);
+        let new_event = |val: &TypedValue, row_kind: RowKind| {
+            let mut new_event = event.clone();
+            let mut entry = Entry::default();
+            entry.value = val.get_data_bytes();
+            entry.set_data_type(val.get_type());
+            new_event.data = vec![entry];
+            new_event.set_row_kind(row_kind);
+            new_event.from_operator_id = self.operator_id;
+            new_event
+        };
+
+        if event.is_retraction() {
+            return match &prev {
+                Some(prev) => {
+                    self.state_manager.set_key_state(&key, &[]);
+                    Ok(vec![new_event(prev, RowKind::Delete)])
+                }
+                None => Ok(vec![]),
+            };
+        }
+
+        let accum = match &prev {
+            Some(accum) => values.fold(accum.clone(), |accum, val| {
                 rt_engine
                     .borrow_mut()
                     .call_two_args((&accum, &val))
                     .unwrap_or(TypedValue::Invalid)
-            })
+            }),
+            None => values
+                .reduce(|prev, next| {
+                    rt_engine
+                        .borrow_mut()
+                        .call_two_args((&prev, &next))
+                        .unwrap_or(TypedValue::Invalid)
+                })
+                .unwrap_or(TypedValue::Invalid),
         };
 
+        // nothing is accumulated for the key
+        if prev.is_none() && accum == TypedValue::Invalid {
+            return Ok(vec![]);
+        }
+
         let value = accum.get_data_bytes();
 
-        self.state_manager.set_key_state(key.as_slice(), &value);
+        self.state_manager.set_key_state(&key, &value);
 
-        let mut new_event = event.clone();
-        let mut entry = Entry::default();
-        entry.value = value;
-        entry.set_data_type(accum.get_type());
-        new_event.data = vec![entry];
-        Ok(vec![new_event])
+        // retract the previous result before emitting the new one
+        match &prev {
+            Some(prev) => Ok(vec![
+                new_event(prev, RowKind::UpdateBefore),
+                new_event(&accum, RowKind::UpdateAfter),
+            ]),
+            None => Ok(vec![new_event(&accum, RowKind::Insert)]),
+        }
     }
 }
 
@@ -397,12 +433,6 @@ new_operator!(KeyByOperator);
 define_operator!(ReduceOperator);
 new_operator!(ReduceOperator);
 
-fn get_operator_state_key(operator_id: NodeIdx, operator: &str, reference: &[u8]) -> Vec<u8> {
-    let mut prefix = format!("{}-{}", operator, operator_id).as_bytes().to_vec();
-    prefix.append(&mut reference.to_vec());
-    prefix
-}
-
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeMap;
@@ -490,6 +520,9 @@ mod tests {
             operator_id: 0,
             host_addr: None,
             upstreams: Default::default(),
+            out_edge_batch: None,
+            parallelism: 0,
+            instance: None,
             details: Some(Details::Mapper(Mapper {
                 value: Some(mapper::Value::Func(Func {
                     function: "function _operator_map_process(a) { return a+1 }".to_string(),
@@ -552,6 +585,9 @@ mod tests {
             operator_id: 0,
             host_addr: None,
             upstreams: Default::default(),
+            out_edge_batch: None,
+            parallelism: 0,
+            instance: None,
             details: Some(Details::Filter(Filter {
                 value: Some(filter::Value::Func(Func {
                     function: "function _operator_filter_process(a) { return a === 1 }".to_string(),
@@ -618,6 +654,9 @@ mod tests {
             operator_id: 0,
             host_addr: None,
             upstreams: Default::default(),
+            out_edge_batch: None,
+            parallelism: 0,
+            instance: None,
             details: Some(Details::KeyBy(KeyBy {
                 value: Some(key_by::Value::Func(Func {
                     function: "function _operator_keyBy_process(a) { return a.foo }".to_string(),
@@ -683,20 +722,23 @@ mod tests {
         use crate::dataflow::get_function_name;
         use crate::dataflow::IOperator;
         use crate::state::MemoryStateManager;
+        use crate::state::StateManager;
         use crate::v8_runtime::RuntimeEngine;
-        use crate::{dataflow::get_operator_state_key, state::StateManager};
         use common::types::TypedValue;
         use proto::common::Func;
         use proto::common::{Entry, KeyedDataEvent};
-        use proto::common::{OperatorInfo, Reducer};
+        use proto::common::{OperatorInfo, Reducer, RowKind};
         use std::cell::RefCell;
 
         let _setup_guard = setup();
 
         let op_info = OperatorInfo {
-            operator_id: 0,
+            operator_id: 1,
             host_addr: None,
             upstreams: Default::default(),
+            out_edge_batch: None,
+            parallelism: 0,
+            instance: None,
             details: Some(Details::Reducer(Reducer {
                 value: Some(reducer::Value::Func(Func {
                     function:
@@ -743,14 +785,71 @@ mod tests {
             entry.value = val.get_data_bytes();
 
             assert_eq!(new_events[0].data, vec![entry]);
+            assert_eq!(new_events[0].from_operator_id, 1);
             let state = operator
                 .state_manager
-                .get_keyed_state(&get_operator_state_key(
-                    operator.operator_id,
-                    "reduce",
-                    &new_events[0].get_key().value,
-                ));
+                .get_keyed_state(&new_events[0].get_key().value);
             assert_eq!(TypedValue::from_vec(&state), val);
+            assert_eq!(new_events[0].row_kind(), RowKind::Insert);
+        }
+
+        event.data = vec![entry.clone()];
+        let result = operator.call_fn(&event, &rt_engine);
+
+        {
+            assert!(result.is_ok());
+            let new_events = result.expect("");
+            assert_eq!(new_events.len(), 2);
+
+            let mut prev_entry = Entry::default();
+            let val = TypedValue::Number(4.0);
+            prev_entry.set_data_type(val.get_type());
+            prev_entry.value = val.get_data_bytes();
+            assert_eq!(new_events[0].data, vec![prev_entry]);
+            assert_eq!(new_events[0].row_kind(), RowKind::UpdateBefore);
+
+            let mut entry = Entry::default();
+            let val = TypedValue::Number(5.0);
+            entry.set_data_type(val.get_type());
+            entry.value = val.get_data_bytes();
+            assert_eq!(new_events[1].data, vec![entry]);
+            assert_eq!(new_events[1].row_kind(), RowKind::UpdateAfter);
+        }
+
+        // a retraction from upstream retracts the result of the key
+        event.set_row_kind(RowKind::Delete);
+        let result = operator.call_fn(&event, &rt_engine);
+        {
+            assert!(result.is_ok());
+            let new_events = result.expect("");
+            assert_eq!(new_events.len(), 1);
+
+            let mut prev_entry = Entry::default();
+            let val = TypedValue::Number(5.0);
+            prev_entry.set_data_type(val.get_type());
+            prev_entry.value = val.get_data_bytes();
+            assert_eq!(new_events[0].data, vec![prev_entry]);
+            assert_eq!(new_events[0].row_kind(), RowKind::Delete);
+            assert!(operator
+                .state_manager
+                .get_keyed_state(&event.get_key().value)
+                .is_empty());
+        }
+
+        // nothing is left to retract
+        let result = operator.call_fn(&event, &rt_engine);
+        assert!(result.is_ok());
+        assert!(result.expect("").is_empty());
+
+        // the key is accumulated from scratch after it's retracted
+        event.set_row_kind(RowKind::UpdateAfter);
+        let result = operator.call_fn(&event, &rt_engine);
+        {
+            assert!(result.is_ok());
+            let new_events = result.expect("");
+            assert_eq!(new_events.len(), 1);
+            assert_eq!(new_events[0].data, vec![entry]);
+            assert_eq!(new_events[0].row_kind(), RowKind::Insert);
         }
     }
 
@@ -773,6 +872,9 @@ mod tests {
             operator_id: 0,
             host_addr: None,
             upstreams: Default::default(),
+            out_edge_batch: None,
+            parallelism: 0,
+            instance: None,
             details: Some(Details::FlatMap(FlatMap {
                 value: Some(flat_map::Value::Func(Func {
                     function: "function _operator_flatMap_process(v) { return [v, v, 2] }"
@@ -845,6 +947,9 @@ mod tests {
             operator_id: 0,
             host_addr: None,
             upstreams: Default::default(),
+            out_edge_batch: None,
+            parallelism: 0,
+            instance: None,
             details: Some(Details::FlatMap(FlatMap {
                 value: Some(flat_map::Value::Func(Func {
                     function: "function _operator_flatMap_process(value) { return value.split(\" \").map(v => { return { t0: 1, t1: v }; }) }".to_string(),
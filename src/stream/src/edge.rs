@@ -1,23 +1,48 @@
 use std::{
+    collections::{BTreeMap, VecDeque},
     fmt::Display,
     marker::PhantomData,
+    pin::Pin,
+    sync::{Arc, Weak},
     task::{Context, Poll},
-    vec,
+    time::Duration,
 };
 
 use common::{
-    collections::lang,
     event::{LocalEvent, StreamEvent},
     net::gateway::taskmanager::SafeTaskManagerRpcGateway,
     types::ExecutorId,
 };
-use proto::common::{KeyedDataEvent, KeyedEventSet, ResourceId};
-use rayon::prelude::{IntoParallelIterator, ParallelIterator};
-use tokio::sync::mpsc::error::TrySendError;
-use tonic::async_trait;
+use futures_util::{ready, Future, FutureExt};
+use proto::{
+    common::{EdgeBatch, KeyedDataEvent, KeyedEventSet, ResourceId},
+    taskmanager::{edge_frame::Frame, EdgeAck, EdgeFrame, EdgeHandshake, EdgeTerminate},
+};
+use tokio::{
+    sync::{mpsc, mpsc::error::TrySendError, watch, Mutex, Notify, Semaphore, TryAcquireError},
+    time::Sleep,
+};
+use tonic::{async_trait, codegen::BoxStream, Streaming};
 
-use crate::{Receiver, Sender};
+use crate::{
+    new_event_channel,
+    spill::{SpillError, SpillQueue, SpillStats},
+    Receiver, Sender,
+};
 
+/// how long the stream of a remote edge waits before reconnecting
+const MIN_RECONNECT_WAIT: Duration = Duration::from_millis(10);
+const MAX_RECONNECT_WAIT: Duration = Duration::from_millis(1000);
+/// a remote edge spills its frames if its stream can't be reconnected after this number of attempts
+const MAX_RECONNECT_ATTEMPTS: u32 = 8;
+/// the number of frames which are buffered before the transport sends them
+const EDGE_FRAME_BUFFER_SIZE: usize = 16;
+
+/// The trait that represents an out-edge.
+///
+/// Out-edges are flow-controlled by credits: the receiver grants the number of events it can take,
+/// and writes wait until the receiver has room for the events instead of dropping them.
+/// Executors stop polling their input while writes are waiting, so the backpressure propagates to the sources.
 #[async_trait]
 pub trait OutEdge: Send + Sync {
     type Output;
@@ -31,10 +56,49 @@ pub trait OutEdge: Send + Sync {
         from_operator_id: ExecutorId,
         iter: Vec<Self::Output>,
     ) -> Result<(), OutEdgeError>;
+
+    /// the number of events the edge can write without waiting
+    fn credits(&self) -> usize;
+
+    /// the size of the events which the edge has spilled and not replayed yet
+    fn spill_stats(&self) -> Option<Arc<SpillStats>> {
+        None
+    }
+
+    /// Wait until the events which have been written are delivered to the downstream operator
+    async fn flush(&self) -> Result<(), OutEdgeError> {
+        Ok(())
+    }
 }
 
+/// Create the queue of an edge between executors in the same process, which has room for `capacity` events.
+/// The queue is bounded by events instead of messages, so that its credits are counted in events as the ones of remote edges
+pub fn new_local_edge<T>(capacity: usize) -> (LocalOutEdge<T>, LocalInEdge<T>) {
+    let capacity = capacity.clamp(1, u32::MAX as usize);
+    let (tx, rx) = new_event_channel(capacity);
+    let permits = Arc::new(Semaphore::new(capacity));
+    (
+        LocalOutEdge {
+            tx,
+            permits: permits.clone(),
+            capacity,
+            _data_type: PhantomData,
+        },
+        LocalInEdge {
+            rx,
+            buffered: Default::default(),
+            permits,
+            capacity,
+        },
+    )
+}
+
+/// The out-edge to an executor in the same process. Events are moved over the queue without being encoded
 pub struct LocalOutEdge<T> {
-    tx: Sender<bytes::Bytes>,
+    tx: Sender<Vec<T>>,
+    // a permit for each event the queue has room for. A message larger than the queue takes all permits
+    permits: Arc<Semaphore>,
+    capacity: usize,
     _data_type: PhantomData<T>,
 }
 
@@ -42,27 +106,66 @@ unsafe impl<T> Send for LocalOutEdge<T> {}
 unsafe impl<T> Sync for LocalOutEdge<T> {}
 
 impl<T> LocalOutEdge<T> {
-    pub fn new(tx: Sender<bytes::Bytes>) -> Self {
-        Self {
-            tx,
-            _data_type: PhantomData,
-        }
+    fn permits_of(&self, events: &[T]) -> u32 {
+        events.len().min(self.capacity) as u32
+    }
+
+    async fn send(&self, events: Vec<T>) -> Result<(), OutEdgeError> {
+        self.permits
+            .acquire_many(self.permits_of(&events))
+            .await
+            .map_err(|err| OutEdgeError::SendToLocalFailed(err.to_string()))?
+            // the permits are released by the in-edge once it receives the message
+            .forget();
+        self.tx
+            .send(events)
+            .await
+            .map_err(|err| OutEdgeError::SendToLocalFailed(err.to_string()))
     }
 }
 
 impl<T: StreamEvent> LocalOutEdge<T> {
-    fn try_write(&self, val: T) -> Result<(), OutEdgeError> {
-        let mut buf = vec![];
-        let mut serializer = rmp_serde::Serializer::new(&mut buf);
-        val.serialize(&mut serializer)
-            .map_err(|err| OutEdgeError::from(err))?;
+    /// write the event without waiting. It returns [OutEdgeError::QueueFull] if the queue has no room for it
+    pub fn try_write(&self, val: T) -> Result<(), OutEdgeError> {
+        self.try_write_all(vec![val])
+    }
 
-        self.tx
-            .try_send(bytes::Bytes::from(buf))
+    /// write all events in one message without waiting. If the queue has no room for all of them, no event is written and it returns [OutEdgeError::QueueFull],
+    /// so that the sender can send the same events again
+    pub fn try_write_all(&self, events: Vec<T>) -> Result<(), OutEdgeError> {
+        let permits = self
+            .permits
+            .try_acquire_many(self.permits_of(&events))
             .map_err(|err| match err {
-                TrySendError::Full(_) => OutEdgeError::QueueFull,
-                TrySendError::Closed(_) => OutEdgeError::QueueClosed,
-            })
+                TryAcquireError::NoPermits => OutEdgeError::QueueFull,
+                TryAcquireError::Closed => OutEdgeError::QueueClosed,
+            })?;
+        self.tx.try_send(events).map_err(|err| match err {
+            TrySendError::Full(_) => OutEdgeError::QueueFull,
+            TrySendError::Closed(_) => OutEdgeError::QueueClosed,
+        })?;
+        permits.forget();
+        Ok(())
+    }
+
+    /// wait until the queue has room for an event
+    pub async fn wait_for_credits(&self) -> Result<(), OutEdgeError> {
+        self.permits
+            .acquire()
+            .await
+            .map(|_| ())
+            .map_err(|err| OutEdgeError::SendToLocalFailed(err.to_string()))
+    }
+}
+
+impl<T> Clone for LocalOutEdge<T> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            permits: self.permits.clone(),
+            capacity: self.capacity,
+            _data_type: PhantomData,
+        }
     }
 }
 
@@ -71,49 +174,29 @@ impl<T: StreamEvent> OutEdge for LocalOutEdge<T> {
     type Output = T;
 
     async fn write(&self, val: T) -> Result<(), OutEdgeError> {
-        let mut buf = vec![];
-        let mut serializer = rmp_serde::Serializer::new(&mut buf);
-        val.serialize(&mut serializer)
-            .map_err(|err| OutEdgeError::from(err))?;
-
-        self.tx
-            .send(bytes::Bytes::from(buf))
-            .await
-            .map_err(|err| OutEdgeError::SendToLocalFailed(err.to_string()))
+        self.send(vec![val]).await
     }
 
+    /// events are written in one message, which waits until the queue has room for all of them
     async fn batch_write(
         &self,
         _job_id: &Option<ResourceId>,
         to_operator_id: ExecutorId,
         _from_operator_id: ExecutorId,
-        iter: Vec<Self::Output>,
+        mut iter: Vec<Self::Output>,
     ) -> Result<(), OutEdgeError> {
-        let failed_events = iter
-            .into_par_iter()
-            .map(|mut event| {
-                let event_id = event.event_id();
-                event.set_to_operator_id(to_operator_id);
-                match self.try_write(event) {
-                    Ok(_) => (event_id, None),
-                    Err(err) => (event_id, Some(err)),
-                }
-            })
-            .collect::<Vec<_>>();
-        if lang::all_match(&failed_events, |(_, err)| err.is_none()) {
-            Ok(())
-        } else {
-            let mut errors = vec![];
-            failed_events
-                .into_iter()
-                .filter(|(_, err)| err.is_some())
-                .for_each(|(event_id, opt)| {
-                    let err = opt.unwrap();
-                    errors.push((event_id, err));
-                });
-
-            Err(OutEdgeError::BatchSendFailed(errors))
+        if iter.is_empty() {
+            return Ok(());
         }
+        iter.iter_mut()
+            .for_each(|event| event.set_to_operator_id(to_operator_id));
+
+        self.send(iter).await
+    }
+
+    /// the number of events the queue has room for
+    fn credits(&self) -> usize {
+        self.permits.available_permits()
     }
 }
 
@@ -125,6 +208,19 @@ pub enum OutEdgeError {
     QueueFull,
     QueueClosed,
     BatchSendFailed(Vec<(i64, OutEdgeError)>),
+    SpillFailed(std::io::Error),
+}
+
+impl OutEdgeError {
+    /// the local queue of the operator is full, or some events of a batch are rejected by it
+    pub fn is_queue_full(&self) -> bool {
+        match self {
+            Self::QueueFull => true,
+            Self::SendToRemoteFailed(status) => status.code() == tonic::Code::ResourceExhausted,
+            Self::BatchSendFailed(errors) => errors.iter().any(|(_, err)| err.is_queue_full()),
+            _ => false,
+        }
+    }
 }
 
 impl From<rmp_serde::encode::Error> for OutEdgeError {
@@ -148,17 +244,392 @@ impl Display for OutEdgeError {
             OutEdgeError::BatchSendFailed(errors) => {
                 f.write_fmt(format_args!("Batchly send event failed: [{:?}]", errors))
             }
+            OutEdgeError::SpillFailed(err) => {
+                f.write_fmt(format_args!("Spill events failed: {}", err))
+            }
         }
     }
 }
 
+/// The sender's state of the stream of a remote edge
+#[derive(Default)]
+struct EdgeStream {
+    handshake: Option<EdgeHandshake>,
+    frames_tx: Option<mpsc::Sender<EdgeFrame>>,
+    acks: Option<Streaming<EdgeAck>>,
+    // frames which haven't been acked. They are sent again after reconnecting
+    unacked: VecDeque<EdgeFrame>,
+    // sequence of the last frame
+    sequence: u64,
+    credits: usize,
+}
+
+impl EdgeStream {
+    fn is_connected(&self) -> bool {
+        self.frames_tx.is_some() && self.acks.is_some()
+    }
+
+    fn disconnect(&mut self) {
+        self.frames_tx = None;
+        self.acks = None;
+        self.credits = 0;
+    }
+
+    /// Open the stream and send the handshake. The remote operator acks it with the last sequence it has accepted,
+    /// and the frames after that sequence are sent again
+    async fn connect(&mut self, gateway: &SafeTaskManagerRpcGateway) -> Result<(), tonic::Status> {
+        let (frames_tx, frames_rx) = mpsc::channel(EDGE_FRAME_BUFFER_SIZE);
+        let handshake = EdgeFrame {
+            sequence: 0,
+            frame: self
+                .handshake
+                .clone()
+                .map(|handshake| Frame::Handshake(handshake)),
+        };
+        frames_tx
+            .send(handshake)
+            .await
+            .map_err(|err| tonic::Status::unavailable(err.to_string()))?;
+
+        let mut acks = gateway
+            .open_edge(futures_util::stream::unfold(
+                frames_rx,
+                |mut rx| async move { rx.recv().await.map(|frame| (frame, rx)) },
+            ))
+            .await?;
+        let ack = acks
+            .message()
+            .await?
+            .ok_or_else(|| tonic::Status::unavailable("edge stream is closed"))?;
+
+        self.frames_tx = Some(frames_tx);
+        self.acks = Some(acks);
+        // a new sender continues the sequence of the edge
+        self.sequence = self.sequence.max(ack.sequence);
+        self.receive_ack(ack);
+
+        for frame in self.unacked.clone() {
+            self.push(frame).await?;
+        }
+        Ok(())
+    }
+
+    async fn push(&mut self, frame: EdgeFrame) -> Result<(), tonic::Status> {
+        match self.frames_tx.clone() {
+            Some(frames_tx) => frames_tx
+                .send(frame)
+                .await
+                .map_err(|err| tonic::Status::unavailable(err.to_string())),
+            None => Err(tonic::Status::unavailable("edge stream is closed")),
+        }
+    }
+
+    /// Acks are cumulative. The credits of the ack don't count the events of the frames which are still in flight
+    fn receive_ack(&mut self, ack: EdgeAck) {
+        while self
+            .unacked
+            .front()
+            .map(|frame| frame.sequence <= ack.sequence)
+            .unwrap_or_default()
+        {
+            self.unacked.pop_front();
+        }
+        let in_flight = self
+            .unacked
+            .iter()
+            .map(|frame| match &frame.frame {
+                Some(Frame::Events(event_set)) => event_set.events.len(),
+                _ => 0,
+            })
+            .sum::<usize>();
+        self.credits = (ack.credits as usize).saturating_sub(in_flight);
+    }
+
+    async fn next_ack(&mut self) -> Result<(), tonic::Status> {
+        let ack = match self.acks.as_mut() {
+            Some(acks) => acks.message().await?,
+            None => None,
+        };
+        match ack {
+            Some(ack) => {
+                self.receive_ack(ack);
+                Ok(())
+            }
+            None => Err(tonic::Status::unavailable("edge stream is closed")),
+        }
+    }
+
+    /// receive the acks which have arrived without waiting
+    fn poll_acks(&mut self) -> Result<(), tonic::Status> {
+        loop {
+            let ack = match self.acks.as_mut() {
+                Some(acks) => match acks.message().now_or_never() {
+                    Some(ack) => ack?,
+                    None => return Ok(()),
+                },
+                None => return Ok(()),
+            };
+            match ack {
+                Some(ack) => self.receive_ack(ack),
+                None => return Err(tonic::Status::unavailable("edge stream is closed")),
+            }
+        }
+    }
+
+    async fn send_frame(
+        &mut self,
+        gateway: &SafeTaskManagerRpcGateway,
+        frame: Frame,
+    ) -> Result<(), tonic::Status> {
+        self.sequence += 1;
+        let frame = EdgeFrame {
+            sequence: self.sequence,
+            frame: Some(frame),
+        };
+        self.unacked.push_back(frame.clone());
+        if self.is_connected() {
+            self.push(frame).await
+        } else {
+            // the frame is sent with other unacked frames after connecting
+            self.connect(gateway).await
+        }
+    }
+
+    /// the frames of events and the control frame after them, which are spilled when they can't be sent
+    fn to_frames(&self, events: Vec<KeyedDataEvent>, control: Option<Frame>) -> Vec<Frame> {
+        let handshake = self.handshake.clone().unwrap_or_default();
+        let mut frames = vec![];
+        if !events.is_empty() {
+            frames.push(Frame::Events(KeyedEventSet {
+                events,
+                job_id: handshake.job_id,
+                to_operator_id: handshake.to_operator_id,
+                from_operator_id: handshake.from_operator_id,
+            }));
+        }
+        frames.extend(control);
+        frames
+    }
+
+    /// send events in frames no larger than the credits
+    async fn write_events(
+        &mut self,
+        gateway: &SafeTaskManagerRpcGateway,
+        events: &mut Vec<KeyedDataEvent>,
+    ) -> Result<(), tonic::Status> {
+        let handshake = self.handshake.clone().unwrap_or_default();
+        while !events.is_empty() {
+            if !self.is_connected() {
+                self.connect(gateway).await?;
+            }
+            self.poll_acks()?;
+            while self.credits == 0 {
+                self.next_ack().await?;
+            }
+
+            let size = self.credits.min(events.len());
+            self.credits -= size;
+            let event_set = KeyedEventSet {
+                events: events.drain(..size).collect(),
+                job_id: handshake.job_id.clone(),
+                to_operator_id: handshake.to_operator_id,
+                from_operator_id: handshake.from_operator_id,
+            };
+            self.send_frame(gateway, Frame::Events(event_set)).await?;
+        }
+        Ok(())
+    }
+}
+
+/// The out-edge to an operator of another TaskManager. Frames are sent in order on a long-lived stream of the edge,
+/// which is reconnected and resumed from the last acked frame on failure.
+///
+/// If the downstream is still unreachable after the retries, the frames are spilled into a bounded [`SpillQueue`] and replayed in order by a background task.
+/// Later frames are queued behind the spilled ones until the queue is drained, and writers wait when it is full.
 pub struct RemoteOutEdge {
+    inner: Arc<RemoteEdgeInner>,
+}
+
+struct RemoteEdgeInner {
     gateway: SafeTaskManagerRpcGateway,
+    stream: Mutex<EdgeStream>,
+    // keep the order of concurrent writes while they're spilled
+    writes: Mutex<()>,
+    spill: Mutex<EdgeSpill>,
+    spill_stats: Arc<SpillStats>,
+    // notified when a spilled frame is replayed
+    spill_room: Notify,
+}
+
+struct EdgeSpill {
+    queue: SpillQueue,
+    replaying: bool,
 }
 
 impl RemoteOutEdge {
     pub fn new(gateway: SafeTaskManagerRpcGateway) -> Self {
-        Self { gateway }
+        Self::with_spill_queue(gateway, SpillQueue::from_env())
+    }
+
+    pub fn with_spill_queue(gateway: SafeTaskManagerRpcGateway, queue: SpillQueue) -> Self {
+        Self {
+            inner: Arc::new(RemoteEdgeInner {
+                gateway,
+                stream: Default::default(),
+                writes: Default::default(),
+                spill_stats: queue.stats(),
+                spill: Mutex::new(EdgeSpill {
+                    queue,
+                    replaying: false,
+                }),
+                spill_room: Notify::new(),
+            }),
+        }
+    }
+
+    /// The stream is opened by the first events, which identify the edge. A control frame is sent after the events
+    async fn send(
+        &self,
+        handshake: Option<EdgeHandshake>,
+        mut events: Vec<KeyedDataEvent>,
+        mut control: Option<Frame>,
+    ) -> Result<(), OutEdgeError> {
+        let _writes = self.inner.writes.lock().await;
+        let mut stream = self.inner.stream.lock().await;
+        if stream.handshake.is_none() {
+            match handshake {
+                Some(handshake) => stream.handshake = Some(handshake),
+                None => return Ok(()),
+            }
+        }
+
+        // the earlier frames are waiting to be replayed
+        if !self.inner.spill.lock().await.queue.is_empty() {
+            let frames = stream.to_frames(events, control);
+            drop(stream);
+            return self.spill(frames).await;
+        }
+
+        let mut wait = MIN_RECONNECT_WAIT;
+        let mut attempts = 0;
+        loop {
+            let result = match stream.write_events(&self.inner.gateway, &mut events).await {
+                Ok(_) => match control.take() {
+                    Some(frame) => stream.send_frame(&self.inner.gateway, frame).await,
+                    None => Ok(()),
+                },
+                Err(status) => Err(status),
+            };
+            match result {
+                Ok(_) => return Ok(()),
+                Err(status) => {
+                    stream.disconnect();
+                    attempts += 1;
+                    if attempts >= MAX_RECONNECT_ATTEMPTS {
+                        tracing::warn!("downstream is unreachable, spilling events: {}", status);
+                        let frames = stream.to_frames(events, control);
+                        drop(stream);
+                        return self.spill(frames).await;
+                    }
+                    tracing::warn!("edge stream is broken, reconnecting: {}", status);
+                    tokio::time::sleep(wait).await;
+                    wait = (wait * 2).min(MAX_RECONNECT_WAIT);
+                }
+            }
+        }
+    }
+
+    /// push frames into the spill queue in order, waiting for the replay when it's full
+    async fn spill(&self, frames: Vec<Frame>) -> Result<(), OutEdgeError> {
+        for mut frame in frames {
+            loop {
+                let mut spill = self.inner.spill.lock().await;
+                let result = spill.queue.try_push(frame).await;
+                if !spill.replaying {
+                    spill.replaying = true;
+                    tokio::spawn(replay(Arc::downgrade(&self.inner)));
+                }
+                match result {
+                    Ok(_) => break,
+                    Err(SpillError::Full(rejected)) => {
+                        frame = rejected;
+                        drop(spill);
+                        self.inner.spill_room.notified().await;
+                    }
+                    Err(SpillError::Io(err)) => return Err(OutEdgeError::SpillFailed(err)),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RemoteEdgeInner {
+    /// Send the spilled frames in order. It returns once the queue is drained, or when the stream is broken again
+    async fn replay_spilled(&self) -> Result<(), tonic::Status> {
+        let mut stream = self.stream.lock().await;
+        loop {
+            let frame = {
+                let mut spill = self.spill.lock().await;
+                match spill.queue.pop_front().await {
+                    Ok(Some(frame)) => frame,
+                    Ok(None) => {
+                        spill.replaying = false;
+                        return Ok(());
+                    }
+                    Err(err) => {
+                        tracing::error!("read spilled frames failed, they are dropped: {}", err);
+                        if let Err(err) = spill.queue.clear().await {
+                            tracing::error!("clear spill queue failed: {}", err);
+                        }
+                        spill.replaying = false;
+                        return Ok(());
+                    }
+                }
+            };
+            self.spill_room.notify_one();
+
+            // a frame which has been written is kept in the unacked frames of the stream, only the rest are put back
+            let result = match frame {
+                Frame::Events(mut event_set) => {
+                    let result = stream
+                        .write_events(&self.gateway, &mut event_set.events)
+                        .await;
+                    if result.is_err() && !event_set.events.is_empty() {
+                        self.spill
+                            .lock()
+                            .await
+                            .queue
+                            .push_front(Frame::Events(event_set));
+                    }
+                    result
+                }
+                frame => stream.send_frame(&self.gateway, frame).await,
+            };
+            if let Err(status) = result {
+                stream.disconnect();
+                return Err(status);
+            }
+        }
+    }
+}
+
+/// replay the spilled frames until the queue is drained or the edge is dropped
+async fn replay(inner: Weak<RemoteEdgeInner>) {
+    let mut wait = MIN_RECONNECT_WAIT;
+    loop {
+        tokio::time::sleep(wait).await;
+        let inner = match inner.upgrade() {
+            Some(inner) => inner,
+            None => return,
+        };
+        match inner.replay_spilled().await {
+            Ok(_) => return,
+            Err(status) => {
+                tracing::warn!("downstream is still unreachable: {}", status);
+                wait = (wait * 2).min(MAX_RECONNECT_WAIT);
+            }
+        }
     }
 }
 
@@ -171,13 +642,22 @@ impl OutEdge for RemoteOutEdge {
 
     async fn write(&self, val: LocalEvent) -> Result<(), OutEdgeError> {
         match val {
-            LocalEvent::Terminate { .. } => Ok(()),
-            LocalEvent::KeyedDataStreamEvent(event) => self
-                .gateway
-                .send_event_to_operator(event)
+            LocalEvent::Terminate { event_time, .. } => {
+                self.send(
+                    None,
+                    vec![],
+                    Some(Frame::Terminate(EdgeTerminate { event_time })),
+                )
                 .await
-                .map(|_| ())
-                .map_err(|err| OutEdgeError::SendToRemoteFailed(err)),
+            }
+            LocalEvent::KeyedDataStreamEvent(event) => {
+                let handshake = EdgeHandshake {
+                    job_id: event.job_id.clone(),
+                    from_operator_id: event.from_operator_id,
+                    to_operator_id: event.to_operator_id,
+                };
+                self.send(Some(handshake), vec![event], None).await
+            }
         }
     }
 
@@ -188,24 +668,214 @@ impl OutEdge for RemoteOutEdge {
         from_operator_id: ExecutorId,
         iter: Vec<Self::Output>,
     ) -> Result<(), OutEdgeError> {
+        let mut control = None;
         let events = iter
             .into_iter()
-            .map(|event| match event {
-                LocalEvent::KeyedDataStreamEvent(e) => e,
-                LocalEvent::Terminate { .. } => KeyedDataEvent::default(),
+            .filter_map(|event| match event {
+                LocalEvent::KeyedDataStreamEvent(e) => Some(e),
+                LocalEvent::Terminate { event_time, .. } => {
+                    control = Some(Frame::Terminate(EdgeTerminate { event_time }));
+                    None
+                }
             })
             .collect();
 
-        self.gateway
-            .batch_send_events_to_operator(KeyedEventSet {
-                events,
-                job_id: job_id.clone(),
-                to_operator_id,
-                from_operator_id,
-            })
+        let handshake = EdgeHandshake {
+            job_id: job_id.clone(),
+            from_operator_id,
+            to_operator_id,
+        };
+        self.send(Some(handshake), events, control).await
+    }
+
+    fn credits(&self) -> usize {
+        self.inner
+            .stream
+            .try_lock()
+            .map(|stream| stream.credits)
+            .unwrap_or_default()
+    }
+
+    fn spill_stats(&self) -> Option<Arc<SpillStats>> {
+        Some(self.inner.spill_stats.clone())
+    }
+
+    /// The spilled frames are replayed and all frames are acked. Frames which are still unacked after reconnecting are sent again
+    async fn flush(&self) -> Result<(), OutEdgeError> {
+        let mut wait = MIN_RECONNECT_WAIT;
+        loop {
+            if self.inner.spill.lock().await.queue.is_empty() {
+                let _writes = self.inner.writes.lock().await;
+                let mut stream = self.inner.stream.lock().await;
+                if stream.unacked.is_empty() {
+                    return Ok(());
+                }
+                let result = if stream.is_connected() {
+                    stream.next_ack().await
+                } else {
+                    stream.connect(&self.inner.gateway).await
+                };
+                match result {
+                    Ok(_) => {
+                        wait = MIN_RECONNECT_WAIT;
+                        continue;
+                    }
+                    Err(status) => {
+                        stream.disconnect();
+                        tracing::warn!("flush edge stream failed, reconnecting: {}", status);
+                    }
+                }
+            }
+            tokio::time::sleep(wait).await;
+            wait = (wait * 2).min(MAX_RECONNECT_WAIT);
+        }
+    }
+}
+
+/// The receiving endpoint of the streams of remote edges to an operator. Frames of each edge are written into the in-edge of the operator in order,
+/// and the last accepted sequence of each edge is kept, so that a reconnected edge can resume from it
+#[derive(Clone)]
+pub struct RemoteInEdge {
+    in_edge: LocalOutEdge<LocalEvent>,
+    sequences: Arc<std::sync::Mutex<BTreeMap<ExecutorId, u64>>>,
+}
+
+impl RemoteInEdge {
+    pub fn new(in_edge: LocalOutEdge<LocalEvent>) -> Self {
+        Self {
+            in_edge,
+            sequences: Default::default(),
+        }
+    }
+
+    /// An ack never grants no credit. If the operator has no room, it's delayed until the operator has
+    async fn ack(&self, sequence: u64) -> Result<EdgeAck, OutEdgeError> {
+        if self.in_edge.credits() == 0 {
+            self.in_edge.wait_for_credits().await?;
+        }
+        Ok(EdgeAck {
+            sequence,
+            credits: u32::try_from(self.in_edge.credits().max(1)).unwrap_or(u32::MAX),
+        })
+    }
+
+    async fn receive_handshake(&self, handshake: &EdgeHandshake) -> Result<EdgeAck, OutEdgeError> {
+        self.ack(self.last_sequence(handshake.from_operator_id))
+            .await
+    }
+
+    /// the last sequence accepted from the upstream operator
+    fn last_sequence(&self, from_operator_id: ExecutorId) -> u64 {
+        self.sequences
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(&from_operator_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Accept the frame if it hasn't been accepted before reconnecting. The sequence advances before the frame is written,
+    /// so that the lock isn't held while waiting for room of the operator, and a reconnected stream resumes after the frame being written
+    fn accept(&self, from_operator_id: ExecutorId, sequence: u64) -> bool {
+        let mut sequences = self.sequences.lock().unwrap_or_else(|err| err.into_inner());
+        let last_sequence = sequences.entry(from_operator_id).or_default();
+        if sequence > *last_sequence {
+            *last_sequence = sequence;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// write the frame into the in-edge, unless it has been accepted before reconnecting
+    async fn receive(
+        &self,
+        handshake: &EdgeHandshake,
+        frame: EdgeFrame,
+    ) -> Result<EdgeAck, OutEdgeError> {
+        if self.accept(handshake.from_operator_id, frame.sequence) {
+            match frame.frame {
+                Some(Frame::Events(event_set)) => {
+                    self.in_edge
+                        .batch_write(
+                            &event_set.job_id,
+                            handshake.to_operator_id,
+                            handshake.from_operator_id,
+                            event_set
+                                .events
+                                .into_iter()
+                                .map(|event| LocalEvent::KeyedDataStreamEvent(event))
+                                .collect(),
+                        )
+                        .await?
+                }
+                Some(Frame::Terminate(terminate)) => {
+                    self.in_edge
+                        .write(LocalEvent::Terminate {
+                            job_id: handshake.job_id.clone().unwrap_or_default(),
+                            to: handshake.to_operator_id,
+                            event_time: terminate.event_time,
+                        })
+                        .await?
+                }
+                Some(Frame::Handshake(_)) | None => {}
+            }
+        }
+        self.ack(self.last_sequence(handshake.from_operator_id))
             .await
-            .map(|_| {})
-            .map_err(|err| OutEdgeError::SendToRemoteFailed(err))
+    }
+
+    /// Serve the stream of an edge whose handshake has been received. It returns the stream of acks
+    pub fn serve(
+        self,
+        handshake: EdgeHandshake,
+        mut frames: Streaming<EdgeFrame>,
+    ) -> BoxStream<EdgeAck> {
+        // acks are cumulative, so only the latest one should be sent
+        let (acks_tx, acks_rx) = watch::channel(EdgeAck::default());
+        tokio::spawn(async move {
+            let mut ack = self.receive_handshake(&handshake).await;
+            loop {
+                match ack {
+                    Ok(ack) => {
+                        if acks_tx.send(ack).is_err() {
+                            break;
+                        }
+                    }
+                    Err(err) => {
+                        tracing::error!(
+                            "receive frame of edge from operator {} to {} failed: {}",
+                            handshake.from_operator_id,
+                            handshake.to_operator_id,
+                            err
+                        );
+                        break;
+                    }
+                }
+                ack = match frames.message().await {
+                    Ok(Some(frame)) => self.receive(&handshake, frame).await,
+                    Ok(None) => break,
+                    Err(status) => {
+                        tracing::warn!(
+                            "edge stream from operator {} to {} is broken: {}",
+                            handshake.from_operator_id,
+                            handshake.to_operator_id,
+                            status
+                        );
+                        break;
+                    }
+                };
+            }
+        });
+
+        Box::pin(futures_util::stream::unfold(
+            acks_rx,
+            |mut acks_rx| async move {
+                acks_rx.changed().await.ok()?;
+                let ack = acks_rx.borrow().clone();
+                Some((Ok(ack), acks_rx))
+            },
+        ))
     }
 }
 
@@ -220,13 +890,18 @@ pub trait InEdge: Send + Sync + Unpin {
 }
 
 pub struct LocalInEdge<T> {
-    rx: Receiver<bytes::Bytes>,
-    _data_type: PhantomData<T>,
+    rx: Receiver<Vec<T>>,
+    // events of the last received message which haven't been polled
+    buffered: VecDeque<T>,
+    permits: Arc<Semaphore>,
+    capacity: usize,
 }
 
 impl<T> Drop for LocalInEdge<T> {
     fn drop(&mut self) {
-        self.rx.close()
+        self.rx.close();
+        // wake up the writers waiting for room
+        self.permits.close()
     }
 }
 
@@ -239,48 +914,154 @@ impl<T: StreamEvent> InEdge for LocalInEdge<T> {
     type Output = T;
 
     async fn next(&mut self) -> Option<T> {
-        self.rx.recv().await.and_then(|buf| {
-            T::from_slice(&buf)
-                .map_err(|err| tracing::error!("{:?}", err))
-                .ok()
-        })
+        futures_util::future::poll_fn(|cx| self.poll_next(cx)).await
     }
 
     fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Self::Output>> {
-        self.rx.poll_recv(cx).map(|r| {
-            r.and_then(|data| {
-                T::from_slice(&data)
-                    .map_err(|err| tracing::error!("deserialize event failed: {}", err))
-                    .ok()
-            })
-        })
+        loop {
+            if let Some(event) = self.buffered.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+            match ready!(self.rx.poll_recv(cx)) {
+                Some(events) => {
+                    self.permits.add_permits(events.len().min(self.capacity));
+                    self.buffered.extend(events)
+                }
+                None => return Poll::Ready(None),
+            }
+        }
     }
 }
 
-impl<T> LocalInEdge<T> {
-    pub fn new(rx: Receiver<bytes::Bytes>) -> Self {
+/// Accumulates the events of out-edges, so that each out-edge writes them in batches.
+/// A batch is flushed once it has `max_batch_size` events, or `max_linger` after its first event is buffered.
+/// The executor polls the linger on each event it processes, and flushes all batches before it terminates
+pub struct EdgeBatcher {
+    max_batch_size: usize,
+    max_linger: Duration,
+    batches: BTreeMap<ExecutorId, Vec<LocalEvent>>,
+    linger: Option<Pin<Box<Sleep>>>,
+}
+
+impl EdgeBatcher {
+    /// events are flushed immediately if there's no [EdgeBatch]
+    pub fn new(conf: Option<&EdgeBatch>) -> Self {
         Self {
-            rx,
-            _data_type: PhantomData,
+            max_batch_size: conf
+                .map(|conf| conf.max_batch_size as usize)
+                .unwrap_or_default()
+                .max(1),
+            max_linger: Duration::from_millis(
+                conf.map(|conf| conf.max_linger_millis).unwrap_or_default(),
+            ),
+            batches: Default::default(),
+            linger: None,
+        }
+    }
+
+    /// buffer the events of the out-edge. It returns the batches which are full
+    pub fn append(
+        &mut self,
+        to_operator_id: ExecutorId,
+        events: Vec<LocalEvent>,
+    ) -> Vec<(ExecutorId, Vec<LocalEvent>)> {
+        self.batches
+            .entry(to_operator_id)
+            .or_default()
+            .extend(events);
+
+        let full = self
+            .batches
+            .iter()
+            .filter(|(_, batch)| batch.len() >= self.max_batch_size)
+            .map(|(to_operator_id, _)| *to_operator_id)
+            .collect::<Vec<_>>();
+        let batches = full
+            .into_iter()
+            .filter_map(|to_operator_id| {
+                self.batches
+                    .remove(&to_operator_id)
+                    .map(|batch| (to_operator_id, batch))
+            })
+            .collect();
+        if self.batches.is_empty() {
+            self.linger = None;
+        } else if self.linger.is_none() {
+            self.linger = Some(Box::pin(tokio::time::sleep(self.max_linger)));
+        }
+        batches
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.batches.is_empty()
+    }
+
+    /// take all batches, no matter whether they are full
+    pub fn flush(&mut self) -> Vec<(ExecutorId, Vec<LocalEvent>)> {
+        self.linger = None;
+        std::mem::take(&mut self.batches).into_iter().collect()
+    }
+
+    /// It's ready with all batches once the first buffered event has lingered for `max_linger`
+    pub fn poll_linger(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Vec<(ExecutorId, Vec<LocalEvent>)>> {
+        match self.linger.as_mut() {
+            Some(linger) => {
+                ready!(linger.as_mut().poll(cx));
+                Poll::Ready(self.flush())
+            }
+            None => Poll::Pending,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use common::event::LocalEvent;
-    use proto::common::KeyedDataEvent;
+    use proto::{
+        common::{EdgeBatch, KeyedDataEvent, KeyedEventSet},
+        taskmanager::{edge_frame::Frame, EdgeFrame, EdgeHandshake, EdgeTerminate},
+    };
+
+    use crate::edge::InEdge;
+
+    use super::{new_local_edge, EdgeBatcher, LocalInEdge, OutEdge, RemoteInEdge};
 
-    use crate::{edge::InEdge, new_event_channel};
+    fn events_frame(sequence: u64, values: &[u8]) -> EdgeFrame {
+        EdgeFrame {
+            sequence,
+            frame: Some(Frame::Events(KeyedEventSet {
+                events: values
+                    .iter()
+                    .map(|value| KeyedDataEvent {
+                        from_operator_id: 1,
+                        to_operator_id: 2,
+                        key: Some(proto::common::Entry {
+                            value: vec![*value].into(),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            })),
+        }
+    }
 
-    use super::{LocalInEdge, LocalOutEdge, OutEdge};
+    async fn next_value(in_edge: &mut LocalInEdge<LocalEvent>) -> u8 {
+        match in_edge.next().await {
+            Some(LocalEvent::KeyedDataStreamEvent(event)) => event.key.unwrap().value[0],
+            _ => panic!("unexpected event"),
+        }
+    }
 
     #[tokio::test]
     async fn test_local_edge_success() {
-        let (tx, rx) = new_event_channel(10);
-
-        let mut in_edge = LocalInEdge::<LocalEvent>::new(rx);
-        let out_edge = LocalOutEdge::<LocalEvent>::new(tx);
+        let (out_edge, mut in_edge) = new_local_edge::<LocalEvent>(10);
 
         let result = out_edge
             .write(LocalEvent::KeyedDataStreamEvent(KeyedDataEvent::default()))
@@ -290,4 +1071,242 @@ mod tests {
         let opt = in_edge.next().await;
         assert!(opt.is_some());
     }
+
+    #[tokio::test]
+    async fn test_local_edge_try_write_all() {
+        let (out_edge, mut in_edge) = new_local_edge::<LocalEvent>(4);
+        let event = || LocalEvent::KeyedDataStreamEvent(KeyedDataEvent::default());
+        assert_eq!(out_edge.credits(), 4);
+
+        // all events are written in one message, and credits are counted in events
+        assert!(out_edge
+            .try_write_all(vec![event(), event(), event()])
+            .is_ok());
+        assert_eq!(out_edge.credits(), 1);
+        assert!(out_edge.try_write(event()).is_ok());
+        assert_eq!(out_edge.credits(), 0);
+
+        // no event is written if the queue has no room for all of them
+        let result = out_edge.try_write_all(vec![event(), event()]);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().is_queue_full());
+
+        for _ in 0..4 {
+            assert!(in_edge.next().await.is_some());
+        }
+        assert_eq!(out_edge.credits(), 4);
+
+        // a message larger than the queue takes all of its room
+        assert!(out_edge
+            .try_write_all((0..5).map(|_| event()).collect())
+            .is_ok());
+        assert_eq!(out_edge.credits(), 0);
+        for _ in 0..5 {
+            assert!(in_edge.next().await.is_some());
+        }
+        assert_eq!(out_edge.credits(), 4);
+
+        // writers waiting for room fail once the in-edge is dropped
+        assert!(out_edge
+            .try_write_all((0..4).map(|_| event()).collect())
+            .is_ok());
+        let (result, _) = tokio::join!(out_edge.write(event()), async move { drop(in_edge) });
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_local_edge_batch_write_waits_for_credits() {
+        let (out_edge, mut in_edge) = new_local_edge::<LocalEvent>(1);
+        let events = (0..3)
+            .map(|_| LocalEvent::KeyedDataStreamEvent(KeyedDataEvent::default()))
+            .collect::<Vec<_>>();
+
+        let (result, received) = tokio::join!(out_edge.batch_write(&None, 1, 0, events), async {
+            let mut received = vec![];
+            for _ in 0..3 {
+                received.push(in_edge.next().await);
+            }
+            received
+        });
+        assert!(result.is_ok());
+        assert_eq!(received.len(), 3);
+        received.into_iter().for_each(|event| match event {
+            Some(LocalEvent::KeyedDataStreamEvent(event)) => assert_eq!(event.to_operator_id, 1),
+            _ => panic!("unexpected event"),
+        });
+    }
+
+    #[tokio::test]
+    async fn test_edge_batcher_flush_full_batches() {
+        let event = || LocalEvent::KeyedDataStreamEvent(KeyedDataEvent::default());
+        let mut batcher = EdgeBatcher::new(Some(&EdgeBatch {
+            max_batch_size: 3,
+            max_linger_millis: 1000,
+        }));
+
+        assert!(batcher.is_empty());
+        assert!(batcher.append(1, vec![event(), event()]).is_empty());
+        assert!(batcher.append(2, vec![event()]).is_empty());
+        let batches = batcher.append(1, vec![event()]);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].0, 1);
+        assert_eq!(batches[0].1.len(), 3);
+
+        let batches = batcher.flush();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].0, 2);
+        assert!(batcher.is_empty());
+        assert!(batcher.flush().is_empty());
+
+        // events are flushed immediately without batch configs
+        let mut batcher = EdgeBatcher::new(None);
+        assert_eq!(batcher.append(1, vec![event()]).len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_edge_batcher_linger() {
+        let mut batcher = EdgeBatcher::new(Some(&EdgeBatch {
+            max_batch_size: 100,
+            max_linger_millis: 10,
+        }));
+        assert!(batcher
+            .append(
+                1,
+                vec![LocalEvent::KeyedDataStreamEvent(KeyedDataEvent::default())]
+            )
+            .is_empty());
+
+        let start = tokio::time::Instant::now();
+        let batches = futures_util::future::poll_fn(|cx| batcher.poll_linger(cx)).await;
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].1.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_remote_in_edge_dedup_frames() {
+        let (out_edge, mut in_edge) = new_local_edge::<LocalEvent>(10);
+        let remote_in_edge = RemoteInEdge::new(out_edge);
+        let handshake = EdgeHandshake {
+            from_operator_id: 1,
+            to_operator_id: 2,
+            ..Default::default()
+        };
+
+        let ack = remote_in_edge
+            .receive(&handshake, events_frame(1, &[1, 2]))
+            .await
+            .unwrap();
+        assert_eq!(ack.sequence, 1);
+        // a duplicated frame is acked again but not written
+        let ack = remote_in_edge
+            .receive(&handshake, events_frame(1, &[1, 2]))
+            .await
+            .unwrap();
+        assert_eq!(ack.sequence, 1);
+        let ack = remote_in_edge
+            .receive(&handshake, events_frame(2, &[3]))
+            .await
+            .unwrap();
+        assert_eq!(ack.sequence, 2);
+
+        for value in [1, 2, 3] {
+            assert_eq!(next_value(&mut in_edge).await, value);
+        }
+        assert!(futures_util::FutureExt::now_or_never(in_edge.next()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_remote_in_edge_resume_after_reconnecting() {
+        let (out_edge, mut in_edge) = new_local_edge::<LocalEvent>(10);
+        let remote_in_edge = RemoteInEdge::new(out_edge);
+        let handshake = EdgeHandshake {
+            from_operator_id: 1,
+            to_operator_id: 2,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            remote_in_edge
+                .receive_handshake(&handshake)
+                .await
+                .unwrap()
+                .sequence,
+            0
+        );
+        for sequence in 1..=3 {
+            remote_in_edge
+                .receive(&handshake, events_frame(sequence, &[sequence as u8]))
+                .await
+                .unwrap();
+        }
+
+        // the upstream reconnects without having received the ack of the 3rd frame
+        let ack = remote_in_edge.receive_handshake(&handshake).await.unwrap();
+        assert_eq!(ack.sequence, 3);
+        assert!(ack.credits > 0);
+        // frames it resends are skipped, and the stream resumes after the last accepted one
+        for sequence in 3..=4 {
+            let ack = remote_in_edge
+                .receive(&handshake, events_frame(sequence, &[sequence as u8]))
+                .await
+                .unwrap();
+            assert_eq!(ack.sequence, sequence);
+        }
+        remote_in_edge
+            .receive(
+                &handshake,
+                EdgeFrame {
+                    sequence: 5,
+                    frame: Some(Frame::Terminate(EdgeTerminate { event_time: 10 })),
+                },
+            )
+            .await
+            .unwrap();
+
+        for value in 1..=4 {
+            assert_eq!(next_value(&mut in_edge).await, value);
+        }
+        assert!(matches!(
+            in_edge.next().await,
+            Some(LocalEvent::Terminate {
+                to: 2,
+                event_time: 10,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_remote_in_edge_releases_sequences_while_writing() {
+        let (out_edge, mut in_edge) = new_local_edge::<LocalEvent>(1);
+        let remote_in_edge = RemoteInEdge::new(out_edge);
+        let handshake = EdgeHandshake {
+            from_operator_id: 1,
+            to_operator_id: 2,
+            ..Default::default()
+        };
+
+        remote_in_edge
+            .in_edge
+            .try_write(LocalEvent::KeyedDataStreamEvent(Default::default()))
+            .unwrap();
+        // the write waits for room of the operator
+        let receiving = tokio::spawn({
+            let remote_in_edge = remote_in_edge.clone();
+            let handshake = handshake.clone();
+            async move {
+                remote_in_edge
+                    .receive(&handshake, events_frame(1, &[1]))
+                    .await
+            }
+        });
+        tokio::task::yield_now().await;
+        assert!(!receiving.is_finished());
+        assert_eq!(remote_in_edge.last_sequence(1), 1);
+
+        assert!(in_edge.next().await.is_some());
+        assert_eq!(next_value(&mut in_edge).await, 1);
+        assert_eq!(receiving.await.unwrap().unwrap().sequence, 1);
+    }
 }
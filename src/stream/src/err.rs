@@ -1,7 +1,10 @@
 use std::fmt::{self, Display};
 
 use common::{
-    err::{KafkaException, RedisException},
+    err::{
+        FileException, HttpException, KafkaException, MqttException, ParquetException,
+        RedisException,
+    },
     event::KafkaEventError,
     types::NodeIdx,
 };
@@ -16,6 +19,10 @@ pub enum ErrorKind {
     SqlExecutionFailed,
     EventSentToRemoteFailed,
     RedisSinkFailed,
+    MqttPublishFailed,
+    FileWriteFailed,
+    HttpRequestFailed,
+    ParquetWriteFailed,
 }
 
 #[derive(Clone, Debug)]
@@ -69,6 +76,42 @@ impl From<RedisException> for SinkException {
     }
 }
 
+impl From<MqttException> for SinkException {
+    fn from(err: MqttException) -> Self {
+        Self {
+            kind: ErrorKind::MqttPublishFailed,
+            msg: format!("{:?}", err),
+        }
+    }
+}
+
+impl From<FileException> for SinkException {
+    fn from(err: FileException) -> Self {
+        Self {
+            kind: ErrorKind::FileWriteFailed,
+            msg: format!("{:?}", err),
+        }
+    }
+}
+
+impl From<HttpException> for SinkException {
+    fn from(err: HttpException) -> Self {
+        Self {
+            kind: ErrorKind::HttpRequestFailed,
+            msg: format!("{:?}", err),
+        }
+    }
+}
+
+impl From<ParquetException> for SinkException {
+    fn from(err: ParquetException) -> Self {
+        Self {
+            kind: ErrorKind::ParquetWriteFailed,
+            msg: format!("{:?}", err),
+        }
+    }
+}
+
 impl From<KafkaEventError> for SinkException {
     fn from(err: KafkaEventError) -> Self {
         Self {
@@ -2,7 +2,9 @@ pub mod connector;
 mod dataflow;
 pub mod edge;
 pub mod err;
-mod state;
+pub mod partition;
+pub mod spill;
+pub mod state;
 pub mod task;
 mod v8_runtime;
 
@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+
+use common::{types::ExecutorId, utils::key_groups};
+use proto::common::{KeyedDataEvent, OperatorInfo};
+
+/// How the events of an operator are distributed over the parallel instances of a downstream operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Partitioning {
+    /// events are sent to the instance which owns the key group of their keys.
+    /// It's used after a KeyBy operator, and before an operator with keyed state even if the keys are set further upstream
+    Hash,
+    /// each instance sends events to the downstream instance of the same index. Both operators have the same parallelism
+    Forward,
+    /// events are sent to the downstream instances in turn
+    Rebalance,
+}
+
+struct InstanceGroup {
+    partitioning: Partitioning,
+    // the instances of a downstream operator, ordered by their indexes
+    instances: Vec<ExecutorId>,
+    next: usize,
+}
+
+impl InstanceGroup {
+    fn select(&mut self, index: u32, event: &KeyedDataEvent) -> ExecutorId {
+        let parallelism = self.instances.len();
+        let position = match self.partitioning {
+            Partitioning::Hash => {
+                let key = event
+                    .key
+                    .as_ref()
+                    .map(|key| key.value.as_ref())
+                    .unwrap_or_default();
+                key_groups::instance_of(key_groups::key_group(key), parallelism as u32) as usize
+            }
+            Partitioning::Forward => index as usize,
+            Partitioning::Rebalance => {
+                self.next = (self.next + 1) % parallelism;
+                self.next
+            }
+        };
+        self.instances[position % parallelism]
+    }
+}
+
+/// Routes the events of an executor to one instance of each downstream operator.
+/// The out edges which the partitioner doesn't know receive every event
+#[derive(Default)]
+pub struct OutEdgePartitioner {
+    // index of the instance of the executor
+    index: u32,
+    // the downstream instances, keyed by their operators
+    groups: BTreeMap<ExecutorId, InstanceGroup>,
+    // the operators of the downstream instances
+    operators: BTreeMap<ExecutorId, ExecutorId>,
+}
+
+impl OutEdgePartitioner {
+    /// `operator` is the one whose results are written to the out edges
+    pub fn new<'a>(
+        operator: &OperatorInfo,
+        downstreams: impl Iterator<Item = &'a OperatorInfo>,
+    ) -> Self {
+        let mut partitioner = Self {
+            index: operator.get_instance_index(),
+            ..Default::default()
+        };
+        let mut instances = BTreeMap::<ExecutorId, Vec<&OperatorInfo>>::new();
+        downstreams.for_each(|downstream| {
+            let logical_operator_id = downstream.get_logical_operator_id();
+            partitioner
+                .operators
+                .insert(downstream.operator_id, logical_operator_id);
+            instances
+                .entry(logical_operator_id)
+                .or_default()
+                .push(downstream);
+        });
+
+        partitioner.groups = instances
+            .into_iter()
+            .map(|(logical_operator_id, mut downstreams)| {
+                downstreams.sort_by_key(|downstream| downstream.get_instance_index());
+                // instances of an operator with keyed state only hold the key groups they own
+                let partitioning = if operator.has_key_by()
+                    || downstreams
+                        .iter()
+                        .any(|downstream| downstream.has_keyed_state())
+                {
+                    Partitioning::Hash
+                } else if operator.get_parallelism() as usize == downstreams.len() {
+                    Partitioning::Forward
+                } else {
+                    Partitioning::Rebalance
+                };
+                (
+                    logical_operator_id,
+                    InstanceGroup {
+                        partitioning,
+                        instances: downstreams
+                            .iter()
+                            .map(|downstream| downstream.operator_id)
+                            .collect(),
+                        next: 0,
+                    },
+                )
+            })
+            .collect();
+        partitioner
+    }
+
+    /// the out edges which the event is written to
+    pub fn route<'a>(
+        &mut self,
+        event: &KeyedDataEvent,
+        out_edges: impl Iterator<Item = &'a ExecutorId>,
+    ) -> Vec<ExecutorId> {
+        let index = self.index;
+        let selected = self
+            .groups
+            .iter_mut()
+            .map(|(logical_operator_id, group)| (*logical_operator_id, group.select(index, event)))
+            .collect::<BTreeMap<_, _>>();
+
+        out_edges
+            .filter(|executor_id| match self.operators.get(executor_id) {
+                Some(logical_operator_id) => selected.get(logical_operator_id) == Some(executor_id),
+                None => true,
+            })
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use common::utils::key_groups;
+    use proto::common::{
+        key_by, mapper, operator_info::Details, reducer, Entry, Func, KeyBy, KeyedDataEvent,
+        Mapper, OperatorInfo, OperatorInstance, Reducer,
+    };
+
+    use super::OutEdgePartitioner;
+
+    fn instance(
+        operator_id: u32,
+        logical_operator_id: u32,
+        index: u32,
+        parallelism: u32,
+        details: Details,
+    ) -> OperatorInfo {
+        OperatorInfo {
+            operator_id,
+            parallelism,
+            instance: Some(OperatorInstance {
+                operator_id: logical_operator_id,
+                index,
+            }),
+            details: Some(details),
+            ..Default::default()
+        }
+    }
+
+    fn mapper() -> Details {
+        Details::Mapper(Mapper {
+            value: Some(mapper::Value::Func(Func {
+                function: "function _operator_map_process(v) { return v }".to_string(),
+            })),
+        })
+    }
+
+    fn keyed_event(key: &str) -> KeyedDataEvent {
+        KeyedDataEvent {
+            key: Some(Entry {
+                value: key.as_bytes().to_vec().into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_hash_partitioning() {
+        let key_by = instance(
+            1,
+            1,
+            0,
+            1,
+            Details::KeyBy(KeyBy {
+                value: Some(key_by::Value::Func(Func {
+                    function: "function _operator_keyBy_process(v) { return v }".to_string(),
+                })),
+            }),
+        );
+        let downstreams = vec![
+            instance(2, 2, 0, 2, mapper()),
+            instance(5, 2, 1, 2, mapper()),
+            instance(3, 3, 0, 1, mapper()),
+        ];
+        let out_edges = vec![2, 3, 4, 5];
+        let mut partitioner = OutEdgePartitioner::new(&key_by, downstreams.iter());
+
+        let mut routes = BTreeMap::new();
+        (0..100).for_each(|i| {
+            let event = keyed_event(&format!("key-{}", i));
+            let route = partitioner.route(&event, out_edges.iter());
+            // one instance of each operator, and the edge which isn't partitioned
+            assert_eq!(route.len(), 3);
+            assert!(route.contains(&3));
+            assert!(route.contains(&4));
+            // events of a key always go to the same instance
+            assert_eq!(partitioner.route(&event, out_edges.iter()), route);
+            routes.insert(i, route);
+        });
+        assert!(routes.values().any(|route| route.contains(&2)));
+        assert!(routes.values().any(|route| route.contains(&5)));
+    }
+
+    #[test]
+    fn test_hash_partitioning_into_keyed_state() {
+        // KeyBy(p=1) -> Mapper(p=1) -> Reducer(p=2): the mapper keeps the keys of the events
+        let upstream = instance(2, 2, 0, 1, mapper());
+        let reducer = || {
+            Details::Reducer(Reducer {
+                value: Some(reducer::Value::Func(Func {
+                    function: "function _operator_reduce_process(a, b) { return a + b }"
+                        .to_string(),
+                })),
+            })
+        };
+        let downstreams = [
+            instance(3, 3, 0, 2, reducer()),
+            instance(4, 3, 1, 2, reducer()),
+        ];
+        let out_edges = [3, 4];
+        let mut partitioner = OutEdgePartitioner::new(&upstream, downstreams.iter());
+
+        (0..100).for_each(|i| {
+            let key = format!("key-{}", i);
+            let owner = key_groups::instance_of(key_groups::key_group(key.as_bytes()), 2);
+            assert_eq!(
+                partitioner.route(&keyed_event(&key), out_edges.iter()),
+                vec![out_edges[owner as usize]]
+            );
+        });
+    }
+
+    #[test]
+    fn test_forward_and_rebalance_partitioning() {
+        let upstream = instance(6, 1, 1, 2, mapper());
+
+        // same parallelism
+        let downstreams = vec![
+            instance(2, 2, 0, 2, mapper()),
+            instance(7, 2, 1, 2, mapper()),
+        ];
+        let mut partitioner = OutEdgePartitioner::new(&upstream, downstreams.iter());
+        (0..4).for_each(|_| {
+            assert_eq!(
+                partitioner.route(&Default::default(), vec![2, 7].iter()),
+                vec![7]
+            );
+        });
+
+        let downstreams = vec![
+            instance(2, 2, 0, 3, mapper()),
+            instance(7, 2, 1, 3, mapper()),
+            instance(8, 2, 2, 3, mapper()),
+        ];
+        let mut partitioner = OutEdgePartitioner::new(&upstream, downstreams.iter());
+        let routes = (0..6)
+            .flat_map(|_| partitioner.route(&Default::default(), vec![2, 7, 8].iter()))
+            .collect::<Vec<_>>();
+        assert_eq!(routes, vec![7, 8, 2, 7, 8, 2]);
+    }
+}
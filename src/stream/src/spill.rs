@@ -0,0 +1,410 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use common::{
+    consts::{
+        default_configs::{
+            DEFAULT_EDGE_SPILL_DIR, DEFAULT_EDGE_SPILL_DISK_BYTES, DEFAULT_EDGE_SPILL_MEMORY_EVENTS,
+        },
+        env_keys::{EDGE_SPILL_DIR, EDGE_SPILL_DISK_BYTES, EDGE_SPILL_MEMORY_EVENTS},
+    },
+    utils::get_env,
+};
+use prost::Message;
+use proto::{
+    common::SpillMetrics,
+    taskmanager::{edge_frame::Frame, EdgeFrame},
+};
+
+// the sequence of spill files created by this process
+static SPILL_FILE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// the length prefix of each frame in a spill file
+const FRAME_HEADER_SIZE: u64 = 4;
+
+#[derive(Debug)]
+pub enum SpillError {
+    /// both the memory and the disk are full. The frame is given back
+    Full(Frame),
+    Io(io::Error),
+}
+
+impl From<io::Error> for SpillError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// The size of a spill queue, which is shared with the task that reports it
+#[derive(Debug, Default)]
+pub struct SpillStats {
+    memory_events: AtomicU64,
+    disk_events: AtomicU64,
+    disk_bytes: AtomicU64,
+}
+
+impl SpillStats {
+    /// whether all the spilled events have been replayed
+    pub fn is_empty(&self) -> bool {
+        self.memory_events.load(Ordering::Relaxed) == 0
+            && self.disk_events.load(Ordering::Relaxed) == 0
+    }
+
+    pub fn metrics(&self) -> SpillMetrics {
+        SpillMetrics {
+            memory_events: self.memory_events.load(Ordering::Relaxed),
+            disk_events: self.disk_events.load(Ordering::Relaxed),
+            disk_bytes: self.disk_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn event_count(frame: &Frame) -> usize {
+    match frame {
+        Frame::Events(event_set) => event_set.events.len(),
+        _ => 0,
+    }
+}
+
+/// An append-only file of length-prefixed frames. It's truncated once all frames are read
+struct SpillFile {
+    path: PathBuf,
+    max_bytes: u64,
+    file: Option<fs::File>,
+    read_offset: u64,
+    write_offset: u64,
+    frames: usize,
+    events: usize,
+}
+
+impl SpillFile {
+    fn is_empty(&self) -> bool {
+        self.frames == 0
+    }
+
+    fn bytes(&self) -> u64 {
+        self.write_offset - self.read_offset
+    }
+
+    fn open(&mut self) -> io::Result<&mut fs::File> {
+        if self.file.is_none() {
+            if let Some(dir) = self.path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .read(true)
+                .write(true)
+                .truncate(true)
+                .open(&self.path)?;
+            self.file = Some(file);
+        }
+        Ok(self.file.as_mut().unwrap())
+    }
+
+    fn push_back(&mut self, frame: Frame) -> Result<(), SpillError> {
+        let events = event_count(&frame);
+        let edge_frame = EdgeFrame {
+            sequence: 0,
+            frame: Some(frame),
+        };
+        let len = edge_frame.encoded_len();
+        let size = FRAME_HEADER_SIZE + len as u64;
+        if self.write_offset + size > self.max_bytes {
+            return Err(SpillError::Full(edge_frame.frame.unwrap()));
+        }
+
+        let offset = self.write_offset;
+        let file = self.open()?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(&(len as u32).to_le_bytes())?;
+        file.write_all(&edge_frame.encode_to_vec())?;
+        self.write_offset += size;
+        self.frames += 1;
+        self.events += events;
+        Ok(())
+    }
+
+    fn pop_front(&mut self) -> io::Result<Option<Frame>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let offset = self.read_offset;
+        let file = self.open()?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut header = [0u8; FRAME_HEADER_SIZE as usize];
+        file.read_exact(&mut header)?;
+        let mut buf = vec![0u8; u32::from_le_bytes(header) as usize];
+        file.read_exact(&mut buf)?;
+        let frame = EdgeFrame::decode(buf.as_slice())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?
+            .frame;
+
+        self.read_offset += FRAME_HEADER_SIZE + buf.len() as u64;
+        self.frames -= 1;
+        self.events -= frame.as_ref().map(event_count).unwrap_or_default();
+        if self.is_empty() {
+            self.clear()?;
+        }
+        Ok(frame)
+    }
+
+    /// drop all frames and give the space back
+    fn clear(&mut self) -> io::Result<()> {
+        self.read_offset = 0;
+        self.write_offset = 0;
+        self.frames = 0;
+        self.events = 0;
+        match self.file.as_mut() {
+            Some(file) => file.set_len(0),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            if let Err(err) = fs::remove_file(&self.path) {
+                tracing::warn!("remove spill file {:?} failed: {}", self.path, err)
+            }
+        }
+    }
+}
+
+/// A bounded FIFO queue of the frames which a remote edge fails to send. Frames are kept in memory until it holds `max_memory_events` events,
+/// and the later frames are appended to a file on local disk, so that frames are always popped in the order they are pushed.
+///
+/// The file is read and written on the blocking threads, so that the disk doesn't block the runtime
+pub struct SpillQueue {
+    memory: VecDeque<Frame>,
+    memory_events: usize,
+    max_memory_events: usize,
+    disk: Arc<Mutex<SpillFile>>,
+    stats: Arc<SpillStats>,
+}
+
+impl SpillQueue {
+    pub fn new(dir: PathBuf, max_memory_events: usize, max_disk_bytes: u64) -> Self {
+        let path = dir.join(format!(
+            "edge-{}-{}.spill",
+            std::process::id(),
+            SPILL_FILE_ID.fetch_add(1, Ordering::SeqCst)
+        ));
+        Self {
+            memory: Default::default(),
+            memory_events: 0,
+            max_memory_events,
+            disk: Arc::new(Mutex::new(SpillFile {
+                path,
+                max_bytes: max_disk_bytes,
+                file: None,
+                read_offset: 0,
+                write_offset: 0,
+                frames: 0,
+                events: 0,
+            })),
+            stats: Default::default(),
+        }
+    }
+
+    /// The spill files are put under the temp dir by default
+    pub fn from_env() -> Self {
+        let dir = get_env(EDGE_SPILL_DIR)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| std::env::temp_dir().join(DEFAULT_EDGE_SPILL_DIR));
+        let max_memory_events = get_env(EDGE_SPILL_MEMORY_EVENTS)
+            .and_then(|size| size.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_EDGE_SPILL_MEMORY_EVENTS);
+        let max_disk_bytes = get_env(EDGE_SPILL_DISK_BYTES)
+            .and_then(|size| size.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_EDGE_SPILL_DISK_BYTES);
+        Self::new(dir, max_memory_events, max_disk_bytes)
+    }
+
+    pub fn stats(&self) -> Arc<SpillStats> {
+        self.stats.clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty() && self.lock_disk().is_empty()
+    }
+
+    fn lock_disk(&self) -> MutexGuard<'_, SpillFile> {
+        self.disk.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// run the I/O of the spill file on the blocking threads
+    async fn with_disk<R, F>(&self, f: F) -> io::Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut SpillFile) -> R + Send + 'static,
+    {
+        let disk = self.disk.clone();
+        tokio::task::spawn_blocking(move || {
+            f(&mut disk.lock().unwrap_or_else(|err| err.into_inner()))
+        })
+        .await
+        .map_err(io::Error::other)
+    }
+
+    pub async fn try_push(&mut self, frame: Frame) -> Result<(), SpillError> {
+        let events = event_count(&frame);
+        // once frames are on disk, later frames have to follow them
+        let result = if self.lock_disk().is_empty()
+            && (self.memory.is_empty() || self.memory_events + events <= self.max_memory_events)
+        {
+            self.memory_events += events;
+            self.memory.push_back(frame);
+            Ok(())
+        } else {
+            match self.with_disk(move |disk| disk.push_back(frame)).await {
+                Ok(result) => result,
+                Err(err) => Err(err.into()),
+            }
+        };
+        self.update_stats();
+        result
+    }
+
+    pub async fn pop_front(&mut self) -> io::Result<Option<Frame>> {
+        let result = match self.memory.pop_front() {
+            Some(frame) => {
+                self.memory_events -= event_count(&frame);
+                Ok(Some(frame))
+            }
+            None => self
+                .with_disk(|disk| disk.pop_front())
+                .await
+                .and_then(|result| result),
+        };
+        self.update_stats();
+        result
+    }
+
+    /// put back a frame which is popped but not sent. It may exceed the memory bound
+    pub fn push_front(&mut self, frame: Frame) {
+        self.memory_events += event_count(&frame);
+        self.memory.push_front(frame);
+        self.update_stats();
+    }
+
+    /// drop all frames, e.g. when the spill file can't be read any more
+    pub async fn clear(&mut self) -> io::Result<()> {
+        self.memory.clear();
+        self.memory_events = 0;
+        let result = self
+            .with_disk(|disk| disk.clear())
+            .await
+            .and_then(|result| result);
+        self.update_stats();
+        result
+    }
+
+    fn update_stats(&self) {
+        let disk = self.lock_disk();
+        self.stats
+            .memory_events
+            .store(self.memory_events as u64, Ordering::Relaxed);
+        self.stats
+            .disk_events
+            .store(disk.events as u64, Ordering::Relaxed);
+        self.stats.disk_bytes.store(disk.bytes(), Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proto::{
+        common::{KeyedDataEvent, KeyedEventSet},
+        taskmanager::{edge_frame::Frame, EdgeTerminate},
+    };
+
+    use super::{SpillError, SpillQueue};
+
+    fn events_frame(start: i64, size: usize) -> Frame {
+        Frame::Events(KeyedEventSet {
+            events: (0..size)
+                .map(|i| KeyedDataEvent {
+                    event_id: start + i as i64,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    fn spill_dir(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join("lightflus-test").join(name)
+    }
+
+    #[tokio::test]
+    async fn test_spill_queue_keeps_order() {
+        let mut queue = SpillQueue::new(spill_dir("test_spill_queue_keeps_order"), 4, 1 << 20);
+        assert!(queue.is_empty());
+
+        queue.try_push(events_frame(0, 2)).await.unwrap();
+        queue.try_push(events_frame(2, 2)).await.unwrap();
+        // memory is full, the later frames are spilled to disk
+        queue.try_push(events_frame(4, 2)).await.unwrap();
+        queue
+            .try_push(Frame::Terminate(EdgeTerminate { event_time: 10 }))
+            .await
+            .unwrap();
+
+        let metrics = queue.stats().metrics();
+        assert_eq!(metrics.memory_events, 4);
+        assert_eq!(metrics.disk_events, 2);
+        assert!(metrics.disk_bytes > 0);
+        assert!(!queue.stats().is_empty());
+
+        // a popped frame can be put back
+        let frame = queue.pop_front().await.unwrap();
+        assert_eq!(frame, Some(events_frame(0, 2)));
+        queue.push_front(frame.unwrap());
+
+        assert_eq!(queue.pop_front().await.unwrap(), Some(events_frame(0, 2)));
+        // memory has room again but frames on disk go first
+        queue.try_push(events_frame(6, 1)).await.unwrap();
+        assert_eq!(queue.pop_front().await.unwrap(), Some(events_frame(2, 2)));
+        assert_eq!(queue.pop_front().await.unwrap(), Some(events_frame(4, 2)));
+        assert_eq!(
+            queue.pop_front().await.unwrap(),
+            Some(Frame::Terminate(EdgeTerminate { event_time: 10 }))
+        );
+        assert_eq!(queue.pop_front().await.unwrap(), Some(events_frame(6, 1)));
+        assert_eq!(queue.pop_front().await.unwrap(), None);
+        assert!(queue.is_empty());
+
+        let metrics = queue.stats().metrics();
+        assert_eq!(metrics.memory_events, 0);
+        assert_eq!(metrics.disk_events, 0);
+        assert_eq!(metrics.disk_bytes, 0);
+        assert!(queue.stats().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_spill_queue_bounded() {
+        let mut queue = SpillQueue::new(spill_dir("test_spill_queue_bounded"), 2, 64);
+        queue.try_push(events_frame(0, 2)).await.unwrap();
+        queue.try_push(events_frame(2, 1)).await.unwrap();
+
+        match queue.try_push(events_frame(3, 100)).await {
+            Err(SpillError::Full(frame)) => assert_eq!(frame, events_frame(3, 100)),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // the rejected frame doesn't change the queue
+        assert_eq!(queue.pop_front().await.unwrap(), Some(events_frame(0, 2)));
+        assert_eq!(queue.pop_front().await.unwrap(), Some(events_frame(2, 1)));
+        assert!(queue.is_empty());
+    }
+}
@@ -1,61 +1,240 @@
-use std::{cell::RefCell, collections::BTreeMap, path::Path};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
 
-use sled::Db;
+use common::{types::NodeIdx, utils::key_groups};
 use proto::common::ResourceId;
+use sled::{Db, Tree};
 
 const KEY_VALUE: &str = "key_value";
 const STATE_MANAGER: &str = "STATE_MANAGER";
 pub(crate) const KEY_VALUE_STATE_PATH: &str = "KEY_VALUE_STATE_PATH";
 const DEFAULT_STATE_PATH: &str = "/tmp/state";
+
+/// The entries of a key group, which are moved together between executors
+pub type KeyGroupEntries = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// The keyed state of an operator. Keys are partitioned into [KEY_GROUPS](key_groups::KEY_GROUPS) key groups by the same hash
+/// as the events which are routed to the parallel instances of the operator, so that an instance only touches the key groups it owns
+/// and a whole key group can be exported from one executor and imported into another
 pub trait StateManager {
     fn get_keyed_state(&self, key: &[u8]) -> Vec<u8>;
     fn set_key_state(&self, key: &[u8], value: &[u8]);
+    fn export_key_group(&self, key_group: u32) -> KeyGroupEntries;
+    /// the entries replace the ones of the same keys
+    fn import_key_group(&self, key_group: u32, entries: KeyGroupEntries);
+    fn remove_key_group(&self, key_group: u32);
 }
 
-fn new_key_value_state_mgt(resource_id: &ResourceId) -> KeyValueStateManager {
+/// the databases are shared by the operators of a job, because a path can not be opened by multiple databases at the same time
+static DATABASES: OnceLock<Mutex<BTreeMap<String, Db>>> = OnceLock::new();
+
+fn open_db(path: String) -> Db {
+    let mut databases = DATABASES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    databases
+        .entry(path)
+        .or_insert_with_key(|path| {
+            sled::open(path)
+                .map_err(|err| tracing::error!("db open failed: {}", err))
+                .unwrap()
+        })
+        .clone()
+}
+
+fn state_path(resource_id: &ResourceId) -> String {
     let mut path =
         common::utils::get_env(KEY_VALUE_STATE_PATH).unwrap_or(DEFAULT_STATE_PATH.to_string());
     path.push_str("/");
     path.push_str(&resource_id.namespace_id);
     path.push_str(&resource_id.resource_id);
-    KeyValueStateManager::new(path)
+    path
+}
+
+fn new_key_value_state_mgt(resource_id: &ResourceId, operator_id: NodeIdx) -> KeyValueStateManager {
+    KeyValueStateManager::new(open_db(state_path(resource_id)), operator_id)
+}
+
+/// Close the databases of a job which has ended. A database is closed once the state managers which are still using it are dropped
+pub fn close_state(job_id: &ResourceId) {
+    let path = state_path(job_id);
+    // see [source_resource_id]
+    let source_prefix = format!("{}-source-", path);
+    if let Some(databases) = DATABASES.get() {
+        databases
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .retain(|db_path, _| db_path != &path && !db_path.starts_with(&source_prefix));
+    }
 }
 
-pub fn new_state_mgt(resource_id: &ResourceId) -> impl StateManager {
+/// Keyed state used to be kept in the default tree of the databases before it was partitioned into key groups.
+/// A source is the only writer of its database, so the positions it has recorded are moved into its key groups.
+/// The database of a job is shared by its operators, whose keys can't be told apart, so a job which still has such state is refused:
+/// its state has to be removed, or the job has to keep running on the release which wrote it
+pub fn upgrade_legacy_state(
+    job_id: &ResourceId,
+    source_ids: impl IntoIterator<Item = NodeIdx>,
+) -> Result<(), String> {
+    if !matches!(state_mgt_type(), StateMangerType::KeyValue) {
+        return Ok(());
+    }
+    for source_id in source_ids {
+        let path = state_path(&source_resource_id(job_id, source_id));
+        if Path::new(&path).exists() {
+            KeyValueStateManager::new(open_db(path.clone()), source_id)
+                .upgrade_legacy_entries()
+                .map_err(|err| format!("upgrade state at {} failed: {}", path, err))?;
+        }
+    }
+
+    let path = state_path(job_id);
+    if Path::new(&path).exists() && !open_db(path.clone()).is_empty() {
+        return Err(format!(
+            "state at {} was written by an earlier release, whose keys can't be partitioned into key groups. Remove it to run the job",
+            path
+        ));
+    }
+    Ok(())
+}
+
+/// the state of the operator in the job. The instances of an operator share its state, so `operator_id` is the id of the operator in the submitted dataflow
+pub fn new_state_mgt(resource_id: &ResourceId, operator_id: NodeIdx) -> impl StateManager {
     match state_mgt_type() {
-        StateMangerType::KeyValue => StateManagerEnum::KeyValue(new_key_value_state_mgt(resource_id)),
+        StateMangerType::KeyValue => {
+            StateManagerEnum::KeyValue(new_key_value_state_mgt(resource_id, operator_id))
+        }
         StateMangerType::Memory => StateManagerEnum::Memory(MemoryStateManager::new()),
     }
 }
 
+/// the positions recorded by a source of the job. A source keeps its state separately, because a state can not be opened by multiple operators at the same time
+pub fn new_source_state_mgt(job_id: &ResourceId, source_id: NodeIdx) -> impl StateManager {
+    new_state_mgt(&source_resource_id(job_id, source_id), source_id)
+}
+
+fn source_resource_id(job_id: &ResourceId, source_id: NodeIdx) -> ResourceId {
+    ResourceId {
+        resource_id: format!("{}-source-{}", &job_id.resource_id, source_id),
+        namespace_id: job_id.namespace_id.clone(),
+    }
+}
+
+/// Each key group of an operator is stored in a separate tree of the database of the job
 pub struct KeyValueStateManager {
     db: Db,
+    operator_id: NodeIdx,
 }
 
 impl KeyValueStateManager {
-    pub fn new<P: AsRef<Path>>(path: P) -> Self {
-        Self {
-            db: sled::open(path)
+    pub fn new(db: Db, operator_id: NodeIdx) -> Self {
+        Self { db, operator_id }
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P, operator_id: NodeIdx) -> Self {
+        Self::new(
+            sled::open(path)
                 .map_err(|err| tracing::error!("db open failed: {}", err))
                 .unwrap(),
+            operator_id,
+        )
+    }
+
+    fn tree_name(&self, key_group: u32) -> String {
+        format!("key-group-{}-{}", self.operator_id, key_group)
+    }
+
+    /// Move the entries of the default tree into the key groups of the operator.
+    /// An entry which is already in its key group has been written since, so it's kept
+    fn upgrade_legacy_entries(&self) -> sled::Result<()> {
+        for entry in self.db.iter() {
+            let (key, value) = entry?;
+            let _ = self
+                .db
+                .open_tree(self.tree_name(key_groups::key_group(&key)))?
+                .compare_and_swap(&key, None as Option<&[u8]>, Some(value))?;
+            self.db.remove(&key)?;
         }
+        Ok(())
+    }
+
+    fn tree(&self, key_group: u32) -> Option<Tree> {
+        self.db
+            .open_tree(self.tree_name(key_group))
+            .map_err(|err| tracing::error!("open tree of key group {} failed: {}", key_group, err))
+            .ok()
     }
 }
 
 impl StateManager for KeyValueStateManager {
     fn get_keyed_state(&self, key: &[u8]) -> Vec<u8> {
-        self.db
-            .get(key)
-            .map(|value| value.map(|v| v.to_vec()).unwrap_or(vec![]))
-            .map_err(|err| tracing::error!("get state failed: {}", err))
+        self.tree(key_groups::key_group(key))
+            .and_then(|tree| {
+                tree.get(key)
+                    .map_err(|err| tracing::error!("get state failed: {}", err))
+                    .ok()
+            })
+            .flatten()
+            .map(|value| value.to_vec())
             .unwrap_or_default()
     }
 
     fn set_key_state(&self, key: &[u8], value: &[u8]) {
+        if let Some(tree) = self.tree(key_groups::key_group(key)) {
+            tree.insert(key, value)
+                .map(|_| {})
+                .map_err(|err| tracing::error!("set key state failed: {}", err))
+                .unwrap_or_default()
+        }
+    }
+
+    fn export_key_group(&self, key_group: u32) -> KeyGroupEntries {
+        // a key group which has never been written has no tree, and it's not created by exporting
+        let tree_name = self.tree_name(key_group);
+        if !self
+            .db
+            .tree_names()
+            .iter()
+            .any(|name| name.as_ref() == tree_name.as_bytes())
+        {
+            return vec![];
+        }
+        self.tree(key_group)
+            .map(|tree| {
+                tree.iter()
+                    .filter_map(|entry| {
+                        entry
+                            .map(|(key, value)| (key.to_vec(), value.to_vec()))
+                            .map_err(|err| tracing::error!("export key state failed: {}", err))
+                            .ok()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn import_key_group(&self, key_group: u32, entries: KeyGroupEntries) {
+        if let Some(tree) = self.tree(key_group) {
+            let mut batch = sled::Batch::default();
+            entries
+                .into_iter()
+                .for_each(|(key, value)| batch.insert(key, value));
+            tree.apply_batch(batch)
+                .map_err(|err| tracing::error!("import key group {} failed: {}", key_group, err))
+                .unwrap_or_default()
+        }
+    }
+
+    fn remove_key_group(&self, key_group: u32) {
         self.db
-            .insert(key, value)
+            .drop_tree(self.tree_name(key_group))
             .map(|_| {})
-            .map_err(|err| tracing::error!("set key state failed: {}", err))
+            .map_err(|err| tracing::error!("remove key group {} failed: {}", key_group, err))
             .unwrap_or_default()
     }
 }
@@ -96,23 +275,72 @@ impl StateManager for StateManagerEnum {
             StateManagerEnum::Memory(manager) => manager.set_key_state(key, value),
         }
     }
+
+    fn export_key_group(&self, key_group: u32) -> KeyGroupEntries {
+        match self {
+            StateManagerEnum::KeyValue(manager) => manager.export_key_group(key_group),
+            StateManagerEnum::Memory(manager) => manager.export_key_group(key_group),
+        }
+    }
+
+    fn import_key_group(&self, key_group: u32, entries: KeyGroupEntries) {
+        match self {
+            StateManagerEnum::KeyValue(manager) => manager.import_key_group(key_group, entries),
+            StateManagerEnum::Memory(manager) => manager.import_key_group(key_group, entries),
+        }
+    }
+
+    fn remove_key_group(&self, key_group: u32) {
+        match self {
+            StateManagerEnum::KeyValue(manager) => manager.remove_key_group(key_group),
+            StateManagerEnum::Memory(manager) => manager.remove_key_group(key_group),
+        }
+    }
 }
 
+type KeyGroupState = BTreeMap<Vec<u8>, Vec<u8>>;
+
 pub struct MemoryStateManager {
-    cache: RefCell<BTreeMap<Vec<u8>, Vec<u8>>>,
+    // the state of each key group
+    cache: RefCell<BTreeMap<u32, KeyGroupState>>,
 }
 
 impl StateManager for MemoryStateManager {
     fn get_keyed_state(&self, key: &[u8]) -> Vec<u8> {
         self.cache
             .borrow()
-            .get(&key.to_vec())
+            .get(&key_groups::key_group(key))
+            .and_then(|entries| entries.get(key))
             .map(|data| data.clone())
             .unwrap_or(vec![])
     }
 
     fn set_key_state(&self, key: &[u8], value: &[u8]) {
-        self.cache.borrow_mut().insert(key.to_vec(), value.to_vec());
+        self.cache
+            .borrow_mut()
+            .entry(key_groups::key_group(key))
+            .or_default()
+            .insert(key.to_vec(), value.to_vec());
+    }
+
+    fn export_key_group(&self, key_group: u32) -> KeyGroupEntries {
+        self.cache
+            .borrow()
+            .get(&key_group)
+            .map(|entries| entries.clone().into_iter().collect())
+            .unwrap_or_default()
+    }
+
+    fn import_key_group(&self, key_group: u32, entries: KeyGroupEntries) {
+        self.cache
+            .borrow_mut()
+            .entry(key_group)
+            .or_default()
+            .extend(entries);
+    }
+
+    fn remove_key_group(&self, key_group: u32) {
+        self.cache.borrow_mut().remove(&key_group);
     }
 }
 
@@ -123,3 +351,73 @@ impl MemoryStateManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use common::utils::key_groups;
+
+    use super::{KeyValueStateManager, MemoryStateManager, StateManager};
+
+    fn test_move_key_groups(from: &impl StateManager, to: &impl StateManager) {
+        from.set_key_state(b"key-1", b"value-1");
+        from.set_key_state(b"key-2", b"value-2");
+        let key_group = key_groups::key_group(b"key-1");
+        assert_eq!(
+            from.export_key_group(key_group)
+                .into_iter()
+                .find(|(key, _)| key.as_slice() == b"key-1"),
+            Some((b"key-1".to_vec(), b"value-1".to_vec()))
+        );
+
+        to.import_key_group(key_group, from.export_key_group(key_group));
+        from.remove_key_group(key_group);
+        assert_eq!(to.get_keyed_state(b"key-1"), b"value-1".to_vec());
+        assert!(from.get_keyed_state(b"key-1").is_empty());
+        assert!(from.export_key_group(key_group).is_empty());
+
+        // other key groups are kept
+        if key_groups::key_group(b"key-2") != key_group {
+            assert_eq!(from.get_keyed_state(b"key-2"), b"value-2".to_vec());
+            assert!(to.get_keyed_state(b"key-2").is_empty());
+        }
+    }
+
+    #[test]
+    fn test_memory_state_move_key_groups() {
+        test_move_key_groups(&MemoryStateManager::new(), &MemoryStateManager::new());
+    }
+
+    #[test]
+    fn test_key_value_state_move_key_groups() {
+        let path = std::env::temp_dir().join("lightflus-test/test_key_value_state_move_key_groups");
+        let _ = std::fs::remove_dir_all(&path);
+        let from = KeyValueStateManager::open(path.join("from"), 1);
+        let to = KeyValueStateManager::open(path.join("to"), 1);
+        test_move_key_groups(&from, &to);
+
+        // operators don't share key groups
+        let other = KeyValueStateManager::new(from.db.clone(), 2);
+        other.set_key_state(b"key-3", b"other");
+        assert!(from.get_keyed_state(b"key-3").is_empty());
+    }
+
+    #[test]
+    fn test_key_value_state_upgrade_legacy_entries() {
+        let path =
+            std::env::temp_dir().join("lightflus-test/test_key_value_state_upgrade_legacy_entries");
+        let _ = std::fs::remove_dir_all(&path);
+        let state = KeyValueStateManager::open(&path, 1);
+        state.db.insert(b"mark", b"legacy-mark".to_vec()).unwrap();
+        state
+            .db
+            .insert(b"offset", b"legacy-offset".to_vec())
+            .unwrap();
+        state.set_key_state(b"offset", b"offset");
+
+        state.upgrade_legacy_entries().unwrap();
+        assert!(state.db.is_empty());
+        assert_eq!(state.get_keyed_state(b"mark"), b"legacy-mark".to_vec());
+        // the entry written since is newer
+        assert_eq!(state.get_keyed_state(b"offset"), b"offset".to_vec());
+    }
+}
@@ -23,36 +23,50 @@ use common::{
     event::LocalEvent,
     futures::join_all,
     map_iter_mut,
-    net::gateway::taskmanager::SafeTaskManagerRpcGateway,
+    net::{gateway::taskmanager::SafeTaskManagerRpcGateway, RpcCompression},
     types::{ExecutorId, SinkId},
-    utils::get_env,
+    utils::{get_env, times::now_timestamp},
 };
 
-use futures_util::{ready, Future};
+use futures_util::Future;
 use proto::common::{
     operator_info::Details, Ack, DataflowMeta, ExecutorInfo, ExecutorStatus, Heartbeat,
     KeyedDataEvent, KeyedEventSet, OperatorInfo, ResourceId,
 };
-use tokio::{sync::RwLock, task::JoinHandle};
+use tokio::{
+    sync::{oneshot, Mutex, RwLock},
+    task::JoinHandle,
+};
+use tonic::transport::ClientTlsConfig;
 
 use crate::{
     connector::{Sink, SinkImpl, Source, SourceImpl},
     dataflow::Execution,
-    edge::{InEdge, LocalInEdge, LocalOutEdge, OutEdge, RemoteOutEdge},
+    edge::{
+        new_local_edge, EdgeBatcher, InEdge, LocalInEdge, LocalOutEdge, OutEdge, RemoteInEdge,
+        RemoteOutEdge,
+    },
     err::{ExecutionError, TaskError},
-    new_event_channel,
+    partition::OutEdgePartitioner,
+    spill::SpillStats,
     state::new_state_mgt,
-    Receiver, Sender,
 };
 
 pub struct Task {
     executor_id: ExecutorId,
     job_id: ResourceId,
-    main_executor_handle: Option<JoinHandle<()>>,
+    // it is taken when the task waits for the executor to terminate
+    main_executor_handle: Mutex<Option<JoinHandle<()>>>,
     downstream: BTreeSet<ExecutorId>,
     last_receive_heartbeat_id: AtomicU64,
-    in_edge: Option<Box<dyn OutEdge<Output = LocalEvent>>>,
+    in_edge: Option<LocalOutEdge<LocalEvent>>,
+    // receives the streams of remote edges, which share the in-edge
+    remote_in_edge: Option<RemoteInEdge>,
     states: Arc<RwLock<ExecutorInfo>>,
+    // the spill queues of the remote out-edges of the executor
+    spill_stats: BTreeMap<ExecutorId, Arc<SpillStats>>,
+    // stops the source of the executor. It's taken once the task is stopped
+    stop_tx: std::sync::Mutex<Option<oneshot::Sender<()>>>,
 }
 
 impl Task {
@@ -63,14 +77,18 @@ impl Task {
         Self {
             executor_id: adjacent_node.center,
             job_id: job_id.clone(),
-            main_executor_handle: None,
+            main_executor_handle: Default::default(),
             downstream: adjacent_node.neighbors.iter().map(|id| *id).collect(),
             last_receive_heartbeat_id: Default::default(),
             in_edge: None,
+            remote_in_edge: None,
             states: Arc::new(RwLock::new(ExecutorInfo {
                 executor_id: adjacent_node.center,
                 status: ExecutorStatus::Initialized as i32,
+                spills: Default::default(),
             })),
+            spill_stats: Default::default(),
+            stop_tx: Default::default(),
         }
     }
 
@@ -94,55 +112,115 @@ impl Task {
             external_sinks: Default::default(),
             executor_id: self.executor_id,
             out_edges: Default::default(),
+            pending_writes: Default::default(),
+            partitioner: Default::default(),
+            batcher: EdgeBatcher::new(operator_info.out_edge_batch.as_ref()),
+            terminated: false,
+            upstreams: operator_info
+                .upstreams
+                .iter()
+                .collect::<BTreeSet<_>>()
+                .len(),
+            terminated_upstreams: 0,
+            stop_rx: None,
+            draining: false,
+            terminating: false,
             in_edge: None,
             source,
             operator_details: details,
+            logical_operator_id: operator_info.get_logical_operator_id(),
+            chained_operators: Default::default(),
             job_id: self.job_id.clone(),
             states: self.states.clone(),
         }
     }
 
-    pub fn start(&mut self, executor: StreamExecutor) {
-        self.main_executor_handle = Some(tokio::spawn(executor));
+    pub fn start(&mut self, mut executor: StreamExecutor) {
+        let (stop_tx, stop_rx) = oneshot::channel();
+        executor.stop_rx = Some(stop_rx);
+        *self.stop_tx.get_mut().unwrap() = Some(stop_tx);
+        self.spill_stats = executor.spill_stats();
+        *self.main_executor_handle.get_mut() = Some(tokio::spawn(executor));
+    }
+
+    /// Stop the source of the executor. The events which have been read are processed and written to the downstream operators,
+    /// which terminate after all of their upstreams. Executors of other operators terminate with their upstreams
+    pub fn stop(&self) {
+        let stop_tx = self.stop_tx.lock().unwrap().take();
+        stop_tx.into_iter().for_each(|stop_tx| {
+            let _ = stop_tx.send(());
+        })
+    }
+
+    /// Wait until the executor terminates. It's aborted if it doesn't terminate in the timeout, and the events it hasn't written are lost
+    pub async fn wait_terminated(&self, timeout: Duration) {
+        let handle = self.main_executor_handle.lock().await.take();
+        if let Some(mut handle) = handle {
+            if tokio::time::timeout(timeout, &mut handle).await.is_err() {
+                tracing::warn!(
+                    "executor {} of job {:?} isn't terminated in {:?} and it's aborted",
+                    self.executor_id,
+                    self.job_id,
+                    timeout
+                );
+                handle.abort();
+                let _ = handle.await;
+            }
+        }
     }
 
-    pub async fn send_event_to_operator(&self, event: LocalEvent) -> Result<(), TaskError> {
+    /// Send the event to the operator without waiting. It returns the credits granted to the sender,
+    /// or [OutEdgeError::QueueFull](crate::edge::OutEdgeError::QueueFull) if the operator has no room for the event
+    pub fn send_event_to_operator(&self, event: LocalEvent) -> Result<u32, TaskError> {
         match &self.in_edge {
             Some(in_edge) => in_edge
-                .write(event)
-                .await
+                .try_write(event)
+                .map(|_| self.get_credits())
                 .map_err(|err| TaskError::OutEdgeError(err)),
-            None => Ok(()),
+            None => Ok(0),
         }
     }
 
-    pub fn set_in_edge(&mut self, in_edge: Box<dyn OutEdge<Output = LocalEvent>>) {
+    pub fn set_in_edge(&mut self, in_edge: LocalOutEdge<LocalEvent>) {
+        self.remote_in_edge = Some(RemoteInEdge::new(in_edge.clone()));
         self.in_edge = Some(in_edge)
     }
 
+    /// the endpoint which serves the streams of remote edges to the operator
+    pub fn get_remote_in_edge(&self) -> Option<RemoteInEdge> {
+        self.remote_in_edge.clone()
+    }
+
+    /// Send all events to the operator without waiting. Either all events are accepted, or none of them is,
+    /// so that the sender can send the same events again once it has credits
     #[inline]
-    pub async fn batch_send_event_to_operator(
-        &self,
-        event_set: KeyedEventSet,
-    ) -> Result<(), TaskError> {
+    pub fn batch_send_event_to_operator(&self, event_set: KeyedEventSet) -> Result<u32, TaskError> {
         match &self.in_edge {
             Some(in_edge) => in_edge
-                .batch_write(
-                    &event_set.job_id,
-                    event_set.to_operator_id,
-                    event_set.from_operator_id,
+                .try_write_all(
                     event_set
                         .events
                         .into_iter()
-                        .map(|event| LocalEvent::KeyedDataStreamEvent(event))
+                        .map(|mut event| {
+                            event.to_operator_id = event_set.to_operator_id;
+                            LocalEvent::KeyedDataStreamEvent(event)
+                        })
                         .collect(),
                 )
-                .await
+                .map(|_| self.get_credits())
                 .map_err(|err| TaskError::OutEdgeError(err)),
-            None => Ok(()),
+            None => Ok(0),
         }
     }
 
+    /// the credits granted to the senders, which are the number of events the in-edge has room for
+    pub fn get_credits(&self) -> u32 {
+        self.in_edge
+            .as_ref()
+            .map(|in_edge| u32::try_from(in_edge.credits()).unwrap_or(u32::MAX))
+            .unwrap_or_default()
+    }
+
     pub fn receive_heartbeat(&self, heartbeat: &Heartbeat) {
         self.last_receive_heartbeat_id.store(
             self.last_receive_heartbeat_id
@@ -152,14 +230,20 @@ impl Task {
     }
 
     pub async fn get_state(&self) -> ExecutorInfo {
-        self.states.read().await.clone()
+        let mut state = self.states.read().await.clone();
+        state.spills = self
+            .spill_stats
+            .iter()
+            .map(|(to_operator_id, stats)| (*to_operator_id, stats.metrics()))
+            .collect();
+        state
     }
 }
 
 pub enum EdgeBuilder<'a> {
     Local {
-        tx: Sender<bytes::Bytes>,
-        rx: Receiver<bytes::Bytes>,
+        out_edge: LocalOutEdge<LocalEvent>,
+        in_edge: LocalInEdge<LocalEvent>,
         operator_info: &'a OperatorInfo,
     },
     Remote {
@@ -172,15 +256,19 @@ impl<'a> EdgeBuilder<'a> {
         let channel_size = get_env(CHANNEL_SIZE)
             .and_then(|size| size.parse::<usize>().ok())
             .unwrap_or(DEFAULT_CHANNEL_SIZE);
-        let (tx, rx) = new_event_channel(channel_size);
+        let (out_edge, in_edge) = new_local_edge(channel_size);
         Self::Local {
-            tx,
-            rx,
+            out_edge,
+            in_edge,
             operator_info,
         }
     }
 
-    pub fn remote(operator_info: &'a OperatorInfo) -> Self {
+    pub fn remote(
+        operator_info: &'a OperatorInfo,
+        compression: RpcCompression,
+        tls: Option<&ClientTlsConfig>,
+    ) -> Self {
         let host_addr = operator_info.get_host_addr_ref().unwrap();
         let connect_timeout = get_env(SEND_OPERATOR_EVENT_CONNECT_TIMEOUT)
             .and_then(|size| size.parse::<u64>().ok())
@@ -188,22 +276,35 @@ impl<'a> EdgeBuilder<'a> {
         let rpc_timeout = get_env(SEND_OPERATOR_EVENT_RPC_TIMEOUT)
             .and_then(|size| size.parse::<u64>().ok())
             .unwrap_or(DEFAULT_SEND_OPERATOR_EVENT_RPC_TIMEOUT_MILLIS);
+        let gateway = SafeTaskManagerRpcGateway::with_timeout(
+            host_addr,
+            Duration::from_secs(connect_timeout),
+            Duration::from_secs(rpc_timeout),
+        )
+        .with_compression(compression);
         Self::Remote {
-            gateway: SafeTaskManagerRpcGateway::with_timeout(
-                host_addr,
-                Duration::from_secs(connect_timeout),
-                Duration::from_secs(rpc_timeout),
-            ),
+            gateway: match tls {
+                Some(tls) => gateway.with_tls(tls.clone()),
+                None => gateway,
+            },
         }
     }
 
     pub fn build_out_edge(&self) -> Box<dyn OutEdge<Output = LocalEvent>> {
         match self {
-            Self::Local { tx, .. } => Box::new(LocalOutEdge::<LocalEvent>::new(tx.clone())),
+            Self::Local { out_edge, .. } => Box::new(out_edge.clone()),
             Self::Remote { gateway } => Box::new(RemoteOutEdge::new(gateway.clone())),
         }
     }
 
+    /// The out-edge which [Task] writes the events sent to the operator into. Only [EdgeBuilder::Local] can create it
+    pub fn build_task_in_edge(&self) -> Option<LocalOutEdge<LocalEvent>> {
+        match self {
+            Self::Local { out_edge, .. } => Some(out_edge.clone()),
+            _ => None,
+        }
+    }
+
     /// Unlike out-edge which the data stream can be broadcast to multiple downstreams, each operator does have only on in-edge to receive data stream.
     /// For different edge type, [EdgeBuilder] will return two different values:
     ///
@@ -211,7 +312,7 @@ impl<'a> EdgeBuilder<'a> {
     /// - then else will return [None]
     pub fn build_in_edge(self) -> Option<Pin<Box<dyn InEdge<Output = LocalEvent>>>> {
         match self {
-            Self::Local { tx: _, rx, .. } => Some(Box::pin(LocalInEdge::<LocalEvent>::new(rx))),
+            Self::Local { in_edge, .. } => Some(Box::pin(in_edge)),
             _ => None,
         }
     }
@@ -224,13 +325,34 @@ pub struct StreamExecutor {
     // executor id
     executor_id: ExecutorId,
     // out edges, remote or local
-    out_edges: BTreeMap<ExecutorId, Box<dyn OutEdge<Output = LocalEvent>>>,
+    out_edges: BTreeMap<ExecutorId, Arc<dyn OutEdge<Output = LocalEvent>>>,
+    // writes to out edges which are waiting for credits. The executor doesn't poll its input until they are finished
+    pending_writes: Vec<Pin<Box<dyn Future<Output = ()> + Send>>>,
+    // chooses the instances of the downstream operators which events are written to
+    partitioner: OutEdgePartitioner,
+    // accumulates events of out edges into batches
+    batcher: EdgeBatcher,
+    // the executor terminates once the pending writes are finished
+    terminated: bool,
+    // the number of upstream instances. The executor terminates once all of them have terminated
+    upstreams: usize,
+    terminated_upstreams: usize,
+    // notified when the task is stopped, then the source stops reading
+    stop_rx: Option<oneshot::Receiver<()>>,
+    // the source has stopped, and the executor terminates once the events which have been read are processed
+    draining: bool,
+    // the terminate event is written to the out edges once the buffered events are written
+    terminating: bool,
     // in edge
     in_edge: Option<Pin<Box<dyn InEdge<Output = LocalEvent>>>>,
     // external source
     source: Option<SourceImpl>,
     // operator details
     operator_details: Details,
+    // id of the operator in the submitted dataflow, which owns the keyed state of the executor
+    logical_operator_id: ExecutorId,
+    // one-to-one operators chained after the operator, which process its results in order
+    chained_operators: Vec<(ExecutorId, Details)>,
     // job id
     job_id: ResourceId,
     // inner states
@@ -250,13 +372,46 @@ impl StreamExecutor {
         executor_id: ExecutorId,
         out_edge: Box<dyn OutEdge<Output = LocalEvent>>,
     ) {
-        self.out_edges.insert(executor_id, out_edge);
+        self.out_edges.insert(executor_id, Arc::from(out_edge));
     }
 
     pub fn set_in_edge(&mut self, in_edge: Option<Pin<Box<dyn InEdge<Output = LocalEvent>>>>) {
         self.in_edge = in_edge;
     }
 
+    /// Distribute the events over the parallel instances of the downstream operators, instead of writing them to all out edges
+    pub fn set_partitioner(&mut self, partitioner: OutEdgePartitioner) {
+        self.partitioner = partitioner;
+    }
+
+    /// the spill queues of the out edges, keyed by the downstream operator
+    pub fn spill_stats(&self) -> BTreeMap<ExecutorId, Arc<SpillStats>> {
+        self.out_edges
+            .iter()
+            .filter_map(|(executor_id, out_edge)| {
+                out_edge.spill_stats().map(|stats| (*executor_id, stats))
+            })
+            .collect()
+    }
+
+    /// Chain a one-to-one operator after the last operator of the executor. The events it receives are passed directly instead of over an edge,
+    /// and the out edges of the executor become the ones of the chained operator
+    pub fn chain_operator(&mut self, operator_info: &OperatorInfo) {
+        self.batcher = EdgeBatcher::new(operator_info.out_edge_batch.as_ref());
+        self.chained_operators.push((
+            operator_info.operator_id,
+            operator_info.details.clone().unwrap(),
+        ));
+    }
+
+    /// the operator whose results are written to the out edges
+    fn output_operator_id(&self) -> ExecutorId {
+        self.chained_operators
+            .last()
+            .map(|(operator_id, _)| *operator_id)
+            .unwrap_or(self.executor_id)
+    }
+
     pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<LocalEvent>> {
         if self.in_edge.is_some() {
             match &mut self.in_edge {
@@ -273,6 +428,151 @@ impl StreamExecutor {
         }
     }
 
+    /// A source stops reading once the task is stopped. Events of a push source which have arrived at the in-edge are drained,
+    /// and so are the ones a pull source has read if it can stop gracefully
+    fn poll_stop(&mut self, cx: &mut Context<'_>) {
+        if self.source.is_none() {
+            return;
+        }
+        let stopped = self
+            .stop_rx
+            .as_mut()
+            .map(|stop_rx| Pin::new(stop_rx).poll(cx).is_ready())
+            .unwrap_or_default();
+        if stopped {
+            self.stop_rx = None;
+            let drains = self
+                .source
+                .as_mut()
+                .map(|source| source.stop())
+                .unwrap_or_default();
+            if drains || self.in_edge.is_some() {
+                self.draining = true;
+            } else {
+                self.terminate();
+            }
+        }
+    }
+
+    /// buffered events are written before the terminate event
+    fn terminate(&mut self) {
+        let batches = self.batcher.flush();
+        self.write_batches(batches);
+        self.terminating = true;
+    }
+
+    /// Write the terminate event to the out edges after the events, and close the external sinks. The executor terminates once the out edges
+    /// are flushed, so that a downstream operator has received all the events of its upstreams when it terminates
+    fn write_terminate(&mut self) {
+        let from_operator_id = self.output_operator_id();
+        let job_id = self.job_id.clone();
+        let out_edges = self.out_edges.clone();
+        let mut external_sinks = std::mem::take(&mut self.external_sinks);
+        self.pending_writes.push(Box::pin(async move {
+            futures_util::future::join_all(out_edges.into_iter().map(
+                |(to_operator_id, out_edge)| {
+                    let job_id = job_id.clone();
+                    async move {
+                        let terminate = LocalEvent::Terminate {
+                            job_id: job_id.clone(),
+                            to: to_operator_id,
+                            event_time: now_timestamp(),
+                        };
+                        let result = match out_edge
+                            .batch_write(
+                                &Some(job_id),
+                                to_operator_id,
+                                from_operator_id,
+                                vec![terminate],
+                            )
+                            .await
+                        {
+                            Ok(_) => out_edge.flush().await,
+                            Err(err) => Err(err),
+                        };
+                        if let Err(err) = result {
+                            tracing::error!(
+                                "terminate out edge to operator {} failed: {}",
+                                to_operator_id,
+                                err
+                            )
+                        }
+                    }
+                },
+            ))
+            .await;
+            for sink in external_sinks.values_mut() {
+                sink.close().await
+            }
+        }));
+    }
+
+    /// The events a source has polled are acked once none of them is buffered by the batcher, being written,
+    /// or spilled by a remote out-edge. Acks are deferred to a later poll while spilled events are replayed
+    fn ack_source(&mut self) {
+        let written = self.batcher.is_empty()
+            && self.pending_writes.is_empty()
+            && self.out_edges.values().all(|out_edge| {
+                out_edge
+                    .spill_stats()
+                    .map(|stats| stats.is_empty())
+                    .unwrap_or(true)
+            });
+        if written {
+            if let Some(source) = self.source.as_mut() {
+                source.ack()
+            }
+        }
+    }
+
+    /// poll the pending writes. It returns `true` if all of them are finished
+    fn poll_pending_writes(&mut self, cx: &mut Context<'_>) -> bool {
+        self.pending_writes
+            .retain_mut(|write| write.as_mut().poll(cx).is_pending());
+        self.pending_writes.is_empty()
+    }
+
+    /// events are routed by the partitioner and buffered by the batcher, and the batches which are full are written to the out edges
+    fn write_to_out_edges(&mut self, event_set: &KeyedEventSet) {
+        let mut routed = BTreeMap::<ExecutorId, Vec<LocalEvent>>::new();
+        event_set.events.iter().for_each(|event| {
+            self.partitioner
+                .route(event, self.out_edges.keys())
+                .into_iter()
+                .for_each(|to_operator_id| {
+                    let mut event = event.clone();
+                    event.to_operator_id = to_operator_id;
+                    routed
+                        .entry(to_operator_id)
+                        .or_default()
+                        .push(LocalEvent::KeyedDataStreamEvent(event));
+                })
+        });
+        let batches = routed
+            .into_iter()
+            .flat_map(|(to_operator_id, events)| self.batcher.append(to_operator_id, events))
+            .collect::<Vec<_>>();
+        self.write_batches(batches)
+    }
+
+    fn write_batches(&mut self, batches: Vec<(ExecutorId, Vec<LocalEvent>)>) {
+        batches.into_iter().for_each(|(to_operator_id, events)| {
+            if let Some(out_edge) = self.out_edges.get(&to_operator_id) {
+                let out_edge = out_edge.clone();
+                let from_operator_id = self.output_operator_id();
+                let job_id = Some(self.job_id.clone());
+                self.pending_writes.push(Box::pin(async move {
+                    if let Err(err) = out_edge
+                        .batch_write(&job_id, to_operator_id, from_operator_id, events)
+                        .await
+                    {
+                        tracing::error!("sink to out edge failed: {}", err)
+                    }
+                }));
+            }
+        })
+    }
+
     #[inline]
     fn process(&mut self, event: KeyedDataEvent, cx: &mut Context<'_>) {
         if self.source.is_some() {
@@ -280,39 +580,52 @@ impl StreamExecutor {
             return;
         }
 
-        let isolate = &mut v8::Isolate::new(Default::default());
-        let scope = &mut v8::HandleScope::new(isolate);
-        let execution = Execution::new(
+        let job_id = event.job_id.clone();
+        let to_operator_id = event.to_operator_id;
+        // chained operators have a single instance, whose id is the one of the operator
+        let events = std::iter::once((
             self.executor_id,
+            self.logical_operator_id,
             &self.operator_details,
-            new_state_mgt(&self.job_id),
-            scope,
+        ))
+        .chain(
+            self.chained_operators
+                .iter()
+                .map(|(operator_id, details)| (*operator_id, *operator_id, details)),
+        )
+        .fold(
+            vec![event],
+            |events, (operator_id, logical_operator_id, details)| {
+                execute(
+                    &self.job_id,
+                    operator_id,
+                    logical_operator_id,
+                    details,
+                    events,
+                )
+            },
         );
 
-        match execution.process(&event) {
-            Ok(events) => self.sink_event_set_to_external_and_local(KeyedEventSet {
+        self.sink_event_set_to_external_and_local(
+            KeyedEventSet {
                 events,
-                job_id: event.job_id.clone(),
-                to_operator_id: event.to_operator_id,
-                from_operator_id: self.executor_id,
-            },cx),
-            Err(err) => match err {
-                ExecutionError::OperatorUnimplemented(_) => {
-                    let event_set = KeyedEventSet {
-                        events: vec![event],
-                        job_id: Some(self.job_id.clone()),
-                        to_operator_id: self.executor_id,
-                        from_operator_id: self.executor_id,
-                    };
-                    self.sink_event_set_to_external_and_local(event_set, cx)
-                },
-                _ => tracing::error!("process event failed: job_id: {:?}, operator_id: {}, event: {:?}. error details: {}", &self.job_id,self.executor_id, event, err)
+                job_id,
+                to_operator_id,
+                from_operator_id: self.output_operator_id(),
             },
-        }
+            cx,
+        )
     }
 
     #[inline]
     fn sink_event_to_external_and_local(&mut self, event: KeyedDataEvent, cx: &mut Context<'_>) {
+        self.write_to_out_edges(&KeyedEventSet {
+            events: vec![event.clone()],
+            job_id: event.job_id.clone(),
+            to_operator_id: event.to_operator_id,
+            from_operator_id: self.executor_id,
+        });
+
         let ref mut external_sink_futures =
             map_iter_mut!(self.external_sinks, |(executor_id, sink)| {
                 let mut new_event = event.clone();
@@ -321,18 +634,6 @@ impl StreamExecutor {
             })
             .collect::<Vec<_>>();
 
-        let ref mut out_edge_futures = map_iter_mut!(self.out_edges, |(executor_id, out_edge)| {
-            let mut new_event = event.clone();
-            new_event.to_operator_id = *executor_id;
-            out_edge.write(LocalEvent::KeyedDataStreamEvent(new_event))
-        })
-        .collect::<Vec<_>>();
-
-        join_all(cx, out_edge_futures, |r| match r {
-            Ok(_) => {}
-            Err(err) => tracing::error!("sink to out edge failed: {}", err),
-        });
-
         join_all(cx, external_sink_futures, |r| match r {
             Ok(_) => {}
             Err(err) => tracing::error!("send to external sink failed: {}", err),
@@ -345,6 +646,8 @@ impl StreamExecutor {
         event_set: KeyedEventSet,
         cx: &mut Context<'_>,
     ) {
+        self.write_to_out_edges(&event_set);
+
         let ref mut external_sink_futures =
             map_iter_mut!(self.external_sinks, |(executor_id, sink)| {
                 let mut new_event_set = event_set.clone();
@@ -353,30 +656,6 @@ impl StreamExecutor {
             })
             .collect::<Vec<_>>();
 
-        let ref mut out_edge_futures = map_iter_mut!(self.out_edges, |(executor_id, out_edge)| {
-            let mut new_event_set = event_set.clone();
-            new_event_set.to_operator_id = *executor_id;
-            out_edge.batch_write(
-                &event_set.job_id,
-                *executor_id,
-                self.executor_id,
-                new_event_set
-                    .events
-                    .into_iter()
-                    .map(|mut event| {
-                        event.to_operator_id = *executor_id;
-                        LocalEvent::KeyedDataStreamEvent(event)
-                    })
-                    .collect(),
-            )
-        })
-        .collect::<Vec<_>>();
-
-        join_all(cx, out_edge_futures, |r| match r {
-            Ok(_) => {}
-            Err(err) => tracing::error!("sink to out edge failed: {}", err),
-        });
-
         join_all(cx, external_sink_futures, |r| match r {
             Ok(_) => {}
             Err(err) => tracing::error!("send to external sink failed: {}", err),
@@ -384,6 +663,37 @@ impl StreamExecutor {
     }
 }
 
+/// Process the events by the operator. Events are passed through if the operator is unimplemented, and the ones which fail are dropped.
+/// The state of the operator is shared by its parallel instances
+fn execute(
+    job_id: &ResourceId,
+    operator_id: ExecutorId,
+    logical_operator_id: ExecutorId,
+    details: &Details,
+    events: Vec<KeyedDataEvent>,
+) -> Vec<KeyedDataEvent> {
+    let isolate = &mut v8::Isolate::new(Default::default());
+    let scope = &mut v8::HandleScope::new(isolate);
+    let execution = Execution::new(
+        operator_id,
+        details,
+        new_state_mgt(job_id, logical_operator_id),
+        scope,
+    );
+
+    events
+        .into_iter()
+        .flat_map(|event| match execution.process(&event) {
+            Ok(events) => events,
+            Err(ExecutionError::OperatorUnimplemented(_)) => vec![event],
+            Err(err) => {
+                tracing::error!("process event failed: job_id: {:?}, operator_id: {}, event: {:?}. error details: {}", job_id, operator_id, event, err);
+                vec![]
+            }
+        })
+        .collect()
+}
+
 impl Future for StreamExecutor {
     type Output = ();
 
@@ -398,22 +708,71 @@ impl Future for StreamExecutor {
             Err(_) => return Poll::Pending,
         }
         loop {
-            let event = ready!(this.poll_next(cx));
+            // the executor stops polling its input until the out edges have credits for the processed events
+            if !this.poll_pending_writes(cx) {
+                return Poll::Pending;
+            }
+            this.ack_source();
+            if this.terminated {
+                return Poll::Ready(());
+            }
+            if this.terminating {
+                this.write_terminate();
+                this.terminated = true;
+                continue;
+            }
+            // batches which have lingered are flushed even if the input is always ready
+            if let Poll::Ready(batches) = this.batcher.poll_linger(cx) {
+                this.write_batches(batches);
+                continue;
+            }
+            this.poll_stop(cx);
+            if this.terminating {
+                continue;
+            }
+            let event = match this.poll_next(cx) {
+                Poll::Ready(event) => event,
+                // the events which have been pushed are processed
+                Poll::Pending if this.draining && this.in_edge.is_some() => {
+                    this.terminate();
+                    continue;
+                }
+                Poll::Pending => return Poll::Pending,
+            };
+            // the events which have been read are processed
+            if event.is_none() && this.draining {
+                this.terminate();
+                continue;
+            }
             match event.into_iter().try_for_each(|event| match event {
-                LocalEvent::Terminate { .. } => return ControlFlow::Break(()),
+                LocalEvent::Terminate { .. } => {
+                    this.terminated_upstreams += 1;
+                    if this.source.is_some() || this.terminated_upstreams >= this.upstreams {
+                        ControlFlow::Break(())
+                    } else {
+                        ControlFlow::Continue(())
+                    }
+                }
                 LocalEvent::KeyedDataStreamEvent(event) => {
                     this.process(event, cx);
                     ControlFlow::Continue(())
                 }
             }) {
                 ControlFlow::Continue(_) => {
-                    if this.source.is_some() {
+                    // events of a push source arrive at the in-edge, which should be drained like other operators
+                    if this.source.is_some() && this.in_edge.is_none() {
+                        if let Poll::Ready(batches) = this.batcher.poll_linger(cx) {
+                            this.write_batches(batches);
+                        }
+                        this.poll_pending_writes(cx);
+                        // yields to other tasks, and polls the source again
+                        cx.waker().wake_by_ref();
                         return Poll::Pending;
                     } else {
                         continue;
                     }
                 }
-                ControlFlow::Break(_) => return Poll::Ready(()),
+                ControlFlow::Break(_) => this.terminate(),
             }
         }
     }
@@ -425,12 +784,12 @@ mod tests {
     use common::{event::LocalEvent, types::TypedValue, utils::times::now_timestamp};
     use proto::common::{
         mapper, operator_info, source, DataTypeEnum, DataflowMeta, Entry, ExecutorStatus, Func,
-        KafkaDesc, KeyedDataEvent, Mapper, OperatorInfo, ResourceId, Source,
+        KafkaDesc, KeyedDataEvent, Mapper, OperatorInfo, ResourceId, RowKind, Source,
     };
 
     use crate::{
-        edge::{InEdge, LocalInEdge, LocalOutEdge, OutEdge},
-        new_event_channel, MOD_TEST_START,
+        edge::{new_local_edge, InEdge, LocalInEdge, LocalOutEdge, OutEdge},
+        MOD_TEST_START,
     };
 
     use super::Task;
@@ -455,7 +814,7 @@ mod tests {
                   v8::V8::initialize();
         });
         std::env::set_var("STATE_MANAGER", "MEM");
-        tracing_subscriber::fmt().init();
+        let _ = tracing_subscriber::fmt().try_init();
         SetupGuard {}
     }
 
@@ -495,6 +854,9 @@ mod tests {
             operator_id: 0,
             host_addr: None,
             upstreams: Default::default(),
+            out_edge_batch: None,
+            parallelism: 0,
+            instance: None,
             details: Some(operator_info::Details::Source(Source {
                 desc: Some(source::Desc::Kafka(KafkaDesc::default())),
             })),
@@ -528,6 +890,9 @@ mod tests {
             operator_id: 1,
             host_addr: None,
             upstreams: Default::default(),
+            out_edge_batch: None,
+            parallelism: 0,
+            instance: None,
             details: Some(operator_info::Details::Mapper(Mapper {
                 value: Some(mapper::Value::Func(Func {
                     function: "function _operator_map_process(a) { return a+1 }".to_string(),
@@ -535,22 +900,19 @@ mod tests {
             })),
         });
 
-        let (tx, rx) = new_event_channel(10);
+        let (in_edge_tx_endpoint, in_edge) = new_local_edge(10);
         {
-            executor.set_in_edge(Some(Box::pin(LocalInEdge::new(rx))));
+            executor.set_in_edge(Some(Box::pin(in_edge)));
             assert!(executor.in_edge.is_some());
         }
 
-        let in_edge_tx_endpoint = LocalOutEdge::new(tx);
-        let (tx, rx) = new_event_channel(10);
+        let (out_edge, out_edge_rx_endpoint) = new_local_edge(10);
 
         {
-            executor.add_out_edge(2, Box::new(LocalOutEdge::new(tx)));
+            executor.add_out_edge(2, Box::new(out_edge));
             assert_eq!(executor.out_edges.len(), 1);
         }
 
-        let out_edge_rx_endpoint = LocalInEdge::new(rx);
-
         let ref mut suite = TestStreamExecutorSuite {
             in_edge_tx_endpoint,
             out_edge_rx_endpoint,
@@ -577,6 +939,7 @@ mod tests {
                         from_operator_id: 0,
                         window: None,
                         event_id: 0,
+                        row_kind: RowKind::Insert as i32,
                     }))
                     .await;
                 assert!(result.is_ok());
@@ -598,6 +961,7 @@ mod tests {
                         from_operator_id: 0,
                         window: None,
                         event_id: 0,
+                        row_kind: RowKind::Insert as i32,
                     }))
                 );
             }
@@ -618,6 +982,308 @@ mod tests {
         let _ = handler.await;
     }
 
+    #[tokio::test]
+    async fn test_stream_executor_closes_sinks_on_terminate() {
+        use std::collections::BTreeMap;
+
+        use common::file::list_files;
+        use proto::common::{parquet_desc, ParquetDesc};
+
+        use crate::connector::{Parquet, SinkImpl};
+
+        let _ = setup();
+        let dir = std::env::temp_dir().join("lightflus-test-executor-parquet-sink");
+        let _ = std::fs::remove_dir_all(&dir);
+        let job_id = ResourceId {
+            resource_id: "resource_id".to_string(),
+            namespace_id: "namespace_id".to_string(),
+        };
+
+        let task = Task::new(
+            &job_id,
+            &DataflowMeta {
+                center: 1,
+                neighbors: vec![],
+            },
+        );
+        let mut executor = task.create_stream_executor(&OperatorInfo {
+            operator_id: 1,
+            details: Some(operator_info::Details::Mapper(Mapper {
+                value: Some(mapper::Value::Func(Func {
+                    function: "function _operator_map_process(a) { return a }".to_string(),
+                })),
+            })),
+            ..Default::default()
+        });
+        executor.add_external_sink(SinkImpl::Parquet(Parquet::with_sink_config(
+            &job_id,
+            2,
+            &ParquetDesc {
+                path: dir.to_str().unwrap_or_default().to_string(),
+                columns: vec![parquet_desc::Column {
+                    name: "word".to_string(),
+                    r#type: parquet_desc::column::Type::String as i32,
+                    required: true,
+                }],
+                partitioning: None,
+                rotation: None,
+                row_group_size: 0,
+            },
+        )));
+
+        let (in_edge, rx) = new_local_edge(10);
+        executor.set_in_edge(Some(Box::pin(rx)));
+        let handler = tokio::spawn(executor);
+
+        let result = in_edge
+            .write(LocalEvent::KeyedDataStreamEvent(KeyedDataEvent {
+                job_id: Some(job_id.clone()),
+                to_operator_id: 1,
+                data: vec![Entry {
+                    data_type: DataTypeEnum::Object as i32,
+                    value: TypedValue::Object(BTreeMap::from([(
+                        "word".to_string(),
+                        TypedValue::String("hello".to_string()),
+                    )]))
+                    .get_data_bytes(),
+                }],
+                event_time: now_timestamp(),
+                row_kind: RowKind::Insert as i32,
+                ..Default::default()
+            }))
+            .await;
+        assert!(result.is_ok());
+
+        let result = in_edge
+            .write(LocalEvent::Terminate {
+                job_id: Default::default(),
+                to: 1,
+                event_time: now_timestamp(),
+            })
+            .await;
+        assert!(result.is_ok());
+        let _ = handler.await;
+
+        // the tail of the stream is committed once the executor terminates
+        let pattern = dir.join("resource_id-2-*.parquet");
+        let files = list_files(pattern.to_str().unwrap_or_default()).expect("list files failed");
+        assert_eq!(files.len(), 1);
+        let in_progress = dir.join(".resource_id-2-*.inprogress");
+        let files =
+            list_files(in_progress.to_str().unwrap_or_default()).expect("list files failed");
+        assert!(files.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_stream_executor_terminates_after_all_upstreams() {
+        use std::time::Duration;
+
+        let _ = setup();
+        let job_id = ResourceId {
+            resource_id: "resource_id".to_string(),
+            namespace_id: "namespace_id".to_string(),
+        };
+        let task = Task::new(
+            &job_id,
+            &DataflowMeta {
+                center: 1,
+                neighbors: vec![2],
+            },
+        );
+        let mut executor = task.create_stream_executor(&OperatorInfo {
+            operator_id: 1,
+            upstreams: vec![10, 11],
+            details: Some(operator_info::Details::Mapper(Mapper {
+                value: Some(mapper::Value::Func(Func {
+                    function: "function _operator_map_process(a) { return a }".to_string(),
+                })),
+            })),
+            ..Default::default()
+        });
+        let (in_edge, rx) = new_local_edge(10);
+        executor.set_in_edge(Some(Box::pin(rx)));
+        let (out_edge, mut out_edge_rx_endpoint) = new_local_edge(10);
+        executor.add_out_edge(2, Box::new(out_edge));
+        let mut handler = tokio::spawn(executor);
+
+        let terminate = |to| LocalEvent::Terminate {
+            job_id: job_id.clone(),
+            to,
+            event_time: now_timestamp(),
+        };
+        let event = KeyedDataEvent {
+            job_id: Some(job_id.clone()),
+            to_operator_id: 1,
+            data: vec![Entry {
+                data_type: DataTypeEnum::Number as i32,
+                value: TypedValue::Number(1.0).get_data_bytes(),
+            }],
+            event_time: now_timestamp(),
+            row_kind: RowKind::Insert as i32,
+            ..Default::default()
+        };
+        assert!(in_edge.write(terminate(1)).await.is_ok());
+        assert!(in_edge
+            .write(LocalEvent::KeyedDataStreamEvent(event.clone()))
+            .await
+            .is_ok());
+
+        // the other upstream hasn't terminated
+        assert!(matches!(
+            out_edge_rx_endpoint.next().await,
+            Some(LocalEvent::KeyedDataStreamEvent(_))
+        ));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), &mut handler)
+                .await
+                .is_err()
+        );
+
+        assert!(in_edge.write(terminate(1)).await.is_ok());
+        assert!(tokio::time::timeout(Duration::from_secs(3), handler)
+            .await
+            .is_ok());
+        // the downstream operator terminates after the executor
+        assert!(matches!(
+            out_edge_rx_endpoint.next().await,
+            Some(LocalEvent::Terminate { to: 2, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stream_executor_chained_operators() {
+        use proto::common::{filter, Filter};
+
+        let _ = setup();
+        let job_id = ResourceId {
+            resource_id: "resource_id".to_string(),
+            namespace_id: "namespace_id".to_string(),
+        };
+        // 1: map -> 2: filter -> 3: map -> 4
+        let operator = |operator_id: u32, details: operator_info::Details| OperatorInfo {
+            operator_id,
+            details: Some(details),
+            ..Default::default()
+        };
+        let map = |operator_id: u32, function: &str| {
+            operator(
+                operator_id,
+                operator_info::Details::Mapper(Mapper {
+                    value: Some(mapper::Value::Func(Func {
+                        function: function.to_string(),
+                    })),
+                }),
+            )
+        };
+        let operators = [
+            map(1, "function _operator_map_process(a) { return a+1 }"),
+            operator(
+                2,
+                operator_info::Details::Filter(Filter {
+                    value: Some(filter::Value::Func(Func {
+                        function: "function _operator_filter_process(a) { return a % 2 === 0 }"
+                            .to_string(),
+                    })),
+                }),
+            ),
+            map(3, "function _operator_map_process(a) { return a*10 }"),
+        ];
+        let new_executor = |operator_info: &OperatorInfo, neighbors: Vec<u32>| {
+            Task::new(
+                &job_id,
+                &DataflowMeta {
+                    center: operator_info.operator_id,
+                    neighbors,
+                },
+            )
+            .create_stream_executor(operator_info)
+        };
+
+        // write 1..=6 to the head and read the events the tail writes to 4
+        let run = |executors: Vec<super::StreamExecutor>| {
+            let job_id = job_id.clone();
+            async move {
+                let (mut in_edge_tx_endpoint, mut out_edge_rx_endpoint) = new_local_edge(10);
+                let mut to_operator_id = 4;
+                let mut handlers = vec![];
+                for mut executor in executors.into_iter().rev() {
+                    let (out_edge, executor_in_edge) = new_local_edge(10);
+                    executor.set_in_edge(Some(Box::pin(executor_in_edge)));
+                    executor.add_out_edge(
+                        to_operator_id,
+                        Box::new(std::mem::replace(&mut in_edge_tx_endpoint, out_edge)),
+                    );
+                    to_operator_id = executor.executor_id;
+                    handlers.push(tokio::spawn(executor));
+                }
+                for value in 1..=6 {
+                    let result = in_edge_tx_endpoint
+                        .write(LocalEvent::KeyedDataStreamEvent(KeyedDataEvent {
+                            job_id: Some(job_id.clone()),
+                            to_operator_id: 1,
+                            data: vec![Entry {
+                                data_type: DataTypeEnum::Number as i32,
+                                value: TypedValue::Number(value as f64).get_data_bytes(),
+                            }],
+                            event_time: now_timestamp(),
+                            row_kind: RowKind::Insert as i32,
+                            ..Default::default()
+                        }))
+                        .await;
+                    assert!(result.is_ok());
+                }
+
+                let mut results = vec![];
+                for _ in 0..3 {
+                    match out_edge_rx_endpoint.next().await {
+                        Some(LocalEvent::KeyedDataStreamEvent(event)) => results.push(event),
+                        event => panic!("unexpected event {:?}", event),
+                    }
+                }
+                handlers.iter().for_each(|handler| handler.abort());
+                results
+            }
+        };
+
+        let mut chained = new_executor(&operators[0], vec![2]);
+        chained.chain_operator(&operators[1]);
+        chained.chain_operator(&operators[2]);
+        assert_eq!(chained.output_operator_id(), 3);
+        let chained = run(vec![chained]).await;
+
+        let unchained = run(vec![
+            new_executor(&operators[0], vec![2]),
+            new_executor(&operators[1], vec![3]),
+            new_executor(&operators[2], vec![4]),
+        ])
+        .await;
+
+        assert_eq!(
+            chained
+                .iter()
+                .map(|event| TypedValue::from(&event.data[0]))
+                .collect::<Vec<_>>(),
+            vec![
+                TypedValue::Number(20.0),
+                TypedValue::Number(40.0),
+                TypedValue::Number(60.0)
+            ]
+        );
+        chained
+            .iter()
+            .zip(unchained.iter())
+            .for_each(|(chained, unchained)| {
+                assert_eq!(chained.data, unchained.data);
+                assert_eq!(chained.to_operator_id, 4);
+                // the events are written by the tail of the chain
+                assert_eq!(chained.from_operator_id, 3);
+                assert_eq!(chained.from_operator_id, unchained.from_operator_id);
+            });
+        assert_eq!(chained.len(), unchained.len());
+    }
+
     #[tokio::test]
     async fn test_stream_executor_window() {}
 }
@@ -0,0 +1,223 @@
+use std::time::Duration;
+
+use common::{event::LocalEvent, net::gateway::taskmanager::SafeTaskManagerRpcGateway};
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use proto::{
+    common::{
+        Ack, Entry, Heartbeat, HostAddr, KeyedDataEvent, KeyedEventSet, ResourceId, Response,
+        SubDataflowStates,
+    },
+    taskmanager::{
+        edge_frame::Frame,
+        task_manager_api_server::{TaskManagerApi, TaskManagerApiServer},
+        BatchSendEventsToOperatorResponse, CreateSubDataflowRequest, CreateSubDataflowResponse,
+        EdgeAck, EdgeFrame, ExportKeyGroupsRequest, GetCollectedEventsRequest,
+        GetCollectedEventsResponse, KeyGroupStates, SendEventToOperatorResponse,
+        StopDataflowResponse,
+    },
+};
+use stream::{
+    edge::{new_local_edge, InEdge, LocalInEdge, OutEdge, RemoteInEdge, RemoteOutEdge},
+    spill::SpillQueue,
+};
+use tokio::{
+    net::{TcpSocket, TcpStream},
+    task::JoinHandle,
+};
+use tonic::{codegen::BoxStream, transport::Server, Request, Status, Streaming};
+
+/// a TaskManager which only serves the edges to one operator
+struct EdgeTaskManager {
+    in_edge: RemoteInEdge,
+}
+
+#[tonic::async_trait]
+impl TaskManagerApi for EdgeTaskManager {
+    async fn send_event_to_operator(
+        &self,
+        _: Request<KeyedDataEvent>,
+    ) -> Result<tonic::Response<SendEventToOperatorResponse>, Status> {
+        Err(Status::unimplemented("send_event_to_operator"))
+    }
+    async fn stop_dataflow(
+        &self,
+        _: Request<ResourceId>,
+    ) -> Result<tonic::Response<StopDataflowResponse>, Status> {
+        Err(Status::unimplemented("stop_dataflow"))
+    }
+    async fn create_sub_dataflow(
+        &self,
+        _: Request<CreateSubDataflowRequest>,
+    ) -> Result<tonic::Response<CreateSubDataflowResponse>, Status> {
+        Err(Status::unimplemented("create_sub_dataflow"))
+    }
+    async fn receive_heartbeat(
+        &self,
+        _: Request<Heartbeat>,
+    ) -> Result<tonic::Response<Response>, Status> {
+        Err(Status::unimplemented("receive_heartbeat"))
+    }
+    async fn receive_ack(&self, _: Request<Ack>) -> Result<tonic::Response<Response>, Status> {
+        Err(Status::unimplemented("receive_ack"))
+    }
+    async fn batch_send_events_to_operator(
+        &self,
+        _: Request<KeyedEventSet>,
+    ) -> Result<tonic::Response<BatchSendEventsToOperatorResponse>, Status> {
+        Err(Status::unimplemented("batch_send_events_to_operator"))
+    }
+    async fn get_sub_dataflow(
+        &self,
+        _: Request<ResourceId>,
+    ) -> Result<tonic::Response<SubDataflowStates>, Status> {
+        Err(Status::unimplemented("get_sub_dataflow"))
+    }
+    async fn get_collected_events(
+        &self,
+        _: Request<GetCollectedEventsRequest>,
+    ) -> Result<tonic::Response<GetCollectedEventsResponse>, Status> {
+        Err(Status::unimplemented("get_collected_events"))
+    }
+    type OpenEdgeStream = BoxStream<EdgeAck>;
+    async fn open_edge(
+        &self,
+        request: Request<Streaming<EdgeFrame>>,
+    ) -> Result<tonic::Response<Self::OpenEdgeStream>, Status> {
+        let mut frames = request.into_inner();
+        match frames.message().await? {
+            Some(EdgeFrame {
+                frame: Some(Frame::Handshake(handshake)),
+                ..
+            }) => Ok(tonic::Response::new(
+                self.in_edge.clone().serve(handshake, frames),
+            )),
+            _ => Err(Status::invalid_argument("handshake is unprovided")),
+        }
+    }
+    async fn export_key_groups(
+        &self,
+        _: Request<ExportKeyGroupsRequest>,
+    ) -> Result<tonic::Response<KeyGroupStates>, Status> {
+        Err(Status::unimplemented("export_key_groups"))
+    }
+    async fn import_key_groups(
+        &self,
+        _: Request<KeyGroupStates>,
+    ) -> Result<tonic::Response<Response>, Status> {
+        Err(Status::unimplemented("import_key_groups"))
+    }
+}
+
+/// A TCP proxy in front of the TaskManager. Aborting it drops all the connections, as if the TaskManager went away
+fn start_proxy(listen_port: u16, upstream_port: u16) -> JoinHandle<()> {
+    let socket = TcpSocket::new_v4().expect("create socket failed");
+    socket.set_reuseaddr(true).expect("set reuseaddr failed");
+    socket
+        .bind(([127, 0, 0, 1], listen_port).into())
+        .expect("bind proxy failed");
+    let listener = socket.listen(16).expect("listen failed");
+    tokio::spawn(async move {
+        let mut connections = FuturesUnordered::new();
+        loop {
+            tokio::select! {
+                Ok((mut inbound, _)) = listener.accept() => {
+                    connections.push(async move {
+                        if let Ok(mut outbound) = TcpStream::connect(("127.0.0.1", upstream_port)).await {
+                            let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+                        }
+                    });
+                }
+                Some(_) = connections.next(), if !connections.is_empty() => {}
+            }
+        }
+    })
+}
+
+fn event(value: u8) -> LocalEvent {
+    LocalEvent::KeyedDataStreamEvent(KeyedDataEvent {
+        job_id: Some(ResourceId::default()),
+        from_operator_id: 1,
+        to_operator_id: 2,
+        key: Some(Entry {
+            value: vec![value].into(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+}
+
+async fn next_value(in_edge: &mut LocalInEdge<LocalEvent>) -> u8 {
+    match tokio::time::timeout(Duration::from_secs(10), in_edge.next()).await {
+        Ok(Some(LocalEvent::KeyedDataStreamEvent(event))) => event.key.unwrap().value[0],
+        _ => panic!("no event is received"),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_remote_out_edge_replays_after_downstream_returns() {
+    // a small in-edge runs out of credits soon, so that writes can't be buffered until the TaskManager returns
+    let (local_out_edge, mut in_edge) = new_local_edge::<LocalEvent>(2);
+    let task_manager = EdgeTaskManager {
+        in_edge: RemoteInEdge::new(local_out_edge),
+    };
+    tokio::spawn(
+        Server::builder()
+            .add_service(TaskManagerApiServer::new(task_manager))
+            .serve(([127, 0, 0, 1], 8803).into()),
+    );
+    let proxy = start_proxy(8802, 8803);
+
+    let dir = std::env::temp_dir()
+        .join("lightflus-test")
+        .join("test_remote_out_edge_replays_after_downstream_returns");
+    let out_edge = RemoteOutEdge::with_spill_queue(
+        SafeTaskManagerRpcGateway::with_timeout(
+            &HostAddr {
+                host: "localhost".to_string(),
+                port: 8802,
+            },
+            Duration::from_millis(200),
+            Duration::from_secs(1),
+        ),
+        SpillQueue::new(dir, 2, 1 << 20),
+    );
+    let spill_stats = out_edge.spill_stats().unwrap();
+
+    for value in 1..=2 {
+        out_edge.write(event(value)).await.expect("write failed");
+        assert_eq!(next_value(&mut in_edge).await, value);
+    }
+
+    // the TaskManager goes away
+    proxy.abort();
+    let _ = proxy.await;
+
+    for value in 3..=8 {
+        out_edge.write(event(value)).await.expect("write failed");
+    }
+    let metrics = spill_stats.metrics();
+    assert!(metrics.memory_events + metrics.disk_events > 0);
+
+    // the TaskManager comes back, and the edge is flushed once all the events are delivered
+    let proxy = start_proxy(8802, 8803);
+    let (flushed, _) = tokio::join!(
+        tokio::time::timeout(Duration::from_secs(10), out_edge.flush()),
+        async {
+            for value in 3..=8 {
+                assert_eq!(next_value(&mut in_edge).await, value);
+            }
+        }
+    );
+    assert!(matches!(flushed, Ok(Ok(()))));
+    // no frame is delivered twice
+    assert!(
+        tokio::time::timeout(Duration::from_millis(500), in_edge.next())
+            .await
+            .is_err()
+    );
+    let metrics = spill_stats.metrics();
+    assert_eq!(metrics.memory_events + metrics.disk_events, 0);
+    assert_eq!(metrics.disk_bytes, 0);
+
+    proxy.abort();
+}
@@ -2,22 +2,28 @@ use std::collections::BTreeMap;
 
 use bytes::Buf;
 use common::{
-    db::MysqlConn,
+    db::{MysqlConn, PostgresConn},
     event::LocalEvent,
+    file::list_files,
     kafka::{run_consumer, KafkaMessage},
+    mqtt::run_subscriber,
     redis::RedisClient,
+    testutils::MockHttpServer,
     types::TypedValue,
     utils::get_env,
 };
 
 use proto::common::{
-    kafka_desc,
+    file_desc, http_desc, kafka_desc, mqtt_desc,
     mysql_desc::{self, statement},
-    redis_desc, DataTypeEnum, Entry, Func, KafkaDesc, KeyedDataEvent, MysqlDesc, RedisDesc,
-    ResourceId,
+    parquet_desc, postgres_desc, redis_desc, CollectDesc, DataTypeEnum, Entry, FileDesc, Func,
+    HttpDesc, KafkaDesc, KeyedDataEvent, KeyedEventSet, MqttDesc, MysqlDesc, ParquetDesc,
+    PostgresDesc, RedisDesc, ResourceId, RowKind, StdoutDesc,
 };
 use sqlx::Row;
-use stream::connector::{Kafka, Mysql, Redis, Sink, SinkImpl};
+use stream::connector::{
+    Collect, File, Http, Kafka, Mqtt, Mysql, Parquet, Postgres, Redis, Sink, SinkImpl, Stdout,
+};
 
 static MOD_TEST_START: std::sync::Once = std::sync::Once::new();
 
@@ -53,6 +59,7 @@ async fn test_kafka_sink() {
                 partition: Some(0),
             }),
             data_type: DataTypeEnum::String as i32,
+            format: kafka_desc::MessageFormat::Plain as i32,
         },
     ));
 
@@ -99,6 +106,7 @@ async fn test_kafka_sink() {
         from_operator_id: 0,
         window: None,
         event_id: 1,
+        row_kind: RowKind::Insert as i32,
     };
 
     let result = kafka_sink
@@ -140,6 +148,7 @@ async fn test_redis_sink_success() {
             password: Default::default(),
             database: 0,
             tls: false,
+            cluster: false,
         }),
         key_extractor: Some(Func {
             function: "function redis_extractor(a) { return a.key }".to_string(),
@@ -147,6 +156,11 @@ async fn test_redis_sink_success() {
         value_extractor: Some(Func {
             function: "function redis_extractor(a) { return a.value }".to_string(),
         }),
+        command: redis_desc::Command::Set as i32,
+        field_extractor: None,
+        score_extractor: None,
+        expire_millis: 0,
+        stream_opts: None,
     };
 
     let mut redis_sink = SinkImpl::Redis(Redis::with_config(1, desc));
@@ -190,6 +204,7 @@ async fn test_redis_sink_success() {
         from_operator_id: 0,
         window: None,
         event_id: 1,
+        row_kind: RowKind::Insert as i32,
     };
 
     let result = redis_sink
@@ -200,13 +215,13 @@ async fn test_redis_sink_success() {
 
     let mut client = RedisClient::new(&desc);
 
-    let result = client.get(&TypedValue::String("word-1".to_string()));
+    let result = client.get(&TypedValue::String("word-1".to_string())).await;
     assert!(result.is_ok());
     let value = result.expect("msg");
 
     assert_eq!(value.as_slice().get_i64(), 10);
 
-    let result = client.get(&TypedValue::String("word-2".to_string()));
+    let result = client.get(&TypedValue::String("word-2".to_string())).await;
     assert!(result.is_ok());
     let value = result.expect("msg");
 
@@ -254,6 +269,9 @@ async fn test_mysql_sink() {
                     },
                 ],
             }),
+            delete_statement: None,
+            polling_opts: None,
+            max_batch_size: 0,
         },
     ));
 
@@ -288,6 +306,7 @@ async fn test_mysql_sink() {
         from_operator_id: 0,
         window: None,
         event_id: 1,
+        row_kind: RowKind::Insert as i32,
     };
 
     let result = mysql.sink(LocalEvent::KeyedDataStreamEvent(event)).await;
@@ -313,3 +332,840 @@ async fn test_mysql_sink() {
 
     assert!(result.is_ok());
 }
+
+#[tokio::test]
+async fn test_mysql_batch_sink() {
+    let _setup_guard = setup();
+    let conn_opts = mysql_desc::ConnectionOpts {
+        host: "localhost".to_string(),
+        username: "ci".to_string(),
+        password: "123".to_string(),
+        database: "ci".to_string(),
+    };
+
+    let mut conn = MysqlConn::from(conn_opts.clone());
+
+    let result = conn
+        .execute(
+            "create table if not exists word_count (word varchar(36), count int, PRIMARY KEY (word))",
+            vec![],
+        )
+        .await;
+    assert!(result.is_ok());
+
+    let extractor = |index: u32, field: &str| statement::Extractor {
+        index,
+        extractor: format!("function mysql_extractor(a) {{return a.{}}}", field),
+    };
+    let mut mysql = SinkImpl::Mysql(Mysql::with_config(
+        0,
+        &MysqlDesc {
+            connection_opts: Some(conn_opts),
+            statement: Some(mysql_desc::Statement {
+                statement: "insert into word_count (word,count) values (?,?) on duplicate key update count = values(count)"
+                    .to_string(),
+                extractors: vec![extractor(1, "word"), extractor(2, "count")],
+            }),
+            delete_statement: Some(mysql_desc::Statement {
+                statement: "delete from word_count where word = ?".to_string(),
+                extractors: vec![extractor(1, "word")],
+            }),
+            polling_opts: None,
+            max_batch_size: 2,
+        },
+    ));
+
+    let event = |event_id: i64, word: &str, count: i64, row_kind: RowKind| KeyedDataEvent {
+        job_id: Some(ResourceId {
+            resource_id: "resource_id".to_string(),
+            namespace_id: "namespaceId".to_string(),
+        }),
+        key: None,
+        to_operator_id: 1,
+        data: vec![Entry {
+            data_type: DataTypeEnum::Object as i32,
+            value: TypedValue::Object(BTreeMap::from([
+                ("word".to_string(), TypedValue::String(word.to_string())),
+                ("count".to_string(), TypedValue::BigInt(count)),
+            ]))
+            .get_data_bytes(),
+        }],
+        event_time: 0,
+        from_operator_id: 0,
+        window: None,
+        event_id,
+        row_kind: row_kind as i32,
+    };
+
+    let result = mysql
+        .batch_sink(KeyedEventSet {
+            events: vec![
+                event(1, "hello", 1, RowKind::Insert),
+                event(2, "world", 1, RowKind::Insert),
+                event(3, "hello", 1, RowKind::UpdateBefore),
+                event(4, "hello", 2, RowKind::UpdateAfter),
+                event(5, "lightflus", 1, RowKind::Insert),
+                event(6, "world", 1, RowKind::Delete),
+            ],
+            job_id: None,
+            to_operator_id: 1,
+            from_operator_id: 0,
+        })
+        .await;
+    assert!(result.is_ok());
+
+    let mut rows = vec![];
+    let result = conn
+        .try_for_each("select * from word_count order by word", vec![], |row| {
+            rows.push((
+                row.try_get::<String, &str>("word").unwrap(),
+                row.try_get::<i32, &str>("count").unwrap(),
+            ));
+            futures_util::future::ready(Ok(()))
+        })
+        .await;
+    assert!(result.is_ok());
+    assert_eq!(
+        rows,
+        vec![("hello".to_string(), 2), ("lightflus".to_string(), 1)]
+    );
+
+    let result = conn
+        .execute("drop table if exists word_count", vec![])
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_postgres_sink() {
+    let _setup_guard = setup();
+    let conn_opts = postgres_desc::ConnectionOpts {
+        host: get_env("POSTGRES_HOST").unwrap_or("localhost".to_string()),
+        username: "ci".to_string(),
+        password: "123".to_string(),
+        database: "ci".to_string(),
+    };
+
+    let mut conn = PostgresConn::from(conn_opts.clone());
+
+    let result = conn
+        .execute(
+            "create table if not exists word_count (word varchar(36) PRIMARY KEY, count bigint)",
+            vec![],
+        )
+        .await;
+    assert!(result.is_ok());
+
+    let extractor = |index: u32, field: &str| postgres_desc::statement::Extractor {
+        index,
+        extractor: format!("function postgres_extractor(a) {{return a.{}}}", field),
+    };
+    let mut postgres = SinkImpl::Postgres(Postgres::with_config(
+        0,
+        &PostgresDesc {
+            connection_opts: Some(conn_opts),
+            statement: Some(postgres_desc::Statement {
+                statement: "insert into word_count (word,count) values ($1,$2) on conflict (word) do update set count = excluded.count"
+                    .to_string(),
+                extractors: vec![extractor(1, "word"), extractor(2, "count")],
+            }),
+            delete_statement: Some(postgres_desc::Statement {
+                statement: "delete from word_count where word = $1".to_string(),
+                extractors: vec![extractor(1, "word")],
+            }),
+            max_batch_size: 2,
+        },
+    ));
+
+    let event = |event_id: i64, word: &str, count: i64, row_kind: RowKind| KeyedDataEvent {
+        job_id: Some(ResourceId {
+            resource_id: "resource_id".to_string(),
+            namespace_id: "namespaceId".to_string(),
+        }),
+        key: None,
+        to_operator_id: 1,
+        data: vec![Entry {
+            data_type: DataTypeEnum::Object as i32,
+            value: TypedValue::Object(BTreeMap::from([
+                ("word".to_string(), TypedValue::String(word.to_string())),
+                ("count".to_string(), TypedValue::BigInt(count)),
+            ]))
+            .get_data_bytes(),
+        }],
+        event_time: 0,
+        from_operator_id: 0,
+        window: None,
+        event_id,
+        row_kind: row_kind as i32,
+    };
+
+    let result = postgres
+        .sink(LocalEvent::KeyedDataStreamEvent(event(
+            1,
+            "flink",
+            1,
+            RowKind::Insert,
+        )))
+        .await;
+    assert!(result.is_ok());
+
+    let result = postgres
+        .batch_sink(KeyedEventSet {
+            events: vec![
+                event(2, "hello", 1, RowKind::Insert),
+                event(3, "world", 1, RowKind::Insert),
+                event(4, "hello", 1, RowKind::UpdateBefore),
+                event(5, "hello", 2, RowKind::UpdateAfter),
+                event(6, "lightflus", 1, RowKind::Insert),
+                event(7, "world", 1, RowKind::Delete),
+                event(8, "flink", 1, RowKind::Delete),
+            ],
+            job_id: None,
+            to_operator_id: 1,
+            from_operator_id: 0,
+        })
+        .await;
+    assert!(result.is_ok());
+
+    let mut rows = vec![];
+    let result = conn
+        .try_for_each("select * from word_count order by word", vec![], |row| {
+            rows.push((
+                row.try_get::<String, &str>("word").unwrap(),
+                row.try_get::<i64, &str>("count").unwrap(),
+            ));
+            futures_util::future::ready(Ok(()))
+        })
+        .await;
+    assert!(result.is_ok());
+    assert_eq!(
+        rows,
+        vec![("hello".to_string(), 2), ("lightflus".to_string(), 1)]
+    );
+
+    let result = conn
+        .execute("drop table if exists word_count", vec![])
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_postgres_sink_repeated_keys() {
+    let _setup_guard = setup();
+    let conn_opts = postgres_desc::ConnectionOpts {
+        host: get_env("POSTGRES_HOST").unwrap_or("localhost".to_string()),
+        username: "ci".to_string(),
+        password: "123".to_string(),
+        database: "ci".to_string(),
+    };
+
+    let mut conn = PostgresConn::from(conn_opts.clone());
+
+    let result = conn
+        .execute(
+            "create table if not exists word_count_repeated_keys (word varchar(36) PRIMARY KEY, count bigint)",
+            vec![],
+        )
+        .await;
+    assert!(result.is_ok());
+
+    let extractor = |index: u32, field: &str| postgres_desc::statement::Extractor {
+        index,
+        extractor: format!("function postgres_extractor(a) {{return a.{}}}", field),
+    };
+    let mut postgres = SinkImpl::Postgres(Postgres::with_config(
+        0,
+        &PostgresDesc {
+            connection_opts: Some(conn_opts),
+            statement: Some(postgres_desc::Statement {
+                statement: "insert into word_count_repeated_keys (word,count) values ($1,$2) on conflict (word) do update set count = excluded.count"
+                    .to_string(),
+                extractors: vec![extractor(1, "word"), extractor(2, "count")],
+            }),
+            delete_statement: Some(postgres_desc::Statement {
+                statement: "delete from word_count_repeated_keys where word = $1".to_string(),
+                extractors: vec![extractor(1, "word")],
+            }),
+            max_batch_size: 0,
+        },
+    ));
+
+    let event = |event_id: i64, word: &str, count: i64, row_kind: RowKind| KeyedDataEvent {
+        job_id: Some(ResourceId {
+            resource_id: "resource_id".to_string(),
+            namespace_id: "namespaceId".to_string(),
+        }),
+        key: None,
+        to_operator_id: 1,
+        data: vec![Entry {
+            data_type: DataTypeEnum::Object as i32,
+            value: TypedValue::Object(BTreeMap::from([
+                ("word".to_string(), TypedValue::String(word.to_string())),
+                ("count".to_string(), TypedValue::BigInt(count)),
+            ]))
+            .get_data_bytes(),
+        }],
+        event_time: 0,
+        from_operator_id: 0,
+        window: None,
+        event_id,
+        row_kind: row_kind as i32,
+    };
+
+    // rows with the same key are merged into one multi-row upsert at the default batch size
+    let result = postgres
+        .batch_sink(KeyedEventSet {
+            events: vec![
+                event(1, "hello", 1, RowKind::Insert),
+                event(2, "world", 1, RowKind::Insert),
+                event(3, "hello", 1, RowKind::UpdateBefore),
+                event(4, "hello", 2, RowKind::UpdateAfter),
+                event(5, "world", 1, RowKind::UpdateBefore),
+                event(6, "world", 2, RowKind::UpdateAfter),
+                event(7, "hello", 2, RowKind::UpdateBefore),
+                event(8, "hello", 3, RowKind::UpdateAfter),
+            ],
+            job_id: None,
+            to_operator_id: 1,
+            from_operator_id: 0,
+        })
+        .await;
+    assert!(result.is_ok());
+
+    let mut rows = vec![];
+    let result = conn
+        .try_for_each(
+            "select * from word_count_repeated_keys order by word",
+            vec![],
+            |row| {
+                rows.push((
+                    row.try_get::<String, &str>("word").unwrap(),
+                    row.try_get::<i64, &str>("count").unwrap(),
+                ));
+                futures_util::future::ready(Ok(()))
+            },
+        )
+        .await;
+    assert!(result.is_ok());
+    assert_eq!(
+        rows,
+        vec![("hello".to_string(), 3), ("world".to_string(), 2)]
+    );
+
+    let result = conn
+        .execute("drop table if exists word_count_repeated_keys", vec![])
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_redis_sink_delete() {
+    let _setup_guard = setup();
+    let ref desc = RedisDesc {
+        connection_opts: Some(redis_desc::ConnectionOpts {
+            host: get_env("REDIS_HOST").unwrap_or("localhost".to_string()),
+            username: Default::default(),
+            password: Default::default(),
+            database: 0,
+            tls: false,
+            cluster: false,
+        }),
+        key_extractor: Some(Func {
+            function: "function redis_extractor(a) { return a.key }".to_string(),
+        }),
+        value_extractor: Some(Func {
+            function: "function redis_extractor(a) { return a.value }".to_string(),
+        }),
+        command: redis_desc::Command::Set as i32,
+        field_extractor: None,
+        score_extractor: None,
+        expire_millis: 0,
+        stream_opts: None,
+    };
+
+    let mut redis_sink = SinkImpl::Redis(Redis::with_config(1, desc));
+
+    let new_event = |row_kind: RowKind| KeyedDataEvent {
+        job_id: Some(ResourceId {
+            resource_id: "resource_id".to_string(),
+            namespace_id: "namespaceId".to_string(),
+        }),
+        key: None,
+        to_operator_id: 1,
+        data: vec![Entry {
+            data_type: DataTypeEnum::Object as i32,
+            value: TypedValue::Object(BTreeMap::from_iter(
+                [
+                    ("key".to_string(), TypedValue::String("word-3".to_string())),
+                    ("value".to_string(), TypedValue::BigInt(10)),
+                ]
+                .iter()
+                .map(|entry| (entry.0.clone(), entry.1.clone())),
+            ))
+            .get_data_bytes(),
+        }],
+        event_time: 0,
+        from_operator_id: 0,
+        window: None,
+        event_id: 1,
+        row_kind: row_kind as i32,
+    };
+
+    let result = redis_sink
+        .sink(LocalEvent::KeyedDataStreamEvent(new_event(RowKind::Insert)))
+        .await;
+    assert!(result.is_ok());
+
+    let mut client = RedisClient::new(&desc);
+    let result = client.get(&TypedValue::String("word-3".to_string())).await;
+    assert!(result.is_ok());
+    assert_eq!(result.expect("msg").as_slice().get_i64(), 10);
+
+    let result = redis_sink
+        .sink(LocalEvent::KeyedDataStreamEvent(new_event(RowKind::Delete)))
+        .await;
+    assert!(result.is_ok());
+
+    let result = client.get(&TypedValue::String("word-3".to_string())).await;
+    assert!(result.is_ok());
+    assert!(result.expect("msg").is_empty());
+}
+
+#[tokio::test]
+async fn test_mqtt_sink() {
+    let desc = MqttDesc {
+        connection_opts: Some(mqtt_desc::ConnectionOpts {
+            broker: get_env("MQTT_HOST").unwrap_or("localhost".to_string()),
+            client_id: Default::default(),
+            username: Default::default(),
+            password: Default::default(),
+            keep_alive_secs: 0,
+        }),
+        topic_filters: vec!["word_count".to_string()],
+        topic: "word_count".to_string(),
+        qos: mqtt_desc::Qos::AtLeastOnce as i32,
+        data_type: DataTypeEnum::Object as i32,
+        persistent_session: false,
+    };
+    let job_id = ResourceId {
+        resource_id: "resource_id".to_string(),
+        namespace_id: "default".to_string(),
+    };
+
+    let mut subscriber = run_subscriber(&desc, "subscriber", 10);
+    // wait for the subscription
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let mut mqtt_sink = SinkImpl::Mqtt(Mqtt::with_sink_config(&job_id, 1, &desc));
+    let new_event = |word: &str, row_kind: RowKind| KeyedDataEvent {
+        job_id: Some(job_id.clone()),
+        key: Some(Entry {
+            data_type: DataTypeEnum::String as i32,
+            value: TypedValue::String(word.to_string()).get_data_bytes(),
+        }),
+        to_operator_id: 1,
+        data: vec![Entry {
+            data_type: DataTypeEnum::Object as i32,
+            value: TypedValue::Object(BTreeMap::from([(
+                "word".to_string(),
+                TypedValue::String(word.to_string()),
+            )]))
+            .get_data_bytes(),
+        }],
+        event_time: 0,
+        from_operator_id: 0,
+        window: None,
+        event_id: 1,
+        row_kind: row_kind as i32,
+    };
+
+    let result = mqtt_sink
+        .batch_sink(KeyedEventSet {
+            events: vec![
+                new_event("hello", RowKind::Insert),
+                // deletions are not published
+                new_event("hello", RowKind::Delete),
+                new_event("lightflus", RowKind::Insert),
+            ],
+            job_id: Some(job_id.clone()),
+            to_operator_id: 1,
+            from_operator_id: 0,
+        })
+        .await;
+    assert!(result.is_ok());
+
+    for word in ["hello", "lightflus"] {
+        let message = subscriber.recv().await.expect("message should be received");
+        assert_eq!(message.topic, "word_count".to_string());
+        assert_eq!(
+            TypedValue::from_slice_with_type(&message.payload, DataTypeEnum::Object),
+            TypedValue::Object(BTreeMap::from([(
+                "word".to_string(),
+                TypedValue::String(word.to_string())
+            )]))
+        );
+        assert!(subscriber.ack(&message).is_ok());
+    }
+
+    mqtt_sink.close_sink();
+    subscriber.close().await;
+}
+
+#[tokio::test]
+async fn test_file_sink() {
+    let dir = std::env::temp_dir().join("lightflus-test-file-sink");
+    let _ = std::fs::remove_dir_all(&dir);
+    let job_id = ResourceId {
+        resource_id: "resource_id".to_string(),
+        namespace_id: "default".to_string(),
+    };
+
+    let mut file_sink = SinkImpl::File(File::with_sink_config(
+        &job_id,
+        1,
+        &FileDesc {
+            path: dir.to_str().unwrap_or_default().to_string(),
+            format: file_desc::Format::JsonLines as i32,
+            csv_opts: None,
+            follow: false,
+            poll_interval_millis: 0,
+            rotation: Some(file_desc::RotationOpts {
+                max_bytes: 16,
+                max_millis: 0,
+            }),
+        },
+    ));
+    let new_event = |word: &str, row_kind: RowKind| KeyedDataEvent {
+        job_id: Some(job_id.clone()),
+        key: None,
+        to_operator_id: 1,
+        data: vec![Entry {
+            data_type: DataTypeEnum::Object as i32,
+            value: TypedValue::Object(BTreeMap::from([(
+                "word".to_string(),
+                TypedValue::String(word.to_string()),
+            )]))
+            .get_data_bytes(),
+        }],
+        event_time: 0,
+        from_operator_id: 0,
+        window: None,
+        event_id: 1,
+        row_kind: row_kind as i32,
+    };
+
+    let result = file_sink
+        .batch_sink(KeyedEventSet {
+            events: vec![
+                new_event("hello", RowKind::Insert),
+                // deletions are not written
+                new_event("hello", RowKind::Delete),
+                new_event("lightflus", RowKind::Insert),
+            ],
+            job_id: Some(job_id.clone()),
+            to_operator_id: 1,
+            from_operator_id: 0,
+        })
+        .await;
+    assert!(result.is_ok());
+    file_sink.close_sink();
+
+    // each line is larger than max_bytes, so they are written into two files
+    let files = list_files(
+        dir.join("resource_id-1-*.jsonl")
+            .to_str()
+            .unwrap_or_default(),
+    )
+    .expect("list files failed");
+    assert_eq!(files.len(), 2);
+    let mut lines = files
+        .iter()
+        .map(|path| std::fs::read_to_string(path).expect("read file failed"))
+        .collect::<Vec<_>>();
+    lines.sort();
+    assert_eq!(
+        lines,
+        vec![
+            "{\"word\":\"hello\"}\n".to_string(),
+            "{\"word\":\"lightflus\"}\n".to_string()
+        ]
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_http_sink() {
+    let server = MockHttpServer::start(vec![503]).await;
+    let job_id = ResourceId {
+        resource_id: "resource_id".to_string(),
+        namespace_id: "default".to_string(),
+    };
+
+    let mut http_sink = SinkImpl::Http(Http::with_sink_config(
+        1,
+        &HttpDesc {
+            token: Default::default(),
+            url: server.url("/webhook"),
+            method: http_desc::Method::Post as i32,
+            headers: Default::default(),
+            body_template: r#"{"text": {{value.word}}}"#.to_string(),
+            body_extractor: None,
+            batch: Some(http_desc::BatchOpts { max_size: 2 }),
+            retry: Some(http_desc::RetryOpts {
+                max_retries: 1,
+                initial_backoff_millis: 10,
+                max_backoff_millis: 0,
+            }),
+            concurrency: 1,
+            timeout_millis: 0,
+        },
+    ));
+    let new_event = |word: &str, row_kind: RowKind| KeyedDataEvent {
+        job_id: Some(job_id.clone()),
+        key: None,
+        to_operator_id: 1,
+        data: vec![Entry {
+            data_type: DataTypeEnum::Object as i32,
+            value: TypedValue::Object(BTreeMap::from([(
+                "word".to_string(),
+                TypedValue::String(word.to_string()),
+            )]))
+            .get_data_bytes(),
+        }],
+        event_time: 0,
+        from_operator_id: 0,
+        window: None,
+        event_id: 1,
+        row_kind: row_kind as i32,
+    };
+
+    let result = http_sink
+        .batch_sink(KeyedEventSet {
+            events: vec![
+                new_event("hello", RowKind::Insert),
+                // deletions are not sent
+                new_event("hello", RowKind::Delete),
+                new_event("lightflus", RowKind::Insert),
+                new_event("world", RowKind::Insert),
+            ],
+            job_id: Some(job_id.clone()),
+            to_operator_id: 1,
+            from_operator_id: 0,
+        })
+        .await;
+    assert!(result.is_ok());
+    http_sink.close_sink();
+
+    let mut bodies = server
+        .received_bodies()
+        .into_iter()
+        .map(|body| String::from_utf8(body).unwrap_or_default())
+        .collect::<Vec<_>>();
+    // the first request fails with 503 and is retried
+    assert_eq!(bodies.len(), 3);
+    bodies.sort();
+    bodies.dedup();
+    assert_eq!(
+        bodies,
+        vec![
+            r#"[{"text":"hello"},{"text":"lightflus"}]"#.to_string(),
+            r#"[{"text":"world"}]"#.to_string(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_parquet_sink() {
+    let dir = std::env::temp_dir().join("lightflus-test-parquet-sink");
+    let _ = std::fs::remove_dir_all(&dir);
+    let job_id = ResourceId {
+        resource_id: "resource_id".to_string(),
+        namespace_id: "default".to_string(),
+    };
+
+    let mut parquet_sink = SinkImpl::Parquet(Parquet::with_sink_config(
+        &job_id,
+        1,
+        &ParquetDesc {
+            path: dir.to_str().unwrap_or_default().to_string(),
+            columns: vec![parquet_desc::Column {
+                name: "word".to_string(),
+                r#type: parquet_desc::column::Type::String as i32,
+                required: true,
+            }],
+            partitioning: Some(parquet_desc::Partitioning {
+                time_format: "dt=%Y-%m-%d/hour=%H".to_string(),
+                fields: vec![],
+            }),
+            rotation: None,
+            row_group_size: 0,
+        },
+    ));
+    let new_event = |word: &str, row_kind: RowKind| KeyedDataEvent {
+        job_id: Some(job_id.clone()),
+        key: None,
+        to_operator_id: 1,
+        data: vec![Entry {
+            data_type: DataTypeEnum::Object as i32,
+            value: TypedValue::Object(BTreeMap::from([(
+                "word".to_string(),
+                TypedValue::String(word.to_string()),
+            )]))
+            .get_data_bytes(),
+        }],
+        // 2026-10-18T03:04:05Z
+        event_time: 1792292645000,
+        from_operator_id: 0,
+        window: None,
+        event_id: 1,
+        row_kind: row_kind as i32,
+    };
+
+    let result = parquet_sink
+        .batch_sink(KeyedEventSet {
+            events: vec![
+                new_event("hello", RowKind::Insert),
+                // deletions are not written
+                new_event("hello", RowKind::Delete),
+                new_event("lightflus", RowKind::Insert),
+            ],
+            job_id: Some(job_id.clone()),
+            to_operator_id: 1,
+            from_operator_id: 0,
+        })
+        .await;
+    assert!(result.is_ok());
+
+    // files are committed when the sink is closed
+    let pattern = dir.join("dt=2026-10-18/hour=03/resource_id-1-*.parquet");
+    let files = list_files(pattern.to_str().unwrap_or_default()).expect("list files failed");
+    assert!(files.is_empty());
+
+    parquet_sink.close_sink();
+    let files = list_files(pattern.to_str().unwrap_or_default()).expect("list files failed");
+    assert_eq!(files.len(), 1);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_parquet_sink_rolls_by_timer() {
+    let dir = std::env::temp_dir().join("lightflus-test-parquet-sink-rolling");
+    let _ = std::fs::remove_dir_all(&dir);
+    let job_id = ResourceId {
+        resource_id: "resource_id".to_string(),
+        namespace_id: "default".to_string(),
+    };
+
+    let mut parquet_sink = SinkImpl::Parquet(Parquet::with_sink_config(
+        &job_id,
+        1,
+        &ParquetDesc {
+            path: dir.to_str().unwrap_or_default().to_string(),
+            columns: vec![parquet_desc::Column {
+                name: "word".to_string(),
+                r#type: parquet_desc::column::Type::String as i32,
+                required: true,
+            }],
+            partitioning: None,
+            rotation: Some(file_desc::RotationOpts {
+                max_bytes: 0,
+                max_millis: 100,
+            }),
+            row_group_size: 0,
+        },
+    ));
+
+    let result = parquet_sink
+        .sink(LocalEvent::KeyedDataStreamEvent(KeyedDataEvent {
+            job_id: Some(job_id.clone()),
+            to_operator_id: 1,
+            data: vec![Entry {
+                data_type: DataTypeEnum::Object as i32,
+                value: TypedValue::Object(BTreeMap::from([(
+                    "word".to_string(),
+                    TypedValue::String("hello".to_string()),
+                )]))
+                .get_data_bytes(),
+            }],
+            row_kind: RowKind::Insert as i32,
+            ..Default::default()
+        }))
+        .await;
+    assert!(result.is_ok());
+
+    // the file is committed once it expires, though no more events arrive
+    let pattern = dir.join("resource_id-1-*.parquet");
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    let files = list_files(pattern.to_str().unwrap_or_default()).expect("list files failed");
+    assert_eq!(files.len(), 1);
+
+    parquet_sink.close().await;
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_stdout_and_collect_sinks() {
+    let job_id = ResourceId {
+        resource_id: "resource_id".to_string(),
+        namespace_id: "default".to_string(),
+    };
+    let new_event = |word: &str, event_id: i64| KeyedDataEvent {
+        job_id: Some(job_id.clone()),
+        key: Some(Entry {
+            data_type: DataTypeEnum::String as i32,
+            value: TypedValue::String(word.to_string()).get_data_bytes(),
+        }),
+        to_operator_id: 1,
+        data: vec![Entry {
+            data_type: DataTypeEnum::String as i32,
+            value: TypedValue::String(word.to_string()).get_data_bytes(),
+        }],
+        event_time: 0,
+        from_operator_id: 0,
+        window: None,
+        event_id,
+        row_kind: RowKind::Insert as i32,
+    };
+    let event_set = KeyedEventSet {
+        events: vec![
+            new_event("hello", 1),
+            new_event("lightflus", 2),
+            new_event("world", 3),
+        ],
+        job_id: Some(job_id.clone()),
+        to_operator_id: 1,
+        from_operator_id: 0,
+    };
+
+    let mut stdout_sink = SinkImpl::Stdout(Stdout::with_config(
+        1,
+        &StdoutDesc {
+            prefix: "debug".to_string(),
+        },
+    ));
+    assert!(stdout_sink.batch_sink(event_set.clone()).await.is_ok());
+    assert!(stdout_sink.get_collected_events().is_none());
+    stdout_sink.close_sink();
+
+    let mut collect_sink = SinkImpl::Collect(Collect::with_config(1, &CollectDesc { capacity: 2 }));
+    let collected = collect_sink
+        .get_collected_events()
+        .expect("collected events are missing");
+    assert!(collect_sink.batch_sink(event_set).await.is_ok());
+    // the oldest event is dropped
+    assert_eq!(
+        collected
+            .get_events()
+            .await
+            .iter()
+            .map(|event| event.event_id)
+            .collect::<Vec<_>>(),
+        vec![2, 3]
+    );
+
+    // events are still readable after the sink is closed
+    collect_sink.close_sink();
+    assert_eq!(collected.get_events().await.len(), 2);
+}
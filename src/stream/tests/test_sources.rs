@@ -1,13 +1,27 @@
 use std::{
+    collections::BTreeMap,
     pin::Pin,
     task::{Context, Poll},
 };
 
-use common::{event::LocalEvent, kafka::run_producer, types::TypedValue, utils::get_env};
+use common::{
+    db::MysqlConn,
+    event::LocalEvent,
+    kafka::run_producer,
+    mqtt::run_publisher,
+    redis::{RedisClient, RedisCommand},
+    types::TypedValue,
+    utils::get_env,
+};
 
 use futures_util::{ready, Future};
-use proto::common::{kafka_desc::KafkaOptions, DataTypeEnum, KafkaDesc, ResourceId};
-use stream::connector::{Kafka, Source};
+use proto::common::{
+    file_desc,
+    kafka_desc::{KafkaOptions, MessageFormat},
+    mqtt_desc, mysql_desc, redis_desc, DataTypeEnum, FileDesc, GeneratorDesc, KafkaDesc, MqttDesc,
+    MysqlDesc, RedisDesc, ResourceId,
+};
+use stream::connector::{File, Generator, Kafka, Mqtt, Mysql, Redis, Source};
 
 #[tokio::test]
 async fn test_kafka_source_next() {
@@ -20,6 +34,7 @@ async fn test_kafka_source_next() {
             partition: None,
         }),
         data_type: DataTypeEnum::String as i32,
+        format: MessageFormat::Plain as i32,
     };
 
     let mut kafka_source = Kafka::with_source_config(
@@ -67,6 +82,7 @@ async fn test_kafka_source_poll_next() {
             partition: None,
         }),
         data_type: DataTypeEnum::String as i32,
+        format: MessageFormat::Plain as i32,
     };
 
     let kafka_source = Kafka::with_source_config(
@@ -126,3 +142,346 @@ async fn test_kafka_source_poll_next() {
     let result = tokio::spawn(test_kafka_poll).await;
     assert!(result.is_ok())
 }
+
+#[tokio::test]
+async fn test_mysql_source_next() {
+    let conn_opts = mysql_desc::ConnectionOpts {
+        host: "localhost".to_string(),
+        username: "ci".to_string(),
+        password: "123".to_string(),
+        database: "ci".to_string(),
+    };
+
+    let mut conn = MysqlConn::from(conn_opts.clone());
+    let result = conn.execute("create table if not exists orders (id int NOT NULL AUTO_INCREMENT, name varchar(36), PRIMARY KEY (id))", vec![]).await;
+    assert!(result.is_ok());
+    let result = conn
+        .execute(
+            "insert into orders (name) values ('order-1'),('order-2')",
+            vec![],
+        )
+        .await;
+    assert!(result.is_ok());
+
+    let mut mysql_source = Mysql::with_source_config(
+        &ResourceId {
+            resource_id: "resource_id".to_string(),
+            namespace_id: "default".to_string(),
+        },
+        0,
+        &MysqlDesc {
+            connection_opts: Some(conn_opts),
+            statement: None,
+            delete_statement: None,
+            polling_opts: Some(mysql_desc::PollingOpts {
+                query: "select * from orders where id > ? order by id".to_string(),
+                incrementing_column: "id".to_string(),
+                interval_millis: 100,
+                start_offset: None,
+            }),
+            max_batch_size: 0,
+        },
+    );
+
+    fn order(id: i64, name: &str) -> TypedValue {
+        TypedValue::Object(BTreeMap::from([
+            ("id".to_string(), TypedValue::BigInt(id)),
+            ("name".to_string(), TypedValue::String(name.to_string())),
+        ]))
+    }
+
+    async fn next_row(source: &mut Mysql) -> TypedValue {
+        match source.next().await {
+            Some(LocalEvent::KeyedDataStreamEvent(e)) => {
+                assert_eq!(e.data.len(), 1);
+                TypedValue::from_slice(&e.data[0].value)
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+
+    assert_eq!(next_row(&mut mysql_source).await, order(1, "order-1"));
+    assert_eq!(next_row(&mut mysql_source).await, order(2, "order-2"));
+
+    let result = conn
+        .execute("insert into orders (name) values ('order-3')", vec![])
+        .await;
+    assert!(result.is_ok());
+
+    // rows have been read will not be emitted again
+    assert_eq!(next_row(&mut mysql_source).await, order(3, "order-3"));
+
+    mysql_source.close_source().await;
+
+    let result = conn.execute("drop table if exists orders", vec![]).await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_redis_stream_source_next() {
+    let desc = RedisDesc {
+        connection_opts: Some(redis_desc::ConnectionOpts {
+            host: "localhost".to_string(),
+            username: Default::default(),
+            password: Default::default(),
+            database: 0,
+            tls: false,
+            cluster: false,
+        }),
+        key_extractor: None,
+        value_extractor: None,
+        command: 0,
+        field_extractor: None,
+        score_extractor: None,
+        expire_millis: 0,
+        stream_opts: Some(redis_desc::StreamOpts {
+            key: "orders".to_string(),
+            group: "lightflus".to_string(),
+            consumer: "consumer-0".to_string(),
+            start_id: "0".to_string(),
+            count: 10,
+            block_millis: 100,
+            claim_min_idle_millis: 0,
+        }),
+    };
+    let job_id = ResourceId {
+        resource_id: "resource_id".to_string(),
+        namespace_id: "default".to_string(),
+    };
+
+    let key = TypedValue::String("orders".to_string());
+    let mut client = RedisClient::new(&desc);
+    let result = client.del(&key).await;
+    assert!(result.is_ok());
+
+    fn order(name: &str) -> TypedValue {
+        TypedValue::Object(BTreeMap::from([(
+            "name".to_string(),
+            TypedValue::String(name.to_string()),
+        )]))
+    }
+
+    let result = client
+        .execute(&[
+            RedisCommand::XAdd {
+                key: key.clone(),
+                value: order("order-1"),
+            },
+            RedisCommand::XAdd {
+                key: key.clone(),
+                value: order("order-2"),
+            },
+        ])
+        .await;
+    assert!(result.is_ok());
+
+    async fn next_entry(source: &mut Redis) -> TypedValue {
+        match source.next().await {
+            Some(LocalEvent::KeyedDataStreamEvent(e)) => {
+                assert_eq!(e.data.len(), 1);
+                TypedValue::from_slice(&e.data[0].value)
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+
+    let mut source = Redis::with_source_config(&job_id, 0, &desc);
+    assert_eq!(next_entry(&mut source).await, order("order-1"));
+    assert_eq!(next_entry(&mut source).await, order("order-2"));
+    // wait for the first entry to be acked
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    source.close_source().await;
+
+    // the second entry has not been acked, it will be delivered again after restarting
+    let mut source = Redis::with_source_config(&job_id, 0, &desc);
+    assert_eq!(next_entry(&mut source).await, order("order-2"));
+
+    let result = client
+        .execute(&[RedisCommand::XAdd {
+            key: key.clone(),
+            value: order("order-3"),
+        }])
+        .await;
+    assert!(result.is_ok());
+    assert_eq!(next_entry(&mut source).await, order("order-3"));
+    source.close_source().await;
+
+    let result = client.del(&key).await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_mqtt_source_next() {
+    let desc = MqttDesc {
+        connection_opts: Some(mqtt_desc::ConnectionOpts {
+            broker: get_env("MQTT_HOST").unwrap_or("localhost".to_string()),
+            client_id: Default::default(),
+            username: Default::default(),
+            password: Default::default(),
+            keep_alive_secs: 0,
+        }),
+        topic_filters: vec!["devices/+/telemetry".to_string()],
+        topic: "devices/device-1/telemetry".to_string(),
+        qos: mqtt_desc::Qos::AtLeastOnce as i32,
+        data_type: DataTypeEnum::Object as i32,
+        persistent_session: false,
+    };
+
+    let mut source = Mqtt::with_source_config(
+        &ResourceId {
+            resource_id: "resource_id".to_string(),
+            namespace_id: "default".to_string(),
+        },
+        0,
+        &desc,
+    );
+    // subscribe topic filters before publishing
+    let result = tokio::time::timeout(std::time::Duration::from_millis(500), source.next()).await;
+    assert!(result.is_err());
+
+    let mut publisher = run_publisher(&desc, "publisher");
+    let result = publisher
+        .publish(r#"{"temperature":"21.5"}"#.as_bytes())
+        .await;
+    assert!(result.is_ok());
+
+    match source.next().await {
+        Some(LocalEvent::KeyedDataStreamEvent(e)) => {
+            assert_eq!(
+                TypedValue::from_slice(&e.get_key().value),
+                TypedValue::String("devices/device-1/telemetry".to_string())
+            );
+            assert_eq!(e.data.len(), 1);
+            assert_eq!(
+                TypedValue::from_slice(&e.data[0].value),
+                TypedValue::Object(BTreeMap::from([(
+                    "temperature".to_string(),
+                    TypedValue::String("21.5".to_string())
+                )]))
+            );
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+
+    publisher.close();
+    source.close_source().await;
+}
+
+#[tokio::test]
+async fn test_file_source_next() {
+    let dir = std::env::temp_dir().join("lightflus-test-file-source");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create dir failed");
+    std::fs::write(dir.join("orders-1.csv"), "id,name\n1,order-1\n").expect("write file failed");
+    std::fs::write(dir.join("orders-2.csv"), "id,name\n2,\"order, 2\"").expect("write file failed");
+
+    let mut source = File::with_source_config(
+        &ResourceId {
+            resource_id: "resource_id".to_string(),
+            namespace_id: "default".to_string(),
+        },
+        0,
+        &FileDesc {
+            path: dir
+                .join("orders-*.csv")
+                .to_str()
+                .unwrap_or_default()
+                .to_string(),
+            format: file_desc::Format::Csv as i32,
+            csv_opts: Some(file_desc::CsvOpts {
+                delimiter: Default::default(),
+                has_header: true,
+                columns: vec![],
+            }),
+            follow: false,
+            poll_interval_millis: 0,
+            rotation: None,
+        },
+    );
+
+    fn order(id: &str, name: &str) -> TypedValue {
+        TypedValue::Object(BTreeMap::from([
+            ("id".to_string(), TypedValue::String(id.to_string())),
+            ("name".to_string(), TypedValue::String(name.to_string())),
+        ]))
+    }
+
+    for expected in [order("1", "order-1"), order("2", "order, 2")] {
+        match source.next().await {
+            Some(LocalEvent::KeyedDataStreamEvent(e)) => {
+                assert_eq!(e.data.len(), 1);
+                assert_eq!(TypedValue::from_slice(&e.data[0].value), expected);
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+
+    // bounded input terminates at the end of files
+    match source.next().await {
+        Some(LocalEvent::Terminate { to, .. }) => assert_eq!(to, 0),
+        event => panic!("unexpected event {:?}", event),
+    }
+
+    source.close_source().await;
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_generator_source_next() {
+    let conf = GeneratorDesc {
+        rate_per_sec: 100,
+        count: 5,
+        key_cardinality: 2,
+        seed: 1,
+        function: None,
+        template: r#"{"id": {{index}}, "user": {{key}}}"#.to_string(),
+    };
+    let job_id = ResourceId {
+        resource_id: "resource_id".to_string(),
+        namespace_id: "default".to_string(),
+    };
+
+    async fn generate(source: &mut Generator) -> Vec<TypedValue> {
+        let mut values = vec![];
+        loop {
+            match source.next().await {
+                Some(LocalEvent::KeyedDataStreamEvent(e)) => {
+                    let key = TypedValue::from_slice(&e.key.expect("key is missing").value);
+                    let value = TypedValue::from_slice(&e.data[0].value);
+                    match &value {
+                        TypedValue::Object(object) => assert_eq!(object.get("user"), Some(&key)),
+                        _ => panic!("unexpected value {:?}", value),
+                    }
+                    values.push(value);
+                }
+                Some(LocalEvent::Terminate { to, .. }) => {
+                    assert_eq!(to, 0);
+                    return values;
+                }
+                None => panic!("generator closed before terminated"),
+            }
+        }
+    }
+
+    let start = std::time::Instant::now();
+    let mut source = Generator::with_source_config(&job_id, 0, &conf);
+    let values = generate(&mut source).await;
+    // 5 events at 100 per second take 50ms
+    assert!(start.elapsed() >= std::time::Duration::from_millis(50));
+    assert_eq!(values.len(), 5);
+    values
+        .iter()
+        .enumerate()
+        .for_each(|(index, value)| match value {
+            TypedValue::Object(object) => {
+                assert_eq!(object.get("id"), Some(&TypedValue::BigInt(index as i64)))
+            }
+            _ => panic!("unexpected value {:?}", value),
+        });
+    source.close_source().await;
+
+    // the same seed generates the same sequence
+    let mut source = Generator::with_source_config(&job_id, 0, &conf);
+    assert_eq!(generate(&mut source).await, values);
+    source.close_source().await;
+}
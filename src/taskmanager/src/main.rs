@@ -2,7 +2,6 @@ use common::utils::get_env;
 use lightflus_core::taskmanager::rpc::load_builder;
 
 use stream::initialize_v8;
-use tonic::transport::Server;
 
 const DEFAULT_WORKER_THREADS_NUM: usize = 100;
 
@@ -25,6 +24,10 @@ fn main() {
             tracing::info!("service will start at {}", builder.port);
 
             initialize_v8();
-            let _ = Server::builder().add_service(server).serve(addr).await;
+            let _ = builder
+                .server_builder()
+                .add_service(server)
+                .serve(addr)
+                .await;
         });
 }